@@ -306,15 +306,11 @@ fn print_section_headers<Elf: FileHeader>(
             }
 
             match section.sh_type(endian) {
-                SHT_SYMTAB => {
-                    if p.options.symbols {
-                        print_section_symbols(p, endian, data, elf, sections, index, section);
-                    }
+                SHT_SYMTAB if p.options.symbols => {
+                    print_section_symbols(p, endian, data, elf, sections, index, section);
                 }
-                SHT_DYNSYM => {
-                    if p.options.elf_dynamic_symbols {
-                        print_section_symbols(p, endian, data, elf, sections, index, section);
-                    }
+                SHT_DYNSYM if p.options.elf_dynamic_symbols => {
+                    print_section_symbols(p, endian, data, elf, sections, index, section);
                 }
                 SHT_REL => print_section_rel(p, endian, data, elf, sections, section),
                 SHT_RELA => print_section_rela(p, endian, data, elf, sections, section),
@@ -338,15 +334,11 @@ fn print_section_headers<Elf: FileHeader>(
                 _ => {}
             }
             match elf.e_machine(endian) {
-                EM_ARM => {
-                    if section.sh_type(endian) == SHT_ARM_ATTRIBUTES {
-                        print_attributes(p, endian, data, elf, section);
-                    }
+                EM_ARM if section.sh_type(endian) == SHT_ARM_ATTRIBUTES => {
+                    print_attributes(p, endian, data, elf, section);
                 }
-                EM_AARCH64 => {
-                    if section.sh_type(endian) == SHT_AARCH64_ATTRIBUTES {
-                        print_attributes(p, endian, data, elf, section);
-                    }
+                EM_AARCH64 if section.sh_type(endian) == SHT_AARCH64_ATTRIBUTES => {
+                    print_attributes(p, endian, data, elf, section);
                 }
                 _ => {}
             }
@@ -3481,6 +3473,9 @@ const FLAGS_DT: &[Flag<u32>] = &flags!(
     DT_PREINIT_ARRAY,
     DT_PREINIT_ARRAYSZ,
     DT_SYMTAB_SHNDX,
+    DT_RELRSZ,
+    DT_RELR,
+    DT_RELRENT,
     DT_GNU_PRELINKED,
     DT_GNU_CONFLICTSZ,
     DT_GNU_LIBLISTSZ,
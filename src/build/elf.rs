@@ -1,5 +1,6 @@
 //! This module provides a [`Builder`] for reading, modifying, and then writing ELF files.
 use alloc::vec::Vec;
+use core::cmp;
 use core::convert::TryInto;
 use core::fmt;
 use core::marker::PhantomData;
@@ -434,10 +435,10 @@ impl<'data> Builder<'data> {
             )
             .map(SectionData::Relocation)
         } else {
-            return Err(Error(format!(
+            Err(Error(format!(
                 "Invalid sh_link {} in relocation section at index {}",
                 link.0, index,
-            )));
+            )))
         }
     }
 
@@ -942,7 +943,12 @@ impl<'data> Builder<'data> {
             if self.gnu_hash_bucket_count == 0 {
                 return Err(Error::new(".gnu.hash bucket count is zero"));
             }
-            // TODO: recalculate bucket_count?
+            if !self.gnu_hash_bloom_count.is_power_of_two() {
+                return Err(Error(format!(
+                    "Invalid .gnu.hash bloom count {}",
+                    self.gnu_hash_bloom_count
+                )));
+            }
             out_dynsyms[num_local_dynamic..].sort_by_key(|sym| match sym.gnu_hash {
                 None => (0, 0),
                 Some(hash) => (1, hash % self.gnu_hash_bucket_count),
@@ -1977,7 +1983,7 @@ impl<'data> Builder<'data> {
     /// To get an accurate result, you may need to first call
     /// [`Self::delete_orphan_symbols`].
     pub fn gnu_hash_size(&self) -> usize {
-        let symbol_count = self.dynamic_symbols.count_defined();
+        let symbol_count = self.gnu_hash_symbol_count();
         self.class().gnu_hash_size(
             self.gnu_hash_bloom_count,
             self.gnu_hash_bucket_count,
@@ -1985,6 +1991,35 @@ impl<'data> Builder<'data> {
         )
     }
 
+    /// Set the GNU hash table parameters for the number of dynamic symbols.
+    ///
+    /// This sets [`Self::gnu_hash_bucket_count`], [`Self::gnu_hash_bloom_count`] and
+    /// [`Self::gnu_hash_bloom_shift`] to values similar to those chosen by linkers:
+    /// an average of 4 symbols per bucket, and 12 bits of bloom filter per symbol.
+    /// The parameters must be set before calling [`Self::set_section_sizes`].
+    ///
+    /// To get an accurate result, you may need to first call
+    /// [`Self::delete_orphan_symbols`].
+    pub fn set_gnu_hash_parameters(&mut self) {
+        let symbol_count = self.gnu_hash_symbol_count() as u32;
+        let word_bits = if self.is_64 { 64 } else { 32 };
+        self.gnu_hash_bucket_count = cmp::max(symbol_count / 4, 1);
+        self.gnu_hash_bloom_count = (symbol_count * 12 / word_bits).next_power_of_two();
+        self.gnu_hash_bloom_shift = 26;
+    }
+
+    /// Number of dynamic symbols that are included in the GNU hash table.
+    ///
+    /// This differs from [`Symbols::count_defined`] in that symbols with a section
+    /// are included even if `st_shndx` is `SHN_UNDEF`, since `st_shndx` is set
+    /// from the section when writing.
+    fn gnu_hash_symbol_count(&self) -> usize {
+        self.dynamic_symbols
+            .into_iter()
+            .filter(|symbol| symbol.section.is_some() || symbol.st_shndx != elf::SHN_UNDEF)
+            .count()
+    }
+
     /// Calculate the size of the GNU symbol version section.
     ///
     /// To get an accurate result, you may need to first call
@@ -288,6 +288,11 @@ pub enum ComdatKind {
     Largest,
     /// Multiple definitions are allowed, and the newest is selected.
     Newest,
+    /// The sections are kept or discarded as a unit, but multiple definitions
+    /// are not removed.
+    ///
+    /// This is only supported for ELF, where it is a section group without `GRP_COMDAT`.
+    Group,
 }
 
 /// The kind of a symbol.
@@ -320,6 +325,13 @@ pub enum SymbolScope {
     /// Symbol is visible to the static linkage unit.
     Linkage,
     /// Symbol is visible to dynamically linked objects.
+    ///
+    /// When writing, a defined symbol with this scope is exported:
+    /// - ELF: a global symbol with `STV_DEFAULT` visibility.
+    /// - Mach-O: an external symbol without `N_PEXT`, so that the linker
+    ///   adds it to the export trie.
+    /// - COFF: an external symbol that is listed in the linker directives
+    ///   written by `Object::add_coff_exports`.
     Dynamic,
 }
 
@@ -363,6 +375,20 @@ pub enum RelocationKind {
     SectionOffset,
     /// The index of the section containing the symbol.
     SectionIndex,
+    /// S + A - TP
+    ///
+    /// The offset of a thread-local symbol from the thread pointer, as used by the
+    /// local-exec TLS model. The addend is only the offset from the symbol: the
+    /// linker adds the architecture's bias between the thread pointer and the TLS
+    /// block, which is negative for x86 and s390x (the TLS block is below the
+    /// thread pointer) and positive for Arm and AArch64 (the TLS block follows the
+    /// thread control block).
+    TpOffset,
+    /// S + A - DTP
+    ///
+    /// The offset of a thread-local symbol within the TLS block of its module, as
+    /// used by the local-dynamic TLS model and debugging information.
+    DtpOffset,
 }
 
 /// Information about how the result of the relocation operation is encoded in the place.
@@ -403,6 +429,15 @@ pub enum RelocationEncoding {
     ///
     /// The `RelocationKind` must be PC relative.
     AArch64Call,
+    /// AArch64 `add` instruction immediate, using bits 12 to 23 of the value.
+    ///
+    /// Used with `RelocationKind::TpOffset` and `RelocationKind::DtpOffset`.
+    AArch64AddHi12,
+    /// AArch64 `add` instruction immediate, using bits 0 to 11 of the value
+    /// without checking for overflow.
+    ///
+    /// Used with `RelocationKind::TpOffset` and `RelocationKind::DtpOffset`.
+    AArch64AddLo12,
 
     /// LoongArch branch offset with two trailing zeros.
     ///
@@ -478,6 +513,104 @@ pub enum FileFlags {
     },
 }
 
+#[cfg(feature = "elf")]
+impl FileFlags {
+    /// Return the ELF file flags for RISC-V.
+    ///
+    /// `rvc` indicates that the code uses compressed instructions.
+    pub fn elf_riscv(float_abi: RiscVFloatAbi, rvc: bool) -> FileFlags {
+        let mut e_flags = match float_abi {
+            RiscVFloatAbi::Soft => crate::elf::EF_RISCV_FLOAT_ABI_SOFT,
+            RiscVFloatAbi::Single => crate::elf::EF_RISCV_FLOAT_ABI_SINGLE,
+            RiscVFloatAbi::Double => crate::elf::EF_RISCV_FLOAT_ABI_DOUBLE,
+            RiscVFloatAbi::Quad => crate::elf::EF_RISCV_FLOAT_ABI_QUAD,
+        };
+        if rvc {
+            e_flags |= crate::elf::EF_RISCV_RVC;
+        }
+        FileFlags::elf(e_flags)
+    }
+
+    /// Return the ELF file flags for MIPS.
+    ///
+    /// `arch` is the instruction set level, which is one of the `EF_MIPS_ARCH_*`
+    /// constants, such as `elf::EF_MIPS_ARCH_32R2`.
+    pub fn elf_mips(abi: MipsAbi, arch: u32) -> FileFlags {
+        let abi = match abi {
+            MipsAbi::O32 => crate::elf::EF_MIPS_ABI_O32,
+            MipsAbi::N32 => crate::elf::EF_MIPS_ABI2,
+            MipsAbi::N64 => 0,
+            MipsAbi::O64 => crate::elf::EF_MIPS_ABI_O64,
+            MipsAbi::Eabi32 => crate::elf::EF_MIPS_ABI_EABI32,
+            MipsAbi::Eabi64 => crate::elf::EF_MIPS_ABI_EABI64,
+        };
+        FileFlags::elf((arch & crate::elf::EF_MIPS_ARCH) | abi)
+    }
+
+    /// Return the ELF file flags for ARM.
+    ///
+    /// This uses version 5 of the ARM EABI.
+    pub fn elf_arm(float_abi: ArmFloatAbi) -> FileFlags {
+        let float_abi = match float_abi {
+            ArmFloatAbi::Soft => crate::elf::EF_ARM_ABI_FLOAT_SOFT,
+            ArmFloatAbi::Hard => crate::elf::EF_ARM_ABI_FLOAT_HARD,
+        };
+        FileFlags::elf(crate::elf::EF_ARM_EABI_VER5 | float_abi)
+    }
+
+    fn elf(e_flags: u32) -> FileFlags {
+        FileFlags::Elf {
+            os_abi: crate::elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_flags,
+        }
+    }
+}
+
+/// The floating point ABI for RISC-V.
+///
+/// This determines the registers used to pass floating point arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RiscVFloatAbi {
+    /// Floating point arguments are passed in integer registers.
+    Soft,
+    /// Single precision floating point arguments are passed in floating point registers.
+    Single,
+    /// Double precision floating point arguments are passed in floating point registers.
+    Double,
+    /// Quad precision floating point arguments are passed in floating point registers.
+    Quad,
+}
+
+/// The ABI for MIPS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MipsAbi {
+    /// The 32-bit O32 ABI.
+    O32,
+    /// The N32 ABI, which uses 64-bit registers and 32-bit pointers.
+    N32,
+    /// The 64-bit N64 ABI.
+    N64,
+    /// The 64-bit O64 ABI.
+    O64,
+    /// The 32-bit embedded ABI.
+    Eabi32,
+    /// The 64-bit embedded ABI.
+    Eabi64,
+}
+
+/// The floating point ABI for ARM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ArmFloatAbi {
+    /// Floating point arguments are passed in integer registers.
+    Soft,
+    /// Floating point arguments are passed in floating point registers.
+    Hard,
+}
+
 /// Segment flags that are specific to each file format.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[non_exhaustive]
@@ -505,6 +638,24 @@ pub enum SegmentFlags {
     },
 }
 
+/// A data compression format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CompressionFormat {
+    /// The data is uncompressed.
+    None,
+    /// The data is compressed, but the compression format is unknown.
+    Unknown,
+    /// ZLIB/DEFLATE.
+    ///
+    /// Used for ELF compression and GNU compressed debug information.
+    Zlib,
+    /// Zstandard.
+    ///
+    /// Used for ELF compression.
+    Zstandard,
+}
+
 /// Section flags that are specific to each file format.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[non_exhaustive]
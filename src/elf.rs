@@ -719,6 +719,8 @@ pub const SHT_CREL: u32 = 0x40000014;
 pub const SHT_LOOS: u32 = 0x6000_0000;
 /// LLVM-style dependent libraries.
 pub const SHT_LLVM_DEPENDENT_LIBRARIES: u32 = 0x6fff4c04;
+/// LLVM basic block address map.
+pub const SHT_LLVM_BB_ADDR_MAP: u32 = 0x6fff4c0a;
 /// Object attributes.
 pub const SHT_GNU_ATTRIBUTES: u32 = 0x6fff_fff5;
 /// GNU-style hash table.
@@ -1564,6 +1566,12 @@ pub const DT_PREINIT_ARRAY: u32 = 32;
 pub const DT_PREINIT_ARRAYSZ: u32 = 33;
 /// Address of SYMTAB_SHNDX section
 pub const DT_SYMTAB_SHNDX: u32 = 34;
+/// Total size of Relr relocs
+pub const DT_RELRSZ: u32 = 35;
+/// Address of Relr relocs
+pub const DT_RELR: u32 = 36;
+/// Size of one Relr reloc
+pub const DT_RELRENT: u32 = 37;
 /// Start of OS-specific
 pub const DT_LOOS: u32 = 0x6000_000d;
 /// End of OS-specific
@@ -4195,12 +4203,60 @@ pub const R_AVR_PORT6: u32 = 34;
 pub const R_AVR_PORT5: u32 = 35;
 pub const R_AVR_32_PCREL: u32 = 36;
 
+// MSP430 values for `FileHeader*::e_flags`.
+
+/// Bitmask for `EF_MSP430_MACH_*`.
+pub const EF_MSP430_MACH: u32 = 0xff;
+
+pub const EF_MSP430_MACH_MSP430X11: u32 = 11;
+pub const EF_MSP430_MACH_MSP430X11X1: u32 = 110;
+pub const EF_MSP430_MACH_MSP430X12: u32 = 12;
+pub const EF_MSP430_MACH_MSP430X13: u32 = 13;
+pub const EF_MSP430_MACH_MSP430X14: u32 = 14;
+pub const EF_MSP430_MACH_MSP430X15: u32 = 15;
+pub const EF_MSP430_MACH_MSP430X16: u32 = 16;
+pub const EF_MSP430_MACH_MSP430X20: u32 = 20;
+pub const EF_MSP430_MACH_MSP430X22: u32 = 22;
+pub const EF_MSP430_MACH_MSP430X23: u32 = 23;
+pub const EF_MSP430_MACH_MSP430X24: u32 = 24;
+pub const EF_MSP430_MACH_MSP430X26: u32 = 26;
+pub const EF_MSP430_MACH_MSP430X31: u32 = 31;
+pub const EF_MSP430_MACH_MSP430X32: u32 = 32;
+pub const EF_MSP430_MACH_MSP430X33: u32 = 33;
+pub const EF_MSP430_MACH_MSP430X41: u32 = 41;
+pub const EF_MSP430_MACH_MSP430X42: u32 = 42;
+pub const EF_MSP430_MACH_MSP430X43: u32 = 43;
+pub const EF_MSP430_MACH_MSP430X44: u32 = 44;
+/// The MSP430X extended architecture.
+pub const EF_MSP430_MACH_MSP430X: u32 = 45;
+pub const EF_MSP430_MACH_MSP430X46: u32 = 46;
+pub const EF_MSP430_MACH_MSP430X47: u32 = 47;
+pub const EF_MSP430_MACH_MSP430X54: u32 = 54;
+
 // MSP430 values for `Rel*::r_type`.
 
+/// No reloc
+pub const R_MSP430_NONE: u32 = 0;
 /// Direct 32 bit
 pub const R_MSP430_32: u32 = 1;
+/// PC relative 10 bit jump offset
+pub const R_MSP430_10_PCREL: u32 = 2;
+/// Direct 16 bit, aligned
+pub const R_MSP430_16: u32 = 3;
+/// PC relative 16 bit, aligned
+pub const R_MSP430_16_PCREL: u32 = 4;
 /// Direct 16 bit
 pub const R_MSP430_16_BYTE: u32 = 5;
+/// PC relative 16 bit
+pub const R_MSP430_16_PCREL_BYTE: u32 = 6;
+/// PC relative 10 bit jump offset, relaxed
+pub const R_MSP430_2X_PCREL: u32 = 7;
+/// PC relative 16 bit, relaxed
+pub const R_MSP430_RL_PCREL: u32 = 8;
+/// Direct 8 bit
+pub const R_MSP430_8: u32 = 9;
+/// Symbol difference
+pub const R_MSP430_SYM_DIFF: u32 = 10;
 
 // Hexagon values for `Rel*::r_type`.
 
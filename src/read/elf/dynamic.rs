@@ -74,6 +74,7 @@ pub trait Dyn: Debug + Pod {
                 | elf::DT_INIT_ARRAY
                 | elf::DT_PREINIT_ARRAY
                 | elf::DT_SYMTAB_SHNDX
+                | elf::DT_RELR
                 | elf::DT_VERDEF
                 | elf::DT_VERNEED
                 | elf::DT_VERSYM
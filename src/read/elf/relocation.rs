@@ -299,6 +299,12 @@ fn parse_relocation<Elf: FileHeader>(
                     elf::R_AARCH64_PREL32 => (K::Relative, g, 32),
                     elf::R_AARCH64_PREL16 => (K::Relative, g, 16),
                     elf::R_AARCH64_CALL26 => (K::PltRelative, E::AArch64Call, 26),
+                    elf::R_AARCH64_TLSLE_ADD_TPREL_HI12 => (K::TpOffset, E::AArch64AddHi12, 12),
+                    elf::R_AARCH64_TLSLE_ADD_TPREL_LO12_NC => (K::TpOffset, E::AArch64AddLo12, 12),
+                    elf::R_AARCH64_TLSLD_ADD_DTPREL_HI12 => (K::DtpOffset, E::AArch64AddHi12, 12),
+                    elf::R_AARCH64_TLSLD_ADD_DTPREL_LO12_NC => {
+                        (K::DtpOffset, E::AArch64AddLo12, 12)
+                    }
                     _ => unknown,
                 }
             } else {
@@ -320,6 +326,8 @@ fn parse_relocation<Elf: FileHeader>(
         },
         elf::EM_ARM => match r_type {
             elf::R_ARM_ABS32 => (K::Absolute, g, 32),
+            elf::R_ARM_TLS_LE32 => (K::TpOffset, g, 32),
+            elf::R_ARM_TLS_LDO32 => (K::DtpOffset, g, 32),
             _ => unknown,
         },
         elf::EM_AVR => match r_type {
@@ -356,6 +364,8 @@ fn parse_relocation<Elf: FileHeader>(
             elf::R_386_PC16 => (K::Relative, g, 16),
             elf::R_386_8 => (K::Absolute, g, 8),
             elf::R_386_PC8 => (K::Relative, g, 8),
+            elf::R_386_TLS_LE => (K::TpOffset, g, 32),
+            elf::R_386_TLS_LDO_32 => (K::DtpOffset, g, 32),
             _ => unknown,
         },
         elf::EM_X86_64 => match r_type {
@@ -370,6 +380,10 @@ fn parse_relocation<Elf: FileHeader>(
             elf::R_X86_64_PC16 => (K::Relative, g, 16),
             elf::R_X86_64_8 => (K::Absolute, g, 8),
             elf::R_X86_64_PC8 => (K::Relative, g, 8),
+            elf::R_X86_64_TPOFF32 => (K::TpOffset, g, 32),
+            elf::R_X86_64_TPOFF64 => (K::TpOffset, g, 64),
+            elf::R_X86_64_DTPOFF32 => (K::DtpOffset, g, 32),
+            elf::R_X86_64_DTPOFF64 => (K::DtpOffset, g, 64),
             _ => unknown,
         },
         elf::EM_HEXAGON => match r_type {
@@ -412,7 +426,9 @@ fn parse_relocation<Elf: FileHeader>(
         },
         elf::EM_MSP430 => match r_type {
             elf::R_MSP430_32 => (K::Absolute, g, 32),
-            elf::R_MSP430_16_BYTE => (K::Absolute, g, 16),
+            elf::R_MSP430_16 | elf::R_MSP430_16_BYTE => (K::Absolute, g, 16),
+            elf::R_MSP430_16_PCREL_BYTE => (K::Relative, g, 16),
+            elf::R_MSP430_8 => (K::Absolute, g, 8),
             _ => unknown,
         },
         elf::EM_PARISC => match r_type {
@@ -432,6 +448,8 @@ fn parse_relocation<Elf: FileHeader>(
         elf::EM_RISCV => match r_type {
             elf::R_RISCV_32 => (K::Absolute, g, 32),
             elf::R_RISCV_64 => (K::Absolute, g, 64),
+            elf::R_RISCV_TLS_DTPREL32 => (K::DtpOffset, g, 32),
+            elf::R_RISCV_TLS_DTPREL64 => (K::DtpOffset, g, 64),
             _ => unknown,
         },
         elf::EM_S390 => match r_type {
@@ -455,6 +473,10 @@ fn parse_relocation<Elf: FileHeader>(
             elf::R_390_GOTOFF64 => (K::GotBaseOffset, g, 64),
             elf::R_390_GOTPC => (K::GotBaseRelative, g, 64),
             elf::R_390_GOTPCDBL => (K::GotBaseRelative, E::S390xDbl, 32),
+            elf::R_390_TLS_LE32 => (K::TpOffset, g, 32),
+            elf::R_390_TLS_LE64 => (K::TpOffset, g, 64),
+            elf::R_390_TLS_LDO32 => (K::DtpOffset, g, 32),
+            elf::R_390_TLS_LDO64 => (K::DtpOffset, g, 64),
             _ => unknown,
         },
         elf::EM_SBF => match r_type {
@@ -63,6 +63,7 @@ where
                 macho::CPU_TYPE_ARM64 | macho::CPU_TYPE_ARM64_32 => {
                     match (reloc.r_type, reloc.r_pcrel) {
                         (macho::ARM64_RELOC_UNSIGNED, false) => (K::Absolute, g),
+                        (macho::ARM64_RELOC_POINTER_TO_GOT, true) => (K::GotRelative, g),
                         (macho::ARM64_RELOC_ADDEND, _) => {
                             paired_addend = i64::from(reloc.r_symbolnum)
                                 .wrapping_shl(64 - 24)
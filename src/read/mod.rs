@@ -873,24 +873,6 @@ struct RelocationMapEntry {
     addend: u64,
 }
 
-/// A data compression format.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[non_exhaustive]
-pub enum CompressionFormat {
-    /// The data is uncompressed.
-    None,
-    /// The data is compressed, but the compression format is unknown.
-    Unknown,
-    /// ZLIB/DEFLATE.
-    ///
-    /// Used for ELF compression and GNU compressed debug information.
-    Zlib,
-    /// Zstandard.
-    ///
-    /// Used for ELF compression.
-    Zstandard,
-}
-
 /// A range in a file that may be compressed.
 ///
 /// Returned by [`ObjectSection::compressed_file_range`].
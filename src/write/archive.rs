@@ -0,0 +1,103 @@
+//! Helpers for writing archive members.
+//!
+//! This does not provide a complete archive writer. Instead, it provides the
+//! primitives required to manually assemble an archive: the caller is
+//! responsible for writing [`archive::MAGIC`] and any symbol table or
+//! long name table members.
+
+use alloc::vec::Vec;
+use core::{fmt, str};
+
+use crate::archive;
+use crate::pod;
+use crate::write::{Error, Result};
+
+/// The size of an archive member header.
+pub const MEMBER_HEADER_SIZE: usize = 60;
+
+/// Create the header for an archive member.
+///
+/// `name` uses the GNU convention: it is terminated with a `/`, and must fit
+/// within the 16 byte name field including the terminator. Names that start
+/// with `/` (such as the symbol table `/`, the long name table `//`, and long
+/// name references `/123`) are written unchanged.
+///
+/// `size` is the size of the member data, excluding this header and any padding.
+/// `date` is the modification timestamp in seconds, and `mode` is the file mode.
+pub fn member_header(
+    name: &[u8],
+    size: u64,
+    date: u64,
+    uid: u32,
+    gid: u32,
+    mode: u32,
+) -> Result<[u8; MEMBER_HEADER_SIZE]> {
+    let mut header = archive::Header {
+        name: [b' '; 16],
+        date: [b' '; 12],
+        uid: [b' '; 6],
+        gid: [b' '; 6],
+        mode: [b' '; 8],
+        size: [b' '; 10],
+        terminator: archive::TERMINATOR,
+    };
+
+    let terminated = name.first() != Some(&b'/');
+    let name_len = name.len() + usize::from(terminated);
+    let field = header.name.get_mut(..name_len).ok_or_else(|| {
+        Error(format!(
+            "archive member name `{}` is too long",
+            str::from_utf8(name).unwrap_or("")
+        ))
+    })?;
+    field[..name.len()].copy_from_slice(name);
+    if terminated {
+        field[name.len()] = b'/';
+    }
+
+    write_field(&mut header.date, "date", format_args!("{}", date))?;
+    write_field(&mut header.uid, "uid", format_args!("{}", uid))?;
+    write_field(&mut header.gid, "gid", format_args!("{}", gid))?;
+    write_field(&mut header.mode, "mode", format_args!("{:o}", mode))?;
+    write_field(&mut header.size, "size", format_args!("{}", size))?;
+
+    let mut bytes = [0; MEMBER_HEADER_SIZE];
+    bytes.copy_from_slice(pod::bytes_of(&header));
+    Ok(bytes)
+}
+
+/// Append an archive member to a buffer.
+///
+/// This writes the member header followed by `data`, and then pads the
+/// member to an even length with a `\n` byte, as required for the next
+/// member header.
+///
+/// See [`member_header`] for the meaning of the parameters.
+pub fn write_member(
+    buffer: &mut Vec<u8>,
+    name: &[u8],
+    data: &[u8],
+    date: u64,
+    uid: u32,
+    gid: u32,
+    mode: u32,
+) -> Result<()> {
+    let header = member_header(name, data.len() as u64, date, uid, gid, mode)?;
+    buffer.reserve(MEMBER_HEADER_SIZE + data.len() + 1);
+    buffer.extend_from_slice(&header);
+    buffer.extend_from_slice(data);
+    if data.len() & 1 != 0 {
+        buffer.push(b'\n');
+    }
+    Ok(())
+}
+
+/// Write a formatted value into a space padded header field.
+fn write_field(field: &mut [u8], name: &str, value: fmt::Arguments<'_>) -> Result<()> {
+    let value = format!("{}", value);
+    field
+        .get_mut(..value.len())
+        .ok_or_else(|| Error(format!("archive member {} `{}` is too large", name, value)))?
+        .copy_from_slice(value.as_bytes());
+    Ok(())
+}
@@ -19,7 +19,33 @@ struct SectionOffsets {
 struct SymbolOffsets {
     name: writer::Name,
     index: u32,
+    aux: SymbolAux,
     aux_count: u8,
+    /// The index of the `.bf` symbol for a function definition.
+    begin_index: u32,
+}
+
+/// The kind of auxiliary symbol records that follow a symbol.
+///
+/// This is determined once per symbol, and then used both when reserving the
+/// symbol table entries and when writing them, so that the two cannot disagree.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum SymbolAux {
+    #[default]
+    None,
+    WeakExternal,
+    FileName,
+    Section,
+    /// A function definition, followed by `.bf` and `.ef` symbols.
+    Function,
+}
+
+/// The auxiliary information for a function definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FunctionAux {
+    length: u32,
+    begin_line: u16,
+    end_line: u16,
 }
 
 /// Internal format to use for the `.drectve` section containing linker
@@ -32,6 +58,81 @@ pub enum CoffExportStyle {
     Gnu,
 }
 
+/// The order of the file ranges in a COFF object file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CoffLayout {
+    /// The section data and relocations are followed by the symbol table and string
+    /// table. This is the conventional layout.
+    DataFirst,
+    /// The symbol table and string table are followed by the section data and
+    /// relocations.
+    SymbolTableFirst,
+}
+
+/// The name of the absolute symbol added by [`Object::add_coff_feat_flags`].
+const FEAT00_NAME: &[u8] = b"@feat.00";
+
+/// The object is compatible with safe exception handling (`/SAFESEH`).
+///
+/// This is a flag for [`Object::add_coff_feat_flags`].
+pub const FEAT00_SAFE_SEH: u32 = 0x1;
+/// The object was compiled with buffer security checks (`/GS`).
+///
+/// This is a flag for [`Object::add_coff_feat_flags`].
+pub const FEAT00_GUARD_STACK: u32 = 0x100;
+/// The object was compiled with additional security checks (`/sdl`).
+///
+/// This is a flag for [`Object::add_coff_feat_flags`].
+pub const FEAT00_SDL: u32 = 0x200;
+/// The object was compiled with control flow guard (`/guard:cf`).
+///
+/// This is a flag for [`Object::add_coff_feat_flags`].
+pub const FEAT00_GUARD_CF: u32 = 0x800;
+/// The object was compiled with EH continuation metadata (`/guard:ehcont`).
+///
+/// This is a flag for [`Object::add_coff_feat_flags`].
+pub const FEAT00_GUARD_EH_CONT: u32 = 0x4000;
+/// The object was compiled for kernel mode (`/kernel`).
+///
+/// This is a flag for [`Object::add_coff_feat_flags`].
+pub const FEAT00_KERNEL: u32 = 0x4000_0000;
+
+/// The search type for a COFF weak external symbol.
+///
+/// This determines how the linker resolves the symbol if it has no strong definition.
+/// See [`Object::add_coff_weak_external`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CoffWeakSearch {
+    /// Don't search libraries for a definition. This is `IMAGE_WEAK_EXTERN_SEARCH_NOLIBRARY`.
+    NoLibrary,
+    /// Search libraries for a definition. This is `IMAGE_WEAK_EXTERN_SEARCH_LIBRARY`.
+    Library,
+    /// The symbol is an alias for the default symbol. This is `IMAGE_WEAK_EXTERN_SEARCH_ALIAS`.
+    Alias,
+}
+
+impl CoffWeakSearch {
+    fn search_type(self) -> u32 {
+        match self {
+            CoffWeakSearch::NoLibrary => coff::IMAGE_WEAK_EXTERN_SEARCH_NOLIBRARY,
+            CoffWeakSearch::Library => coff::IMAGE_WEAK_EXTERN_SEARCH_LIBRARY,
+            CoffWeakSearch::Alias => coff::IMAGE_WEAK_EXTERN_SEARCH_ALIAS,
+        }
+    }
+}
+
+// CodeView constants used by `add_coff_compiler_info`.
+const CV_SIGNATURE_C13: u32 = 4;
+const DEBUG_S_SYMBOLS: u32 = 0xf1;
+const S_OBJNAME: u16 = 0x1101;
+const S_COMPILE3: u16 = 0x113c;
+const CV_CFL_80386: u16 = 0x03;
+const CV_CFL_ARMNT: u16 = 0xf4;
+const CV_CFL_ARM64: u16 = 0xf6;
+const CV_CFL_X64: u16 = 0xd0;
+
 impl<'a> Object<'a> {
     pub(crate) fn coff_section_info(
         &self,
@@ -72,6 +173,21 @@ impl<'a> Object<'a> {
                 // Unsupported section.
                 (&[], &[], SectionKind::Note, SectionFlags::None)
             }
+            // The CRT calls the initializers in this section, sorted by name.
+            StandardSection::InitArray => (
+                &[],
+                &b".CRT$XCU"[..],
+                SectionKind::ReadOnlyData,
+                SectionFlags::None,
+            ),
+            StandardSection::FiniArray | StandardSection::PreInitArray => {
+                // Unsupported section.
+                (&[], &[], SectionKind::Data, SectionFlags::None)
+            }
+            StandardSection::EhFrame => {
+                // Unsupported section.
+                (&[], &[], SectionKind::ReadOnlyData, SectionFlags::None)
+            }
         }
     }
 
@@ -361,35 +477,399 @@ impl<'a> Object<'a> {
     }
 
     /// Appends linker directives to the `.drectve` section to tell the linker
-    /// to export all symbols with `SymbolScope::Dynamic`.
+    /// to export all defined symbols with `SymbolScope::Dynamic`.
     ///
     /// This must be called after all symbols have been defined.
-    pub fn add_coff_exports(&mut self, style: CoffExportStyle) {
-        assert_eq!(self.format, BinaryFormat::Coff);
+    ///
+    /// Returns an error if the format is not COFF.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn add_coff_exports(&mut self, style: CoffExportStyle) -> Result<()> {
+        if self.format != BinaryFormat::Coff {
+            return Err(Error(format!(
+                "unsupported exports for format {:?}",
+                self.format
+            )));
+        }
 
         let mut directives = vec![];
-        for symbol in &self.symbols {
-            if symbol.scope == SymbolScope::Dynamic {
+        for (_, symbol) in self.exported_symbols() {
+            let mut directive = Vec::new();
+            match style {
+                CoffExportStyle::Msvc => directive.extend(b"/EXPORT:\""),
+                CoffExportStyle::Gnu => directive.extend(b"-export:\""),
+            }
+            directive.extend(&symbol.name);
+            directive.extend(b"\"");
+            if symbol.kind != SymbolKind::Text {
                 match style {
-                    CoffExportStyle::Msvc => directives.extend(b" /EXPORT:\""),
-                    CoffExportStyle::Gnu => directives.extend(b" -export:\""),
-                }
-                directives.extend(&symbol.name);
-                directives.extend(b"\"");
-                if symbol.kind != SymbolKind::Text {
-                    match style {
-                        CoffExportStyle::Msvc => directives.extend(b",DATA"),
-                        CoffExportStyle::Gnu => directives.extend(b",data"),
-                    }
+                    CoffExportStyle::Msvc => directive.extend(b",DATA"),
+                    CoffExportStyle::Gnu => directive.extend(b",data"),
                 }
             }
+            directives.push(directive);
+        }
+        for directive in &directives {
+            self.coff_append_directive(directive);
+        }
+        Ok(())
+    }
+
+    /// Append a linker directive to the `.drectve` section.
+    ///
+    /// The directive is a command line option for the linker, such as
+    /// `/DEFAULTLIB:"msvcrt"`. A space is inserted before the directive.
+    ///
+    /// The `.drectve` section is created if required. It has the
+    /// `IMAGE_SCN_LNK_INFO` and `IMAGE_SCN_LNK_REMOVE` characteristics, and
+    /// does not have a section symbol.
+    ///
+    /// Returns an error if the format is not COFF.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn add_coff_directive(&mut self, directive: &[u8]) -> Result<()> {
+        if self.format != BinaryFormat::Coff {
+            return Err(Error(format!(
+                "unsupported linker directive for format {:?}",
+                self.format
+            )));
+        }
+        self.coff_append_directive(directive);
+        Ok(())
+    }
+
+    fn coff_append_directive(&mut self, directive: &[u8]) {
+        let drectve = match self.coff_directives {
+            Some(section) => section,
+            None => {
+                let section = self.add_section(vec![], b".drectve".to_vec(), SectionKind::Linker);
+                self.coff_directives = Some(section);
+                section
+            }
+        };
+        let section = &mut self.sections[drectve.0];
+        section.append_data(b" ", 1);
+        section.append_data(directive, 1);
+    }
+
+    /// Specify the order of the file ranges when writing.
+    ///
+    /// The default is [`CoffLayout::DataFirst`]. The pointers in the file header and
+    /// section headers are set to match the layout.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn set_coff_layout(&mut self, layout: CoffLayout) {
+        self.coff_layout = layout;
+    }
+
+    /// Set whether the big object format may be used when writing.
+    ///
+    /// The regular COFF file header limits the number of sections to 65279.
+    /// If this is enabled and the object has more sections than that, then
+    /// the object is written using the big object format (`/bigobj` in MSVC),
+    /// which uses [`crate::pe::AnonObjectHeaderBigobj`] and 32-bit section numbers.
+    /// Otherwise, writing returns an error for too many sections.
+    ///
+    /// The default is `false`.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn set_coff_bigobj(&mut self, bigobj: bool) {
+        self.coff_bigobj = bigobj;
+    }
+
+    /// Add the `@feat.00` symbol, which advertises features of the object to the linker.
+    ///
+    /// The symbol is an absolute symbol whose value is `flags`, which is a combination
+    /// of the `FEAT00_*` constants, such as [`FEAT00_SAFE_SEH`]. If the symbol has
+    /// already been added, then its value is replaced. The symbol name is not mangled.
+    ///
+    /// Returns an error if the format is not COFF.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn add_coff_feat_flags(&mut self, flags: u32) -> Result<SymbolId> {
+        if self.format != BinaryFormat::Coff {
+            return Err(Error(format!(
+                "unsupported feature flags for format {:?}",
+                self.format
+            )));
+        }
+
+        let existing = self.symbols.iter().position(|symbol| {
+            symbol.name == FEAT00_NAME && symbol.section == SymbolSection::Absolute
+        });
+        if let Some(index) = existing {
+            self.symbols[index].value = flags.into();
+            return Ok(SymbolId(index));
+        }
+        Ok(self.add_raw_symbol(Symbol {
+            name: FEAT00_NAME.to_vec(),
+            value: flags.into(),
+            size: 0,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: SymbolSection::Absolute,
+            flags: SymbolFlags::None,
+        }))
+    }
+
+    /// Add an x86-64 PC-relative relocation for an instruction with a trailing immediate.
+    ///
+    /// The relocation type is `IMAGE_REL_AMD64_REL32_N`, where `N` is `trailing`, the
+    /// number of bytes of immediate data after the relocated field. `N` may be zero,
+    /// which is `IMAGE_REL_AMD64_REL32`. As for [`Self::add_pc_relative_relocation`],
+    /// `addend` is the offset of the target from `symbol`, and does not include any
+    /// bias for the PC.
+    ///
+    /// Returns an error if the format is not COFF, if the architecture is not x86-64,
+    /// or if `trailing` is greater than 5.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn add_coff_rel32_relocation(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        symbol: SymbolId,
+        addend: i64,
+        trailing: u8,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Coff {
+            return Err(Error(format!(
+                "unsupported REL32 relocation for format {:?}",
+                self.format
+            )));
+        }
+
+        if self.architecture != Architecture::X86_64 || trailing > 5 {
+            return Err(Error(format!(
+                "unsupported REL32 relocation with {} trailing bytes for architecture {:?}",
+                trailing, self.architecture
+            )));
+        }
+        self.add_relocation(
+            section,
+            Relocation {
+                offset,
+                symbol,
+                // The PC is the address of the next instruction.
+                addend: addend - 4 - i64::from(trailing),
+                flags: RelocationFlags::Coff {
+                    typ: coff::IMAGE_REL_AMD64_REL32 + u16::from(trailing),
+                },
+            },
+        )
+    }
+
+    /// Add a weak external symbol that resolves to `default` if there is no
+    /// other definition.
+    ///
+    /// The symbol is written with the `IMAGE_SYM_CLASS_WEAK_EXTERNAL` storage class,
+    /// and an auxiliary record containing the symbol table index of `default` and
+    /// the `search` type. Unlike other weak symbols, no default symbol is generated.
+    ///
+    /// If a symbol named `name` is already an undefined reference, then that symbol
+    /// is made weak and returned.
+    ///
+    /// Returns an error if the format is not COFF, if `default` is weak or is a
+    /// section or file symbol, or if a symbol named `name` is already defined, has
+    /// a different kind, or is already a weak external.
+    ///
+    /// Requires `feature = "coff"`.
+    pub fn add_coff_weak_external(
+        &mut self,
+        name: Vec<u8>,
+        default: SymbolId,
+        search: CoffWeakSearch,
+    ) -> Result<SymbolId> {
+        if self.format != BinaryFormat::Coff {
+            return Err(Error(format!(
+                "unsupported weak external for format {:?}",
+                self.format
+            )));
+        }
+
+        let default_symbol = self.symbol(default);
+        if default_symbol.weak
+            || default_symbol.kind == SymbolKind::Section
+            || default_symbol.kind == SymbolKind::File
+        {
+            return Err(Error(format!(
+                "invalid default symbol `{}` for weak external",
+                default_symbol.name().unwrap_or("")
+            )));
+        }
+        let kind = default_symbol.kind;
+        if let Some(symbol_id) = self.symbol_id(&name) {
+            // Make an existing undefined reference weak, so that the weak external
+            // is not lost when references are deduplicated.
+            let symbol = self.symbol(symbol_id);
+            if !symbol.is_undefined()
+                || symbol.kind != kind
+                || self.coff_weak_externals.contains_key(&symbol_id)
+            {
+                return Err(Error(format!(
+                    "invalid weak external for existing symbol `{}`",
+                    symbol.name().unwrap_or("")
+                )));
+            }
+            self.symbol_mut(symbol_id).weak = true;
+            self.coff_weak_externals
+                .insert(symbol_id, (default, search));
+            return Ok(symbol_id);
+        }
+        let symbol_id = self.add_symbol(Symbol {
+            name,
+            value: 0,
+            size: 0,
+            kind,
+            scope: SymbolScope::Linkage,
+            weak: true,
+            section: SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        self.coff_weak_externals
+            .insert(symbol_id, (default, search));
+        Ok(symbol_id)
+    }
+
+    /// Add the auxiliary symbols for a function definition.
+    ///
+    /// When the object is written, the function symbol will be given a function
+    /// definition auxiliary record containing `length`, the size of the function code.
+    /// It will be followed by `.bf` and `.ef` symbols which mark the beginning and
+    /// end of the function, with auxiliary records containing `begin_line` and `end_line`.
+    /// The function definition records are chained together for debuggers.
+    ///
+    /// Returns an error if the format is not COFF, if the symbol is not a function
+    /// defined in a section, or if it is weak.
+    pub fn add_coff_function_aux(
+        &mut self,
+        function: SymbolId,
+        length: u32,
+        begin_line: u16,
+        end_line: u16,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Coff {
+            return Err(Error(format!(
+                "unsupported function auxiliary records for format {:?}",
+                self.format
+            )));
+        }
+
+        let symbol = self.symbol(function);
+        if symbol.kind != SymbolKind::Text || symbol.section.id().is_none() || symbol.weak {
+            return Err(Error(format!(
+                "invalid function symbol `{}` for auxiliary records",
+                symbol.name().unwrap_or("")
+            )));
+        }
+        self.coff_function_aux.insert(
+            function,
+            FunctionAux {
+                length,
+                begin_line,
+                end_line,
+            },
+        );
+        Ok(())
+    }
+
+    /// Add a `.debug$S` section containing CodeView records that identify
+    /// the compiler that produced the object.
+    ///
+    /// This emits a symbol subsection containing an `S_OBJNAME` record for
+    /// `obj_name`, and an `S_COMPILE3` record for `compiler`. `version` is
+    /// used for both the front end and back end versions, in the order
+    /// major, minor, build, and QFE. The source language is recorded as C.
+    pub fn add_coff_compiler_info(
+        &mut self,
+        compiler: &str,
+        version: (u16, u16, u16, u16),
+        obj_name: &str,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Coff {
+            return Err(Error(format!(
+                "unsupported compiler info for format {:?}",
+                self.format
+            )));
+        }
+
+        let machine = match (self.architecture, self.sub_architecture) {
+            (Architecture::Arm, None) => CV_CFL_ARMNT,
+            (Architecture::Aarch64, _) => CV_CFL_ARM64,
+            (Architecture::I386, None) => CV_CFL_80386,
+            (Architecture::X86_64, None) => CV_CFL_X64,
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented CodeView machine for architecture {:?} with sub-architecture {:?}",
+                    self.architecture, self.sub_architecture
+                )));
+            }
+        };
+
+        let mut records = Vec::new();
+
+        let mut record = Vec::new();
+        record.extend_from_slice(&0u32.to_le_bytes());
+        record.extend_from_slice(obj_name.as_bytes());
+        record.push(0);
+        push_codeview_record(&mut records, S_OBJNAME, &record)?;
+
+        let mut record = Vec::new();
+        record.extend_from_slice(&0u32.to_le_bytes());
+        record.extend_from_slice(&machine.to_le_bytes());
+        let (major, minor, build, qfe) = version;
+        for _ in 0..2 {
+            for value in [major, minor, build, qfe] {
+                record.extend_from_slice(&value.to_le_bytes());
+            }
         }
-        let drectve = self.add_section(vec![], b".drectve".to_vec(), SectionKind::Linker);
-        self.append_section_data(drectve, &directives, 1);
+        record.extend_from_slice(compiler.as_bytes());
+        record.push(0);
+        push_codeview_record(&mut records, S_COMPILE3, &record)?;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&CV_SIGNATURE_C13.to_le_bytes());
+        data.extend_from_slice(&DEBUG_S_SYMBOLS.to_le_bytes());
+        data.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        data.extend_from_slice(&records);
+
+        let section = self.add_section(vec![], b".debug$S".to_vec(), SectionKind::Debug);
+        self.append_section_data(section, &data, 4);
+        Ok(())
+    }
+
+    pub(crate) fn coff_machine(&self) -> Result<u16> {
+        let machine = match (self.architecture, self.sub_architecture, self.endian) {
+            (Architecture::Arm, None, _) => coff::IMAGE_FILE_MACHINE_ARMNT,
+            (Architecture::Aarch64, None, _) => coff::IMAGE_FILE_MACHINE_ARM64,
+            (Architecture::Aarch64, Some(SubArchitecture::Arm64EC), _) => {
+                coff::IMAGE_FILE_MACHINE_ARM64EC
+            }
+            (Architecture::I386, None, _) => coff::IMAGE_FILE_MACHINE_I386,
+            (Architecture::X86_64, None, _) => coff::IMAGE_FILE_MACHINE_AMD64,
+            (Architecture::PowerPc | Architecture::PowerPc64, None, Endianness::Little) => {
+                coff::IMAGE_FILE_MACHINE_POWERPC
+            }
+            (Architecture::PowerPc | Architecture::PowerPc64, None, Endianness::Big) => {
+                coff::IMAGE_FILE_MACHINE_POWERPCBE
+            }
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented architecture {:?} with sub-architecture {:?}",
+                    self.architecture, self.sub_architecture
+                )));
+            }
+        };
+        Ok(machine)
     }
 
     pub(crate) fn coff_write(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
-        let mut writer = writer::Writer::new(buffer);
+        let mut writer = if self.coff_bigobj && self.sections.len() > 0xfeff {
+            writer::Writer::new_bigobj(buffer)
+        } else {
+            writer::Writer::new(buffer)
+        };
 
         // Add section strings to strtab.
         let mut section_offsets = vec![SectionOffsets::default(); self.sections.len()];
@@ -417,7 +897,7 @@ impl<'a> Object<'a> {
                 ComdatKind::ExactMatch => coff::IMAGE_COMDAT_SELECT_EXACT_MATCH,
                 ComdatKind::Largest => coff::IMAGE_COMDAT_SELECT_LARGEST,
                 ComdatKind::Newest => coff::IMAGE_COMDAT_SELECT_NEWEST,
-                ComdatKind::Unknown => {
+                ComdatKind::Unknown | ComdatKind::Group => {
                     return Err(Error(format!(
                         "unsupported COMDAT symbol `{}` kind {:?}",
                         symbol.name().unwrap_or(""),
@@ -441,7 +921,16 @@ impl<'a> Object<'a> {
         }
 
         // Prepare creation of weak default symbols
-        let weak_symbol_count = self.symbols.iter().filter(|symbol| symbol.weak).count();
+        // Weak externals added by `add_coff_weak_external` use their own default symbol.
+        let generate_weak_default = |index: usize, symbol: &Symbol| {
+            symbol.weak && !self.coff_weak_externals.contains_key(&SymbolId(index))
+        };
+        let weak_symbol_count = self
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(index, symbol)| generate_weak_default(*index, symbol))
+            .count();
         let mut weak_default_names = HashMap::new();
         let mut weak_default_offsets = HashMap::new();
 
@@ -493,7 +982,7 @@ impl<'a> Object<'a> {
                 .symbols
                 .iter()
                 .enumerate()
-                .filter(|(_, symbol)| symbol.weak)
+                .filter(|(index, symbol)| generate_weak_default(*index, symbol))
             {
                 let mut weak_default_name = [b".weak.", symbol.name.as_slice()].concat();
                 if !weak_default_unique_name.is_empty() {
@@ -508,7 +997,7 @@ impl<'a> Object<'a> {
         // Reserve symbol indices and add symbol strings to strtab.
         let mut symbol_offsets = vec![SymbolOffsets::default(); self.symbols.len()];
         for (index, symbol) in self.symbols.iter().enumerate() {
-            if symbol.weak {
+            if generate_weak_default(index, symbol) {
                 // Reserve the weak default symbol
                 let weak_default_name = weak_default_names.get(&index).unwrap_or_else(|| {
                     unreachable!("weak default symbol name should have been created")
@@ -519,63 +1008,66 @@ impl<'a> Object<'a> {
                     SymbolOffsets {
                         name: writer.add_name(weak_default_name.as_slice()),
                         index: writer.reserve_symbol_index(),
-                        aux_count: 0,
+                        ..Default::default()
                     },
                 );
             }
 
             symbol_offsets[index].index = writer.reserve_symbol_index();
-            let mut name = &*symbol.name;
-            match symbol.kind {
-                _ if symbol.weak => {
-                    symbol_offsets[index].aux_count = writer.reserve_aux_weak_external();
-                }
-                SymbolKind::File => {
-                    // Name goes in auxiliary symbol records.
-                    symbol_offsets[index].aux_count = writer.reserve_aux_file_name(&symbol.name);
-                    name = b".file";
+            let aux = self.coff_symbol_aux(index, symbol)?;
+            symbol_offsets[index].aux = aux;
+            symbol_offsets[index].aux_count = match aux {
+                SymbolAux::None => 0,
+                SymbolAux::WeakExternal => writer.reserve_aux_weak_external(),
+                SymbolAux::FileName => writer.reserve_aux_file_name(&symbol.name),
+                SymbolAux::Section => writer.reserve_aux_section(),
+                SymbolAux::Function => {
+                    let aux_count = writer.reserve_aux_function();
+                    // The `.bf` and `.ef` symbols follow the function symbol.
+                    symbol_offsets[index].begin_index = writer.reserve_symbol_index();
+                    writer.reserve_aux_function_begin_end();
+                    writer.reserve_symbol_index();
+                    writer.reserve_aux_function_begin_end();
+                    aux_count
                 }
-                SymbolKind::Section if symbol.section.id().is_some() => {
-                    symbol_offsets[index].aux_count = writer.reserve_aux_section();
-                }
-                _ => {}
+            };
+            // The name of a file symbol goes in auxiliary symbol records.
+            let name = if aux == SymbolAux::FileName {
+                &b".file"[..]
+            } else {
+                &*symbol.name
             };
             symbol_offsets[index].name = writer.add_name(name);
         }
 
+        // Chain the function definitions together, in symbol table order.
+        let mut next_function = vec![0; self.symbols.len()];
+        let mut next_index = 0;
+        for index in (0..self.symbols.len()).rev() {
+            if symbol_offsets[index].begin_index != 0 {
+                next_function[index] = next_index;
+                next_index = symbol_offsets[index].index;
+            }
+        }
+
         // Reserve file ranges.
         writer.reserve_file_header();
-        writer.reserve_section_headers(self.sections.len() as u16);
+        writer.reserve_section_headers_bigobj(self.sections.len() as u32);
+        if self.coff_layout == CoffLayout::SymbolTableFirst {
+            writer.reserve_symtab_strtab();
+        }
         for (index, section) in self.sections.iter().enumerate() {
             section_offsets[index].offset = writer.reserve_section(section.data.len());
             section_offsets[index].reloc_offset =
                 writer.reserve_relocations(section.relocations.len());
         }
-        writer.reserve_symtab_strtab();
+        if self.coff_layout == CoffLayout::DataFirst {
+            writer.reserve_symtab_strtab();
+        }
 
         // Start writing.
         writer.write_file_header(writer::FileHeader {
-            machine: match (self.architecture, self.sub_architecture, self.endian) {
-                (Architecture::Arm, None, _) => coff::IMAGE_FILE_MACHINE_ARMNT,
-                (Architecture::Aarch64, None, _) => coff::IMAGE_FILE_MACHINE_ARM64,
-                (Architecture::Aarch64, Some(SubArchitecture::Arm64EC), _) => {
-                    coff::IMAGE_FILE_MACHINE_ARM64EC
-                }
-                (Architecture::I386, None, _) => coff::IMAGE_FILE_MACHINE_I386,
-                (Architecture::X86_64, None, _) => coff::IMAGE_FILE_MACHINE_AMD64,
-                (Architecture::PowerPc | Architecture::PowerPc64, None, Endianness::Little) => {
-                    coff::IMAGE_FILE_MACHINE_POWERPC
-                }
-                (Architecture::PowerPc | Architecture::PowerPc64, None, Endianness::Big) => {
-                    coff::IMAGE_FILE_MACHINE_POWERPCBE
-                }
-                _ => {
-                    return Err(Error(format!(
-                        "unimplemented architecture {:?} with sub-architecture {:?}",
-                        self.architecture, self.sub_architecture
-                    )));
-                }
-            },
+            machine: self.coff_machine()?,
             time_date_stamp: 0,
             characteristics: match self.flags {
                 FileFlags::Coff { characteristics } => characteristics,
@@ -637,9 +1129,45 @@ impl<'a> Object<'a> {
             });
         }
 
-        // Write section data and relocations.
-        for section in &self.sections {
-            writer.write_section(&section.data);
+        // Write section data, relocations, and symbols.
+        match self.coff_layout {
+            CoffLayout::DataFirst => {
+                self.coff_write_sections(&mut writer, &symbol_offsets)?;
+                self.coff_write_symbols(
+                    &mut writer,
+                    &section_offsets,
+                    &symbol_offsets,
+                    &weak_default_offsets,
+                    &next_function,
+                )?;
+            }
+            CoffLayout::SymbolTableFirst => {
+                self.coff_write_symbols(
+                    &mut writer,
+                    &section_offsets,
+                    &symbol_offsets,
+                    &weak_default_offsets,
+                    &next_function,
+                )?;
+                self.coff_write_sections(&mut writer, &symbol_offsets)?;
+            }
+        }
+
+        debug_assert_eq!(writer.reserved_len(), writer.len());
+
+        Ok(())
+    }
+}
+
+impl<'a> Object<'a> {
+    /// Write the section data and relocations.
+    fn coff_write_sections(
+        &self,
+        writer: &mut writer::Writer<'_>,
+        symbol_offsets: &[SymbolOffsets],
+    ) -> Result<()> {
+        for (index, section) in self.sections.iter().enumerate() {
+            writer.write_section(&self.section_write_data(SectionId(index))?);
 
             if !section.relocations.is_empty() {
                 //debug_assert_eq!(section_offsets[index].reloc_offset, buffer.len());
@@ -658,8 +1186,18 @@ impl<'a> Object<'a> {
                 }
             }
         }
+        Ok(())
+    }
 
-        // Write symbols.
+    /// Write the symbol table and string table.
+    fn coff_write_symbols(
+        &self,
+        writer: &mut writer::Writer<'_>,
+        section_offsets: &[SectionOffsets],
+        symbol_offsets: &[SymbolOffsets],
+        weak_default_offsets: &HashMap<usize, SymbolOffsets>,
+        next_function: &[u32],
+    ) -> Result<()> {
         for (index, symbol) in self.symbols.iter().enumerate() {
             let SymbolFlags::None = symbol.flags else {
                 return Err(Error(format!(
@@ -670,15 +1208,15 @@ impl<'a> Object<'a> {
             };
             let section_number = match symbol.section {
                 // weak symbols are always undefined
-                _ if symbol.weak => coff::IMAGE_SYM_UNDEFINED as u16,
+                _ if symbol.weak => coff::IMAGE_SYM_UNDEFINED,
                 SymbolSection::None => {
                     debug_assert_eq!(symbol.kind, SymbolKind::File);
-                    coff::IMAGE_SYM_DEBUG as u16
+                    coff::IMAGE_SYM_DEBUG
                 }
-                SymbolSection::Undefined => coff::IMAGE_SYM_UNDEFINED as u16,
-                SymbolSection::Absolute => coff::IMAGE_SYM_ABSOLUTE as u16,
-                SymbolSection::Common => coff::IMAGE_SYM_UNDEFINED as u16,
-                SymbolSection::Section(id) => id.0 as u16 + 1,
+                SymbolSection::Undefined => coff::IMAGE_SYM_UNDEFINED,
+                SymbolSection::Absolute => coff::IMAGE_SYM_ABSOLUTE,
+                SymbolSection::Common => coff::IMAGE_SYM_UNDEFINED,
+                SymbolSection::Section(id) => id.0 as i32 + 1,
             };
             let typ = if symbol.kind == SymbolKind::Text {
                 coff::IMAGE_SYM_DTYPE_FUNCTION << coff::IMAGE_SYM_DTYPE_SHIFT
@@ -741,18 +1279,20 @@ impl<'a> Object<'a> {
                 symbol.value as u32
             };
 
+            let weak_external = self.coff_weak_externals.get(&SymbolId(index));
+
             // write the weak default symbol before the weak symbol
-            if symbol.weak {
+            if symbol.weak && weak_external.is_none() {
                 let weak_default_symbol = weak_default_offsets.get(&index).unwrap_or_else(|| {
                     unreachable!("weak symbol should have a weak default offset")
                 });
 
-                writer.write_symbol(writer::Symbol {
+                writer.write_symbol_ex(writer::SymbolEx {
                     name: weak_default_symbol.name,
                     value: symbol.value as u32,
                     section_number: match symbol.section {
-                        SymbolSection::Section(id) => id.0 as u16 + 1,
-                        SymbolSection::Undefined => coff::IMAGE_SYM_ABSOLUTE as u16,
+                        SymbolSection::Section(id) => id.0 as i32 + 1,
+                        SymbolSection::Undefined => coff::IMAGE_SYM_ABSOLUTE,
                         o => {
                             return Err(Error(format!(
                                 "invalid symbol section for weak external `{}` section {o:?}",
@@ -766,7 +1306,7 @@ impl<'a> Object<'a> {
                 });
             }
 
-            writer.write_symbol(writer::Symbol {
+            writer.write_symbol_ex(writer::SymbolEx {
                 name: symbol_offsets[index].name,
                 value,
                 section_number,
@@ -776,23 +1316,32 @@ impl<'a> Object<'a> {
             });
 
             // Write auxiliary symbols.
-            match symbol.kind {
-                _ if symbol.weak => {
-                    let weak_default_offset =
-                        weak_default_offsets.get(&index).unwrap_or_else(|| {
-                            unreachable!("weak symbol should have a weak default offset")
-                        });
-
-                    let weak_default_sym_index = weak_default_offset.index;
+            match symbol_offsets[index].aux {
+                SymbolAux::WeakExternal => {
+                    let (weak_default_sym_index, weak_search_type) = match weak_external {
+                        Some(&(default, search)) => {
+                            (symbol_offsets[default.0].index, search.search_type())
+                        }
+                        None => {
+                            let weak_default_offset =
+                                weak_default_offsets.get(&index).unwrap_or_else(|| {
+                                    unreachable!("weak symbol should have a weak default offset")
+                                });
+                            (
+                                weak_default_offset.index,
+                                coff::IMAGE_WEAK_EXTERN_SEARCH_NOLIBRARY,
+                            )
+                        }
+                    };
                     writer.write_aux_weak_external(writer::AuxSymbolWeak {
                         weak_default_sym_index,
-                        weak_search_type: coff::IMAGE_WEAK_EXTERN_SEARCH_NOLIBRARY,
+                        weak_search_type,
                     });
                 }
-                SymbolKind::File => {
+                SymbolAux::FileName => {
                     writer.write_aux_file_name(&symbol.name, number_of_aux_symbols);
                 }
-                SymbolKind::Section if symbol.section.id().is_some() => {
+                SymbolAux::Section => {
                     debug_assert_eq!(number_of_aux_symbols, 1);
                     let section_index = symbol.section.id().unwrap().0;
                     let section = &self.sections[section_index];
@@ -803,24 +1352,76 @@ impl<'a> Object<'a> {
                         check_sum: if section.is_bss() {
                             0
                         } else {
-                            checksum(section.data())
+                            checksum(&self.section_write_data(SectionId(section_index))?)
                         },
                         number: section_offsets[section_index].associative_section,
                         selection: section_offsets[section_index].selection,
                     });
                 }
-                _ => {
+                SymbolAux::Function => {
+                    debug_assert_eq!(number_of_aux_symbols, 1);
+                    let aux = self.coff_function_aux[&SymbolId(index)];
+                    let next = next_function[index];
+                    // The `.bf` symbols follow their function symbol.
+                    let next_begin = if next != 0 { next + 2 } else { 0 };
+                    writer.write_aux_function(writer::AuxSymbolFunction {
+                        tag_index: symbol_offsets[index].begin_index,
+                        total_size: aux.length,
+                        pointer_to_linenumber: 0,
+                        pointer_to_next_function: next,
+                    });
+                    for (name, value, linenumber, pointer_to_next_function) in [
+                        (b".bf", symbol.value, aux.begin_line, next_begin),
+                        (
+                            b".ef",
+                            symbol.value + u64::from(aux.length),
+                            aux.end_line,
+                            0,
+                        ),
+                    ] {
+                        let name = writer.add_name(name);
+                        writer.write_symbol_ex(writer::SymbolEx {
+                            name,
+                            value: value as u32,
+                            section_number,
+                            typ: 0,
+                            storage_class: coff::IMAGE_SYM_CLASS_FUNCTION,
+                            number_of_aux_symbols: 1,
+                        });
+                        writer.write_aux_function_begin_end(writer::AuxSymbolFunctionBeginEnd {
+                            linenumber,
+                            pointer_to_next_function,
+                        });
+                    }
+                }
+                SymbolAux::None => {
                     debug_assert_eq!(number_of_aux_symbols, 0);
                 }
             }
         }
-
         writer.write_strtab();
-
-        debug_assert_eq!(writer.reserved_len(), writer.len());
-
         Ok(())
     }
+
+    /// Return the kind of auxiliary symbol records to write for a symbol.
+    fn coff_symbol_aux(&self, index: usize, symbol: &Symbol) -> Result<SymbolAux> {
+        let function = self.coff_function_aux.contains_key(&SymbolId(index));
+        Ok(match symbol.kind {
+            _ if symbol.weak => {
+                if function {
+                    return Err(Error(format!(
+                        "weak symbol `{}` cannot have function auxiliary records",
+                        symbol.name().unwrap_or("")
+                    )));
+                }
+                SymbolAux::WeakExternal
+            }
+            SymbolKind::File => SymbolAux::FileName,
+            SymbolKind::Section if symbol.section.id().is_some() => SymbolAux::Section,
+            _ if function => SymbolAux::Function,
+            _ => SymbolAux::None,
+        })
+    }
 }
 
 // JamCRC
@@ -829,3 +1430,20 @@ fn checksum(data: &[u8]) -> u32 {
     hasher.update(data);
     !hasher.finalize()
 }
+
+/// Append a CodeView symbol record, padded to a multiple of 4 bytes.
+fn push_codeview_record(buffer: &mut Vec<u8>, kind: u16, data: &[u8]) -> Result<()> {
+    let len = align(4 + data.len(), 4);
+    // The record length excludes the length field itself.
+    if len - 2 > u16::MAX as usize {
+        return Err(Error(format!(
+            "CodeView record is too large ({} bytes)",
+            data.len()
+        )));
+    }
+    buffer.extend_from_slice(&((len - 2) as u16).to_le_bytes());
+    buffer.extend_from_slice(&kind.to_le_bytes());
+    buffer.extend_from_slice(data);
+    buffer.resize(buffer.len() + len - 4 - data.len(), 0);
+    Ok(())
+}
@@ -3,12 +3,17 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use core::mem;
 
-use crate::endian::{LittleEndian as LE, U16Bytes, U32Bytes, U16, U32};
+use crate::endian::{I32Bytes, LittleEndian as LE, U16Bytes, U32Bytes, U16, U32};
 use crate::pe;
 use crate::write::string::{StringId, StringTable};
 use crate::write::util;
 use crate::write::{Error, Result, WritableBuffer};
 
+/// The maximum number of sections without the big object format.
+///
+/// Larger section numbers would overlap the reserved values such as `IMAGE_SYM_ABSOLUTE`.
+const MAX_SECTIONS: u32 = 0xfeff;
+
 /// A helper for writing COFF files.
 ///
 /// Writing uses a two phase approach. The first phase builds up all of the information
@@ -25,15 +30,22 @@ use crate::write::{Error, Result, WritableBuffer};
 /// The second phase writes everything out in order. Thus the caller must ensure writing
 /// is in the same order that file ranges were reserved. There are debug asserts to assist
 /// with checking this.
+///
+/// Use [`Self::new_bigobj`] to write the big object format, which supports more sections.
 #[allow(missing_debug_implementations)]
 pub struct Writer<'a> {
     buffer: &'a mut dyn WritableBuffer,
     len: usize,
+    bigobj: bool,
 
-    section_num: u16,
+    section_num: u32,
 
     symtab_offset: u32,
     symtab_num: u32,
+    /// The number of symbol table entries that have been written.
+    symtab_written: u32,
+    /// The number of auxiliary symbols still to be written for the last symbol.
+    aux_remaining: u8,
 
     strtab: StringTable<'a>,
     strtab_len: usize,
@@ -47,11 +59,14 @@ impl<'a> Writer<'a> {
         Writer {
             buffer,
             len: 0,
+            bigobj: false,
 
             section_num: 0,
 
             symtab_offset: 0,
             symtab_num: 0,
+            symtab_written: 0,
+            aux_remaining: 0,
 
             strtab: StringTable::default(),
             strtab_len: 0,
@@ -60,6 +75,27 @@ impl<'a> Writer<'a> {
         }
     }
 
+    /// Create a new `Writer` for the big object format.
+    ///
+    /// This format uses [`pe::AnonObjectHeaderBigobj`] for the file header, and
+    /// [`pe::ImageSymbolEx`] for the symbol table, so that section numbers are
+    /// 32 bits instead of 16 bits.
+    pub fn new_bigobj(buffer: &'a mut dyn WritableBuffer) -> Self {
+        Writer {
+            bigobj: true,
+            ..Self::new(buffer)
+        }
+    }
+
+    /// Return the size of a symbol table entry.
+    fn symbol_size(&self) -> usize {
+        if self.bigobj {
+            pe::IMAGE_SIZEOF_SYMBOL_EX
+        } else {
+            pe::IMAGE_SIZEOF_SYMBOL
+        }
+    }
+
     /// Return the current file length that has been reserved.
     pub fn reserved_len(&self) -> usize {
         self.len
@@ -114,7 +150,11 @@ impl<'a> Writer<'a> {
     /// This must be at the start of the file.
     pub fn reserve_file_header(&mut self) {
         debug_assert_eq!(self.len, 0);
-        self.reserve(mem::size_of::<pe::ImageFileHeader>(), 1);
+        if self.bigobj {
+            self.reserve(mem::size_of::<pe::AnonObjectHeaderBigobj>(), 1);
+        } else {
+            self.reserve(mem::size_of::<pe::ImageFileHeader>(), 1);
+        }
     }
 
     /// Write the file header.
@@ -122,18 +162,46 @@ impl<'a> Writer<'a> {
     /// This must be at the start of the file.
     ///
     /// Fields that can be derived from known information are automatically set by this function.
+    ///
+    /// For the big object format, `header.characteristics` is ignored.
     pub fn write_file_header(&mut self, header: FileHeader) -> Result<()> {
         debug_assert_eq!(self.buffer.len(), 0);
 
+        if !self.bigobj && self.section_num > MAX_SECTIONS {
+            return Err(Error(format!(
+                "too many sections {} (max {}) without big object format",
+                self.section_num, MAX_SECTIONS
+            )));
+        }
+
         // Start writing.
         self.buffer
             .reserve(self.len)
             .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
 
         // Write file header.
+        if self.bigobj {
+            let header = pe::AnonObjectHeaderBigobj {
+                sig1: U16::new(LE, pe::IMAGE_FILE_MACHINE_UNKNOWN),
+                sig2: U16::new(LE, 0xffff),
+                version: U16::new(LE, 2),
+                machine: U16::new(LE, header.machine),
+                time_date_stamp: U32::new(LE, header.time_date_stamp),
+                class_id: pe::ANON_OBJECT_HEADER_BIGOBJ_CLASS_ID,
+                size_of_data: U32::default(),
+                flags: U32::default(),
+                meta_data_size: U32::default(),
+                meta_data_offset: U32::default(),
+                number_of_sections: U32::new(LE, self.section_num),
+                pointer_to_symbol_table: U32::new(LE, self.symtab_offset),
+                number_of_symbols: U32::new(LE, self.symtab_num),
+            };
+            self.buffer.write(&header);
+            return Ok(());
+        }
         let header = pe::ImageFileHeader {
             machine: U16::new(LE, header.machine),
-            number_of_sections: U16::new(LE, self.section_num),
+            number_of_sections: U16::new(LE, self.section_num as u16),
             time_date_stamp: U32::new(LE, header.time_date_stamp),
             pointer_to_symbol_table: U32::new(LE, self.symtab_offset),
             number_of_symbols: U32::new(LE, self.symtab_num),
@@ -147,6 +215,14 @@ impl<'a> Writer<'a> {
 
     /// Reserve the range for the section headers.
     pub fn reserve_section_headers(&mut self, section_num: u16) {
+        self.reserve_section_headers_bigobj(section_num.into());
+    }
+
+    /// Reserve the range for the section headers, with a 32-bit section count.
+    ///
+    /// The number of sections must not exceed 65279 unless using the big object format.
+    /// This is checked by [`Self::write_file_header`].
+    pub fn reserve_section_headers_bigobj(&mut self, section_num: u32) {
         debug_assert_eq!(self.section_num, 0);
         self.section_num = section_num;
         self.reserve(
@@ -318,23 +394,66 @@ impl<'a> Writer<'a> {
     }
 
     /// Write a symbol table entry.
+    ///
+    /// The auxiliary symbols for the entry must be written next, and their count
+    /// must match `symbol.number_of_aux_symbols`.
     pub fn write_symbol(&mut self, symbol: Symbol) {
-        let mut coff_symbol = pe::ImageSymbol {
-            name: [0; 8],
-            value: U32Bytes::new(LE, symbol.value),
-            section_number: U16Bytes::new(LE, symbol.section_number),
-            typ: U16Bytes::new(LE, symbol.typ),
+        let section_number = if symbol.section_number > pe::IMAGE_SYM_SECTION_MAX {
+            // Reserved values such as `IMAGE_SYM_ABSOLUTE` are negative.
+            i32::from(symbol.section_number as i16)
+        } else {
+            i32::from(symbol.section_number)
+        };
+        self.write_symbol_ex(SymbolEx {
+            name: symbol.name,
+            value: symbol.value,
+            section_number,
+            typ: symbol.typ,
             storage_class: symbol.storage_class,
             number_of_aux_symbols: symbol.number_of_aux_symbols,
-        };
+        });
+    }
+
+    /// Write a symbol table entry with a 32-bit section number.
+    ///
+    /// Section numbers greater than 65279 are only valid for the big object format.
+    ///
+    /// The auxiliary symbols for the entry must be written next, and their count
+    /// must match `symbol.number_of_aux_symbols`.
+    pub fn write_symbol_ex(&mut self, symbol: SymbolEx) {
+        debug_assert_eq!(self.aux_remaining, 0);
+        self.symtab_written += 1;
+        self.aux_remaining = symbol.number_of_aux_symbols;
+        let mut name = [0; 8];
         match symbol.name {
-            Name::Short(name) => coff_symbol.name = name,
+            Name::Short(short_name) => name = short_name,
             Name::Long(str_id) => {
                 let str_offset = self.strtab.get_offset(str_id);
-                coff_symbol.name[4..8].copy_from_slice(&u32::to_le_bytes(str_offset as u32));
+                name[4..8].copy_from_slice(&u32::to_le_bytes(str_offset as u32));
             }
         }
-        self.buffer.write(&coff_symbol);
+        if self.bigobj {
+            let coff_symbol = pe::ImageSymbolEx {
+                name,
+                value: U32Bytes::new(LE, symbol.value),
+                section_number: I32Bytes::new(LE, symbol.section_number),
+                typ: U16Bytes::new(LE, symbol.typ),
+                storage_class: symbol.storage_class,
+                number_of_aux_symbols: symbol.number_of_aux_symbols,
+            };
+            self.buffer.write(&coff_symbol);
+        } else {
+            debug_assert!(symbol.section_number <= MAX_SECTIONS as i32);
+            let coff_symbol = pe::ImageSymbol {
+                name,
+                value: U32Bytes::new(LE, symbol.value),
+                section_number: U16Bytes::new(LE, symbol.section_number as u16),
+                typ: U16Bytes::new(LE, symbol.typ),
+                storage_class: symbol.storage_class,
+                number_of_aux_symbols: symbol.number_of_aux_symbols,
+            };
+            self.buffer.write(&coff_symbol);
+        }
     }
 
     /// Reserve auxiliary symbols for a file name.
@@ -344,14 +463,16 @@ impl<'a> Writer<'a> {
     /// This must be called before [`Self::reserve_symtab_strtab`].
     pub fn reserve_aux_file_name(&mut self, name: &[u8]) -> u8 {
         debug_assert_eq!(self.symtab_offset, 0);
-        let aux_count = (name.len() + pe::IMAGE_SIZEOF_SYMBOL - 1) / pe::IMAGE_SIZEOF_SYMBOL;
+        let symbol_size = self.symbol_size();
+        let aux_count = (name.len() + symbol_size - 1) / symbol_size;
         self.symtab_num += aux_count as u32;
         aux_count as u8
     }
 
     /// Write auxiliary symbols for a file name.
     pub fn write_aux_file_name(&mut self, name: &[u8], aux_count: u8) {
-        let aux_len = aux_count as usize * pe::IMAGE_SIZEOF_SYMBOL;
+        self.wrote_aux(aux_count);
+        let aux_len = aux_count as usize * self.symbol_size();
         debug_assert!(aux_len >= name.len());
         let old_len = self.buffer.len();
         self.buffer.write_bytes(name);
@@ -371,6 +492,7 @@ impl<'a> Writer<'a> {
 
     /// Write an auxiliary symbol for a section.
     pub fn write_aux_section(&mut self, section: AuxSymbolSection) {
+        self.wrote_aux(1);
         let aux = pe::ImageAuxSymbolSection {
             length: U32Bytes::new(LE, section.length),
             number_of_relocations: if section.number_of_relocations > 0xffff {
@@ -386,6 +508,7 @@ impl<'a> Writer<'a> {
             high_number: U16Bytes::new(LE, (section.number >> 16) as u16),
         };
         self.buffer.write(&aux);
+        self.write_aux_padding();
     }
 
     /// Reserve an auxiliary symbol for a weak external.
@@ -401,6 +524,7 @@ impl<'a> Writer<'a> {
 
     /// Write an auxiliary symbol for a weak external.
     pub fn write_aux_weak_external(&mut self, weak: AuxSymbolWeak) {
+        self.wrote_aux(1);
         let aux = pe::ImageAuxSymbolWeak {
             weak_default_sym_index: U32Bytes::new(LE, weak.weak_default_sym_index),
             weak_search_type: U32Bytes::new(LE, weak.weak_search_type),
@@ -409,6 +533,72 @@ impl<'a> Writer<'a> {
         // write padding for the unused field
         const PAD_LEN: usize = pe::IMAGE_SIZEOF_SYMBOL - mem::size_of::<pe::ImageAuxSymbolWeak>();
         self.buffer.write_bytes(&[0u8; PAD_LEN]);
+        self.write_aux_padding();
+    }
+
+    /// Reserve an auxiliary symbol for a function definition.
+    ///
+    /// Returns the number of auxiliary symbols required.
+    ///
+    /// This must be called before [`Self::reserve_symtab_strtab`].
+    pub fn reserve_aux_function(&mut self) -> u8 {
+        debug_assert_eq!(self.symtab_offset, 0);
+        self.symtab_num += 1;
+        1
+    }
+
+    /// Write an auxiliary symbol for a function definition.
+    pub fn write_aux_function(&mut self, function: AuxSymbolFunction) {
+        self.wrote_aux(1);
+        let aux = pe::ImageAuxSymbolFunction {
+            tag_index: U32Bytes::new(LE, function.tag_index),
+            total_size: U32Bytes::new(LE, function.total_size),
+            pointer_to_linenumber: U32Bytes::new(LE, function.pointer_to_linenumber),
+            pointer_to_next_function: U32Bytes::new(LE, function.pointer_to_next_function),
+            unused: [0; 2],
+        };
+        self.buffer.write(&aux);
+        self.write_aux_padding();
+    }
+
+    /// Reserve an auxiliary symbol for a `.bf` or `.ef` symbol.
+    ///
+    /// Returns the number of auxiliary symbols required.
+    ///
+    /// This must be called before [`Self::reserve_symtab_strtab`].
+    pub fn reserve_aux_function_begin_end(&mut self) -> u8 {
+        debug_assert_eq!(self.symtab_offset, 0);
+        self.symtab_num += 1;
+        1
+    }
+
+    /// Write an auxiliary symbol for a `.bf` or `.ef` symbol.
+    pub fn write_aux_function_begin_end(&mut self, begin_end: AuxSymbolFunctionBeginEnd) {
+        self.wrote_aux(1);
+        let aux = pe::ImageAuxSymbolFunctionBeginEnd {
+            unused1: [0; 4],
+            linenumber: U16Bytes::new(LE, begin_end.linenumber),
+            unused2: [0; 6],
+            pointer_to_next_function: U32Bytes::new(LE, begin_end.pointer_to_next_function),
+            unused3: [0; 2],
+        };
+        self.buffer.write(&aux);
+        self.write_aux_padding();
+    }
+
+    /// Write padding after an auxiliary symbol record for the big object format.
+    fn write_aux_padding(&mut self) {
+        if self.bigobj {
+            const PAD_LEN: usize = pe::IMAGE_SIZEOF_SYMBOL_EX - pe::IMAGE_SIZEOF_SYMBOL;
+            self.buffer.write_bytes(&[0u8; PAD_LEN]);
+        }
+    }
+
+    /// Record that auxiliary symbols have been written for the last symbol.
+    fn wrote_aux(&mut self, count: u8) {
+        debug_assert!(self.aux_remaining >= count);
+        self.aux_remaining = self.aux_remaining.wrapping_sub(count);
+        self.symtab_written += u32::from(count);
     }
 
     /// Return the number of reserved symbol table entries.
@@ -443,7 +633,7 @@ impl<'a> Writer<'a> {
     /// indices or add strings.
     pub fn reserve_symtab_strtab(&mut self) {
         debug_assert_eq!(self.symtab_offset, 0);
-        self.symtab_offset = self.reserve(self.symtab_num as usize * pe::IMAGE_SIZEOF_SYMBOL, 1);
+        self.symtab_offset = self.reserve(self.symtab_num as usize * self.symbol_size(), 1);
 
         debug_assert_eq!(self.strtab_offset, 0);
         // First 4 bytes of strtab are the length.
@@ -453,7 +643,11 @@ impl<'a> Writer<'a> {
     }
 
     /// Write the string table.
+    ///
+    /// This must be called after writing all symbol table entries.
     pub fn write_strtab(&mut self) {
+        debug_assert_eq!(self.aux_remaining, 0);
+        debug_assert_eq!(self.symtab_written, self.symtab_num);
         debug_assert_eq!(self.strtab_offset, self.buffer.len() as u32);
         self.buffer
             .write_bytes(&u32::to_le_bytes(self.strtab_len as u32));
@@ -520,6 +714,20 @@ pub struct Symbol {
     pub number_of_aux_symbols: u8,
 }
 
+/// Native endian version of [`pe::ImageSymbolEx`].
+#[allow(missing_docs)]
+#[derive(Debug, Default, Clone)]
+pub struct SymbolEx {
+    pub name: Name,
+    pub value: u32,
+    /// This may be `pe::IMAGE_SYM_UNDEFINED`, `pe::IMAGE_SYM_ABSOLUTE`,
+    /// `pe::IMAGE_SYM_DEBUG`, or a 1-based section index.
+    pub section_number: i32,
+    pub typ: u16,
+    pub storage_class: u8,
+    pub number_of_aux_symbols: u8,
+}
+
 /// Native endian version of [`pe::ImageAuxSymbolSection`].
 #[allow(missing_docs)]
 #[derive(Debug, Default, Clone)]
@@ -533,6 +741,24 @@ pub struct AuxSymbolSection {
     pub selection: u8,
 }
 
+/// Native endian version of [`pe::ImageAuxSymbolFunction`].
+#[allow(missing_docs)]
+#[derive(Debug, Default, Clone)]
+pub struct AuxSymbolFunction {
+    pub tag_index: u32,
+    pub total_size: u32,
+    pub pointer_to_linenumber: u32,
+    pub pointer_to_next_function: u32,
+}
+
+/// Native endian version of [`pe::ImageAuxSymbolFunctionBeginEnd`].
+#[allow(missing_docs)]
+#[derive(Debug, Default, Clone)]
+pub struct AuxSymbolFunctionBeginEnd {
+    pub linenumber: u16,
+    pub pointer_to_next_function: u32,
+}
+
 /// Native endian version of [`pe::ImageAuxSymbolWeak`].
 #[allow(missing_docs)]
 #[derive(Debug, Default, Clone)]
@@ -4,6 +4,8 @@
 //! This is also used to provide ELF support for [`write::Object`](crate::write::Object).
 
 mod object;
+pub use object::{BbAddrMap, BbAddrMapBlock, CoreNote, CorePrPsInfo, CorePrStatus, NT_OBJECT_LMA};
+pub(crate) use object::{ElfBuildId, ElfChecksum, ElfVersion};
 
 mod writer;
 pub use writer::*;
@@ -1,10 +1,135 @@
+use alloc::borrow::Cow;
 use alloc::vec::Vec;
+use core::ops::Range;
+use core::{cmp, mem};
 
+use crate::endian::{U16Bytes, U32Bytes, U64Bytes};
 use crate::write::elf::writer::*;
 use crate::write::string::StringId;
 use crate::write::*;
 use crate::{elf, pod};
 
+/// The owner name of the section LMA note.
+const LMA_NOTE_NAME: &[u8] = b"object\0";
+
+/// The note type of the section LMA note added by [`Object::set_section_lma`].
+pub const NT_OBJECT_LMA: u32 = 1;
+
+/// The version of the `SHT_LLVM_BB_ADDR_MAP` encoding written by [`Object::add_elf_bb_addr_map`].
+const BB_ADDR_MAP_VERSION: u8 = 2;
+
+/// A builder for the basic block address map of an `SHT_LLVM_BB_ADDR_MAP` section.
+///
+/// See [`Object::add_elf_bb_addr_map`].
+#[derive(Debug, Default, Clone)]
+pub struct BbAddrMap {
+    functions: Vec<(SymbolId, Vec<BbAddrMapBlock>)>,
+}
+
+impl BbAddrMap {
+    /// Create an empty basic block address map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the basic blocks for a function.
+    ///
+    /// `symbol` is the function symbol. The blocks must be sorted by offset
+    /// and must not overlap. Each block is given an ID equal to its index in
+    /// `blocks`.
+    pub fn add_function(&mut self, symbol: SymbolId, blocks: Vec<BbAddrMapBlock>) {
+        self.functions.push((symbol, blocks));
+    }
+}
+
+/// A basic block in a [`BbAddrMap`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BbAddrMapBlock {
+    /// The offset of the block from the start of the function.
+    pub offset: u64,
+    /// The size of the block.
+    pub size: u64,
+    /// The block metadata, such as whether the block ends with a return.
+    pub metadata: u32,
+}
+
+/// A note in the `PT_NOTE` segment of an ELF core file.
+///
+/// See [`Object::add_core_note`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum CoreNote {
+    /// An `NT_PRSTATUS` note containing the status and registers of a thread.
+    PrStatus(CorePrStatus),
+    /// An `NT_PRPSINFO` note containing information about the process.
+    PrPsInfo(CorePrPsInfo),
+    /// A note with an arbitrary owner, type, and descriptor, such as `NT_AUXV`.
+    Other {
+        /// The note owner, not including the null terminator.
+        name: Vec<u8>,
+        /// The note type.
+        n_type: u32,
+        /// The note descriptor.
+        desc: Vec<u8>,
+    },
+}
+
+/// The contents of an `NT_PRSTATUS` note.
+///
+/// The CPU times and `pr_fpvalid` are written as zero.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CorePrStatus {
+    /// The signal that caused the thread to stop, which is written to both
+    /// `pr_info.si_signo` and `pr_cursig`.
+    pub signal: u16,
+    /// The set of pending signals.
+    pub sigpend: u64,
+    /// The set of held signals.
+    pub sighold: u64,
+    /// The thread ID.
+    pub pid: u32,
+    /// The parent process ID.
+    pub ppid: u32,
+    /// The process group ID.
+    pub pgrp: u32,
+    /// The session ID.
+    pub sid: u32,
+    /// The general purpose registers, in the order of the architecture's
+    /// `elf_gregset_t`, such as `struct user_regs_struct` on x86-64.
+    pub registers: Vec<u64>,
+}
+
+/// The contents of an `NT_PRPSINFO` note.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CorePrPsInfo {
+    /// The numeric process state.
+    pub state: u8,
+    /// The character for the process state, such as `b'R'`.
+    pub sname: u8,
+    /// Whether the process is a zombie.
+    pub zomb: u8,
+    /// The nice value.
+    pub nice: i8,
+    /// The process flags.
+    pub flag: u64,
+    /// The user ID.
+    pub uid: u32,
+    /// The group ID.
+    pub gid: u32,
+    /// The process ID.
+    pub pid: u32,
+    /// The parent process ID.
+    pub ppid: u32,
+    /// The process group ID.
+    pub pgrp: u32,
+    /// The session ID.
+    pub sid: u32,
+    /// The file name of the executable. At most 16 bytes.
+    pub fname: Vec<u8>,
+    /// The initial part of the argument list. At most 80 bytes.
+    pub psargs: Vec<u8>,
+}
+
 #[derive(Clone, Copy)]
 struct ComdatOffsets {
     offset: usize,
@@ -26,40 +151,1547 @@ struct SymbolOffsets {
     str_id: Option<StringId>,
 }
 
-// Public methods.
-impl<'a> Object<'a> {
-    /// Add a property with a u32 value to the ELF ".note.gnu.property" section.
+// Public methods.
+impl<'a> Object<'a> {
+    /// Add a property with a u32 value to the ELF ".note.gnu.property" section.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_elf_gnu_property_u32(&mut self, property: u32, value: u32) {
+        if self.format != BinaryFormat::Elf {
+            return;
+        }
+
+        let align = if self.elf_is_64() { 8 } else { 4 };
+        let mut data = Vec::with_capacity(32);
+        let n_name = b"GNU\0";
+        data.extend_from_slice(pod::bytes_of(&elf::NoteHeader32 {
+            n_namesz: U32::new(self.endian, n_name.len() as u32),
+            n_descsz: U32::new(self.endian, util::align(3 * 4, align) as u32),
+            n_type: U32::new(self.endian, elf::NT_GNU_PROPERTY_TYPE_0),
+        }));
+        data.extend_from_slice(n_name);
+        // This happens to already be aligned correctly.
+        debug_assert_eq!(util::align(data.len(), align), data.len());
+        data.extend_from_slice(pod::bytes_of(&U32::new(self.endian, property)));
+        // Value size
+        data.extend_from_slice(pod::bytes_of(&U32::new(self.endian, 4)));
+        data.extend_from_slice(pod::bytes_of(&U32::new(self.endian, value)));
+        util::write_align(&mut data, align);
+
+        let section = self.section_id(StandardSection::GnuProperty);
+        self.append_section_data(section, &data, align as u64);
+    }
+
+    /// Add the AArch64 feature property to the ELF ".note.gnu.property" section.
+    ///
+    /// This adds a `GNU_PROPERTY_AARCH64_FEATURE_1_AND` property with the
+    /// `GNU_PROPERTY_AARCH64_FEATURE_1_BTI` bit set if `bti` is true, and the
+    /// `GNU_PROPERTY_AARCH64_FEATURE_1_PAC` bit set if `pac` is true. The linker
+    /// combines this property using a bitwise AND, so a feature is only enabled in
+    /// the output if every input object has the property with that bit set.
+    ///
+    /// This must be called at most once for an object.
+    ///
+    /// Returns an error if the format is not ELF, if the architecture is not AArch64,
+    /// or if the property has already been added.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_aarch64_features(&mut self, bti: bool, pac: bool) -> Result<()> {
+        if self.format != BinaryFormat::Elf
+            || !matches!(
+                self.architecture,
+                Architecture::Aarch64 | Architecture::Aarch64_Ilp32
+            )
+        {
+            return Err(Error(format!(
+                "unsupported AArch64 features for format {:?} architecture {:?}",
+                self.format, self.architecture
+            )));
+        }
+        if self.elf_has_gnu_property(elf::GNU_PROPERTY_AARCH64_FEATURE_1_AND) {
+            return Err(Error(String::from(
+                "AArch64 features have already been set",
+            )));
+        }
+        let mut features = 0;
+        if bti {
+            features |= elf::GNU_PROPERTY_AARCH64_FEATURE_1_BTI;
+        }
+        if pac {
+            features |= elf::GNU_PROPERTY_AARCH64_FEATURE_1_PAC;
+        }
+        self.add_elf_gnu_property_u32(elf::GNU_PROPERTY_AARCH64_FEATURE_1_AND, features);
+        Ok(())
+    }
+
+    /// Return the `SymbolId` of the `_GLOBAL_OFFSET_TABLE_` symbol.
+    ///
+    /// This is the GOT base used by relocations such as `R_386_GOTPC`, which
+    /// are generated for [`RelocationKind::GotBaseRelative`]. The linker defines
+    /// the symbol, so it is added as an undefined global symbol if it doesn't
+    /// already exist.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn elf_got_symbol(&mut self) -> SymbolId {
+        let name = &b"_GLOBAL_OFFSET_TABLE_"[..];
+        if let Some(symbol_id) = self.symbol_id(name) {
+            return symbol_id;
+        }
+        self.add_symbol(Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        })
+    }
+
+    /// Set the `EI_OSABI` and `EI_ABIVERSION` fields of the ELF file header.
+    ///
+    /// This replaces the values in the [`FileFlags::Elf`] set by [`Self::flags`],
+    /// and preserves any `e_flags`.
+    ///
+    /// If the object contains an `STT_GNU_IFUNC` symbol and the OS ABI is
+    /// `ELFOSABI_NONE`, then `ELFOSABI_GNU` is used instead when writing. Other OS ABI
+    /// values that do not support `STT_GNU_IFUNC` are an error when writing.
+    ///
+    /// Returns an error if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_elf_osabi(&mut self, osabi: u8, abiversion: u8) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported OS ABI for format {:?}",
+                self.format
+            )));
+        }
+        let e_flags = match self.flags {
+            FileFlags::Elf { e_flags, .. } => e_flags,
+            _ => 0,
+        };
+        self.flags = FileFlags::Elf {
+            os_abi: osabi,
+            abi_version: abiversion,
+            e_flags,
+        };
+        Ok(())
+    }
+
+    /// Specify the alignment of notes added by [`Self::add_elf_note`].
+    ///
+    /// The gABI specifies 8 byte alignment for ELF64 notes, and this is expected
+    /// by some tools such as those on Solaris and illumos. However, GNU tools use
+    /// 4 byte alignment for most notes on all ELF classes, so this is the default.
+    ///
+    /// Returns an error if `align` is not 4 or 8.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_elf_note_alignment(&mut self, align: u64) -> Result<()> {
+        if align != 4 && align != 8 {
+            return Err(Error(format!("invalid ELF note alignment {}", align)));
+        }
+        self.elf_note_align = align;
+        Ok(())
+    }
+
+    /// Specify whether relative relocations are written to `SHT_RELR` sections.
+    ///
+    /// `SHT_RELR` sections are only read by dynamic loaders, so they are only
+    /// supported for static-PIE executables (see [`Self::set_elf_static_pie`]).
+    /// Writing a relocatable file or core file returns an error if this is enabled.
+    ///
+    /// For static-PIE executables, relative relocations at addresses that are
+    /// aligned to the address size are written to `.relr.dyn`, and the remaining
+    /// relative relocations are written to `.rela.dyn` (or `.rel.dyn`).
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_elf_use_relr(&mut self, use_relr: bool) {
+        self.elf_use_relr = use_relr;
+    }
+
+    /// Specify which symbols have their size inferred when writing.
+    ///
+    /// Some tools, such as profilers, need the size of function symbols to
+    /// attribute addresses to functions. If enabled, each function symbol that has
+    /// a size of zero is given a size that extends to the next function symbol in the
+    /// same section, or to the end of the section. Labels and data symbols are not
+    /// considered to be the start of the next function. Data symbols are similarly
+    /// extended to the next data symbol if requested.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_elf_symbol_size_inference(&mut self, inference: SymbolSizeInference) {
+        self.elf_size_inference = inference;
+    }
+
+    /// Append an ELF note to a section.
+    ///
+    /// `name` is the note owner, such as `b"GNU"`, and must not include the
+    /// null terminator. The name and descriptor are padded to the alignment
+    /// specified by [`Self::set_elf_note_alignment`], and the section alignment
+    /// is increased to match. Returns the section offset of the note.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_elf_note(
+        &mut self,
+        section: SectionId,
+        name: &[u8],
+        n_type: u32,
+        desc: &[u8],
+    ) -> u64 {
+        let align = self.elf_note_align as usize;
+        let mut data = Vec::with_capacity(12 + name.len() + desc.len() + 2 * align);
+        data.extend_from_slice(pod::bytes_of(&elf::NoteHeader32 {
+            n_namesz: U32::new(self.endian, name.len() as u32 + 1),
+            n_descsz: U32::new(self.endian, desc.len() as u32),
+            n_type: U32::new(self.endian, n_type),
+        }));
+        data.extend_from_slice(name);
+        data.push(0);
+        util::write_align(&mut data, align);
+        data.extend_from_slice(desc);
+        util::write_align(&mut data, align);
+        self.append_section_data(section, &data, align as u64)
+    }
+
+    /// Add a `.llvm_bb_addr_map` section containing a basic block address map.
+    ///
+    /// The section has a type of `SHT_LLVM_BB_ADDR_MAP`, and uses version 2 of the
+    /// encoding with no optional features. Each function entry contains an absolute
+    /// relocation for the function address, followed by the ULEB128 encoded
+    /// number of blocks. Each block is encoded as its ID, the offset from the end of
+    /// the previous block, its size, and its metadata.
+    ///
+    /// The section does not have the `SHF_LINK_ORDER` flag, so it is not removed by
+    /// the linker if the functions are garbage collected.
+    ///
+    /// Returns an error if the format is not ELF, or if the blocks of a function
+    /// are not sorted or overlap.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_elf_bb_addr_map(&mut self, map: &BbAddrMap) -> Result<SectionId> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported basic block address map for format {:?}",
+                self.format
+            )));
+        }
+        let address_size = match self.architecture.address_size() {
+            Some(address_size) => address_size.bytes(),
+            None => {
+                return Err(Error(format!(
+                    "unknown address size for architecture {:?}",
+                    self.architecture
+                )))
+            }
+        };
+        let mut data = Vec::new();
+        let mut relocations = Vec::with_capacity(map.functions.len());
+        for (symbol, blocks) in &map.functions {
+            data.push(BB_ADDR_MAP_VERSION);
+            // Feature flags.
+            data.push(0);
+            relocations.push(Relocation {
+                offset: data.len() as u64,
+                symbol: *symbol,
+                addend: 0,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: address_size * 8,
+                },
+            });
+            data.resize(data.len() + usize::from(address_size), 0);
+            util::write_uleb128(&mut data, blocks.len() as u64);
+            let mut end = 0;
+            for (id, block) in blocks.iter().enumerate() {
+                if block.offset < end {
+                    return Err(Error(format!(
+                        "invalid basic block offset {:#x} for function `{}`",
+                        block.offset,
+                        self.symbol(*symbol).name().unwrap_or("")
+                    )));
+                }
+                util::write_uleb128(&mut data, id as u64);
+                util::write_uleb128(&mut data, block.offset - end);
+                util::write_uleb128(&mut data, block.size);
+                util::write_uleb128(&mut data, block.metadata.into());
+                end = block.offset + block.size;
+            }
+        }
+        let section = self.add_section(
+            Vec::new(),
+            b".llvm_bb_addr_map".to_vec(),
+            SectionKind::Elf(elf::SHT_LLVM_BB_ADDR_MAP),
+        );
+        self.set_section_data(section, data, 1);
+        self.add_relocations(section, relocations)?;
+        Ok(section)
+    }
+
+    /// Set the compression algorithm for a section.
+    ///
+    /// The section data will be compressed when the object is written, and
+    /// the `SHF_COMPRESSED` flag will be set. This is intended for non-allocated
+    /// sections, such as DWARF sections. `CompressionFormat::None` disables compression.
+    ///
+    /// Returns an error if the format is not ELF, if the section is allocated or
+    /// contains uninitialized data, or if the `compression` feature is not enabled.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_section_compression(
+        &mut self,
+        section: SectionId,
+        compression: CompressionFormat,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported compressed section for format {:?}",
+                self.format
+            )));
+        }
+        if compression == CompressionFormat::None {
+            self.elf_compression.remove(&section);
+            return Ok(());
+        }
+        if compression != CompressionFormat::Zlib && compression != CompressionFormat::Zstandard {
+            return Err(Error(format!(
+                "unsupported section compression {:?}",
+                compression
+            )));
+        }
+        if cfg!(not(feature = "compression")) {
+            return Err(Error(format!(
+                "unsupported section compression {:?} without the `compression` feature",
+                compression
+            )));
+        }
+        self.elf_check_compression(self.section(section))?;
+        self.elf_compression.insert(section, compression);
+        Ok(())
+    }
+
+    fn elf_check_compression(&self, section: &Section<'_>) -> Result<()> {
+        let sh_flags = match self.section_flags(section) {
+            SectionFlags::Elf { sh_flags } => sh_flags,
+            _ => 0,
+        };
+        if section.is_bss() || sh_flags & u64::from(elf::SHF_ALLOC | elf::SHF_COMPRESSED) != 0 {
+            return Err(Error(format!(
+                "unsupported compression for section `{}`",
+                section.name().unwrap_or("")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Return the compressed data for a section, including the compression header,
+    /// and the alignment of the compression header.
+    fn elf_compress_section(
+        &self,
+        section: &Section<'_>,
+        data: &[u8],
+        compression: CompressionFormat,
+    ) -> Result<(Vec<u8>, u64)> {
+        self.elf_check_compression(section)?;
+        let ch_type = match compression {
+            CompressionFormat::Zlib => elf::ELFCOMPRESS_ZLIB,
+            CompressionFormat::Zstandard => elf::ELFCOMPRESS_ZSTD,
+            _ => {
+                return Err(Error(format!(
+                    "unsupported section compression {:?}",
+                    compression
+                )))
+            }
+        };
+        let (mut compressed, header_align) =
+            self.elf_compression_header(ch_type, section.size, section.align)?;
+        compress(compression, data, &mut compressed)?;
+        Ok((compressed, header_align))
+    }
+
+    /// Return the compression header, and its alignment.
+    ///
+    /// Returns an error if `size` or `align` do not fit in a 32-bit header.
+    fn elf_compression_header(
+        &self,
+        ch_type: u32,
+        size: u64,
+        align: u64,
+    ) -> Result<(Vec<u8>, u64)> {
+        let endian = self.endian;
+        Ok(if self.elf_is_64() {
+            let header = elf::CompressionHeader64 {
+                ch_type: U32Bytes::new(endian, ch_type),
+                ch_reserved: U32Bytes::new(endian, 0),
+                ch_size: U64Bytes::new(endian, size),
+                ch_addralign: U64Bytes::new(endian, align),
+            };
+            (pod::bytes_of(&header).to_vec(), 8)
+        } else {
+            if size > u64::from(u32::MAX) || align > u64::from(u32::MAX) {
+                return Err(Error(format!(
+                    "compressed section size {:#x} or alignment {:#x} is too large",
+                    size, align
+                )));
+            }
+            let header = elf::CompressionHeader32 {
+                ch_type: U32Bytes::new(endian, ch_type),
+                ch_size: U32Bytes::new(endian, size as u32),
+                ch_addralign: U32Bytes::new(endian, align as u32),
+            };
+            (pod::bytes_of(&header).to_vec(), 4)
+        })
+    }
+
+    /// Append a function pointer to the `.preinit_array` section.
+    ///
+    /// The dynamic linker calls the functions in this section before those in
+    /// `.init_array`. It is only used for executables.
+    ///
+    /// The section is created if it does not already exist. The entry is an
+    /// address sized field with a relocation for `func`. Returns the section
+    /// offset of the entry.
+    ///
+    /// Returns an error if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_preinit_array_entry(&mut self, func: SymbolId) -> Result<u64> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported preinit array for format {:?}",
+                self.format
+            )));
+        }
+        let align = self
+            .architecture
+            .address_size()
+            .map_or(1, |size| size.bytes().into());
+        let section = self.section_id(StandardSection::PreInitArray);
+        let mut entry = self.append_section_struct(section, align);
+        entry.field_ptr(func)?;
+        Ok(entry.finish())
+    }
+
+    /// Add a `.note.gnu.build-id` section containing a GNU build ID.
+    ///
+    /// See [`BuildIdStyle`] for the available build IDs. Build IDs that are
+    /// derived from the contents are computed when the object is written.
+    ///
+    /// Returns an error if the format is not ELF, if a build ID has already been
+    /// added, or if the length of an explicit build ID is invalid for its style.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_build_id(&mut self, style: BuildIdStyle) -> Result<SectionId> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported build ID for format {:?}",
+                self.format
+            )));
+        }
+        if self.elf_build_id.is_some() {
+            return Err(Error(String::from("build ID has already been added")));
+        }
+        let desc = match &style {
+            BuildIdStyle::Uuid => vec![0; 16],
+            BuildIdStyle::FromContents => vec![0; 20],
+            BuildIdStyle::Sha1(id) if id.len() == 20 => id.clone(),
+            BuildIdStyle::Md5(id) | BuildIdStyle::ExplicitUuid(id) if id.len() == 16 => id.clone(),
+            BuildIdStyle::Sha1(id) | BuildIdStyle::Md5(id) | BuildIdStyle::ExplicitUuid(id) => {
+                return Err(Error(format!(
+                    "invalid build ID length {} for {:?}",
+                    id.len(),
+                    style
+                )));
+            }
+        };
+        let section = self.add_section(
+            Vec::new(),
+            b".note.gnu.build-id".to_vec(),
+            SectionKind::Note,
+        );
+        self.section_mut(section).flags = SectionFlags::Elf {
+            sh_flags: u64::from(elf::SHF_ALLOC),
+        };
+        let offset = self.add_elf_note(section, b"GNU", elf::NT_GNU_BUILD_ID, &desc);
+        // The descriptor follows the note header and name, padded to the note alignment.
+        let desc_offset = offset as usize
+            + util::align(
+                mem::size_of::<elf::NoteHeader32<Endianness>>() + 4,
+                self.elf_note_align as usize,
+            );
+        self.elf_build_id = Some(ElfBuildId {
+            section,
+            desc_offset,
+            style,
+        });
+        Ok(section)
+    }
+
+    /// Return the conventional path of the debug object for the build ID.
+    ///
+    /// The path is relative to a debug directory such as `/usr/lib/debug`, and has the form
+    /// `.build-id/ab/cdef....debug`, where the first byte of the build ID is the directory
+    /// name and the remaining bytes are the file name, both in lowercase hex.
+    ///
+    /// Build IDs that are derived from the contents are computed from the current contents.
+    ///
+    /// Returns `None` if a build ID has not been added.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn build_id_debug_path(&self) -> Option<String> {
+        let build_id = self.elf_build_id.as_ref()?;
+        let hash;
+        let uuid;
+        let desc = match &build_id.style {
+            BuildIdStyle::FromContents => {
+                hash = self.content_hash();
+                &hash[..]
+            }
+            BuildIdStyle::Uuid => {
+                uuid = uuid_from_hash(&self.content_hash());
+                &uuid[..]
+            }
+            BuildIdStyle::Sha1(id) | BuildIdStyle::Md5(id) | BuildIdStyle::ExplicitUuid(id) => {
+                &id[..]
+            }
+        };
+        let (first, rest) = desc.split_first()?;
+        let mut path = format!(".build-id/{:02x}/", first);
+        for byte in rest {
+            path.push_str(&format!("{:02x}", byte));
+        }
+        path.push_str(".debug");
+        Some(path)
+    }
+
+    /// Write a stripped object and a separate object containing its debugging information.
+    ///
+    /// Returns the stripped object followed by the debug object.
+    ///
+    /// The debug sections are removed from the stripped object, along with any
+    /// symbols defined in them, and a `.gnu_debuglink` section is added that
+    /// contains the file name of the debug object and the CRC-32 of the written
+    /// debug object. The file name is the build ID in lowercase hex followed by
+    /// `.debug`, or the [content hash](Self::content_hash) if there is no build ID.
+    /// Use [`Self::emit_split_debug_with_name`] to specify the file name.
+    ///
+    /// The debug object contains the debug sections and the note sections. The
+    /// other sections are retained with the same flags and size, but without their
+    /// data or relocations, so that the debugging information can still refer to
+    /// them. A build ID that is derived from the contents is computed from this
+    /// object, so that both objects have the same build ID.
+    ///
+    /// Returns an error if the format is not ELF, or if a non-debug section refers
+    /// to a symbol in a debug section.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn emit_split_debug(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.elf_split_debug(None)
+    }
+
+    /// Write a stripped object and a separate object containing its debugging information,
+    /// using the given file name for the `.gnu_debuglink` section.
+    ///
+    /// This is the same as [`Self::emit_split_debug`], except that the file name
+    /// of the debug object is `debug_file_name`.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn emit_split_debug_with_name(&self, debug_file_name: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.elf_split_debug(Some(debug_file_name))
+    }
+
+    fn elf_split_debug(&self, debug_file_name: Option<&[u8]>) -> Result<(Vec<u8>, Vec<u8>)> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported split debug for format {:?}",
+                self.format
+            )));
+        }
+        let mut object = self.clone();
+        object.elf_freeze_build_id();
+        let is_debug: Vec<bool> = object
+            .sections
+            .iter()
+            .map(|section| matches!(section.kind, SectionKind::Debug | SectionKind::DebugString))
+            .collect();
+
+        let mut debug = object.clone();
+        let mut emptied = vec![false; debug.sections.len()];
+        for index in 0..debug.sections.len() {
+            let section = &debug.sections[index];
+            if is_debug[index] || section.kind == SectionKind::Note || section.is_bss() {
+                continue;
+            }
+            let flags = debug.section_flags(section);
+            let section = &mut debug.sections[index];
+            section.flags = flags;
+            section.kind = if section.kind == SectionKind::Tls {
+                SectionKind::UninitializedTls
+            } else {
+                SectionKind::UninitializedData
+            };
+            section.data = Cow::Borrowed(&[]);
+            section.relocations.clear();
+            section.relocation_content.clear();
+            emptied[index] = true;
+            let id = SectionId(index);
+            debug.elf_compression.remove(&id);
+            if let Some(sh_type) = debug.elf_section_type.get_mut(&id) {
+                *sh_type = elf::SHT_NOBITS;
+            }
+        }
+        debug.fixups.retain(|fixup| !emptied[fixup.section.0]);
+        debug
+            .symbol_end_relocations
+            .retain(|&(section, _), _| !emptied[section.0]);
+        let debug_data = debug.write()?;
+
+        let mut debuglink = match debug_file_name {
+            Some(name) => name.to_vec(),
+            None => {
+                // Content derived build IDs have been replaced by explicit build IDs.
+                let id = match object.elf_build_id.as_ref().map(|b| &b.style) {
+                    Some(
+                        BuildIdStyle::Sha1(id)
+                        | BuildIdStyle::Md5(id)
+                        | BuildIdStyle::ExplicitUuid(id),
+                    ) => id.clone(),
+                    _ => object.content_hash().to_vec(),
+                };
+                let mut name = String::new();
+                for byte in id {
+                    name.push_str(&format!("{:02x}", byte));
+                }
+                name.push_str(".debug");
+                name.into_bytes()
+            }
+        };
+        object.remove_sections(&is_debug)?;
+        debuglink.push(0);
+        util::write_align(&mut debuglink, 4);
+        let crc = crc32fast::hash(&debug_data);
+        debuglink.extend_from_slice(pod::bytes_of(&U32::new(self.endian, crc)));
+        let section =
+            object.add_section(Vec::new(), b".gnu_debuglink".to_vec(), SectionKind::Other);
+        object.set_section_data(section, debuglink, 4);
+        Ok((object.write()?, debug_data))
+    }
+
+    /// Replace a build ID that is derived from the contents with an explicit build ID.
+    fn elf_freeze_build_id(&mut self) {
+        let Some(build_id) = &self.elf_build_id else {
+            return;
+        };
+        let hash = self.content_hash();
+        let (desc, style): (Vec<u8>, fn(Vec<u8>) -> BuildIdStyle) = match build_id.style {
+            BuildIdStyle::FromContents => (hash.to_vec(), BuildIdStyle::Sha1),
+            BuildIdStyle::Uuid => (uuid_from_hash(&hash).to_vec(), BuildIdStyle::ExplicitUuid),
+            _ => return,
+        };
+        let (section, desc_offset) = (build_id.section, build_id.desc_offset);
+        self.sections[section.0].data.to_mut()[desc_offset..][..desc.len()].copy_from_slice(&desc);
+        self.elf_build_id.as_mut().unwrap().style = style(desc);
+    }
+
+    /// Add a section containing a checksum of the file contents.
+    ///
+    /// The section is named `name` and contains a 32-bit checksum that uses the
+    /// endianness of the object. The checksum is computed after the rest of the file
+    /// has been laid out and written, including any build ID.
+    ///
+    /// `range` is the range of file offsets to compute the checksum over. If `range`
+    /// is `None`, then the checksum is computed over the whole file. In both cases,
+    /// the checksum field itself is excluded from the input.
+    ///
+    /// Writing returns an error if `range` extends past the end of the file. Since
+    /// the checksum must be patched after the file is written, the file is written
+    /// to a temporary buffer first.
+    ///
+    /// Returns an error if the format is not ELF, or if a checksum section has
+    /// already been added.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_checksum_section(
+        &mut self,
+        name: Vec<u8>,
+        kind: ChecksumKind,
+        range: Option<Range<u64>>,
+    ) -> Result<SectionId> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported checksum section for format {:?}",
+                self.format
+            )));
+        }
+        if self.elf_checksum.is_some() {
+            return Err(Error(String::from(
+                "checksum section has already been added",
+            )));
+        }
+        let section = self.add_section(Vec::new(), name, SectionKind::ReadOnlyData);
+        self.set_section_data(section, vec![0; 4], 4);
+        self.elf_checksum = Some(ElfChecksum {
+            section,
+            kind,
+            range,
+        });
+        Ok(section)
+    }
+
+    /// Add a symbol version definition.
+    ///
+    /// The version is written to the `.gnu.version_d` section. Use
+    /// [`Self::set_symbol_version`] to attach the version to defined symbols.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_version_definition(&mut self, version: &[u8]) -> VersionId {
+        let id = VersionId(self.elf_versions.len());
+        self.elf_versions.push(ElfVersion {
+            name: version.to_vec(),
+            file: None,
+        });
+        id
+    }
+
+    /// Add a symbol version requirement for a version defined by a shared object.
+    ///
+    /// `file` is the name of the shared object, such as `b"libc.so.6"`, and `version`
+    /// is the version name, such as `b"GLIBC_2.2.5"`. The requirement is written to
+    /// the `.gnu.version_r` section. Use [`Self::set_symbol_version`] to attach the
+    /// version to undefined symbols.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_version_need(&mut self, file: &[u8], version: &[u8]) -> VersionId {
+        let id = VersionId(self.elf_versions.len());
+        self.elf_versions.push(ElfVersion {
+            name: version.to_vec(),
+            file: Some(file.to_vec()),
+        });
+        id
+    }
+
+    /// Attach a version to a symbol.
+    ///
+    /// The version index of each symbol is written to the `.gnu.version` section,
+    /// which is linked to `.symtab`. The version names in `.gnu.version_d` and
+    /// `.gnu.version_r` are stored in `.strtab`.
+    ///
+    /// The version is also recorded using the GNU assembler convention of appending
+    /// it to the symbol name: `name@VERSION` for a hidden version, and `name@@VERSION`
+    /// for the default version.
+    ///
+    /// Returns an error if the format is not ELF, if the version is a requirement and
+    /// the symbol is defined, or if the version is a definition and the symbol is undefined.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_symbol_version(
+        &mut self,
+        symbol_id: SymbolId,
+        version: VersionId,
+        hidden: bool,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported symbol version for format {:?}",
+                self.format
+            )));
+        }
+        let symbol = self.symbol(symbol_id);
+        let need = self.elf_versions[version.0].file.is_some();
+        if symbol.kind == SymbolKind::Section
+            || symbol.kind == SymbolKind::File
+            || symbol.is_local()
+            || need != symbol.is_undefined()
+        {
+            return Err(Error(format!(
+                "invalid version `{}` for symbol `{}`",
+                String::from_utf8_lossy(&self.elf_versions[version.0].name),
+                symbol.name().unwrap_or("")
+            )));
+        }
+        self.elf_symbol_versions
+            .insert(symbol_id, (version, hidden));
+        Ok(())
+    }
+
+    /// Set the ELF section type for a section.
+    ///
+    /// By default, the `sh_type` field in the section header is derived from the
+    /// section kind. This overrides that type, while still using the section kind
+    /// for the default section flags. For example, an `SHT_X86_64_UNWIND` section
+    /// can use `SectionKind::ReadOnlyData` to be allocated.
+    ///
+    /// Returns an error if the format is not ELF, or if `sh_type` is `SHT_NOBITS`
+    /// and the section does not contain uninitialized data, or vice versa.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_section_type(&mut self, section: SectionId, sh_type: u32) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported section type for format {:?}",
+                self.format
+            )));
+        }
+        let section_ref = self.section(section);
+        if section_ref.is_bss() != (sh_type == elf::SHT_NOBITS) {
+            return Err(Error(format!(
+                "invalid type {:#x} for section `{}` kind {:?}",
+                sh_type,
+                section_ref.name().unwrap_or(""),
+                section_ref.kind
+            )));
+        }
+        self.elf_section_type.insert(section, sh_type);
+        Ok(())
+    }
+
+    /// Set the size of the entries in a section of fixed-size mergeable constants.
+    ///
+    /// The section is marked with `SHF_MERGE` and its `sh_entsize` is set to `entsize`,
+    /// so that the linker can deduplicate identical entries. This can also be used to
+    /// change the entry size of a string section, such as for UTF-16 strings.
+    /// String sections otherwise have an entry size of 1.
+    ///
+    /// Returns an error if the format is not ELF or if `entsize` is zero.
+    /// Writing the object returns an error if the section size is not a multiple
+    /// of `entsize`.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_section_entsize(&mut self, section: SectionId, entsize: u64) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported section entry size for format {:?}",
+                self.format
+            )));
+        }
+        if entsize == 0 {
+            return Err(Error(format!(
+                "invalid zero entry size for section `{}`",
+                self.section(section).name().unwrap_or("")
+            )));
+        }
+        self.elf_section_entsize.insert(section, entsize);
+        Ok(())
+    }
+
+    /// Set the load memory address (LMA) hint for a section.
+    ///
+    /// ELF relocatable objects have no field for the LMA, so the hints are written to
+    /// a `.note.object.lma` section of type `SHT_NOTE` with no flags. The section
+    /// contains a single note with the owner name `"object"` and type
+    /// [`NT_OBJECT_LMA`](crate::write::elf::NT_OBJECT_LMA). The note descriptor is an array of 16 byte entries, sorted
+    /// by section index, with the following fields in the byte order of the object:
+    ///
+    /// - a `u32` containing the ELF section index of the section
+    /// - a `u32` that is reserved and set to zero
+    /// - a `u64` containing the LMA
+    ///
+    /// Setting the LMA again for the same section replaces the previous hint.
+    ///
+    /// Returns an error if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_section_lma(&mut self, section: SectionId, lma: u64) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported section LMA for format {:?}",
+                self.format
+            )));
+        }
+        self.elf_section_lma.insert(section, lma);
+        Ok(())
+    }
+
+    /// Return the size of the section LMA note.
+    fn elf_lma_note_size(&self) -> usize {
+        mem::size_of::<elf::NoteHeader32<Endianness>>()
+            + util::align(LMA_NOTE_NAME.len(), 4)
+            + self.elf_section_lma.len() * 16
+    }
+
+    /// Return the contents of the section LMA note.
+    fn elf_lma_note_data(&self, section_offsets: &[SectionOffsets]) -> Vec<u8> {
+        let mut entries: Vec<_> = self
+            .elf_section_lma
+            .iter()
+            .map(|(section, lma)| (section_offsets[section.0].index.0, *lma))
+            .collect();
+        entries.sort_unstable();
+
+        let mut data = Vec::with_capacity(self.elf_lma_note_size());
+        let header = elf::NoteHeader32 {
+            n_namesz: U32::new(self.endian, LMA_NOTE_NAME.len() as u32),
+            n_descsz: U32::new(self.endian, (entries.len() * 16) as u32),
+            n_type: U32::new(self.endian, NT_OBJECT_LMA),
+        };
+        data.extend_from_slice(pod::bytes_of(&header));
+        data.extend_from_slice(LMA_NOTE_NAME);
+        data.resize(util::align(data.len(), 4), 0);
+        for (index, lma) in entries {
+            data.extend_from_slice(pod::bytes_of(&U32Bytes::new(self.endian, index)));
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(pod::bytes_of(&U64Bytes::new(self.endian, lma)));
+        }
+        debug_assert_eq!(data.len(), self.elf_lma_note_size());
+        data
+    }
+
+    /// Return the address set by [`Self::set_section_address`].
+    fn elf_section_address(&self, section: SectionId) -> u64 {
+        self.elf_section_address.get(&section).copied().unwrap_or(0)
+    }
+
+    /// Set the virtual address of a section.
+    ///
+    /// The address is written to `sh_addr`. For core files, it is also the address
+    /// of the `PT_LOAD` segment for the section. By default, the address is zero.
+    ///
+    /// Returns an error if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_section_address(&mut self, section: SectionId, address: u64) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported section address for format {:?}",
+                self.format
+            )));
+        }
+        self.elf_section_address.insert(section, address);
+        Ok(())
+    }
+
+    /// Specify whether an ELF core file is written.
+    ///
+    /// If enabled, the file type is `ET_CORE` instead of `ET_REL`. Each section
+    /// with the `SHF_ALLOC` flag is also written as a `PT_LOAD` segment at the
+    /// address given by [`Self::set_section_address`], which must be a multiple of
+    /// the section alignment. The notes added by [`Self::add_core_note`] are written
+    /// to a `PT_NOTE` segment. Sections, symbols and relocations are still written,
+    /// so the file can be inspected with the usual tools.
+    ///
+    /// Returns an error if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_elf_core(&mut self, core: bool) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported core file for format {:?}",
+                self.format
+            )));
+        }
+        self.elf_core = core;
+        Ok(())
+    }
+
+    /// Specify whether a static-PIE executable is written.
+    ///
+    /// If enabled, the file type is `ET_DYN` and there is no `PT_INTERP` segment.
+    /// Each section with the `SHF_ALLOC` flag is written as a `PT_LOAD` segment at the
+    /// address given by [`Self::set_section_address`], with a file offset that is
+    /// congruent to the address modulo the maximum page size of the architecture.
+    /// The addresses of these sections must not overlap, and sections with the
+    /// `SHF_TLS` flag are not supported.
+    ///
+    /// All relocations must be relative relocations added by
+    /// [`Self::add_elf_relative_relocation`]. The final address of each relocation
+    /// is written to the section data. The relocations in allocated sections are also
+    /// written without a symbol to an allocated `.rela.dyn` (or `.rel.dyn`) section,
+    /// or to a `.relr.dyn` section if enabled by [`Self::set_elf_use_relr`], so that the
+    /// startup code of the executable can apply them. These sections and a `.dynamic`
+    /// section that only contains their tags are placed in a writable `PT_LOAD` segment
+    /// on the page after the highest section address, and the `.dynamic` section is
+    /// also described by a `PT_DYNAMIC` segment.
+    ///
+    /// Symbol values are written as addresses, and the entry point is set by
+    /// [`Self::set_elf_entry`]. This is not compatible with [`Self::set_elf_core`].
+    ///
+    /// Returns an error if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_elf_static_pie(&mut self, static_pie: bool) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported static-PIE for format {:?}",
+                self.format
+            )));
+        }
+        self.elf_static_pie = static_pie;
+        Ok(())
+    }
+
+    /// Set the entry point of an ELF executable.
+    ///
+    /// `symbol` must be defined in a section. The entry point is only written for
+    /// static-PIE executables, which are enabled by [`Self::set_elf_static_pie`].
+    ///
+    /// Returns an error if the format is not ELF.
     ///
     /// Requires `feature = "elf"`.
-    pub fn add_elf_gnu_property_u32(&mut self, property: u32, value: u32) {
+    pub fn set_elf_entry(&mut self, symbol: SymbolId) -> Result<()> {
         if self.format != BinaryFormat::Elf {
-            return;
+            return Err(Error(format!(
+                "unsupported entry point for format {:?}",
+                self.format
+            )));
         }
+        self.elf_entry = Some(symbol);
+        Ok(())
+    }
 
-        let align = if self.elf_is_64() { 8 } else { 4 };
-        let mut data = Vec::with_capacity(32);
-        let n_name = b"GNU\0";
+    /// Append a note to the `PT_NOTE` segment of an ELF core file.
+    ///
+    /// `NT_PRSTATUS` and `NT_PRPSINFO` notes have an owner of `CORE`, and use the
+    /// Linux layout of `struct elf_prstatus` and `struct elf_prpsinfo` for the
+    /// architecture. These are supported for `I386`, `X86_64`, `Arm`, `Aarch64`,
+    /// `LoongArch64`, `PowerPc`, `PowerPc64`, `Riscv32` and `Riscv64`. Notes are
+    /// aligned to 4 bytes, which is what Linux uses for core files on all ELF classes.
+    ///
+    /// Returns an error if the core file was not enabled by [`Self::set_elf_core`],
+    /// if the architecture is not supported, if the number of registers does not
+    /// match the architecture, or if a field does not fit in the note.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_core_note(&mut self, note: CoreNote) -> Result<()> {
+        if self.format != BinaryFormat::Elf || !self.elf_core {
+            return Err(Error(String::from("core notes require an ELF core file")));
+        }
+        let (name, n_type, desc) = match note {
+            CoreNote::PrStatus(status) => (
+                b"CORE".to_vec(),
+                elf::NT_PRSTATUS,
+                self.elf_prstatus_data(&status)?,
+            ),
+            CoreNote::PrPsInfo(info) => (
+                b"CORE".to_vec(),
+                elf::NT_PRPSINFO,
+                self.elf_prpsinfo_data(&info)?,
+            ),
+            CoreNote::Other { name, n_type, desc } => (name, n_type, desc),
+        };
+        let data = &mut self.elf_core_notes;
         data.extend_from_slice(pod::bytes_of(&elf::NoteHeader32 {
-            n_namesz: U32::new(self.endian, n_name.len() as u32),
-            n_descsz: U32::new(self.endian, util::align(3 * 4, align) as u32),
-            n_type: U32::new(self.endian, elf::NT_GNU_PROPERTY_TYPE_0),
+            n_namesz: U32::new(self.endian, name.len() as u32 + 1),
+            n_descsz: U32::new(self.endian, desc.len() as u32),
+            n_type: U32::new(self.endian, n_type),
         }));
-        data.extend_from_slice(n_name);
-        // This happens to already be aligned correctly.
-        debug_assert_eq!(util::align(data.len(), align), data.len());
-        data.extend_from_slice(pod::bytes_of(&U32::new(self.endian, property)));
-        // Value size
-        data.extend_from_slice(pod::bytes_of(&U32::new(self.endian, 4)));
-        data.extend_from_slice(pod::bytes_of(&U32::new(self.endian, value)));
-        util::write_align(&mut data, align);
+        data.extend_from_slice(&name);
+        data.push(0);
+        util::write_align(data, 4);
+        data.extend_from_slice(&desc);
+        util::write_align(data, 4);
+        Ok(())
+    }
 
-        let section = self.section_id(StandardSection::GnuProperty);
-        self.append_section_data(section, &data, align as u64);
+    /// Return the number of registers in `elf_gregset_t`, and whether
+    /// `__kernel_uid_t` is 16 bits.
+    fn elf_core_layout(&self) -> Result<(usize, bool)> {
+        Ok(match self.architecture {
+            Architecture::I386 => (17, true),
+            Architecture::Arm => (18, true),
+            Architecture::X86_64 => (27, false),
+            Architecture::Aarch64 => (34, false),
+            Architecture::LoongArch64 => (45, false),
+            Architecture::PowerPc | Architecture::PowerPc64 => (48, false),
+            Architecture::Riscv32 | Architecture::Riscv64 => (32, false),
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented core notes for architecture {:?}",
+                    self.architecture
+                )));
+            }
+        })
+    }
+
+    /// Return the descriptor of an `NT_PRSTATUS` note.
+    fn elf_prstatus_data(&self, status: &CorePrStatus) -> Result<Vec<u8>> {
+        let (num_registers, _) = self.elf_core_layout()?;
+        if status.registers.len() != num_registers {
+            return Err(Error(format!(
+                "invalid register count {} for architecture {:?}, expected {}",
+                status.registers.len(),
+                self.architecture,
+                num_registers
+            )));
+        }
+        let mut data = CoreNoteData::new(self.endian, self.elf_is_64());
+        // pr_info
+        data.u32(status.signal.into());
+        data.u32(0);
+        data.u32(0);
+        data.u16(status.signal);
+        data.word(status.sigpend);
+        data.word(status.sighold);
+        data.u32(status.pid);
+        data.u32(status.ppid);
+        data.u32(status.pgrp);
+        data.u32(status.sid);
+        // pr_utime, pr_stime, pr_cutime, pr_cstime
+        for _ in 0..8 {
+            data.word(0);
+        }
+        for &register in &status.registers {
+            data.word(register);
+        }
+        // pr_fpvalid
+        data.u32(0);
+        Ok(data.finish())
+    }
+
+    /// Return the descriptor of an `NT_PRPSINFO` note.
+    fn elf_prpsinfo_data(&self, info: &CorePrPsInfo) -> Result<Vec<u8>> {
+        let (_, uid16) = self.elf_core_layout()?;
+        if uid16 && (info.uid > 0xffff || info.gid > 0xffff) {
+            return Err(Error(format!(
+                "invalid uid {} or gid {} for architecture {:?}",
+                info.uid, info.gid, self.architecture
+            )));
+        }
+        if info.fname.len() > 16 || info.psargs.len() > 80 {
+            return Err(Error(format!(
+                "invalid core note file name length {} or argument length {}",
+                info.fname.len(),
+                info.psargs.len()
+            )));
+        }
+        let mut data = CoreNoteData::new(self.endian, self.elf_is_64());
+        data.bytes(&[info.state, info.sname, info.zomb, info.nice as u8]);
+        data.word(info.flag);
+        if uid16 {
+            data.u16(info.uid as u16);
+            data.u16(info.gid as u16);
+        } else {
+            data.u32(info.uid);
+            data.u32(info.gid);
+        }
+        data.u32(info.pid);
+        data.u32(info.ppid);
+        data.u32(info.pgrp);
+        data.u32(info.sid);
+        data.bytes(&info.fname);
+        data.bytes(&[0; 16][info.fname.len()..]);
+        data.bytes(&info.psargs);
+        data.bytes(&[0; 80][info.psargs.len()..]);
+        Ok(data.finish())
+    }
+
+    /// Set the visibility of a symbol.
+    ///
+    /// By default, the visibility is `STV_HIDDEN` for symbols with a scope of
+    /// [`SymbolScope::Linkage`], and `STV_DEFAULT` otherwise. This overrides the
+    /// visibility in the `st_other` field, including for symbols with explicit
+    /// [`SymbolFlags::Elf`].
+    ///
+    /// Returns an error if the format is not ELF, or if the symbol is a section or
+    /// file symbol.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_symbol_visibility(
+        &mut self,
+        symbol_id: SymbolId,
+        visibility: ElfVisibility,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported symbol visibility for format {:?}",
+                self.format
+            )));
+        }
+        let symbol = self.symbol(symbol_id);
+        if symbol.kind == SymbolKind::Section || symbol.kind == SymbolKind::File {
+            return Err(Error(format!(
+                "invalid visibility {:?} for symbol `{}` kind {:?}",
+                visibility,
+                symbol.name().unwrap_or(""),
+                symbol.kind
+            )));
+        }
+        self.elf_visibility.insert(symbol_id, visibility);
+        Ok(())
+    }
+
+    /// Mark a function symbol as an indirect function.
+    ///
+    /// The symbol is written with a type of `STT_GNU_IFUNC`. The symbol value is the
+    /// address of a resolver function, which is called at load time to return the
+    /// address of the implementation. References to the symbol are resolved to
+    /// that implementation. Use [`Self::add_elf_ifunc_relocation`] for pointers
+    /// that call the resolver directly.
+    ///
+    /// The OS ABI is set to `ELFOSABI_GNU` when writing, unless it was set to
+    /// another OS ABI that supports indirect functions.
+    ///
+    /// Returns an error if the format is not ELF, or if the symbol is not a
+    /// text symbol that is defined in a section.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_symbol_ifunc(&mut self, symbol_id: SymbolId) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported indirect function for format {:?}",
+                self.format
+            )));
+        }
+        let symbol = self.symbol(symbol_id);
+        if symbol.kind != SymbolKind::Text || !matches!(symbol.section, SymbolSection::Section(_)) {
+            return Err(Error(format!(
+                "invalid indirect function symbol `{}` kind {:?} section {:?}",
+                symbol.name().unwrap_or(""),
+                symbol.kind,
+                symbol.section
+            )));
+        }
+        self.elf_ifunc.insert(symbol_id);
+        Ok(())
+    }
+
+    /// Set whether a section is retained by the linker.
+    ///
+    /// A retained section is written with the `SHF_GNU_RETAIN` flag, so that
+    /// it is kept by `--gc-sections` even if it is not referenced.
+    ///
+    /// The OS ABI is set to `ELFOSABI_GNU` when writing, unless it was set to
+    /// another OS ABI that supports retained sections.
+    ///
+    /// Returns an error if the format is not ELF.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_section_retain(&mut self, section: SectionId, retain: bool) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported section retain for format {:?}",
+                self.format
+            )));
+        }
+        if retain {
+            self.elf_retain.insert(section);
+        } else {
+            self.elf_retain.remove(&section);
+        }
+        Ok(())
+    }
+
+    /// Return the symbol sizes, including any inferred sizes.
+    fn elf_symbol_sizes(&self) -> Vec<u64> {
+        let mut sizes: Vec<_> = self.symbols.iter().map(|symbol| symbol.size).collect();
+        let data = match self.elf_size_inference {
+            SymbolSizeInference::None => return sizes,
+            SymbolSizeInference::Functions => false,
+            SymbolSizeInference::FunctionsAndData => true,
+        };
+        // Symbols are only extended to the next symbol of the same class.
+        let class = |symbol: &Symbol| match symbol.kind {
+            SymbolKind::Text => Some(0),
+            SymbolKind::Data | SymbolKind::Tls if data && !symbol.is_common() => Some(1),
+            _ => None,
+        };
+        let mut starts: Vec<_> = self
+            .symbols
+            .iter()
+            .filter_map(|symbol| Some((class(symbol)?, symbol.section.id()?.0, symbol.value)))
+            .collect();
+        starts.sort_unstable();
+        for (symbol, size) in self.symbols.iter().zip(sizes.iter_mut()) {
+            if *size != 0 {
+                continue;
+            }
+            let (Some(class), Some(section)) = (class(symbol), symbol.section.id()) else {
+                continue;
+            };
+            let key = (class, section.0, symbol.value);
+            let next = starts.partition_point(|start| *start <= key);
+            let end = match starts.get(next) {
+                Some(&(next_class, next_section, value))
+                    if next_class == class && next_section == section.0 =>
+                {
+                    value
+                }
+                _ => self.sections[section.0].size,
+            };
+            *size = end.saturating_sub(symbol.value);
+        }
+        sizes
+    }
+
+    /// Return the symbol names with the symbol version appended.
+    fn elf_versioned_symbol_names(&self) -> Vec<Option<Vec<u8>>> {
+        let mut names = vec![None; self.symbols.len()];
+        for (symbol_id, (version, hidden)) in &self.elf_symbol_versions {
+            let mut name = self.symbols[symbol_id.0].name.clone();
+            name.extend_from_slice(if *hidden { b"@" } else { b"@@" });
+            name.extend_from_slice(&self.elf_versions[version.0].name);
+            names[symbol_id.0] = Some(name);
+        }
+        names
+    }
+
+    /// Return the data for the `.gnu.version`, `.gnu.version_d` and `.gnu.version_r`
+    /// sections.
+    ///
+    /// `.gnu.version` has an entry for each symbol in `.symtab`. The version at index `i`
+    /// of `elf_versions` has the version index `i + 2`, since the indices 0 and 1 are
+    /// used for local and global symbols. This must be called after the string table
+    /// has been reserved.
+    fn elf_version_data(
+        &self,
+        writer: &Writer<'_>,
+        symbol_offsets: &[SymbolOffsets],
+        str_ids: &[(StringId, Option<StringId>)],
+    ) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let endian = self.endian;
+        let version_index = |index: usize| index as u16 + elf::VER_NDX_GLOBAL + 1;
+
+        let mut versym = vec![U16::new(endian, elf::VER_NDX_LOCAL); writer.symbol_count() as usize];
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            if symbol.is_local() {
+                continue;
+            }
+            let ndx = match self.elf_symbol_versions.get(&SymbolId(index)) {
+                Some((version, true)) => version_index(version.0) | elf::VERSYM_HIDDEN,
+                Some((version, false)) => version_index(version.0),
+                None => elf::VER_NDX_GLOBAL,
+            };
+            versym[symbol_offsets[index].index.0 as usize] = U16::new(endian, ndx);
+        }
+        let versym = pod::bytes_of_slice(&versym).to_vec();
+
+        let verdef_size = mem::size_of::<elf::Verdef<Endianness>>();
+        let verdaux_size = mem::size_of::<elf::Verdaux<Endianness>>();
+        let verdefs: Vec<usize> = (0..self.elf_versions.len())
+            .filter(|&index| self.elf_versions[index].file.is_none())
+            .collect();
+        let mut verdef = Vec::new();
+        for (i, &index) in verdefs.iter().enumerate() {
+            let vd_next = if i + 1 == verdefs.len() {
+                0
+            } else {
+                verdef_size + verdaux_size
+            };
+            verdef.extend_from_slice(pod::bytes_of(&elf::Verdef {
+                vd_version: U16::new(endian, elf::VER_DEF_CURRENT),
+                vd_flags: U16::new(endian, 0),
+                vd_ndx: U16::new(endian, version_index(index)),
+                vd_cnt: U16::new(endian, 1),
+                vd_hash: U32::new(endian, elf::hash(&self.elf_versions[index].name)),
+                vd_aux: U32::new(endian, verdef_size as u32),
+                vd_next: U32::new(endian, vd_next as u32),
+            }));
+            verdef.extend_from_slice(pod::bytes_of(&elf::Verdaux {
+                vda_name: U32::new(endian, writer.string_offset(str_ids[index].0) as u32),
+                vda_next: U32::new(endian, 0),
+            }));
+        }
+
+        // Group the requirements by the shared object that defines them.
+        let verneed_size = mem::size_of::<elf::Verneed<Endianness>>();
+        let vernaux_size = mem::size_of::<elf::Vernaux<Endianness>>();
+        let mut files: Vec<(&[u8], Vec<usize>)> = Vec::new();
+        for (index, version) in self.elf_versions.iter().enumerate() {
+            if let Some(file) = &version.file {
+                match files.iter_mut().find(|(name, _)| *name == &file[..]) {
+                    Some((_, indices)) => indices.push(index),
+                    None => files.push((file, vec![index])),
+                }
+            }
+        }
+        let mut verneed = Vec::new();
+        for (i, (_, indices)) in files.iter().enumerate() {
+            let vn_next = if i + 1 == files.len() {
+                0
+            } else {
+                verneed_size + indices.len() * vernaux_size
+            };
+            let file = str_ids[indices[0]].1.unwrap();
+            verneed.extend_from_slice(pod::bytes_of(&elf::Verneed {
+                vn_version: U16::new(endian, elf::VER_NEED_CURRENT),
+                vn_cnt: U16::new(endian, indices.len() as u16),
+                vn_file: U32::new(endian, writer.string_offset(file) as u32),
+                vn_aux: U32::new(endian, verneed_size as u32),
+                vn_next: U32::new(endian, vn_next as u32),
+            }));
+            for (j, &index) in indices.iter().enumerate() {
+                let vna_next = if j + 1 == indices.len() {
+                    0
+                } else {
+                    vernaux_size
+                };
+                verneed.extend_from_slice(pod::bytes_of(&elf::Vernaux {
+                    vna_hash: U32::new(endian, elf::hash(&self.elf_versions[index].name)),
+                    vna_flags: U16::new(endian, 0),
+                    vna_other: U16::new(endian, version_index(index)),
+                    vna_name: U32::new(endian, writer.string_offset(str_ids[index].0) as u32),
+                    vna_next: U32::new(endian, vna_next as u32),
+                }));
+            }
+        }
+
+        (versym, verdef, verneed)
+    }
+
+    /// Set the data for an existing section to data that has already been compressed.
+    ///
+    /// `data` is the compressed data, excluding the ELF compression header.
+    /// The compression header is created using `size` and `align`, which are the
+    /// size and alignment of the uncompressed data, and `ch_type`, which is the
+    /// compression algorithm such as `elf::ELFCOMPRESS_ZLIB`. The `SHF_COMPRESSED`
+    /// flag is added to the section flags.
+    ///
+    /// Returns an error if the section already has data, if the section contains
+    /// uninitialized data, or if `align` is not a power of two.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn set_section_data_compressed(
+        &mut self,
+        section: SectionId,
+        size: u64,
+        data: &[u8],
+        ch_type: u32,
+        align: u64,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported compressed section for format {:?}",
+                self.format
+            )));
+        }
+        if !align.is_power_of_two() {
+            return Err(Error(format!(
+                "invalid compressed section alignment {}",
+                align
+            )));
+        }
+
+        let (mut compressed, header_align) = self.elf_compression_header(ch_type, size, align)?;
+        compressed.extend_from_slice(data);
+        self.sections[section.0].try_set_data(compressed, header_align)?;
+
+        let sh_flags = match self.section_flags(self.section(section)) {
+            SectionFlags::Elf { sh_flags } => sh_flags,
+            _ => 0,
+        };
+        self.section_mut(section).flags = SectionFlags::Elf {
+            sh_flags: sh_flags | u64::from(elf::SHF_COMPRESSED),
+        };
+        Ok(())
+    }
+
+    /// Add an ELF relative relocation, such as `R_X86_64_RELATIVE`, to a section.
+    ///
+    /// This is the relocation type used for pointers in position independent data.
+    /// The relocation refers to the section symbol for the section containing
+    /// `symbol`, and has an addend that is the section offset of `symbol` plus `addend`.
+    ///
+    /// `symbol` must be defined in a section.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_elf_relative_relocation(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        symbol: SymbolId,
+        addend: i64,
+    ) -> Result<()> {
+        let r_type = self.elf_relative_relocation_type();
+        self.elf_add_symbolless_relocation(section, offset, symbol, addend, "relative", r_type)
+    }
+
+    /// Add an ELF indirect relative relocation, such as `R_X86_64_IRELATIVE`, to a section.
+    ///
+    /// This is the relocation type used for pointers to functions that are
+    /// resolved at load time by calling `resolver`, which is usually a
+    /// `STT_GNU_IFUNC` symbol. The relocation refers to the section symbol for
+    /// the section containing `resolver`, and has an addend that is the section
+    /// offset of `resolver`.
+    ///
+    /// The relocation is added to the relocation section for `section`. It is
+    /// not moved to `.rela.plt`.
+    ///
+    /// `resolver` must be defined in a section.
+    ///
+    /// Requires `feature = "elf"`.
+    pub fn add_elf_ifunc_relocation(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        resolver: SymbolId,
+    ) -> Result<()> {
+        let r_type = self.elf_irelative_relocation_type();
+        self.elf_add_symbolless_relocation(
+            section,
+            offset,
+            resolver,
+            0,
+            "indirect relative",
+            r_type,
+        )
     }
 }
 
 // Private methods.
 impl<'a> Object<'a> {
+    /// Return true if the ".note.gnu.property" section contains `property`.
+    fn elf_has_gnu_property(&self, property: u32) -> bool {
+        let Some(&section) = self.standard_sections.get(&StandardSection::GnuProperty) else {
+            return false;
+        };
+        let data = self.section(section).data();
+        let align = if self.elf_is_64() { 8 } else { 4 };
+        let u32_at = |offset: usize| {
+            data.get(offset..)
+                .and_then(|data| pod::from_bytes::<U32<Endianness>>(data).ok())
+                .map(|(x, _)| x.get(self.endian) as usize)
+        };
+        let mut offset = 0;
+        while let (Some(n_namesz), Some(n_descsz), Some(n_type)) =
+            (u32_at(offset), u32_at(offset + 4), u32_at(offset + 8))
+        {
+            let desc = offset + util::align(12 + n_namesz, align);
+            if n_type == elf::NT_GNU_PROPERTY_TYPE_0 as usize {
+                let mut pr = desc;
+                while pr + 8 <= desc + n_descsz {
+                    let (Some(pr_type), Some(pr_datasz)) = (u32_at(pr), u32_at(pr + 4)) else {
+                        break;
+                    };
+                    if pr_type == property as usize {
+                        return true;
+                    }
+                    pr += 8 + util::align(pr_datasz, align);
+                }
+            }
+            offset = desc + util::align(n_descsz, align);
+        }
+        false
+    }
+
+    /// Return the OS ABI, changed to `ELFOSABI_GNU` if GNU extensions are used.
+    fn elf_gnu_os_abi(&self, os_abi: u8) -> Result<u8> {
+        let ifunc = self.symbols.iter().enumerate().find(|(index, symbol)| {
+            self.elf_ifunc.contains(&SymbolId(*index))
+                || matches!(
+                    self.symbol_flags(symbol),
+                    SymbolFlags::Elf { st_info, .. } if st_info & 0xf == elf::STT_GNU_IFUNC
+                )
+        });
+        let extension = if let Some((_, ifunc)) = ifunc {
+            format!("STT_GNU_IFUNC symbol `{}`", ifunc.name().unwrap_or(""))
+        } else if let Some(section) = self.elf_retain.iter().min() {
+            let section = self.section(*section);
+            format!("SHF_GNU_RETAIN section `{}`", section.name().unwrap_or(""))
+        } else {
+            return Ok(os_abi);
+        };
+        match os_abi {
+            elf::ELFOSABI_NONE => Ok(elf::ELFOSABI_GNU),
+            elf::ELFOSABI_GNU | elf::ELFOSABI_FREEBSD => Ok(os_abi),
+            _ => Err(Error(format!(
+                "{} requires ELFOSABI_GNU, but OS ABI is {}",
+                extension, os_abi
+            ))),
+        }
+    }
+
     pub(crate) fn elf_section_info(
         &self,
         section: StandardSection,
@@ -67,12 +1699,18 @@ impl<'a> Object<'a> {
         match section {
             StandardSection::Text => (&[], &b".text"[..], SectionKind::Text, SectionFlags::None),
             StandardSection::Data => (&[], &b".data"[..], SectionKind::Data, SectionFlags::None),
-            StandardSection::ReadOnlyData | StandardSection::ReadOnlyString => (
+            StandardSection::ReadOnlyData => (
                 &[],
                 &b".rodata"[..],
                 SectionKind::ReadOnlyData,
                 SectionFlags::None,
             ),
+            StandardSection::ReadOnlyString => (
+                &[],
+                &b".rodata.str1.1"[..],
+                SectionKind::ReadOnlyString,
+                SectionFlags::None,
+            ),
             StandardSection::ReadOnlyDataWithRel => (
                 &[],
                 b".data.rel.ro",
@@ -108,6 +1746,36 @@ impl<'a> Object<'a> {
                     sh_flags: u64::from(elf::SHF_ALLOC),
                 },
             ),
+            StandardSection::InitArray => (
+                &[],
+                &b".init_array"[..],
+                SectionKind::Elf(elf::SHT_INIT_ARRAY),
+                SectionFlags::Elf {
+                    sh_flags: u64::from(elf::SHF_ALLOC | elf::SHF_WRITE),
+                },
+            ),
+            StandardSection::FiniArray => (
+                &[],
+                &b".fini_array"[..],
+                SectionKind::Elf(elf::SHT_FINI_ARRAY),
+                SectionFlags::Elf {
+                    sh_flags: u64::from(elf::SHF_ALLOC | elf::SHF_WRITE),
+                },
+            ),
+            StandardSection::PreInitArray => (
+                &[],
+                &b".preinit_array"[..],
+                SectionKind::Elf(elf::SHT_PREINIT_ARRAY),
+                SectionFlags::Elf {
+                    sh_flags: u64::from(elf::SHF_ALLOC | elf::SHF_WRITE),
+                },
+            ),
+            StandardSection::EhFrame => (
+                &[],
+                &b".eh_frame"[..],
+                SectionKind::ReadOnlyData,
+                SectionFlags::None,
+            ),
         }
     }
 
@@ -194,7 +1862,7 @@ impl<'a> Object<'a> {
         SymbolFlags::Elf { st_info, st_other }
     }
 
-    fn elf_has_relocation_addend(&self) -> Result<bool> {
+    pub(crate) fn elf_has_relocation_addend(&self) -> Result<bool> {
         Ok(match self.architecture {
             Architecture::Aarch64 => true,
             Architecture::Aarch64_Ilp32 => true,
@@ -238,6 +1906,227 @@ impl<'a> Object<'a> {
         })
     }
 
+    /// Add a relative or indirect relative relocation.
+    ///
+    /// When these relocations are applied by the dynamic loader in a loaded image,
+    /// they are evaluated as `B + A`, where `B` is the base address of the image,
+    /// and do not use a symbol. In a relocatable object, the relocation refers to
+    /// the section symbol for the section containing `symbol`, and the addend is
+    /// the offset of `symbol` in that section plus `addend`.
+    fn elf_add_symbolless_relocation(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        symbol: SymbolId,
+        addend: i64,
+        description: &str,
+        r_type: Option<u32>,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Elf {
+            return Err(Error(format!(
+                "unsupported {} relocation for format {:?}",
+                description, self.format
+            )));
+        }
+        let r_type = r_type.ok_or_else(|| {
+            Error(format!(
+                "unimplemented {} relocation for architecture {:?}",
+                description, self.architecture
+            ))
+        })?;
+        let (section_symbol, symbol_offset) =
+            self.symbol_section_and_offset(symbol).ok_or_else(|| {
+                Error(format!(
+                    "{} relocation symbol `{}` is not defined in a section",
+                    description,
+                    self.symbol(symbol).name().unwrap_or("")
+                ))
+            })?;
+        self.add_relocation(
+            section,
+            Relocation {
+                offset,
+                symbol: section_symbol,
+                addend: symbol_offset as i64 + addend,
+                flags: RelocationFlags::Elf { r_type },
+            },
+        )
+    }
+
+    /// Return the relative relocation type of the architecture, such as `R_X86_64_RELATIVE`.
+    ///
+    /// Returns `None` if the architecture does not define one.
+    fn elf_relative_relocation_type(&self) -> Option<u32> {
+        Some(match self.architecture {
+            Architecture::Aarch64 => elf::R_AARCH64_RELATIVE,
+            Architecture::Aarch64_Ilp32 => elf::R_AARCH64_P32_RELATIVE,
+            Architecture::Alpha => elf::R_ALPHA_RELATIVE,
+            Architecture::Arm => elf::R_ARM_RELATIVE,
+            Architecture::Csky => elf::R_CKCORE_RELATIVE,
+            Architecture::E2K32 => elf::R_E2K_32_RELATIVE,
+            Architecture::E2K64 => elf::R_E2K_64_RELATIVE,
+            Architecture::I386 => elf::R_386_RELATIVE,
+            Architecture::X86_64 | Architecture::X86_64_X32 => elf::R_X86_64_RELATIVE,
+            Architecture::LoongArch32 | Architecture::LoongArch64 => elf::R_LARCH_RELATIVE,
+            Architecture::M68k => elf::R_68K_RELATIVE,
+            Architecture::PowerPc => elf::R_PPC_RELATIVE,
+            Architecture::PowerPc64 => elf::R_PPC64_RELATIVE,
+            Architecture::Riscv32 | Architecture::Riscv64 => elf::R_RISCV_RELATIVE,
+            Architecture::S390x => elf::R_390_RELATIVE,
+            Architecture::Sparc | Architecture::Sparc32Plus | Architecture::Sparc64 => {
+                elf::R_SPARC_RELATIVE
+            }
+            Architecture::SuperH => elf::R_SH_RELATIVE,
+            Architecture::Xtensa => elf::R_XTENSA_RELATIVE,
+            _ => return None,
+        })
+    }
+
+    /// Return the indirect relative relocation type of the architecture, such as
+    /// `R_X86_64_IRELATIVE`.
+    ///
+    /// Returns `None` if the architecture does not define one.
+    fn elf_irelative_relocation_type(&self) -> Option<u32> {
+        Some(match self.architecture {
+            Architecture::Aarch64 => elf::R_AARCH64_IRELATIVE,
+            Architecture::Aarch64_Ilp32 => elf::R_AARCH64_P32_IRELATIVE,
+            Architecture::Arm => elf::R_ARM_IRELATIVE,
+            Architecture::E2K32 => elf::R_E2K_32_IRELATIVE,
+            Architecture::E2K64 => elf::R_E2K_64_IRELATIVE,
+            Architecture::I386 => elf::R_386_IRELATIVE,
+            Architecture::X86_64 | Architecture::X86_64_X32 => elf::R_X86_64_IRELATIVE,
+            Architecture::LoongArch32 | Architecture::LoongArch64 => elf::R_LARCH_IRELATIVE,
+            Architecture::PowerPc => elf::R_PPC_IRELATIVE,
+            Architecture::PowerPc64 => elf::R_PPC64_IRELATIVE,
+            Architecture::Riscv32 | Architecture::Riscv64 => elf::R_RISCV_IRELATIVE,
+            Architecture::S390x => elf::R_390_IRELATIVE,
+            Architecture::Sparc | Architecture::Sparc32Plus | Architecture::Sparc64 => {
+                elf::R_SPARC_IRELATIVE
+            }
+            _ => return None,
+        })
+    }
+
+    /// Return the maximum page size of the architecture.
+    ///
+    /// This is the alignment of the load segments of static-PIE executables.
+    fn elf_max_page_size(&self) -> u64 {
+        match self.architecture {
+            Architecture::Aarch64
+            | Architecture::Aarch64_Ilp32
+            | Architecture::Arm
+            | Architecture::LoongArch32
+            | Architecture::LoongArch64
+            | Architecture::Mips
+            | Architecture::Mips64
+            | Architecture::Mips64_N32
+            | Architecture::PowerPc64 => 0x10000,
+            _ => 0x1000,
+        }
+    }
+
+    /// Return the address of a symbol in a static-PIE executable.
+    ///
+    /// Returns `None` if the symbol is not defined in a section.
+    fn elf_symbol_address(&self, symbol: SymbolId) -> Option<u64> {
+        let symbol = &self.symbols[symbol.0];
+        match symbol.section {
+            SymbolSection::Section(section) => {
+                Some(self.elf_section_address(section).wrapping_add(symbol.value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Apply the relocations of a static-PIE executable to the section data.
+    ///
+    /// Returns the relocations in allocated sections that must also be applied by
+    /// the startup code. The first vector contains the address and value of the
+    /// relocations for `.rela.dyn`, and the second contains the addresses of the
+    /// relocations for `.relr.dyn`. Both are sorted by address.
+    fn elf_static_pie_relocations(
+        &self,
+        write_data: &mut [Cow<'_, [u8]>],
+    ) -> Result<(Vec<(u64, u64)>, Vec<u64>)> {
+        let relative = self.elf_relative_relocation_type();
+        let is_rela = self.elf_has_relocation_addend()?;
+        let word_size = if self.elf_is_64() { 8 } else { 4 };
+        let mut rel = Vec::new();
+        let mut relr = Vec::new();
+        for (index, section) in self.sections.iter().enumerate() {
+            let is_alloc = match self.section_flags(section) {
+                SectionFlags::Elf { sh_flags } => sh_flags & u64::from(elf::SHF_ALLOC) != 0,
+                _ => false,
+            };
+            for (reloc_index, reloc) in section.relocations.iter().enumerate() {
+                match reloc.flags {
+                    RelocationFlags::Elf { r_type } if Some(r_type) == relative => {}
+                    _ => {
+                        return Err(Error(format!(
+                            "unsupported static-PIE relocation in section `{}` at offset {:#x}: \
+                             only relative relocations are supported",
+                            section.name().unwrap_or(""),
+                            reloc.offset
+                        )));
+                    }
+                }
+                let target = self.elf_symbol_address(reloc.symbol).ok_or_else(|| {
+                    Error(format!(
+                        "relative relocation symbol `{}` is not defined in a section",
+                        self.relocation_symbol_name(reloc.symbol)
+                    ))
+                })?;
+                let data = write_data[index].to_mut();
+                let offset = reloc.offset as usize;
+                let place = data
+                    .get_mut(offset..)
+                    .and_then(|data| data.get_mut(..word_size));
+                let Some(place) = place else {
+                    return Err(Error(format!(
+                        "invalid relative relocation offset {:#x} in section `{}`",
+                        reloc.offset,
+                        section.name().unwrap_or("")
+                    )));
+                };
+                let addend = if is_rela {
+                    self.relocation_write_addend(SectionId(index), reloc_index) as u64
+                } else if word_size == 8 {
+                    pod::from_bytes::<U64Bytes<_>>(place)
+                        .unwrap()
+                        .0
+                        .get(self.endian)
+                } else {
+                    u64::from(
+                        pod::from_bytes::<U32Bytes<_>>(place)
+                            .unwrap()
+                            .0
+                            .get(self.endian),
+                    )
+                };
+                let value = target.wrapping_add(addend);
+                if word_size == 8 {
+                    place.copy_from_slice(pod::bytes_of(&U64Bytes::new(self.endian, value)));
+                } else {
+                    place.copy_from_slice(pod::bytes_of(&U32Bytes::new(self.endian, value as u32)));
+                }
+                if is_alloc {
+                    let address = self
+                        .elf_section_address(SectionId(index))
+                        .wrapping_add(reloc.offset);
+                    if self.elf_use_relr && address % word_size as u64 == 0 {
+                        relr.push(address);
+                    } else {
+                        rel.push((address, value));
+                    }
+                }
+            }
+        }
+        rel.sort_unstable();
+        relr.sort_unstable();
+        relr.dedup();
+        Ok((rel, relr))
+    }
+
     pub(crate) fn elf_translate_relocation(&mut self, reloc: &mut Relocation) -> Result<()> {
         use RelocationEncoding as E;
         use RelocationKind as K;
@@ -264,6 +2153,10 @@ impl<'a> Object<'a> {
                 (K::Relative, E::Generic, 16) => elf::R_AARCH64_PREL16,
                 (K::Relative, E::AArch64Call, 26) => elf::R_AARCH64_CALL26,
                 (K::PltRelative, E::AArch64Call, 26) => elf::R_AARCH64_CALL26,
+                (K::TpOffset, E::AArch64AddHi12, 12) => elf::R_AARCH64_TLSLE_ADD_TPREL_HI12,
+                (K::TpOffset, E::AArch64AddLo12, 12) => elf::R_AARCH64_TLSLE_ADD_TPREL_LO12_NC,
+                (K::DtpOffset, E::AArch64AddHi12, 12) => elf::R_AARCH64_TLSLD_ADD_DTPREL_HI12,
+                (K::DtpOffset, E::AArch64AddLo12, 12) => elf::R_AARCH64_TLSLD_ADD_DTPREL_LO12_NC,
                 _ => return unsupported_reloc(),
             },
             Architecture::Aarch64_Ilp32 => match (kind, encoding, size) {
@@ -282,6 +2175,8 @@ impl<'a> Object<'a> {
             },
             Architecture::Arm => match (kind, encoding, size) {
                 (K::Absolute, _, 32) => elf::R_ARM_ABS32,
+                (K::TpOffset, _, 32) => elf::R_ARM_TLS_LE32,
+                (K::DtpOffset, _, 32) => elf::R_ARM_TLS_LDO32,
                 _ => return unsupported_reloc(),
             },
             Architecture::Avr => match (kind, encoding, size) {
@@ -310,6 +2205,8 @@ impl<'a> Object<'a> {
                 (K::Relative, 16) => elf::R_386_PC16,
                 (K::Absolute, 8) => elf::R_386_8,
                 (K::Relative, 8) => elf::R_386_PC8,
+                (K::TpOffset, 32) => elf::R_386_TLS_LE,
+                (K::DtpOffset, 32) => elf::R_386_TLS_LDO_32,
                 _ => return unsupported_reloc(),
             },
             Architecture::E2K32 | Architecture::E2K64 => match (kind, encoding, size) {
@@ -327,12 +2224,19 @@ impl<'a> Object<'a> {
                 (K::Got, _, 32) => elf::R_X86_64_GOT32,
                 (K::PltRelative, _, 32) => elf::R_X86_64_PLT32,
                 (K::GotRelative, _, 32) => elf::R_X86_64_GOTPCREL,
+                (K::GotBaseOffset, _, 64) => elf::R_X86_64_GOTOFF64,
+                (K::GotBaseRelative, _, 32) => elf::R_X86_64_GOTPC32,
+                (K::GotBaseRelative, _, 64) => elf::R_X86_64_GOTPC64,
                 (K::Absolute, E::Generic, 32) => elf::R_X86_64_32,
                 (K::Absolute, E::X86Signed, 32) => elf::R_X86_64_32S,
                 (K::Absolute, _, 16) => elf::R_X86_64_16,
                 (K::Relative, _, 16) => elf::R_X86_64_PC16,
                 (K::Absolute, _, 8) => elf::R_X86_64_8,
                 (K::Relative, _, 8) => elf::R_X86_64_PC8,
+                (K::TpOffset, _, 32) => elf::R_X86_64_TPOFF32,
+                (K::TpOffset, _, 64) => elf::R_X86_64_TPOFF64,
+                (K::DtpOffset, _, 32) => elf::R_X86_64_DTPOFF32,
+                (K::DtpOffset, _, 64) => elf::R_X86_64_DTPOFF64,
                 _ => return unsupported_reloc(),
             },
             Architecture::Hppa => match (kind, encoding, size) {
@@ -386,6 +2290,8 @@ impl<'a> Object<'a> {
             Architecture::Msp430 => match (kind, encoding, size) {
                 (K::Absolute, _, 32) => elf::R_MSP430_32,
                 (K::Absolute, _, 16) => elf::R_MSP430_16_BYTE,
+                (K::Relative, _, 16) => elf::R_MSP430_16_PCREL_BYTE,
+                (K::Absolute, _, 8) => elf::R_MSP430_8,
                 _ => return unsupported_reloc(),
             },
             Architecture::PowerPc => match (kind, encoding, size) {
@@ -401,6 +2307,8 @@ impl<'a> Object<'a> {
                 (K::Absolute, _, 32) => elf::R_RISCV_32,
                 (K::Absolute, _, 64) => elf::R_RISCV_64,
                 (K::Relative, E::Generic, 32) => elf::R_RISCV_32_PCREL,
+                (K::DtpOffset, E::Generic, 32) => elf::R_RISCV_TLS_DTPREL32,
+                (K::DtpOffset, E::Generic, 64) => elf::R_RISCV_TLS_DTPREL64,
                 _ => return unsupported_reloc(),
             },
             Architecture::S390x => match (kind, encoding, size) {
@@ -424,6 +2332,10 @@ impl<'a> Object<'a> {
                 (K::GotBaseOffset, E::Generic, 64) => elf::R_390_GOTOFF64,
                 (K::GotBaseRelative, E::Generic, 64) => elf::R_390_GOTPC,
                 (K::GotBaseRelative, E::S390xDbl, 32) => elf::R_390_GOTPCDBL,
+                (K::TpOffset, E::Generic, 32) => elf::R_390_TLS_LE32,
+                (K::TpOffset, E::Generic, 64) => elf::R_390_TLS_LE64,
+                (K::DtpOffset, E::Generic, 32) => elf::R_390_TLS_LDO32,
+                (K::DtpOffset, E::Generic, 64) => elf::R_390_TLS_LDO64,
                 _ => return unsupported_reloc(),
             },
             Architecture::Sbf => match (kind, encoding, size) {
@@ -494,7 +2406,10 @@ impl<'a> Object<'a> {
         let size = match self.architecture {
             Architecture::Arm => match r_type {
                 elf::R_ARM_ABS16 => Some(16),
-                elf::R_ARM_ABS32 | elf::R_ARM_REL32 => Some(32),
+                elf::R_ARM_ABS32
+                | elf::R_ARM_REL32
+                | elf::R_ARM_RELATIVE
+                | elf::R_ARM_IRELATIVE => Some(32),
                 _ => None,
             },
             Architecture::Bpf => match r_type {
@@ -510,7 +2425,9 @@ impl<'a> Object<'a> {
                 | elf::R_386_GOT32
                 | elf::R_386_PLT32
                 | elf::R_386_GOTOFF
-                | elf::R_386_GOTPC => Some(32),
+                | elf::R_386_GOTPC
+                | elf::R_386_RELATIVE
+                | elf::R_386_IRELATIVE => Some(32),
                 _ => None,
             },
             Architecture::Mips => match r_type {
@@ -526,12 +2443,89 @@ impl<'a> Object<'a> {
             },
             _ => {
                 return Err(Error(format!(
-                    "unimplemented architecture {:?}",
-                    self.architecture
+                    "unimplemented architecture {:?}",
+                    self.architecture
+                )));
+            }
+        };
+        size.ok_or_else(|| Error(format!("unsupported relocation for size {:?}", reloc)))
+    }
+
+    /// Check that the ABI in the file flags is valid for the architecture.
+    fn elf_check_flags(&self, e_flags: u32) -> Result<()> {
+        let valid = match self.architecture {
+            Architecture::Arm => {
+                let float_abi = elf::EF_ARM_ABI_FLOAT_SOFT | elf::EF_ARM_ABI_FLOAT_HARD;
+                e_flags & float_abi != float_abi
+                    && e_flags & elf::EF_ARM_EABIMASK <= elf::EF_ARM_EABI_VER5
+            }
+            Architecture::Mips | Architecture::Mips64 | Architecture::Mips64_N32 => {
+                let abi2 = e_flags & elf::EF_MIPS_ABI2 != 0;
+                match (self.architecture, e_flags & elf::EF_MIPS_ABI) {
+                    (Architecture::Mips, 0 | elf::EF_MIPS_ABI_O32 | elf::EF_MIPS_ABI_EABI32) => {
+                        !abi2
+                    }
+                    (Architecture::Mips64, 0 | elf::EF_MIPS_ABI_O64 | elf::EF_MIPS_ABI_EABI64) => {
+                        !abi2
+                    }
+                    (Architecture::Mips64_N32, 0) => true,
+                    _ => false,
+                }
+            }
+            Architecture::Riscv32 => e_flags & elf::EF_RISCV_RV64ILP32 == 0,
+            _ => true,
+        };
+        if !valid {
+            return Err(Error(format!(
+                "invalid ELF flags {:#x} for architecture {:?}",
+                e_flags, self.architecture
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn elf_machine(&self) -> Result<u16> {
+        Ok(match (self.architecture, self.sub_architecture) {
+            (Architecture::Aarch64, None) => elf::EM_AARCH64,
+            (Architecture::Aarch64_Ilp32, None) => elf::EM_AARCH64,
+            (Architecture::Alpha, None) => elf::EM_ALPHA,
+            (Architecture::Arm, None) => elf::EM_ARM,
+            (Architecture::Avr, None) => elf::EM_AVR,
+            (Architecture::Bpf, None) => elf::EM_BPF,
+            (Architecture::Csky, None) => elf::EM_CSKY,
+            (Architecture::E2K32, None) => elf::EM_MCST_ELBRUS,
+            (Architecture::E2K64, None) => elf::EM_MCST_ELBRUS,
+            (Architecture::I386, None) => elf::EM_386,
+            (Architecture::X86_64, None) => elf::EM_X86_64,
+            (Architecture::X86_64_X32, None) => elf::EM_X86_64,
+            (Architecture::Hppa, None) => elf::EM_PARISC,
+            (Architecture::Hexagon, None) => elf::EM_HEXAGON,
+            (Architecture::LoongArch32, None) => elf::EM_LOONGARCH,
+            (Architecture::LoongArch64, None) => elf::EM_LOONGARCH,
+            (Architecture::M68k, None) => elf::EM_68K,
+            (Architecture::Mips, None) => elf::EM_MIPS,
+            (Architecture::Mips64, None) => elf::EM_MIPS,
+            (Architecture::Mips64_N32, None) => elf::EM_MIPS,
+            (Architecture::Msp430, None) => elf::EM_MSP430,
+            (Architecture::PowerPc, None) => elf::EM_PPC,
+            (Architecture::PowerPc64, None) => elf::EM_PPC64,
+            (Architecture::Riscv32, None) => elf::EM_RISCV,
+            (Architecture::Riscv64, None) => elf::EM_RISCV,
+            (Architecture::S390x, None) => elf::EM_S390,
+            (Architecture::Sbf, None) => elf::EM_SBF,
+            (Architecture::Sharc, None) => elf::EM_SHARC,
+            (Architecture::Sparc, None) => elf::EM_SPARC,
+            (Architecture::Sparc32Plus, None) => elf::EM_SPARC32PLUS,
+            (Architecture::Sparc64, None) => elf::EM_SPARCV9,
+            (Architecture::SuperH, None) => elf::EM_SH,
+            (Architecture::Xtensa, None) => elf::EM_XTENSA,
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented architecture {:?} with sub-architecture {:?}",
+                    self.architecture, self.sub_architecture
                 )));
             }
-        };
-        size.ok_or_else(|| Error(format!("unsupported relocation for size {:?}", reloc)))
+        })
     }
 
     pub(crate) fn elf_is_64(&self) -> bool {
@@ -542,6 +2536,55 @@ impl<'a> Object<'a> {
     }
 
     pub(crate) fn elf_write(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        let Some(checksum) = &self.elf_checksum else {
+            self.elf_write_file(buffer)?;
+            return Ok(());
+        };
+        // The checksum covers the written file, so it can only be patched in afterwards.
+        let mut data = Vec::new();
+        let section_offsets = self.elf_write_file(&mut data)?;
+        let offset = section_offsets[checksum.section.0].offset;
+        let range = match &checksum.range {
+            Some(range) => range.start as usize..range.end as usize,
+            None => 0..data.len(),
+        };
+        if range.start > range.end || range.end > data.len() {
+            return Err(Error(format!(
+                "invalid checksum range {:#x}..{:#x} for file size {:#x}",
+                range.start,
+                range.end,
+                data.len()
+            )));
+        }
+        let field = offset..offset + 4;
+        let value = checksum.kind.compute(&[
+            &data[range.start..field.start.clamp(range.start, range.end)],
+            &data[field.end.clamp(range.start, range.end)..range.end],
+        ]);
+        data[field].copy_from_slice(pod::bytes_of(&U32Bytes::new(self.endian, value)));
+        buffer
+            .reserve(data.len())
+            .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
+        buffer.write_bytes(&data);
+        Ok(())
+    }
+
+    /// Write the file, and return the section offsets.
+    fn elf_write_file(&self, buffer: &mut dyn WritableBuffer) -> Result<Vec<SectionOffsets>> {
+        if self.elf_static_pie && self.elf_core {
+            return Err(Error(String::from(
+                "static-PIE executables cannot be core files",
+            )));
+        }
+        if self.elf_use_relr && !self.elf_static_pie {
+            return Err(Error(String::from(
+                "`SHT_RELR` relocations are not supported for relocatable or core files",
+            )));
+        }
+        // The relocations of static-PIE executables are applied when writing.
+        let has_relocations =
+            |section: &Section<'_>| !self.elf_static_pie && !section.relocations.is_empty();
+
         // Create reloc section header names so we can reference them.
         let is_rela = self.elf_has_relocation_addend()?;
         let reloc_names: Vec<_> = self
@@ -551,7 +2594,7 @@ impl<'a> Object<'a> {
                 let mut reloc_name = Vec::with_capacity(
                     if is_rela { ".rela".len() } else { ".rel".len() } + section.name.len(),
                 );
-                if !section.relocations.is_empty() {
+                if has_relocations(section) {
                     reloc_name.extend_from_slice(if is_rela {
                         &b".rela"[..]
                     } else {
@@ -563,14 +2606,145 @@ impl<'a> Object<'a> {
             })
             .collect();
 
+        let mut write_data = Vec::with_capacity(self.sections.len());
+        for index in 0..self.sections.len() {
+            write_data.push(self.section_write_data(SectionId(index))?);
+        }
+        let (dynamic_rel, dynamic_relr) = if self.elf_static_pie {
+            self.elf_static_pie_relocations(&mut write_data)?
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        // Compress section data.
+        let mut compressed_data = Vec::with_capacity(self.sections.len());
+        for (index, section) in self.sections.iter().enumerate() {
+            compressed_data.push(match self.elf_compression.get(&SectionId(index)) {
+                Some(&compression) => {
+                    Some(self.elf_compress_section(section, &write_data[index], compression)?)
+                }
+                None => None,
+            });
+        }
+        // Fill in the build ID if it is derived from the contents.
+        let build_id_data = self.elf_build_id.as_ref().and_then(|build_id| {
+            let hash = self.content_hash();
+            let desc = match build_id.style {
+                BuildIdStyle::FromContents => &hash[..],
+                BuildIdStyle::Uuid => &uuid_from_hash(&hash)[..],
+                _ => return None,
+            };
+            let mut data = write_data[build_id.section.0].to_vec();
+            data[build_id.desc_offset..][..desc.len()].copy_from_slice(desc);
+            Some((build_id.section, data))
+        });
+        // Return the data and alignment to write for a section.
+        let section_data = |index: usize| match (&compressed_data[index], &build_id_data) {
+            (Some((data, align)), _) => (&data[..], *align),
+            (None, Some((section, data))) if section.0 == index => {
+                (&data[..], self.sections[index].align)
+            }
+            (None, _) => (&*write_data[index], self.sections[index].align),
+        };
+
+        let versioned_names = self.elf_versioned_symbol_names();
+        let symbol_sizes = self.elf_symbol_sizes();
+
         // Start calculating offsets of everything.
         let mut writer = Writer::new(self.endian, self.elf_is_64(), buffer);
         writer.reserve_file_header();
 
+        // Core files and static-PIE executables have a load segment for each allocated
+        // section, sorted by address.
+        let mut load_sections = Vec::new();
+        let has_core_notes = self.elf_core && !self.elf_core_notes.is_empty();
+        if self.elf_core || self.elf_static_pie {
+            for (index, section) in self.sections.iter().enumerate() {
+                if let SectionFlags::Elf { sh_flags } = self.section_flags(section) {
+                    if sh_flags & u64::from(elf::SHF_ALLOC) != 0 {
+                        load_sections.push((index, sh_flags));
+                    }
+                }
+            }
+            load_sections.sort_by_key(|&(index, _)| self.elf_section_address(SectionId(index)));
+        }
+        // The alignment of the load segment for each section of a static-PIE executable.
+        // The file offset of the section must be congruent to its address modulo this.
+        let mut segment_align = vec![0; self.sections.len()];
+        let page_size = self.elf_max_page_size();
+        let word_size = if self.elf_is_64() { 8 } else { 4 };
+        let mut load_end = 0;
+        for &(index, sh_flags) in &load_sections {
+            let section = &self.sections[index];
+            let address = self.elf_section_address(SectionId(index));
+            // The file offset of the section is aligned to the section alignment,
+            // so this ensures that `p_offset` is congruent to `p_vaddr` modulo `p_align`.
+            if address % section.align != 0 {
+                return Err(Error(format!(
+                    "section `{}` address {:#x} is not aligned to {}",
+                    section.name().unwrap_or(""),
+                    address,
+                    section.align
+                )));
+            }
+            if !self.elf_static_pie {
+                continue;
+            }
+            if sh_flags & u64::from(elf::SHF_TLS) != 0 {
+                return Err(Error(format!(
+                    "unsupported static-PIE TLS section `{}`",
+                    section.name().unwrap_or("")
+                )));
+            }
+            if address < load_end {
+                return Err(Error(format!(
+                    "section `{}` address {:#x} overlaps the previous section",
+                    section.name().unwrap_or(""),
+                    address
+                )));
+            }
+            load_end = address.checked_add(section.size).ok_or_else(|| {
+                Error(format!(
+                    "section `{}` address {:#x} is too large",
+                    section.name().unwrap_or(""),
+                    address
+                ))
+            })?;
+            segment_align[index] = cmp::max(page_size, section.align);
+        }
+        if self.elf_core {
+            writer.reserve_program_headers((load_sections.len() + has_core_notes as usize) as u32);
+        } else if self.elf_static_pie {
+            // There is also a load segment for the dynamic relocations, and a
+            // dynamic segment.
+            writer.reserve_program_headers(load_sections.len() as u32 + 2);
+        }
+        let core_notes_offset = if has_core_notes {
+            writer.reserve(self.elf_core_notes.len(), 4)
+        } else {
+            0
+        };
+
         // Calculate size of section data.
+        // The build ID note is placed first so that it is within the first page of the file.
+        let (early_sections, late_sections): (Vec<_>, Vec<_>) = (0..self.sections.len())
+            .partition(|&index| self.sections[index].name == b".note.gnu.build-id");
+        let mut data_offsets = vec![0; self.sections.len()];
+        let mut reserve_section_data = |writer: &mut Writer<'_>, index: usize| {
+            let (data, align) = section_data(index);
+            if segment_align[index] != 0 {
+                let offset = writer.reserved_len() as u64;
+                let address = self.elf_section_address(SectionId(index));
+                let padding = address.wrapping_sub(offset) & (segment_align[index] - 1);
+                writer.reserve_until((offset + padding) as usize);
+            }
+            data_offsets[index] = writer.reserve(data.len(), align as usize);
+        };
+        for &index in &early_sections {
+            reserve_section_data(&mut writer, index);
+        }
         let mut comdat_offsets = Vec::with_capacity(self.comdats.len());
         for comdat in &self.comdats {
-            if comdat.kind != ComdatKind::Any {
+            if comdat.kind != ComdatKind::Any && comdat.kind != ComdatKind::Group {
                 return Err(Error(format!(
                     "unsupported COMDAT symbol `{}` kind {:?}",
                     self.symbols[comdat.symbol.0].name().unwrap_or(""),
@@ -583,13 +2757,54 @@ impl<'a> Object<'a> {
             let str_id = writer.add_section_name(b".group");
             comdat_offsets.push(ComdatOffsets { offset, str_id });
         }
+        for &index in &late_sections {
+            reserve_section_data(&mut writer, index);
+        }
+        let lma_note_offset = if self.elf_section_lma.is_empty() {
+            0
+        } else {
+            writer.reserve(self.elf_lma_note_size(), 4)
+        };
+        // The dynamic relocations and `.dynamic` section of a static-PIE executable are
+        // placed in a writable segment on the page after the last section.
+        let mut dynamic_segment = None;
+        if self.elf_static_pie {
+            let offset = writer.reserve(0, word_size);
+            let address = util::align_u64(load_end, page_size) + offset as u64 % page_size;
+            let rel_offset = writer.reserved_len();
+            if !dynamic_rel.is_empty() {
+                writer.reserve_relocations(dynamic_rel.len(), is_rela);
+            }
+            let relr_offset = writer.reserved_len();
+            let relr_entries = relr_entries(&dynamic_relr, word_size as u64);
+            if !relr_entries.is_empty() {
+                writer.reserve_relative_relocations(relr_entries.len());
+            }
+            let dynamic_offset = writer.reserved_len();
+            let dynamic_num = 1
+                + if dynamic_rel.is_empty() { 0 } else { 3 }
+                + if relr_entries.is_empty() { 0 } else { 3 };
+            writer.reserve_dynamic(dynamic_num);
+            dynamic_segment = Some(StaticPieSegment {
+                offset,
+                address,
+                rel: rel_offset..relr_offset,
+                relr: relr_offset..dynamic_offset,
+                relr_entries,
+                dynamic: dynamic_offset..writer.reserved_len(),
+            });
+        }
         let mut section_offsets = Vec::with_capacity(self.sections.len());
-        for (section, reloc_name) in self.sections.iter().zip(reloc_names.iter()) {
+        for ((section, reloc_name), &offset) in self
+            .sections
+            .iter()
+            .zip(reloc_names.iter())
+            .zip(data_offsets.iter())
+        {
             let index = writer.reserve_section_index();
-            let offset = writer.reserve(section.data.len(), section.align as usize);
             let str_id = writer.add_section_name(&section.name);
             let mut reloc_str_id = None;
-            if !section.relocations.is_empty() {
+            if has_relocations(section) {
                 writer.reserve_section_index();
                 reloc_str_id = Some(writer.add_section_name(reloc_name));
             }
@@ -602,44 +2817,158 @@ impl<'a> Object<'a> {
                 reloc_str_id,
             });
         }
+        let lma_note_str_id = if self.elf_section_lma.is_empty() {
+            None
+        } else {
+            writer.reserve_section_index();
+            Some(writer.add_section_name(b".note.object.lma"))
+        };
+        let (mut rel_dyn_str_id, mut relr_dyn_str_id) = (None, None);
+        if let Some(segment) = &dynamic_segment {
+            if !segment.rel.is_empty() {
+                writer.reserve_section_index();
+                rel_dyn_str_id = Some(writer.add_section_name(if is_rela {
+                    &b".rela.dyn"[..]
+                } else {
+                    &b".rel.dyn"[..]
+                }));
+            }
+            if !segment.relr.is_empty() {
+                writer.reserve_section_index();
+                relr_dyn_str_id = Some(writer.add_section_name(b".relr.dyn"));
+            }
+            writer.reserve_dynamic_section_index();
+        }
 
         // Calculate index of symbols and add symbol strings to strtab.
         let mut symbol_offsets = vec![SymbolOffsets::default(); self.symbols.len()];
         writer.reserve_null_symbol_index();
-        // Local symbols must come before global.
-        for (index, symbol) in self.symbols.iter().enumerate() {
-            if symbol.is_local() {
-                let section_index = symbol.section.id().map(|s| section_offsets[s.0].index);
-                symbol_offsets[index].index = writer.reserve_symbol_index(section_index);
+        // Local symbols must come before global. Within the local symbols,
+        // file symbols come first, followed by section symbols.
+        let mut symbol_order: Vec<usize> = (0..self.symbols.len()).collect();
+        symbol_order.sort_by_key(|&index| {
+            let symbol = &self.symbols[index];
+            match (symbol.is_local(), symbol.kind) {
+                (true, SymbolKind::File) => 0,
+                (true, SymbolKind::Section) => 1,
+                (true, _) => 2,
+                (false, _) => 3,
             }
-        }
-        let symtab_num_local = writer.symbol_count();
-        for (index, symbol) in self.symbols.iter().enumerate() {
-            if !symbol.is_local() {
-                let section_index = symbol.section.id().map(|s| section_offsets[s.0].index);
-                symbol_offsets[index].index = writer.reserve_symbol_index(section_index);
+        });
+        let mut symtab_num_local = writer.symbol_count();
+        for &index in &symbol_order {
+            let symbol = &self.symbols[index];
+            if symbol.is_local() {
+                symtab_num_local += 1;
             }
+            let section_index = symbol.section.id().map(|s| section_offsets[s.0].index);
+            symbol_offsets[index].index = writer.reserve_symbol_index(section_index);
         }
         for (index, symbol) in self.symbols.iter().enumerate() {
             if symbol.kind != SymbolKind::Section && !symbol.name.is_empty() {
-                symbol_offsets[index].str_id = Some(writer.add_string(&symbol.name));
+                let name = versioned_names[index].as_ref().unwrap_or(&symbol.name);
+                symbol_offsets[index].str_id = Some(writer.add_string(name));
             }
         }
+        // The version sections use the string table of the symbol table
+        // that `.gnu.version` is linked to.
+        if self.elf_versions.len() > usize::from(elf::VERSYM_VERSION - elf::VER_NDX_GLOBAL) {
+            return Err(Error(format!(
+                "too many symbol versions: {}",
+                self.elf_versions.len()
+            )));
+        }
+        let version_str_ids: Vec<_> = self
+            .elf_versions
+            .iter()
+            .map(|version| {
+                let name = writer.add_string(&version.name);
+                let file = version.file.as_ref().map(|file| writer.add_string(file));
+                (name, file)
+            })
+            .collect();
 
         // Calculate size of symbols.
-        writer.reserve_symtab_section_index();
+        let symtab_index = writer.reserve_symtab_section_index();
         writer.reserve_symtab();
         if writer.symtab_shndx_needed() {
             writer.reserve_symtab_shndx_section_index();
         }
         writer.reserve_symtab_shndx();
-        writer.reserve_strtab_section_index();
+        let strtab_index = writer.reserve_strtab_section_index();
         writer.reserve_strtab();
 
+        // Calculate size of symbol versions.
+        let mut version_sections = Vec::new();
+        if !self.elf_versions.is_empty() {
+            let (versym, verdef, verneed) =
+                self.elf_version_data(&writer, &symbol_offsets, &version_str_ids);
+            let verdef_count = self
+                .elf_versions
+                .iter()
+                .filter(|v| v.file.is_none())
+                .count();
+            let mut files: Vec<&[u8]> = self
+                .elf_versions
+                .iter()
+                .filter_map(|v| v.file.as_deref())
+                .collect();
+            files.sort_unstable();
+            files.dedup();
+            let verneed_count = files.len();
+            let sections = [
+                (
+                    &b".gnu.version"[..],
+                    elf::SHT_GNU_VERSYM,
+                    versym,
+                    2,
+                    symtab_index,
+                    0,
+                ),
+                (
+                    &b".gnu.version_d"[..],
+                    elf::SHT_GNU_VERDEF,
+                    verdef,
+                    4,
+                    strtab_index,
+                    verdef_count,
+                ),
+                (
+                    &b".gnu.version_r"[..],
+                    elf::SHT_GNU_VERNEED,
+                    verneed,
+                    4,
+                    strtab_index,
+                    verneed_count,
+                ),
+            ];
+            for (name, sh_type, data, align, link, info) in sections {
+                if data.is_empty() {
+                    continue;
+                }
+                writer.reserve_section_index();
+                let str_id = writer.add_section_name(name);
+                let offset = writer.reserve(data.len(), align);
+                let header = SectionHeader {
+                    name: Some(str_id),
+                    sh_type,
+                    sh_flags: 0,
+                    sh_addr: 0,
+                    sh_offset: offset as u64,
+                    sh_size: data.len() as u64,
+                    sh_link: link.0,
+                    sh_info: info as u32,
+                    sh_addralign: align as u64,
+                    sh_entsize: if sh_type == elf::SHT_GNU_VERSYM { 2 } else { 0 },
+                };
+                version_sections.push((header, data));
+            }
+        }
+
         // Calculate size of relocations.
         for (index, section) in self.sections.iter().enumerate() {
             let count = section.relocations.len();
-            if count != 0 {
+            if has_relocations(section) {
                 section_offsets[index].reloc_offset = writer.reserve_relocations(count, is_rela);
             }
         }
@@ -650,48 +2979,25 @@ impl<'a> Object<'a> {
         writer.reserve_section_headers();
 
         // Start writing.
-        let e_type = elf::ET_REL;
-        let e_machine = match (self.architecture, self.sub_architecture) {
-            (Architecture::Aarch64, None) => elf::EM_AARCH64,
-            (Architecture::Aarch64_Ilp32, None) => elf::EM_AARCH64,
-            (Architecture::Alpha, None) => elf::EM_ALPHA,
-            (Architecture::Arm, None) => elf::EM_ARM,
-            (Architecture::Avr, None) => elf::EM_AVR,
-            (Architecture::Bpf, None) => elf::EM_BPF,
-            (Architecture::Csky, None) => elf::EM_CSKY,
-            (Architecture::E2K32, None) => elf::EM_MCST_ELBRUS,
-            (Architecture::E2K64, None) => elf::EM_MCST_ELBRUS,
-            (Architecture::I386, None) => elf::EM_386,
-            (Architecture::X86_64, None) => elf::EM_X86_64,
-            (Architecture::X86_64_X32, None) => elf::EM_X86_64,
-            (Architecture::Hppa, None) => elf::EM_PARISC,
-            (Architecture::Hexagon, None) => elf::EM_HEXAGON,
-            (Architecture::LoongArch32, None) => elf::EM_LOONGARCH,
-            (Architecture::LoongArch64, None) => elf::EM_LOONGARCH,
-            (Architecture::M68k, None) => elf::EM_68K,
-            (Architecture::Mips, None) => elf::EM_MIPS,
-            (Architecture::Mips64, None) => elf::EM_MIPS,
-            (Architecture::Mips64_N32, None) => elf::EM_MIPS,
-            (Architecture::Msp430, None) => elf::EM_MSP430,
-            (Architecture::PowerPc, None) => elf::EM_PPC,
-            (Architecture::PowerPc64, None) => elf::EM_PPC64,
-            (Architecture::Riscv32, None) => elf::EM_RISCV,
-            (Architecture::Riscv64, None) => elf::EM_RISCV,
-            (Architecture::S390x, None) => elf::EM_S390,
-            (Architecture::Sbf, None) => elf::EM_SBF,
-            (Architecture::Sharc, None) => elf::EM_SHARC,
-            (Architecture::Sparc, None) => elf::EM_SPARC,
-            (Architecture::Sparc32Plus, None) => elf::EM_SPARC32PLUS,
-            (Architecture::Sparc64, None) => elf::EM_SPARCV9,
-            (Architecture::SuperH, None) => elf::EM_SH,
-            (Architecture::Xtensa, None) => elf::EM_XTENSA,
-            _ => {
-                return Err(Error(format!(
-                    "unimplemented architecture {:?} with sub-architecture {:?}",
-                    self.architecture, self.sub_architecture
-                )));
+        let e_type = if self.elf_core {
+            elf::ET_CORE
+        } else if self.elf_static_pie {
+            elf::ET_DYN
+        } else {
+            elf::ET_REL
+        };
+        let e_entry = match self.elf_entry {
+            Some(symbol) if self.elf_static_pie => {
+                self.elf_symbol_address(symbol).ok_or_else(|| {
+                    Error(format!(
+                        "entry point symbol `{}` is not defined in a section",
+                        self.symbol(symbol).name().unwrap_or("")
+                    ))
+                })?
             }
+            _ => 0,
         };
+        let e_machine = self.elf_machine()?;
         let (os_abi, abi_version, mut e_flags) = if let FileFlags::Elf {
             os_abi,
             abi_version,
@@ -703,6 +3009,8 @@ impl<'a> Object<'a> {
             (elf::ELFOSABI_NONE, 0, 0)
         };
 
+        let os_abi = self.elf_gnu_os_abi(os_abi)?;
+        self.elf_check_flags(e_flags)?;
         if self.architecture == Architecture::Mips64_N32 {
             e_flags |= elf::EF_MIPS_ABI2;
         }
@@ -712,33 +3020,170 @@ impl<'a> Object<'a> {
             abi_version,
             e_type,
             e_machine,
-            e_entry: 0,
+            e_entry,
             e_flags,
         })?;
 
+        // Write program headers and core notes.
+        if self.elf_core || self.elf_static_pie {
+            writer.write_align_program_headers();
+            if has_core_notes {
+                writer.write_program_header(&ProgramHeader {
+                    p_type: elf::PT_NOTE,
+                    p_flags: 0,
+                    p_offset: core_notes_offset as u64,
+                    p_vaddr: 0,
+                    p_paddr: 0,
+                    p_filesz: self.elf_core_notes.len() as u64,
+                    p_memsz: 0,
+                    p_align: 4,
+                });
+            }
+            for &(index, sh_flags) in &load_sections {
+                let section = &self.sections[index];
+                let mut p_flags = elf::PF_R;
+                if sh_flags & u64::from(elf::SHF_WRITE) != 0 {
+                    p_flags |= elf::PF_W;
+                }
+                if sh_flags & u64::from(elf::SHF_EXECINSTR) != 0 {
+                    p_flags |= elf::PF_X;
+                }
+                let address = self.elf_section_address(SectionId(index));
+                writer.write_program_header(&ProgramHeader {
+                    p_type: elf::PT_LOAD,
+                    p_flags,
+                    p_offset: data_offsets[index] as u64,
+                    p_vaddr: address,
+                    p_paddr: if self.elf_static_pie { address } else { 0 },
+                    p_filesz: section_data(index).0.len() as u64,
+                    p_memsz: section.size,
+                    p_align: if self.elf_static_pie {
+                        segment_align[index]
+                    } else {
+                        section.align
+                    },
+                });
+            }
+            if let Some(segment) = &dynamic_segment {
+                let size = (segment.dynamic.end - segment.offset) as u64;
+                writer.write_program_header(&ProgramHeader {
+                    p_type: elf::PT_LOAD,
+                    p_flags: elf::PF_R | elf::PF_W,
+                    p_offset: segment.offset as u64,
+                    p_vaddr: segment.address,
+                    p_paddr: segment.address,
+                    p_filesz: size,
+                    p_memsz: size,
+                    p_align: page_size,
+                });
+                let address = segment.address_of(segment.dynamic.start);
+                let size = segment.dynamic.len() as u64;
+                writer.write_program_header(&ProgramHeader {
+                    p_type: elf::PT_DYNAMIC,
+                    p_flags: elf::PF_R | elf::PF_W,
+                    p_offset: segment.dynamic.start as u64,
+                    p_vaddr: address,
+                    p_paddr: address,
+                    p_filesz: size,
+                    p_memsz: size,
+                    p_align: word_size as u64,
+                });
+            }
+            if has_core_notes {
+                writer.write_align(4);
+                debug_assert_eq!(core_notes_offset, writer.len());
+                writer.write(&self.elf_core_notes);
+            }
+        }
+
         // Write section data.
+        for &index in &early_sections {
+            writer.pad_until(section_offsets[index].offset);
+            writer.write(section_data(index).0);
+        }
         for comdat in &self.comdats {
-            writer.write_comdat_header();
+            if comdat.kind == ComdatKind::Group {
+                writer.write_group_header(0);
+            } else {
+                writer.write_comdat_header();
+            }
             for section in &comdat.sections {
                 writer.write_comdat_entry(section_offsets[section.0].index);
             }
         }
-        for (index, section) in self.sections.iter().enumerate() {
-            writer.write_align(section.align as usize);
-            debug_assert_eq!(section_offsets[index].offset, writer.len());
-            writer.write(&section.data);
+        for &index in &late_sections {
+            writer.pad_until(section_offsets[index].offset);
+            writer.write(section_data(index).0);
+        }
+        if lma_note_str_id.is_some() {
+            writer.write_align(4);
+            debug_assert_eq!(lma_note_offset, writer.len());
+            writer.write(&self.elf_lma_note_data(&section_offsets));
+        }
+
+        // Write the dynamic relocations and `.dynamic` section.
+        if let Some(segment) = &dynamic_segment {
+            let r_type = self.elf_relative_relocation_type().unwrap_or(0);
+            writer.pad_until(segment.offset);
+            for &(r_offset, value) in &dynamic_rel {
+                writer.write_relocation(
+                    is_rela,
+                    &Rel {
+                        r_offset,
+                        r_sym: 0,
+                        r_type,
+                        r_addend: value as i64,
+                    },
+                );
+            }
+            for &entry in &segment.relr_entries {
+                writer.write_relative_relocation(entry);
+            }
+            writer.write_align_dynamic();
+            if !segment.rel.is_empty() {
+                let (tag, size_tag, entry_tag) = if is_rela {
+                    (elf::DT_RELA, elf::DT_RELASZ, elf::DT_RELAENT)
+                } else {
+                    (elf::DT_REL, elf::DT_RELSZ, elf::DT_RELENT)
+                };
+                writer.write_dynamic(tag, segment.address_of(segment.rel.start));
+                writer.write_dynamic(size_tag, segment.rel.len() as u64);
+                writer.write_dynamic(entry_tag, (segment.rel.len() / dynamic_rel.len()) as u64);
+            }
+            if !segment.relr.is_empty() {
+                writer.write_dynamic(elf::DT_RELR, segment.address_of(segment.relr.start));
+                writer.write_dynamic(elf::DT_RELRSZ, segment.relr.len() as u64);
+                writer.write_dynamic(elf::DT_RELRENT, word_size as u64);
+            }
+            writer.write_dynamic(elf::DT_NULL, 0);
         }
 
         // Write symbols.
         writer.write_null_symbol();
         let mut write_symbol = |index: usize, symbol: &Symbol| -> Result<()> {
-            let SymbolFlags::Elf { st_info, st_other } = self.symbol_flags(symbol) else {
+            let SymbolFlags::Elf {
+                mut st_info,
+                mut st_other,
+            } = self.symbol_flags(symbol)
+            else {
                 return Err(Error(format!(
                     "unimplemented symbol `{}` kind {:?}",
                     symbol.name().unwrap_or(""),
                     symbol.kind
                 )));
             };
+            if self.elf_ifunc.contains(&SymbolId(index)) {
+                st_info = (st_info & !0xf) | elf::STT_GNU_IFUNC;
+            }
+            if let Some(visibility) = self.elf_visibility.get(&SymbolId(index)) {
+                let st_visibility = match visibility {
+                    ElfVisibility::Default => elf::STV_DEFAULT,
+                    ElfVisibility::Internal => elf::STV_INTERNAL,
+                    ElfVisibility::Hidden => elf::STV_HIDDEN,
+                    ElfVisibility::Protected => elf::STV_PROTECTED,
+                };
+                st_other = (st_other & !0x3) | st_visibility;
+            }
             let (st_shndx, section) = match symbol.section {
                 SymbolSection::None => {
                     debug_assert_eq!(symbol.kind, SymbolKind::File);
@@ -749,49 +3194,55 @@ impl<'a> Object<'a> {
                 SymbolSection::Common => (elf::SHN_COMMON, None),
                 SymbolSection::Section(id) => (0, Some(section_offsets[id.0].index)),
             };
+            // Symbol values in executables are addresses.
+            let st_value = match symbol.section {
+                SymbolSection::Section(id) if self.elf_static_pie => {
+                    self.elf_section_address(id).wrapping_add(symbol.value)
+                }
+                _ => symbol.value,
+            };
             writer.write_symbol(&Sym {
                 name: symbol_offsets[index].str_id,
                 section,
                 st_info,
                 st_other,
                 st_shndx,
-                st_value: symbol.value,
-                st_size: symbol.size,
+                st_value,
+                st_size: symbol_sizes[index],
             });
             Ok(())
         };
-        for (index, symbol) in self.symbols.iter().enumerate() {
-            if symbol.is_local() {
-                write_symbol(index, symbol)?;
-            }
-        }
-        for (index, symbol) in self.symbols.iter().enumerate() {
-            if !symbol.is_local() {
-                write_symbol(index, symbol)?;
-            }
+        for &index in &symbol_order {
+            write_symbol(index, &self.symbols[index])?;
         }
         writer.write_symtab_shndx();
         writer.write_strtab();
+        for (header, data) in &version_sections {
+            writer.write_align(header.sh_addralign as usize);
+            debug_assert_eq!(header.sh_offset as usize, writer.len());
+            writer.write(data);
+        }
 
         // Write relocations.
         for (index, section) in self.sections.iter().enumerate() {
-            if !section.relocations.is_empty() {
+            if has_relocations(section) {
                 writer.write_align_relocation();
                 debug_assert_eq!(section_offsets[index].reloc_offset, writer.len());
-                for reloc in &section.relocations {
+                for (reloc_index, reloc) in section.relocations.iter().enumerate() {
                     let r_type = if let RelocationFlags::Elf { r_type } = reloc.flags {
                         r_type
                     } else {
                         return Err(Error("invalid relocation flags".into()));
                     };
                     let r_sym = symbol_offsets[reloc.symbol.0].index.0;
+                    let r_addend = self.relocation_write_addend(SectionId(index), reloc_index);
                     writer.write_relocation(
                         is_rela,
                         &Rel {
                             r_offset: reloc.offset,
                             r_sym,
                             r_type,
-                            r_addend: reloc.addend,
+                            r_addend,
                         },
                     );
                 }
@@ -803,7 +3254,6 @@ impl<'a> Object<'a> {
         // Write section headers.
         writer.write_null_section_header();
 
-        let symtab_index = writer.symtab_index();
         for (comdat, comdat_offset) in self.comdats.iter().zip(comdat_offsets.iter()) {
             writer.write_comdat_section_header(
                 comdat_offset.str_id,
@@ -814,38 +3264,65 @@ impl<'a> Object<'a> {
             );
         }
         for (index, section) in self.sections.iter().enumerate() {
-            let sh_type = match section.kind {
-                SectionKind::UninitializedData | SectionKind::UninitializedTls => elf::SHT_NOBITS,
-                SectionKind::Note => elf::SHT_NOTE,
-                SectionKind::Elf(sh_type) => sh_type,
-                _ => elf::SHT_PROGBITS,
+            let sh_type = match (self.elf_section_type.get(&SectionId(index)), section.kind) {
+                (Some(&sh_type), _) => sh_type,
+                (None, SectionKind::UninitializedData | SectionKind::UninitializedTls) => {
+                    elf::SHT_NOBITS
+                }
+                (None, SectionKind::Note) => elf::SHT_NOTE,
+                (None, SectionKind::Elf(sh_type)) => sh_type,
+                (None, _) => elf::SHT_PROGBITS,
             };
-            let SectionFlags::Elf { sh_flags } = self.section_flags(section) else {
+            let SectionFlags::Elf { mut sh_flags } = self.section_flags(section) else {
                 return Err(Error(format!(
                     "unimplemented section `{}` kind {:?}",
                     section.name().unwrap_or(""),
                     section.kind
                 )));
             };
-            // TODO: not sure if this is correct, maybe user should determine this
-            let sh_entsize = match section.kind {
-                SectionKind::ReadOnlyString | SectionKind::OtherString => 1,
-                _ => 0,
+            if self.elf_retain.contains(&SectionId(index)) {
+                sh_flags |= u64::from(elf::SHF_GNU_RETAIN);
+            }
+            let sh_entsize = match self.elf_section_entsize.get(&SectionId(index)) {
+                Some(&entsize) => {
+                    if section.size % entsize != 0 {
+                        return Err(Error(format!(
+                            "section `{}` size {:#x} is not a multiple of entry size {}",
+                            section.name().unwrap_or(""),
+                            section.size,
+                            entsize
+                        )));
+                    }
+                    sh_flags |= u64::from(elf::SHF_MERGE);
+                    entsize
+                }
+                None => match section.kind {
+                    SectionKind::ReadOnlyString
+                    | SectionKind::OtherString
+                    | SectionKind::DebugString => 1,
+                    _ => 0,
+                },
             };
+            let (mut sh_size, mut sh_addralign) = (section.size, section.align);
+            if let Some((data, align)) = &compressed_data[index] {
+                sh_flags |= u64::from(elf::SHF_COMPRESSED);
+                sh_size = data.len() as u64;
+                sh_addralign = *align;
+            }
             writer.write_section_header(&SectionHeader {
                 name: Some(section_offsets[index].str_id),
                 sh_type,
                 sh_flags,
-                sh_addr: 0,
+                sh_addr: self.elf_section_address(SectionId(index)),
                 sh_offset: section_offsets[index].offset as u64,
-                sh_size: section.size,
+                sh_size,
                 sh_link: 0,
                 sh_info: 0,
-                sh_addralign: section.align,
+                sh_addralign,
                 sh_entsize,
             });
 
-            if !section.relocations.is_empty() {
+            if has_relocations(section) {
                 writer.write_relocation_section_header(
                     section_offsets[index].reloc_str_id.unwrap(),
                     section_offsets[index].index,
@@ -856,14 +3333,242 @@ impl<'a> Object<'a> {
                 );
             }
         }
+        if let Some(str_id) = lma_note_str_id {
+            writer.write_section_header(&SectionHeader {
+                name: Some(str_id),
+                sh_type: elf::SHT_NOTE,
+                sh_flags: 0,
+                sh_addr: 0,
+                sh_offset: lma_note_offset as u64,
+                sh_size: self.elf_lma_note_size() as u64,
+                sh_link: 0,
+                sh_info: 0,
+                sh_addralign: 4,
+                sh_entsize: 0,
+            });
+        }
+        if let Some(segment) = &dynamic_segment {
+            if let Some(str_id) = rel_dyn_str_id {
+                writer.write_section_header(&SectionHeader {
+                    name: Some(str_id),
+                    sh_type: if is_rela { elf::SHT_RELA } else { elf::SHT_REL },
+                    sh_flags: elf::SHF_ALLOC.into(),
+                    sh_addr: segment.address_of(segment.rel.start),
+                    sh_offset: segment.rel.start as u64,
+                    sh_size: segment.rel.len() as u64,
+                    sh_link: 0,
+                    sh_info: 0,
+                    sh_addralign: word_size as u64,
+                    sh_entsize: (segment.rel.len() / dynamic_rel.len()) as u64,
+                });
+            }
+            if let Some(str_id) = relr_dyn_str_id {
+                writer.write_section_header(&SectionHeader {
+                    name: Some(str_id),
+                    sh_type: elf::SHT_RELR,
+                    sh_flags: elf::SHF_ALLOC.into(),
+                    sh_addr: segment.address_of(segment.relr.start),
+                    sh_offset: segment.relr.start as u64,
+                    sh_size: segment.relr.len() as u64,
+                    sh_link: 0,
+                    sh_info: 0,
+                    sh_addralign: word_size as u64,
+                    sh_entsize: word_size as u64,
+                });
+            }
+            writer.write_dynamic_section_header(segment.address_of(segment.dynamic.start));
+        }
 
         writer.write_symtab_section_header(symtab_num_local);
         writer.write_symtab_shndx_section_header();
         writer.write_strtab_section_header();
+        for (header, _) in &version_sections {
+            writer.write_section_header(header);
+        }
         writer.write_shstrtab_section_header();
 
         debug_assert_eq!(writer.reserved_len(), writer.len());
 
-        Ok(())
+        Ok(section_offsets)
+    }
+}
+
+/// The file layout of the dynamic relocations and `.dynamic` section of a static-PIE
+/// executable.
+struct StaticPieSegment {
+    /// The file offset of the segment.
+    offset: usize,
+    /// The address of the segment.
+    address: u64,
+    /// The file range of `.rela.dyn` or `.rel.dyn`.
+    rel: Range<usize>,
+    /// The file range of `.relr.dyn`.
+    relr: Range<usize>,
+    relr_entries: Vec<u64>,
+    /// The file range of `.dynamic`.
+    dynamic: Range<usize>,
+}
+
+impl StaticPieSegment {
+    /// Return the address of a file offset within the segment.
+    fn address_of(&self, offset: usize) -> u64 {
+        self.address + (offset - self.offset) as u64
+    }
+}
+
+/// Encode sorted, word aligned addresses as `SHT_RELR` entries.
+///
+/// Each address entry is followed by bitmap entries. Bit 0 of a bitmap entry is
+/// set to distinguish it from an address, and each following bit marks one of the
+/// next `word_size * 8 - 1` words.
+fn relr_entries(addresses: &[u64], word_size: u64) -> Vec<u64> {
+    let bitmap_bits = word_size * 8 - 1;
+    let mut entries = Vec::new();
+    let mut addresses = addresses.iter().copied().peekable();
+    while let Some(base) = addresses.next() {
+        entries.push(base);
+        let mut next = base + word_size;
+        loop {
+            let mut bitmap = 0u64;
+            while let Some(&address) = addresses.peek() {
+                let bit = (address - next) / word_size;
+                if bit >= bitmap_bits {
+                    break;
+                }
+                bitmap |= 1 << bit;
+                addresses.next();
+            }
+            if bitmap == 0 {
+                break;
+            }
+            entries.push((bitmap << 1) | 1);
+            next += bitmap_bits * word_size;
+        }
+    }
+    entries
+}
+
+/// A builder for the descriptor of a core note, using the C layout for the ELF class.
+struct CoreNoteData {
+    endian: Endianness,
+    is_64: bool,
+    data: Vec<u8>,
+}
+
+impl CoreNoteData {
+    fn new(endian: Endianness, is_64: bool) -> Self {
+        CoreNoteData {
+            endian,
+            is_64,
+            data: Vec::new(),
+        }
+    }
+
+    fn align(&mut self, align: usize) {
+        self.data.resize(util::align(self.data.len(), align), 0);
     }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.align(2);
+        self.bytes(pod::bytes_of(&U16Bytes::new(self.endian, value)));
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.bytes(pod::bytes_of(&U32Bytes::new(self.endian, value)));
+    }
+
+    /// Write a `long`.
+    fn word(&mut self, value: u64) {
+        if self.is_64 {
+            self.align(8);
+            self.bytes(pod::bytes_of(&U64Bytes::new(self.endian, value)));
+        } else {
+            self.u32(value as u32);
+        }
+    }
+
+    /// Return the data, padded to the alignment of a `long`.
+    fn finish(mut self) -> Vec<u8> {
+        self.align(if self.is_64 { 8 } else { 4 });
+        self.data
+    }
+}
+
+/// A symbol version definition or requirement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ElfVersion {
+    name: Vec<u8>,
+    /// The shared object that defines the version, if this is a requirement.
+    file: Option<Vec<u8>>,
+}
+
+/// The build ID note section.
+#[derive(Debug, Clone)]
+pub(crate) struct ElfBuildId {
+    pub section: SectionId,
+    /// The section offset of the note descriptor.
+    pub desc_offset: usize,
+    pub style: BuildIdStyle,
+}
+
+/// The checksum section.
+#[derive(Debug, Clone)]
+pub(crate) struct ElfChecksum {
+    pub section: SectionId,
+    pub kind: ChecksumKind,
+    /// The range of file offsets to compute the checksum over.
+    pub range: Option<Range<u64>>,
+}
+
+impl ChecksumKind {
+    /// Compute the checksum of the concatenation of `data`.
+    fn compute(self, data: &[&[u8]]) -> u32 {
+        match self {
+            ChecksumKind::Crc32 => {
+                let mut hasher = crc32fast::Hasher::new();
+                for data in data {
+                    hasher.update(data);
+                }
+                hasher.finalize()
+            }
+            ChecksumKind::Sum32 => data
+                .iter()
+                .flat_map(|data| data.iter())
+                .fold(0u32, |sum, &byte| sum.wrapping_add(u32::from(byte))),
+        }
+    }
+}
+
+/// Append the compressed form of `data` to `out`.
+#[cfg(feature = "compression")]
+fn compress(compression: CompressionFormat, data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    use std::io::Write;
+
+    match compression {
+        CompressionFormat::Zlib => {
+            let mut encoder = flate2::write::ZlibEncoder::new(out, flate2::Compression::default());
+            encoder
+                .write_all(data)
+                .and_then(|_| encoder.finish())
+                .map_err(|_| Error(String::from("zlib compression failed")))?;
+        }
+        CompressionFormat::Zstandard => {
+            ruzstd::encoding::compress(data, out, ruzstd::encoding::CompressionLevel::Fastest);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "compression"))]
+fn compress(compression: CompressionFormat, _data: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+    Err(Error(format!(
+        "unsupported section compression {:?} without the `compression` feature",
+        compression
+    )))
 }
@@ -644,6 +644,13 @@ impl<'a> Writer<'a> {
         self.strtab.add(name)
     }
 
+    /// Return the offset of a string in the string table.
+    ///
+    /// This must be called after [`Self::reserve_strtab`].
+    pub fn string_offset(&self, id: StringId) -> usize {
+        self.strtab.get_offset(id)
+    }
+
     /// Return true if `.strtab` is needed.
     pub fn strtab_needed(&self) -> bool {
         self.need_strtab
@@ -1946,6 +1953,27 @@ impl<'a> Writer<'a> {
         }
     }
 
+    /// Reserve a file range for the given number of relative relocation entries.
+    ///
+    /// Returns the offset of the range.
+    pub fn reserve_relative_relocations(&mut self, count: usize) -> usize {
+        self.reserve(count * self.class().relr_size(), self.elf_align)
+    }
+
+    /// Write a relative relocation entry.
+    ///
+    /// This is either an even address, or an odd bitmap of the following
+    /// addresses.
+    pub fn write_relative_relocation(&mut self, entry: u64) {
+        let endian = self.endian;
+        if self.is_64 {
+            self.buffer.write(&elf::Relr64(U64::new(endian, entry)));
+        } else {
+            self.buffer
+                .write(&elf::Relr32(U32::new(endian, entry as u32)));
+        }
+    }
+
     /// Write the section header for a relocation section.
     ///
     /// `section` is the index of the section the relocations apply to,
@@ -2013,8 +2041,16 @@ impl<'a> Writer<'a> {
 
     /// Write `GRP_COMDAT` at the start of the COMDAT section.
     pub fn write_comdat_header(&mut self) {
+        self.write_group_header(elf::GRP_COMDAT);
+    }
+
+    /// Write the group flags at the start of a group section.
+    ///
+    /// A group without `GRP_COMDAT` keeps its sections together, but
+    /// duplicate groups are not discarded.
+    pub fn write_group_header(&mut self, flags: u32) {
         util::write_align(self.buffer, 4);
-        self.buffer.write(&U32::new(self.endian, elf::GRP_COMDAT));
+        self.buffer.write(&U32::new(self.endian, flags));
     }
 
     /// Write an entry in a COMDAT section.
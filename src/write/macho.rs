@@ -13,6 +13,8 @@ struct SectionOffsets {
     address: u64,
     reloc_offset: usize,
     reloc_count: usize,
+    reserved1: u32,
+    reserved2: u32,
 }
 
 #[derive(Default, Clone, Copy)]
@@ -22,7 +24,7 @@ struct SymbolOffsets {
 }
 
 /// The customizable portion of a [`macho::BuildVersionCommand`].
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive] // May want to add the tool list?
 pub struct MachOBuildVersion {
     /// One of the `PLATFORM_` constants (for example,
@@ -37,12 +39,115 @@ pub struct MachOBuildVersion {
 }
 
 impl MachOBuildVersion {
-    fn cmdsize(&self) -> u32 {
+    /// Encode the version `X.Y.Z` in nibbles as `xxxx.yy.zz`.
+    ///
+    /// For example, `encode_version(10, 9, 0)` is `0x000a_0900`.
+    pub fn encode_version(major: u16, minor: u8, patch: u8) -> u32 {
+        (u32::from(major) << 16) | (u32::from(minor) << 8) | u32::from(patch)
+    }
+
+    fn cmdsize(&self, version_min: bool) -> u32 {
         // Same size for both endianness, and we don't have `ntools`.
-        let sz = mem::size_of::<macho::BuildVersionCommand<Endianness>>();
+        let sz = if version_min {
+            mem::size_of::<macho::VersionMinCommand<Endianness>>()
+        } else {
+            mem::size_of::<macho::BuildVersionCommand<Endianness>>()
+        };
         debug_assert!(sz <= u32::MAX as usize);
         sz as u32
     }
+
+    /// Return the `LC_VERSION_MIN_*` command for the platform.
+    fn version_min_cmd(&self) -> Result<u32> {
+        match self.platform {
+            macho::PLATFORM_MACOS => Ok(macho::LC_VERSION_MIN_MACOSX),
+            macho::PLATFORM_IOS | macho::PLATFORM_IOSSIMULATOR => {
+                Ok(macho::LC_VERSION_MIN_IPHONEOS)
+            }
+            macho::PLATFORM_TVOS | macho::PLATFORM_TVOSSIMULATOR => Ok(macho::LC_VERSION_MIN_TVOS),
+            macho::PLATFORM_WATCHOS | macho::PLATFORM_WATCHOSSIMULATOR => {
+                Ok(macho::LC_VERSION_MIN_WATCHOS)
+            }
+            platform => Err(Error(format!(
+                "unsupported LC_VERSION_MIN platform {}",
+                platform
+            ))),
+        }
+    }
+}
+
+/// An entry in a Mach-O `LC_SEGMENT_SPLIT_INFO` command.
+///
+/// This describes a location that must be adjusted if the segments are slid
+/// independently, such as when the dylib is placed in the dyld shared cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MachOSplitInfo {
+    /// The kind of adjustment.
+    ///
+    /// For example, 1 is a 32-bit pointer and 2 is a 64-bit pointer.
+    /// Must not be zero.
+    pub kind: u8,
+    /// The section containing the location.
+    pub section: SectionId,
+    /// The offset of the location within the section.
+    pub offset: u64,
+}
+
+/// The kind of a Mach-O data in code entry.
+///
+/// This is used for the `kind` field of a [`macho::DataInCodeEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MachODataInCodeKind {
+    /// Generic data, such as a constant island.
+    ///
+    /// `DICE_KIND_DATA`
+    Data,
+    /// A jump table of 8-bit entries.
+    ///
+    /// `DICE_KIND_JUMP_TABLE8`
+    JumpTable8,
+    /// A jump table of 16-bit entries.
+    ///
+    /// `DICE_KIND_JUMP_TABLE16`
+    JumpTable16,
+    /// A jump table of 32-bit entries.
+    ///
+    /// `DICE_KIND_JUMP_TABLE32`
+    JumpTable32,
+    /// A jump table of 32-bit absolute addresses.
+    ///
+    /// `DICE_KIND_ABS_JUMP_TABLE32`
+    AbsJumpTable32,
+}
+
+impl MachODataInCodeKind {
+    fn dice_kind(self) -> u16 {
+        let kind = match self {
+            MachODataInCodeKind::Data => macho::DICE_KIND_DATA,
+            MachODataInCodeKind::JumpTable8 => macho::DICE_KIND_JUMP_TABLE8,
+            MachODataInCodeKind::JumpTable16 => macho::DICE_KIND_JUMP_TABLE16,
+            MachODataInCodeKind::JumpTable32 => macho::DICE_KIND_JUMP_TABLE32,
+            MachODataInCodeKind::AbsJumpTable32 => macho::DICE_KIND_ABS_JUMP_TABLE32,
+        };
+        kind as u16
+    }
+}
+
+/// An entry in a Mach-O `LC_DATA_IN_CODE` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct MachODataInCode {
+    pub(crate) section: SectionId,
+    pub(crate) offset: u64,
+    pub(crate) length: u16,
+    pub(crate) kind: MachODataInCodeKind,
+}
+
+/// The indirect symbols of a Mach-O symbol stub or pointer section.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct MachOIndirectSymbols {
+    pub(crate) stub_size: u32,
+    pub(crate) symbols: Vec<SymbolId>,
 }
 
 // Public methods.
@@ -62,6 +167,460 @@ impl<'a> Object<'a> {
     pub fn set_macho_build_version(&mut self, info: MachOBuildVersion) {
         self.macho_build_version = Some(info);
     }
+
+    /// Specify whether the build version is written as a legacy `LC_VERSION_MIN_*` command.
+    ///
+    /// By default, the information set by [`Self::set_macho_build_version`] is written
+    /// as an `LC_BUILD_VERSION` command. Deployment targets that predate that command,
+    /// such as macOS 10.13 or iOS 11 and earlier, require an `LC_VERSION_MIN_MACOSX`,
+    /// `LC_VERSION_MIN_IPHONEOS`, `LC_VERSION_MIN_TVOS` or `LC_VERSION_MIN_WATCHOS`
+    /// command instead, which contains the same minimum OS and SDK versions.
+    ///
+    /// Writing returns an error if the platform has no `LC_VERSION_MIN_*` command.
+    ///
+    /// Requires `feature = "macho"`.
+    #[inline]
+    pub fn set_macho_version_min(&mut self, version_min: bool) {
+        self.macho_version_min = version_min;
+    }
+
+    /// Specify the fields of a Mach-O `LC_ENCRYPTION_INFO` command.
+    ///
+    /// The command is `LC_ENCRYPTION_INFO_64` for 64-bit architectures. `crypt_off`
+    /// and `crypt_size` are the file offset and size of the region that is
+    /// encrypted, and `crypt_id` is the encryption system, where 0 means that the
+    /// region is not encrypted yet.
+    ///
+    /// Returns an error if the format is not Mach-O, or if `crypt_off` or
+    /// `crypt_size` is not a multiple of the 4 KiB page size. Writing returns an
+    /// error if the region is not within the file.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn set_macho_encryption_info(
+        &mut self,
+        crypt_off: u32,
+        crypt_size: u32,
+        crypt_id: u32,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported encryption info for format {:?}",
+                self.format
+            )));
+        }
+        if crypt_off % 0x1000 != 0 || crypt_size % 0x1000 != 0 {
+            return Err(Error(format!(
+                "unaligned encryption info offset {:#x} size {:#x}",
+                crypt_off, crypt_size
+            )));
+        }
+        self.macho_encryption_info = Some((crypt_off, crypt_size, crypt_id));
+        Ok(())
+    }
+
+    /// Write a fat (universal) Mach-O file containing each of the given objects.
+    ///
+    /// The file starts with a big-endian [`macho::FatHeader`] and an entry for each
+    /// object, followed by the objects themselves, each aligned to 4 KiB. The
+    /// 64-bit fat format is used if an offset or size does not fit in 32 bits.
+    ///
+    /// Returns an error if there are no objects, if an object is not Mach-O, or if
+    /// two objects have the same CPU type and subtype.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn write_macho_fat(objects: &[&Object<'_>]) -> Result<Vec<u8>> {
+        const ALIGN_SHIFT: u32 = 12;
+        if objects.is_empty() {
+            return Err(Error(String::from("no objects for fat Mach-O file")));
+        }
+        let mut cpu_types = Vec::with_capacity(objects.len());
+        let mut slices = Vec::with_capacity(objects.len());
+        for object in objects {
+            if object.format != BinaryFormat::MachO {
+                return Err(Error(format!(
+                    "unsupported format {:?} for fat Mach-O file",
+                    object.format
+                )));
+            }
+            let (cputype, mut cpusubtype) = object.macho_cpu_type()?;
+            if let Some(cpu_subtype) = object.macho_cpu_subtype {
+                cpusubtype = cpu_subtype;
+            }
+            if cpu_types.contains(&(cputype, cpusubtype)) {
+                return Err(Error(format!(
+                    "duplicate architecture {:?} in fat Mach-O file",
+                    object.architecture
+                )));
+            }
+            cpu_types.push((cputype, cpusubtype));
+            slices.push(object.write()?);
+        }
+
+        // Calculate the offsets of the slices, assuming the 32-bit format first.
+        let layout = |arch_size: usize| {
+            let mut offset = mem::size_of::<macho::FatHeader>() + objects.len() * arch_size;
+            let mut offsets = Vec::with_capacity(slices.len());
+            for slice in &slices {
+                offset = align(offset, 1 << ALIGN_SHIFT);
+                offsets.push(offset);
+                offset += slice.len();
+            }
+            (offsets, offset)
+        };
+        let (mut offsets, mut len) = layout(mem::size_of::<macho::FatArch32>());
+        let is_64 = len > u32::MAX as usize;
+        if is_64 {
+            (offsets, len) = layout(mem::size_of::<macho::FatArch64>());
+        }
+
+        let mut buffer = Vec::with_capacity(len);
+        let magic = if is_64 {
+            macho::FAT_MAGIC_64
+        } else {
+            macho::FAT_MAGIC
+        };
+        buffer.extend_from_slice(pod::bytes_of(&macho::FatHeader {
+            magic: U32::new(BigEndian, magic),
+            nfat_arch: U32::new(BigEndian, objects.len() as u32),
+        }));
+        for ((&(cputype, cpusubtype), &offset), slice) in
+            cpu_types.iter().zip(offsets.iter()).zip(slices.iter())
+        {
+            let cputype = U32::new(BigEndian, cputype);
+            let cpusubtype = U32::new(BigEndian, cpusubtype);
+            let align = U32::new(BigEndian, ALIGN_SHIFT);
+            if is_64 {
+                buffer.extend_from_slice(pod::bytes_of(&macho::FatArch64 {
+                    cputype,
+                    cpusubtype,
+                    offset: U64::new(BigEndian, offset as u64),
+                    size: U64::new(BigEndian, slice.len() as u64),
+                    align,
+                    reserved: U32::default(),
+                }));
+            } else {
+                buffer.extend_from_slice(pod::bytes_of(&macho::FatArch32 {
+                    cputype,
+                    cpusubtype,
+                    offset: U32::new(BigEndian, offset as u32),
+                    size: U32::new(BigEndian, slice.len() as u32),
+                    align,
+                }));
+            }
+        }
+        for (&offset, slice) in offsets.iter().zip(slices.iter()) {
+            buffer.resize(offset, 0);
+            buffer.extend_from_slice(slice);
+        }
+        debug_assert_eq!(buffer.len(), len);
+        Ok(buffer)
+    }
+
+    /// Specify the UUID for a Mach-O `LC_UUID` command.
+    ///
+    /// [`BuildIdStyle::Uuid`] derives the UUID from [`Self::content_hash`] when
+    /// the object is written, in the same way as for an ELF build ID with the
+    /// same style. [`BuildIdStyle::Md5`] and [`BuildIdStyle::ExplicitUuid`] are
+    /// explicit UUIDs.
+    ///
+    /// Returns an error if the format is not Mach-O, or if the style does not
+    /// have a length of 16 bytes.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn set_macho_uuid(&mut self, style: BuildIdStyle) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported UUID for format {:?}",
+                self.format
+            )));
+        }
+        match &style {
+            BuildIdStyle::Uuid => {}
+            BuildIdStyle::Md5(id) | BuildIdStyle::ExplicitUuid(id) if id.len() == 16 => {}
+            _ => return Err(Error(format!("unsupported UUID style {:?}", style))),
+        }
+        self.macho_uuid = Some(style);
+        Ok(())
+    }
+
+    /// Add attributes to the flags of a Mach-O section.
+    ///
+    /// `attributes` is a combination of `S_ATTR_*` constants, such as
+    /// [`S_ATTR_NO_DEAD_STRIP`](macho::S_ATTR_NO_DEAD_STRIP) for metadata sections
+    /// that must not be dead stripped. The attributes are added to the section
+    /// flags when writing, whether those flags are derived from the section kind
+    /// or were set explicitly in [`Section::flags`].
+    ///
+    /// Returns an error if the format is not Mach-O, or if `attributes` contains
+    /// bits of the section type.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn add_macho_section_attributes(
+        &mut self,
+        section: SectionId,
+        attributes: u32,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported section attributes for format {:?}",
+                self.format
+            )));
+        }
+        if attributes & macho::SECTION_TYPE != 0 {
+            return Err(Error(format!(
+                "invalid section attributes {:#x} for section `{}`",
+                attributes,
+                self.section(section).name().unwrap_or("")
+            )));
+        }
+        *self.macho_section_attributes.entry(section).or_insert(0) |= attributes;
+        Ok(())
+    }
+
+    /// Add an entry to the Mach-O `__LD,__compact_unwind` section.
+    ///
+    /// The entry describes the unwind information for the `length` bytes of code
+    /// starting at the function symbol `func`. `encoding` is the compact unwind
+    /// encoding, which is one of the `UNWIND_*` encodings for the architecture.
+    /// `personality` and `lsda` are the optional personality function and
+    /// language specific data area. The linker converts these entries into the
+    /// `__unwind_info` section.
+    ///
+    /// The section is created if it does not already exist. Each entry contains
+    /// the function address, the length and encoding as 32-bit values, and the
+    /// personality and LSDA addresses, with relocations for each address.
+    /// Returns the section offset of the entry.
+    ///
+    /// Returns an error if the format is not Mach-O.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn add_macho_compact_unwind(
+        &mut self,
+        func: SymbolId,
+        length: u32,
+        encoding: u32,
+        personality: Option<SymbolId>,
+        lsda: Option<SymbolId>,
+    ) -> Result<u64> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported compact unwind for format {:?}",
+                self.format
+            )));
+        }
+        let address_size = match self.architecture.address_size() {
+            Some(address_size) => address_size.bytes(),
+            None => {
+                return Err(Error(format!(
+                    "unknown address size for architecture {:?}",
+                    self.architecture
+                )))
+            }
+        };
+        let section = match self.section_by_segment_and_name(b"__LD", b"__compact_unwind") {
+            Some(section) => section,
+            None => {
+                let section = self.add_section(
+                    b"__LD".to_vec(),
+                    b"__compact_unwind".to_vec(),
+                    SectionKind::ReadOnlyData,
+                );
+                self.section_mut(section).flags = SectionFlags::MachO {
+                    flags: macho::S_ATTR_DEBUG,
+                };
+                section
+            }
+        };
+        let mut entry = self.append_section_struct(section, address_size.into());
+        entry.field_ptr(func)?;
+        entry.field_u32(length)?;
+        entry.field_u32(encoding)?;
+        for symbol in [personality, lsda] {
+            match symbol {
+                Some(symbol) => entry.field_ptr(symbol)?,
+                None => entry.field_bytes(&[0; 8][..usize::from(address_size)])?,
+            };
+        }
+        Ok(entry.finish())
+    }
+
+    /// Add a reference to a personality routine in the Mach-O `__TEXT,__eh_frame` section.
+    ///
+    /// The personality routine is referenced indirectly through its GOT entry, since
+    /// it is usually defined in another image. This adds a 32-bit GOT relative
+    /// relocation at `offset` in `section`, which must be the personality pointer in
+    /// the augmentation data of a CIE. The pointer encoding in the augmentation string
+    /// must be `DW_EH_PE_indirect | DW_EH_PE_pcrel | DW_EH_PE_sdata4` (`0x9b`).
+    ///
+    /// Returns an error if the format is not Mach-O, or if the architecture does not
+    /// support GOT relative pointers.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn add_macho_eh_frame_personality(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        personality: SymbolId,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported eh_frame personality for format {:?}",
+                self.format
+            )));
+        }
+        self.add_relocation(
+            section,
+            Relocation {
+                offset,
+                symbol: personality,
+                addend: 0,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::GotRelative,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        )
+    }
+
+    /// Mark a symbol as an alternate entry point.
+    ///
+    /// The symbol is written with the `N_ALT_ENTRY` bit in `n_desc`. When the object
+    /// uses subsections via symbols, this makes the linker keep the symbol in the
+    /// same atom as the preceding symbol, instead of starting a new atom.
+    ///
+    /// Returns an error if the format is not Mach-O, or if the symbol is not
+    /// defined in a section.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn set_macho_alt_entry(&mut self, symbol_id: SymbolId) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported alternate entry for format {:?}",
+                self.format
+            )));
+        }
+        let symbol = self.symbol(symbol_id);
+        if !matches!(symbol.section, SymbolSection::Section(_)) {
+            return Err(Error(format!(
+                "invalid alternate entry symbol `{}` section {:?}",
+                symbol.name().unwrap_or(""),
+                symbol.section
+            )));
+        }
+        self.macho_alt_entry.insert(symbol_id);
+        Ok(())
+    }
+
+    /// Specify the entries for a Mach-O `LC_SEGMENT_SPLIT_INFO` command.
+    ///
+    /// The entries are encoded in the original split info format. The entries
+    /// are grouped by kind, and for each kind, the kind is followed by the ULEB128
+    /// deltas between the sorted addresses of its locations, and a terminating zero.
+    /// The stream ends with another zero. The first delta for each kind is relative
+    /// to address zero.
+    ///
+    /// Returns an error if the format is not Mach-O, if an entry has a kind of zero,
+    /// or if an entry is not within its section. Writing returns an error if an
+    /// entry has an address of zero, since it would encode as a terminator.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn set_macho_split_info(&mut self, entries: Vec<MachOSplitInfo>) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported split info for format {:?}",
+                self.format
+            )));
+        }
+        for entry in &entries {
+            let section = self.section(entry.section);
+            if entry.kind == 0 || entry.offset >= section.size {
+                return Err(Error(format!(
+                    "invalid split info kind {} offset {:#x} in section `{}`",
+                    entry.kind,
+                    entry.offset,
+                    section.name().unwrap_or("")
+                )));
+            }
+        }
+        self.macho_split_info = entries;
+        Ok(())
+    }
+
+    /// Set the indirect symbols for a Mach-O symbol stub or pointer section.
+    ///
+    /// Each entry of `symbols` is the symbol for the corresponding slot in
+    /// `section`. The section type must be [`S_SYMBOL_STUBS`](macho::S_SYMBOL_STUBS),
+    /// in which case `stub_size` is the size of each stub, or one of
+    /// [`S_NON_LAZY_SYMBOL_POINTERS`](macho::S_NON_LAZY_SYMBOL_POINTERS) and
+    /// [`S_LAZY_SYMBOL_POINTERS`](macho::S_LAZY_SYMBOL_POINTERS), in which case
+    /// `stub_size` must be 0 and each slot is the size of an address.
+    ///
+    /// When writing, the symbols are added to the indirect symbol table of the
+    /// `LC_DYSYMTAB` command, and the section's `reserved1` field is set to the
+    /// index of its first entry in the table and `reserved2` is set to `stub_size`.
+    /// The section type and size are checked when writing.
+    ///
+    /// Returns an error if the format is not Mach-O.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn set_macho_indirect_symbols(
+        &mut self,
+        section: SectionId,
+        symbols: Vec<SymbolId>,
+        stub_size: u32,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported indirect symbols for format {:?}",
+                self.format
+            )));
+        }
+        self.macho_indirect_symbols
+            .insert(section, MachOIndirectSymbols { stub_size, symbols });
+        Ok(())
+    }
+
+    /// Add an entry to the Mach-O `LC_DATA_IN_CODE` command.
+    ///
+    /// This marks `length` bytes at `offset` in `section` as data, such as a jump
+    /// table or constant island, so that linkers and disassemblers do not treat
+    /// them as instructions. The entries are written sorted by address.
+    ///
+    /// Returns an error if the format is not Mach-O, if `length` is zero or does
+    /// not fit in 16 bits, or if the range is not within the section.
+    ///
+    /// Requires `feature = "macho"`.
+    pub fn add_macho_data_in_code(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        length: u64,
+        kind: MachODataInCodeKind,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::MachO {
+            return Err(Error(format!(
+                "unsupported data in code for format {:?}",
+                self.format
+            )));
+        }
+        let size = self.section(section).size;
+        if length == 0 || length > u64::from(u16::MAX) || offset > size || length > size - offset {
+            return Err(Error(format!(
+                "invalid data in code offset {:#x} length {:#x} in section `{}`",
+                offset,
+                length,
+                self.section(section).name().unwrap_or("")
+            )));
+        }
+        self.macho_data_in_code.push(MachODataInCode {
+            section,
+            offset,
+            length: length as u16,
+            kind,
+        });
+        Ok(())
+    }
 }
 
 // Private methods.
@@ -74,6 +633,14 @@ impl<'a> Object<'a> {
         }
     }
 
+    pub(crate) fn macho_dwarf_section_name(&self, name: &[u8]) -> Vec<u8> {
+        let name = name.strip_prefix(b".").unwrap_or(name);
+        let mut macho_name = b"__".to_vec();
+        macho_name.extend_from_slice(name);
+        macho_name.truncate(16);
+        macho_name
+    }
+
     pub(crate) fn macho_section_info(
         &self,
         section: StandardSection,
@@ -143,6 +710,39 @@ impl<'a> Object<'a> {
                 // Unsupported section.
                 (&[], &[], SectionKind::Note, SectionFlags::None)
             }
+            StandardSection::InitArray => (
+                &b"__DATA"[..],
+                &b"__mod_init_func"[..],
+                SectionKind::Data,
+                SectionFlags::MachO {
+                    flags: macho::S_MOD_INIT_FUNC_POINTERS,
+                },
+            ),
+            StandardSection::FiniArray => (
+                &b"__DATA"[..],
+                &b"__mod_term_func"[..],
+                SectionKind::Data,
+                SectionFlags::MachO {
+                    flags: macho::S_MOD_TERM_FUNC_POINTERS,
+                },
+            ),
+            StandardSection::PreInitArray => {
+                // Unsupported section.
+                (&[], &[], SectionKind::Data, SectionFlags::None)
+            }
+            // The linker uses the live support attribute to keep the FDEs for
+            // functions that are not dead stripped.
+            StandardSection::EhFrame => (
+                &b"__TEXT"[..],
+                &b"__eh_frame"[..],
+                SectionKind::ReadOnlyData,
+                SectionFlags::MachO {
+                    flags: macho::S_COALESCED
+                        | macho::S_ATTR_NO_TOC
+                        | macho::S_ATTR_STRIP_STATIC_SYMS
+                        | macho::S_ATTR_LIVE_SUPPORT,
+                },
+            ),
         }
     }
 
@@ -325,6 +925,9 @@ impl<'a> Object<'a> {
             Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => match (kind, encoding) {
                 (K::Absolute, E::Generic) => (false, macho::ARM64_RELOC_UNSIGNED),
                 (K::Relative, E::AArch64Call) => (true, macho::ARM64_RELOC_BRANCH26),
+                (K::GotRelative, E::Generic) if size == 32 => {
+                    (true, macho::ARM64_RELOC_POINTER_TO_GOT)
+                }
                 _ => return unsupported_reloc(),
             },
             _ => {
@@ -392,6 +995,93 @@ impl<'a> Object<'a> {
         }
     }
 
+    pub(crate) fn macho_cpu_type(&self) -> Result<(u32, u32)> {
+        Ok(match (self.architecture, self.sub_architecture) {
+            (Architecture::Arm, None) => (macho::CPU_TYPE_ARM, macho::CPU_SUBTYPE_ARM_ALL),
+            (Architecture::Aarch64, None) => (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64_ALL),
+            (Architecture::Aarch64, Some(SubArchitecture::Arm64E)) => {
+                (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64E)
+            }
+            (Architecture::Aarch64_Ilp32, None) => {
+                (macho::CPU_TYPE_ARM64_32, macho::CPU_SUBTYPE_ARM64_32_V8)
+            }
+            (Architecture::I386, None) => (macho::CPU_TYPE_X86, macho::CPU_SUBTYPE_I386_ALL),
+            (Architecture::X86_64, None) => (macho::CPU_TYPE_X86_64, macho::CPU_SUBTYPE_X86_64_ALL),
+            (Architecture::PowerPc, None) => {
+                (macho::CPU_TYPE_POWERPC, macho::CPU_SUBTYPE_POWERPC_ALL)
+            }
+            (Architecture::PowerPc64, None) => {
+                (macho::CPU_TYPE_POWERPC64, macho::CPU_SUBTYPE_POWERPC_ALL)
+            }
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented architecture {:?} with sub-architecture {:?}",
+                    self.architecture, self.sub_architecture
+                )));
+            }
+        })
+    }
+
+    /// Encode the `LC_SEGMENT_SPLIT_INFO` data, without alignment padding.
+    fn macho_split_info_data(&self, section_offsets: &[SectionOffsets]) -> Result<Vec<u8>> {
+        let mut entries: Vec<(u8, u64)> = self
+            .macho_split_info
+            .iter()
+            .map(|entry| {
+                let address = section_offsets[entry.section.0].address + entry.offset;
+                (entry.kind, address)
+            })
+            .collect();
+        entries.sort_unstable();
+        entries.dedup();
+        if let Some(entry) = entries.iter().find(|entry| entry.1 == 0) {
+            return Err(Error(format!(
+                "unsupported split info kind {} at address 0",
+                entry.0
+            )));
+        }
+
+        let mut data = Vec::new();
+        let mut entries = entries.iter().peekable();
+        while let Some(&(kind, address)) = entries.next() {
+            data.push(kind);
+            write_uleb128(&mut data, address);
+            let mut last = address;
+            while let Some(&(_, address)) = entries.next_if(|entry| entry.0 == kind) {
+                write_uleb128(&mut data, address - last);
+                last = address;
+            }
+            data.push(0);
+        }
+        data.push(0);
+        Ok(data)
+    }
+
+    /// Encode the `LC_DATA_IN_CODE` entries, sorted by address.
+    fn macho_data_in_code_data(&self, section_offsets: &[SectionOffsets]) -> Vec<u8> {
+        let mut entries: Vec<(u64, u16, u16)> = self
+            .macho_data_in_code
+            .iter()
+            .map(|entry| {
+                let address = section_offsets[entry.section.0].address + entry.offset;
+                (address, entry.length, entry.kind.dice_kind())
+            })
+            .collect();
+        entries.sort_unstable();
+        entries.dedup();
+
+        let mut data = Vec::new();
+        for (address, length, kind) in entries {
+            let entry = macho::DataInCodeEntry {
+                offset: U32::new(self.endian, address as u32),
+                length: U16::new(self.endian, length),
+                kind: U16::new(self.endian, kind),
+            };
+            data.extend_from_slice(pod::bytes_of(&entry));
+        }
+        data
+    }
+
     pub(crate) fn macho_write(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
         let address_size = self.architecture.address_size().unwrap();
         let endian = self.endian;
@@ -422,8 +1112,30 @@ impl<'a> Object<'a> {
 
         // Calculate size of build version.
         let build_version_offset = offset;
+        let mut version_min_cmd = 0;
         if let Some(version) = &self.macho_build_version {
-            offset += version.cmdsize() as usize;
+            if self.macho_version_min {
+                version_min_cmd = version.version_min_cmd()?;
+            }
+            offset += version.cmdsize(self.macho_version_min) as usize;
+            ncmds += 1;
+        }
+
+        // Calculate size of UUID command.
+        let uuid_command_offset = offset;
+        if self.macho_uuid.is_some() {
+            offset += mem::size_of::<macho::UuidCommand<Endianness>>();
+            ncmds += 1;
+        }
+
+        // Calculate size of encryption info command.
+        let encryption_info_command_offset = offset;
+        let encryption_info_command_len = match address_size {
+            AddressSize::U64 => mem::size_of::<macho::EncryptionInfoCommand64<Endianness>>(),
+            _ => mem::size_of::<macho::EncryptionInfoCommand32<Endianness>>(),
+        };
+        if self.macho_encryption_info.is_some() {
+            offset += encryption_info_command_len;
             ncmds += 1;
         }
 
@@ -439,6 +1151,20 @@ impl<'a> Object<'a> {
         offset += dysymtab_command_len;
         ncmds += 1;
 
+        // Calculate size of split info command.
+        let split_info_command_offset = offset;
+        if !self.macho_split_info.is_empty() {
+            offset += mem::size_of::<macho::LinkeditDataCommand<Endianness>>();
+            ncmds += 1;
+        }
+
+        // Calculate size of data in code command.
+        let data_in_code_command_offset = offset;
+        if !self.macho_data_in_code.is_empty() {
+            offset += mem::size_of::<macho::LinkeditDataCommand<Endianness>>();
+            ncmds += 1;
+        }
+
         let sizeofcmds = offset - command_offset;
 
         // Calculate size of section data.
@@ -515,12 +1241,61 @@ impl<'a> Object<'a> {
             nsyms += 1;
         }
 
+        // Calculate indirect symbol table.
+        let mut indirect_symbols = Vec::new();
+        for (index, section) in self.sections.iter().enumerate() {
+            let indirect = match self.macho_indirect_symbols.get(&SectionId(index)) {
+                Some(indirect) => indirect,
+                None => continue,
+            };
+            let flags = match self.section_flags(section) {
+                SectionFlags::MachO { flags } => flags,
+                _ => 0,
+            };
+            let entry_size = match (flags & macho::SECTION_TYPE, indirect.stub_size) {
+                (macho::S_SYMBOL_STUBS, stub_size) if stub_size != 0 => u64::from(stub_size),
+                (macho::S_NON_LAZY_SYMBOL_POINTERS | macho::S_LAZY_SYMBOL_POINTERS, 0) => {
+                    u64::from(address_size.bytes())
+                }
+                _ => {
+                    return Err(Error(format!(
+                        "invalid indirect symbols for section `{}` flags {:#x} stub size {}",
+                        section.name().unwrap_or(""),
+                        flags,
+                        indirect.stub_size
+                    )));
+                }
+            };
+            if section.size != indirect.symbols.len() as u64 * entry_size {
+                return Err(Error(format!(
+                    "section `{}` size {:#x} does not match {} indirect symbols",
+                    section.name().unwrap_or(""),
+                    section.size,
+                    indirect.symbols.len()
+                )));
+            }
+            section_offsets[index].reserved1 = indirect_symbols.len() as u32;
+            section_offsets[index].reserved2 = indirect.stub_size;
+            for symbol_id in &indirect.symbols {
+                let symbol = &self.symbols[symbol_id.0];
+                if let SymbolKind::File | SymbolKind::Section = symbol.kind {
+                    return Err(Error(format!(
+                        "unsupported indirect symbol `{}` kind {:?}",
+                        symbol.name().unwrap_or(""),
+                        symbol.kind
+                    )));
+                }
+                indirect_symbols.push(symbol_offsets[symbol_id.0].index as u32);
+            }
+        }
+
         // Calculate size of relocations.
         for (index, section) in self.sections.iter().enumerate() {
-            let count: usize = section
-                .relocations
-                .iter()
-                .map(|reloc| 1 + usize::from(reloc.addend != 0))
+            let count: usize = (0..section.relocations.len())
+                .map(|reloc_index| {
+                    let addend = self.relocation_write_addend(SectionId(index), reloc_index);
+                    1 + usize::from(addend != 0)
+                })
                 .sum();
             if count != 0 {
                 offset = align(offset, pointer_align);
@@ -531,12 +1306,40 @@ impl<'a> Object<'a> {
             }
         }
 
+        // Calculate size of split info.
+        let mut split_info_data = Vec::new();
+        let mut split_info_offset = 0;
+        if !self.macho_split_info.is_empty() {
+            split_info_data = self.macho_split_info_data(&section_offsets)?;
+            write_align(&mut split_info_data, pointer_align);
+            offset = align(offset, pointer_align);
+            split_info_offset = offset;
+            offset += split_info_data.len();
+        }
+
+        // Calculate size of data in code.
+        let mut data_in_code_data = Vec::new();
+        let mut data_in_code_offset = 0;
+        if !self.macho_data_in_code.is_empty() {
+            data_in_code_data = self.macho_data_in_code_data(&section_offsets);
+            offset = align(offset, pointer_align);
+            data_in_code_offset = offset;
+            offset += data_in_code_data.len();
+        }
+
         // Calculate size of symtab.
         offset = align(offset, pointer_align);
         let symtab_offset = offset;
         let symtab_len = nsyms * macho.nlist_size();
         offset += symtab_len;
 
+        // Calculate size of indirect symbol table.
+        let mut indirect_symbols_offset = 0;
+        if !indirect_symbols.is_empty() {
+            indirect_symbols_offset = offset;
+            offset += indirect_symbols.len() * 4;
+        }
+
         // Calculate size of strtab.
         let strtab_offset = offset;
         // Start with null name.
@@ -545,36 +1348,22 @@ impl<'a> Object<'a> {
         write_align(&mut strtab_data, pointer_align);
         offset += strtab_data.len();
 
+        if let Some((crypt_off, crypt_size, _)) = self.macho_encryption_info {
+            if u64::from(crypt_off) + u64::from(crypt_size) > offset as u64 {
+                return Err(Error(format!(
+                    "encryption info offset {:#x} size {:#x} is outside file size {:#x}",
+                    crypt_off, crypt_size, offset
+                )));
+            }
+        }
+
         // Start writing.
         buffer
             .reserve(offset)
             .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
 
         // Write file header.
-        let (cputype, mut cpusubtype) = match (self.architecture, self.sub_architecture) {
-            (Architecture::Arm, None) => (macho::CPU_TYPE_ARM, macho::CPU_SUBTYPE_ARM_ALL),
-            (Architecture::Aarch64, None) => (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64_ALL),
-            (Architecture::Aarch64, Some(SubArchitecture::Arm64E)) => {
-                (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64E)
-            }
-            (Architecture::Aarch64_Ilp32, None) => {
-                (macho::CPU_TYPE_ARM64_32, macho::CPU_SUBTYPE_ARM64_32_V8)
-            }
-            (Architecture::I386, None) => (macho::CPU_TYPE_X86, macho::CPU_SUBTYPE_I386_ALL),
-            (Architecture::X86_64, None) => (macho::CPU_TYPE_X86_64, macho::CPU_SUBTYPE_X86_64_ALL),
-            (Architecture::PowerPc, None) => {
-                (macho::CPU_TYPE_POWERPC, macho::CPU_SUBTYPE_POWERPC_ALL)
-            }
-            (Architecture::PowerPc64, None) => {
-                (macho::CPU_TYPE_POWERPC64, macho::CPU_SUBTYPE_POWERPC_ALL)
-            }
-            _ => {
-                return Err(Error(format!(
-                    "unimplemented architecture {:?} with sub-architecture {:?}",
-                    self.architecture, self.sub_architecture
-                )));
-            }
-        };
+        let (cputype, mut cpusubtype) = self.macho_cpu_type()?;
 
         if let Some(cpu_subtype) = self.macho_cpu_subtype {
             cpusubtype = cpu_subtype;
@@ -639,13 +1428,16 @@ impl<'a> Object<'a> {
                     ))
                 })?
                 .copy_from_slice(&section.segment);
-            let SectionFlags::MachO { flags } = self.section_flags(section) else {
+            let SectionFlags::MachO { mut flags } = self.section_flags(section) else {
                 return Err(Error(format!(
                     "unimplemented section `{}` kind {:?}",
                     section.name().unwrap_or(""),
                     section.kind
                 )));
             };
+            if let Some(attributes) = self.macho_section_attributes.get(&SectionId(index)) {
+                flags |= attributes;
+            }
             macho.write_section(
                 buffer,
                 SectionHeader {
@@ -658,6 +1450,8 @@ impl<'a> Object<'a> {
                     reloff: section_offsets[index].reloc_offset as u32,
                     nreloc: section_offsets[index].reloc_count as u32,
                     flags,
+                    reserved1: section_offsets[index].reserved1,
+                    reserved2: section_offsets[index].reserved2,
                 },
             );
         }
@@ -665,16 +1459,69 @@ impl<'a> Object<'a> {
         // Write build version.
         if let Some(version) = &self.macho_build_version {
             debug_assert_eq!(build_version_offset, buffer.len());
-            buffer.write(&macho::BuildVersionCommand {
-                cmd: U32::new(endian, macho::LC_BUILD_VERSION),
-                cmdsize: U32::new(endian, version.cmdsize()),
-                platform: U32::new(endian, version.platform),
-                minos: U32::new(endian, version.minos),
-                sdk: U32::new(endian, version.sdk),
-                ntools: U32::new(endian, 0),
+            if self.macho_version_min {
+                buffer.write(&macho::VersionMinCommand {
+                    cmd: U32::new(endian, version_min_cmd),
+                    cmdsize: U32::new(endian, version.cmdsize(true)),
+                    version: U32::new(endian, version.minos),
+                    sdk: U32::new(endian, version.sdk),
+                });
+            } else {
+                buffer.write(&macho::BuildVersionCommand {
+                    cmd: U32::new(endian, macho::LC_BUILD_VERSION),
+                    cmdsize: U32::new(endian, version.cmdsize(false)),
+                    platform: U32::new(endian, version.platform),
+                    minos: U32::new(endian, version.minos),
+                    sdk: U32::new(endian, version.sdk),
+                    ntools: U32::new(endian, 0),
+                });
+            }
+        }
+
+        // Write UUID command.
+        if let Some(style) = &self.macho_uuid {
+            debug_assert_eq!(uuid_command_offset, buffer.len());
+            let mut uuid = [0; 16];
+            match style {
+                BuildIdStyle::Md5(id) | BuildIdStyle::ExplicitUuid(id) => uuid.copy_from_slice(id),
+                _ => uuid = uuid_from_hash(&self.content_hash()),
+            }
+            buffer.write(&macho::UuidCommand {
+                cmd: U32::new(endian, macho::LC_UUID),
+                cmdsize: U32::new(
+                    endian,
+                    mem::size_of::<macho::UuidCommand<Endianness>>() as u32,
+                ),
+                uuid,
             });
         }
 
+        // Write encryption info command.
+        if let Some((crypt_off, crypt_size, crypt_id)) = self.macho_encryption_info {
+            debug_assert_eq!(encryption_info_command_offset, buffer.len());
+            let cmdsize = U32::new(endian, encryption_info_command_len as u32);
+            let cryptoff = U32::new(endian, crypt_off);
+            let cryptsize = U32::new(endian, crypt_size);
+            let cryptid = U32::new(endian, crypt_id);
+            match address_size {
+                AddressSize::U64 => buffer.write(&macho::EncryptionInfoCommand64 {
+                    cmd: U32::new(endian, macho::LC_ENCRYPTION_INFO_64),
+                    cmdsize,
+                    cryptoff,
+                    cryptsize,
+                    cryptid,
+                    pad: U32::default(),
+                }),
+                _ => buffer.write(&macho::EncryptionInfoCommand32 {
+                    cmd: U32::new(endian, macho::LC_ENCRYPTION_INFO),
+                    cmdsize,
+                    cryptoff,
+                    cryptsize,
+                    cryptid,
+                }),
+            }
+        }
+
         // Write symtab command.
         debug_assert_eq!(symtab_command_offset, buffer.len());
         let symtab_command = macho::SymtabCommand {
@@ -707,8 +1554,8 @@ impl<'a> Object<'a> {
             nmodtab: U32::default(),
             extrefsymoff: U32::default(),
             nextrefsyms: U32::default(),
-            indirectsymoff: U32::default(),
-            nindirectsyms: U32::default(),
+            indirectsymoff: U32::new(endian, indirect_symbols_offset as u32),
+            nindirectsyms: U32::new(endian, indirect_symbols.len() as u32),
             extreloff: U32::default(),
             nextrel: U32::default(),
             locreloff: U32::default(),
@@ -716,11 +1563,39 @@ impl<'a> Object<'a> {
         };
         buffer.write(&dysymtab_command);
 
+        // Write split info command.
+        if !self.macho_split_info.is_empty() {
+            debug_assert_eq!(split_info_command_offset, buffer.len());
+            buffer.write(&macho::LinkeditDataCommand {
+                cmd: U32::new(endian, macho::LC_SEGMENT_SPLIT_INFO),
+                cmdsize: U32::new(
+                    endian,
+                    mem::size_of::<macho::LinkeditDataCommand<Endianness>>() as u32,
+                ),
+                dataoff: U32::new(endian, split_info_offset as u32),
+                datasize: U32::new(endian, split_info_data.len() as u32),
+            });
+        }
+
+        // Write data in code command.
+        if !self.macho_data_in_code.is_empty() {
+            debug_assert_eq!(data_in_code_command_offset, buffer.len());
+            buffer.write(&macho::LinkeditDataCommand {
+                cmd: U32::new(endian, macho::LC_DATA_IN_CODE),
+                cmdsize: U32::new(
+                    endian,
+                    mem::size_of::<macho::LinkeditDataCommand<Endianness>>() as u32,
+                ),
+                dataoff: U32::new(endian, data_in_code_offset as u32),
+                datasize: U32::new(endian, data_in_code_data.len() as u32),
+            });
+        }
+
         // Write section data.
         for (index, section) in self.sections.iter().enumerate() {
             if !section.is_bss() {
                 buffer.resize(section_offsets[index].offset);
-                buffer.write_bytes(&section.data);
+                buffer.write_bytes(&self.section_write_data(SectionId(index))?);
             }
         }
         debug_assert_eq!(segment_file_offset + segment_file_size, buffer.len());
@@ -731,7 +1606,7 @@ impl<'a> Object<'a> {
                 write_align(buffer, pointer_align);
                 debug_assert_eq!(section_offsets[index].reloc_offset, buffer.len());
 
-                let mut write_reloc = |reloc: &Relocation| {
+                let mut write_reloc = |reloc_index: usize, reloc: &Relocation| {
                     let (r_type, r_pcrel, r_length) = if let RelocationFlags::MachO {
                         r_type,
                         r_pcrel,
@@ -744,7 +1619,8 @@ impl<'a> Object<'a> {
                     };
 
                     // Write explicit addend.
-                    if reloc.addend != 0 {
+                    let addend = self.relocation_write_addend(SectionId(index), reloc_index);
+                    if addend != 0 {
                         let r_type = match self.architecture {
                             Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => {
                                 macho::ARM64_RELOC_ADDEND
@@ -756,7 +1632,7 @@ impl<'a> Object<'a> {
 
                         let reloc_info = macho::RelocationInfo {
                             r_address: reloc.offset as u32,
-                            r_symbolnum: reloc.addend as u32,
+                            r_symbolnum: addend as u32,
                             r_pcrel: false,
                             r_length,
                             r_extern: false,
@@ -801,17 +1677,31 @@ impl<'a> Object<'a> {
                     first.offset < last.offset
                 };
                 if need_reverse(&section.relocations) {
-                    for reloc in section.relocations.iter().rev() {
-                        write_reloc(reloc)?;
+                    for (reloc_index, reloc) in section.relocations.iter().enumerate().rev() {
+                        write_reloc(reloc_index, reloc)?;
                     }
                 } else {
-                    for reloc in &section.relocations {
-                        write_reloc(reloc)?;
+                    for (reloc_index, reloc) in section.relocations.iter().enumerate() {
+                        write_reloc(reloc_index, reloc)?;
                     }
                 }
             }
         }
 
+        // Write split info.
+        if !split_info_data.is_empty() {
+            write_align(buffer, pointer_align);
+            debug_assert_eq!(split_info_offset, buffer.len());
+            buffer.write_bytes(&split_info_data);
+        }
+
+        // Write data in code.
+        if !data_in_code_data.is_empty() {
+            write_align(buffer, pointer_align);
+            debug_assert_eq!(data_in_code_offset, buffer.len());
+            buffer.write_bytes(&data_in_code_data);
+        }
+
         // Write symtab.
         write_align(buffer, pointer_align);
         debug_assert_eq!(symtab_offset, buffer.len());
@@ -845,13 +1735,16 @@ impl<'a> Object<'a> {
                 }
             }
 
-            let SymbolFlags::MachO { n_desc } = self.symbol_flags(symbol) else {
+            let SymbolFlags::MachO { mut n_desc } = self.symbol_flags(symbol) else {
                 return Err(Error(format!(
                     "unimplemented symbol `{}` kind {:?}",
                     symbol.name().unwrap_or(""),
                     symbol.kind
                 )));
             };
+            if self.macho_alt_entry.contains(&SymbolId(index)) {
+                n_desc |= macho::N_ALT_ENTRY;
+            }
 
             let n_value = match symbol.section.id() {
                 Some(section) => section_offsets[section.0].address + symbol.value,
@@ -875,6 +1768,14 @@ impl<'a> Object<'a> {
             );
         }
 
+        // Write indirect symbol table.
+        if !indirect_symbols.is_empty() {
+            debug_assert_eq!(indirect_symbols_offset, buffer.len());
+            for index in &indirect_symbols {
+                buffer.write(&U32::new(endian, *index));
+            }
+        }
+
         // Write strtab.
         debug_assert_eq!(strtab_offset, buffer.len());
         buffer.write_bytes(&strtab_data);
@@ -917,6 +1818,8 @@ pub struct SectionHeader {
     reloff: u32,
     nreloc: u32,
     flags: u32,
+    reserved1: u32,
+    reserved2: u32,
 }
 
 struct Nlist {
@@ -1008,8 +1911,8 @@ impl<E: Endian> MachO for MachO32<E> {
             reloff: U32::new(endian, section.reloff),
             nreloc: U32::new(endian, section.nreloc),
             flags: U32::new(endian, section.flags),
-            reserved1: U32::default(),
-            reserved2: U32::default(),
+            reserved1: U32::new(endian, section.reserved1),
+            reserved2: U32::new(endian, section.reserved2),
         };
         buffer.write(&section);
     }
@@ -1098,8 +2001,8 @@ impl<E: Endian> MachO for MachO64<E> {
             reloff: U32::new(endian, section.reloff),
             nreloc: U32::new(endian, section.nreloc),
             flags: U32::new(endian, section.flags),
-            reserved1: U32::default(),
-            reserved2: U32::default(),
+            reserved1: U32::new(endian, section.reserved1),
+            reserved2: U32::new(endian, section.reserved2),
             reserved3: U32::default(),
         };
         buffer.write(&section);
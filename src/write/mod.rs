@@ -10,22 +10,32 @@
 //! and [PE](pe::Writer).
 
 use alloc::borrow::Cow;
+use alloc::boxed::Box;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::{fmt, result, str};
+use core::{fmt, hash::Hash, mem, result, str};
 #[cfg(not(feature = "std"))]
 use hashbrown::HashMap;
+#[cfg(all(not(feature = "std"), any(feature = "elf", feature = "macho")))]
+use hashbrown::HashSet;
+#[cfg(all(feature = "std", any(feature = "elf", feature = "macho")))]
+use std::collections::HashSet;
 #[cfg(feature = "std")]
-use std::{boxed::Box, collections::HashMap, error, io};
+use std::{collections::HashMap, error, io};
 
-use crate::endian::{Endianness, U32, U64};
+use crate::endian::{Endianness, U16, U32, U64};
+use crate::pod;
 
 pub use crate::common::*;
 
+#[cfg(feature = "archive")]
+pub mod archive;
+
 #[cfg(feature = "coff")]
 pub mod coff;
 #[cfg(feature = "coff")]
-pub use coff::CoffExportStyle;
+pub use coff::{CoffExportStyle, CoffLayout, CoffWeakSearch};
 
 #[cfg(feature = "elf")]
 pub mod elf;
@@ -33,13 +43,17 @@ pub mod elf;
 #[cfg(feature = "macho")]
 mod macho;
 #[cfg(feature = "macho")]
-pub use macho::MachOBuildVersion;
+pub use macho::{MachOBuildVersion, MachODataInCodeKind, MachOSplitInfo};
 
 #[cfg(feature = "pe")]
 pub mod pe;
 
 #[cfg(feature = "xcoff")]
 mod xcoff;
+#[cfg(feature = "xcoff")]
+pub use xcoff::XcoffAuxHeader;
+
+mod sha1;
 
 pub(crate) mod string;
 pub use string::StringId;
@@ -67,7 +81,7 @@ impl core::error::Error for Error {}
 pub type Result<T> = result::Result<T, Error>;
 
 /// A writable relocatable object file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Object<'a> {
     format: BinaryFormat,
     architecture: Architecture,
@@ -78,12 +92,88 @@ pub struct Object<'a> {
     symbols: Vec<Symbol>,
     symbol_map: HashMap<Vec<u8>, SymbolId>,
     comdats: Vec<Comdat>,
+    fixups: Vec<Fixup>,
+    /// Relocations added by `add_symbol_end_relocation`, keyed by section and
+    /// relocation index. The value is whether the relocation has an implicit addend.
+    symbol_end_relocations: HashMap<(SectionId, usize), bool>,
     /// File flags that are specific to each file format.
     pub flags: FileFlags,
     /// The symbol name mangling scheme.
     pub mangling: Mangling,
     #[cfg(feature = "coff")]
     stub_symbols: HashMap<SymbolId, SymbolId>,
+    /// COFF function definitions added by `add_coff_function_aux`.
+    #[cfg(feature = "coff")]
+    coff_function_aux: HashMap<SymbolId, coff::FunctionAux>,
+    /// COFF weak externals added by `add_coff_weak_external`.
+    #[cfg(feature = "coff")]
+    coff_weak_externals: HashMap<SymbolId, (SymbolId, CoffWeakSearch)>,
+    /// The order of the file ranges. Only used if format is COFF.
+    #[cfg(feature = "coff")]
+    coff_layout: CoffLayout,
+    /// Whether the big object format may be used. Only used if format is COFF.
+    #[cfg(feature = "coff")]
+    coff_bigobj: bool,
+    /// The `.drectve` section used by `add_coff_directive`.
+    #[cfg(feature = "coff")]
+    coff_directives: Option<SectionId>,
+    /// Alignment of ELF notes added by `add_elf_note`. Only used if format is ELF.
+    #[cfg(feature = "elf")]
+    elf_note_align: u64,
+    /// ELF sections that are compressed when writing.
+    #[cfg(feature = "elf")]
+    elf_compression: HashMap<SectionId, CompressionFormat>,
+    /// ELF symbol versions added by `add_version_definition` and `add_version_need`.
+    #[cfg(feature = "elf")]
+    elf_versions: Vec<elf::ElfVersion>,
+    /// ELF symbol versions set by `set_symbol_version`, and whether they are hidden.
+    #[cfg(feature = "elf")]
+    elf_symbol_versions: HashMap<SymbolId, (VersionId, bool)>,
+    /// The ELF build ID note added by `add_build_id`.
+    #[cfg(feature = "elf")]
+    elf_build_id: Option<elf::ElfBuildId>,
+    /// The ELF checksum section added by `add_checksum_section`.
+    #[cfg(feature = "elf")]
+    elf_checksum: Option<elf::ElfChecksum>,
+    /// ELF symbol visibilities set by `set_symbol_visibility`.
+    #[cfg(feature = "elf")]
+    elf_visibility: HashMap<SymbolId, ElfVisibility>,
+    /// ELF indirect function symbols set by `set_symbol_ifunc`.
+    #[cfg(feature = "elf")]
+    elf_ifunc: HashSet<SymbolId>,
+    /// ELF sections that are retained by `set_section_retain`.
+    #[cfg(feature = "elf")]
+    elf_retain: HashSet<SectionId>,
+    /// ELF symbols that have their size inferred.
+    #[cfg(feature = "elf")]
+    elf_size_inference: SymbolSizeInference,
+    /// Whether relative relocations are written to `SHT_RELR` sections.
+    #[cfg(feature = "elf")]
+    elf_use_relr: bool,
+    /// ELF section types set by `set_section_type`.
+    #[cfg(feature = "elf")]
+    elf_section_type: HashMap<SectionId, u32>,
+    /// ELF section entry sizes set by `set_section_entsize`.
+    #[cfg(feature = "elf")]
+    elf_section_entsize: HashMap<SectionId, u64>,
+    /// ELF section LMA hints set by `set_section_lma`.
+    #[cfg(feature = "elf")]
+    elf_section_lma: HashMap<SectionId, u64>,
+    /// ELF section addresses set by `set_section_address`.
+    #[cfg(feature = "elf")]
+    elf_section_address: HashMap<SectionId, u64>,
+    /// Whether a static-PIE `ET_DYN` file is written. Set by `set_elf_static_pie`.
+    #[cfg(feature = "elf")]
+    elf_static_pie: bool,
+    /// The ELF entry point set by `set_elf_entry`.
+    #[cfg(feature = "elf")]
+    elf_entry: Option<SymbolId>,
+    /// Whether an `ET_CORE` file is written. Set by `set_elf_core`.
+    #[cfg(feature = "elf")]
+    elf_core: bool,
+    /// The contents of the `PT_NOTE` segment added by `add_core_note`.
+    #[cfg(feature = "elf")]
+    elf_core_notes: Vec<u8>,
     /// Mach-O "_tlv_bootstrap" symbol.
     #[cfg(feature = "macho")]
     tlv_bootstrap: Option<SymbolId>,
@@ -92,11 +182,51 @@ pub struct Object<'a> {
     macho_cpu_subtype: Option<u32>,
     #[cfg(feature = "macho")]
     macho_build_version: Option<MachOBuildVersion>,
+    /// Write the build version as `LC_VERSION_MIN_*`. Set by `set_macho_version_min`.
+    #[cfg(feature = "macho")]
+    macho_version_min: bool,
+    /// Mach-O `LC_ENCRYPTION_INFO` offset, size and ID set by `set_macho_encryption_info`.
+    #[cfg(feature = "macho")]
+    macho_encryption_info: Option<(u32, u32, u32)>,
+    /// Mach-O `LC_UUID` set by `set_macho_uuid`.
+    #[cfg(feature = "macho")]
+    macho_uuid: Option<BuildIdStyle>,
+    /// Mach-O `LC_SEGMENT_SPLIT_INFO` entries set by `set_macho_split_info`.
+    #[cfg(feature = "macho")]
+    macho_split_info: Vec<MachOSplitInfo>,
+    /// Mach-O section attributes added by `add_macho_section_attributes`.
+    #[cfg(feature = "macho")]
+    macho_section_attributes: HashMap<SectionId, u32>,
+    /// Mach-O symbols marked by `set_macho_alt_entry`.
+    #[cfg(feature = "macho")]
+    macho_alt_entry: HashSet<SymbolId>,
+    /// Mach-O indirect symbols set by `set_macho_indirect_symbols`.
+    #[cfg(feature = "macho")]
+    macho_indirect_symbols: HashMap<SectionId, macho::MachOIndirectSymbols>,
+    /// Mach-O `LC_DATA_IN_CODE` entries added by `add_macho_data_in_code`.
+    #[cfg(feature = "macho")]
+    macho_data_in_code: Vec<macho::MachODataInCode>,
     /// Mach-O MH_SUBSECTIONS_VIA_SYMBOLS flag. Only ever set if format is Mach-O.
     #[cfg(feature = "macho")]
     macho_subsections_via_symbols: bool,
+    /// XCOFF auxiliary header set by `set_xcoff_aux_header`.
+    #[cfg(feature = "xcoff")]
+    xcoff_aux_header: Option<XcoffAuxHeader>,
+    /// XCOFF csect symbol types and storage mapping classes set by `set_xcoff_csect`.
+    #[cfg(feature = "xcoff")]
+    xcoff_csect: HashMap<SymbolId, (u8, u8)>,
+    relocation_translator: Option<Arc<dyn RelocationTranslator + Send + Sync>>,
 }
 
+// `Object` must remain usable from multiple threads.
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    #[allow(dead_code)]
+    fn assert_object() {
+        assert_send_sync::<Object<'static>>();
+    }
+};
+
 impl<'a> Object<'a> {
     /// Create an empty object file.
     pub fn new(format: BinaryFormat, architecture: Architecture, endian: Endianness) -> Object<'a> {
@@ -110,10 +240,60 @@ impl<'a> Object<'a> {
             symbols: Vec::new(),
             symbol_map: HashMap::new(),
             comdats: Vec::new(),
+            fixups: Vec::new(),
+            symbol_end_relocations: HashMap::new(),
             flags: FileFlags::None,
             mangling: Mangling::default(format, architecture),
             #[cfg(feature = "coff")]
             stub_symbols: HashMap::new(),
+            #[cfg(feature = "coff")]
+            coff_function_aux: HashMap::new(),
+            #[cfg(feature = "coff")]
+            coff_weak_externals: HashMap::new(),
+            #[cfg(feature = "coff")]
+            coff_layout: CoffLayout::DataFirst,
+            #[cfg(feature = "coff")]
+            coff_bigobj: false,
+            #[cfg(feature = "coff")]
+            coff_directives: None,
+            #[cfg(feature = "elf")]
+            elf_note_align: 4,
+            #[cfg(feature = "elf")]
+            elf_compression: HashMap::new(),
+            #[cfg(feature = "elf")]
+            elf_versions: Vec::new(),
+            #[cfg(feature = "elf")]
+            elf_symbol_versions: HashMap::new(),
+            #[cfg(feature = "elf")]
+            elf_build_id: None,
+            #[cfg(feature = "elf")]
+            elf_checksum: None,
+            #[cfg(feature = "elf")]
+            elf_visibility: HashMap::new(),
+            #[cfg(feature = "elf")]
+            elf_ifunc: HashSet::new(),
+            #[cfg(feature = "elf")]
+            elf_retain: HashSet::new(),
+            #[cfg(feature = "elf")]
+            elf_size_inference: SymbolSizeInference::None,
+            #[cfg(feature = "elf")]
+            elf_use_relr: false,
+            #[cfg(feature = "elf")]
+            elf_section_type: HashMap::new(),
+            #[cfg(feature = "elf")]
+            elf_section_entsize: HashMap::new(),
+            #[cfg(feature = "elf")]
+            elf_section_lma: HashMap::new(),
+            #[cfg(feature = "elf")]
+            elf_section_address: HashMap::new(),
+            #[cfg(feature = "elf")]
+            elf_static_pie: false,
+            #[cfg(feature = "elf")]
+            elf_entry: None,
+            #[cfg(feature = "elf")]
+            elf_core: false,
+            #[cfg(feature = "elf")]
+            elf_core_notes: Vec::new(),
             #[cfg(feature = "macho")]
             tlv_bootstrap: None,
             #[cfg(feature = "macho")]
@@ -121,7 +301,28 @@ impl<'a> Object<'a> {
             #[cfg(feature = "macho")]
             macho_build_version: None,
             #[cfg(feature = "macho")]
+            macho_version_min: false,
+            #[cfg(feature = "macho")]
+            macho_encryption_info: None,
+            #[cfg(feature = "macho")]
+            macho_uuid: None,
+            #[cfg(feature = "macho")]
+            macho_split_info: Vec::new(),
+            #[cfg(feature = "macho")]
+            macho_section_attributes: HashMap::new(),
+            #[cfg(feature = "macho")]
+            macho_alt_entry: HashSet::new(),
+            #[cfg(feature = "macho")]
+            macho_indirect_symbols: HashMap::new(),
+            #[cfg(feature = "macho")]
+            macho_data_in_code: Vec::new(),
+            #[cfg(feature = "macho")]
             macho_subsections_via_symbols: false,
+            #[cfg(feature = "xcoff")]
+            xcoff_aux_header: None,
+            #[cfg(feature = "xcoff")]
+            xcoff_csect: HashMap::new(),
+            relocation_translator: None,
         }
     }
 
@@ -148,6 +349,50 @@ impl<'a> Object<'a> {
         self.sub_architecture = sub_architecture;
     }
 
+    /// Retarget the object to a different architecture and endianness.
+    ///
+    /// If the current mangling is the default for the previous architecture,
+    /// then it is changed to the default for the new architecture. This does
+    /// not change the names of symbols that have already been added.
+    ///
+    /// Existing relocations must still be expressible for the new target.
+    /// If they are not, an error is returned and the object is unchanged.
+    /// Implicit relocation addends are converted to the new endianness, but
+    /// other section data is not.
+    pub fn set_architecture(
+        &mut self,
+        architecture: Architecture,
+        sub_architecture: Option<SubArchitecture>,
+        endian: Endianness,
+    ) -> Result<()> {
+        let previous = (self.architecture, self.sub_architecture, self.endian);
+        let has_relocations = self.sections.iter().any(|s| !s.relocations.is_empty());
+        let previous_target = if has_relocations {
+            Some(self.relocation_target()?)
+        } else {
+            None
+        };
+
+        self.architecture = architecture;
+        self.sub_architecture = sub_architecture;
+        self.endian = endian;
+        if let Some(previous_target) = previous_target {
+            if let Err(error) = self.check_relocation_target(previous_target) {
+                (self.architecture, self.sub_architecture, self.endian) = previous;
+                return Err(error);
+            }
+            let (_, implicit_addend) = previous_target;
+            if implicit_addend && endian != previous.2 {
+                self.swap_relocation_addends()?;
+            }
+        }
+
+        if self.mangling == Mangling::default(self.format, previous.0) {
+            self.mangling = Mangling::default(self.format, architecture);
+        }
+        Ok(())
+    }
+
     /// Return the current mangling setting.
     #[inline]
     pub fn mangling(&self) -> Mangling {
@@ -155,11 +400,68 @@ impl<'a> Object<'a> {
     }
 
     /// Specify the mangling setting.
+    ///
+    /// This only affects symbols that are added later.
+    /// See [`Self::remangle_existing`] to also update existing symbols.
     #[inline]
     pub fn set_mangling(&mut self, mangling: Mangling) {
         self.mangling = mangling;
     }
 
+    /// Specify the mangling setting, and apply it to the symbols that have already been added.
+    ///
+    /// Unlike [`Self::set_mangling`], which only affects symbols that are added later,
+    /// this replaces the global prefix of the existing text, data and TLS symbols.
+    /// The unmangled names used by [`Self::symbol_id`] are unchanged.
+    pub fn remangle_existing(&mut self, mangling: Mangling) {
+        let old_prefix = self.mangling.global_prefix();
+        let new_prefix = mangling.global_prefix();
+        self.mangling = mangling;
+        if old_prefix == new_prefix {
+            return;
+        }
+
+        let mut unmangled_names = HashMap::new();
+        for (name, symbol_id) in &self.symbol_map {
+            unmangled_names.insert(*symbol_id, name);
+        }
+        for (index, symbol) in self.symbols.iter_mut().enumerate() {
+            if symbol.name.is_empty()
+                || !(symbol.kind == SymbolKind::Text
+                    || symbol.kind == SymbolKind::Data
+                    || symbol.kind == SymbolKind::Tls)
+            {
+                continue;
+            }
+            let mut name = match unmangled_names.get(&SymbolId(index)) {
+                Some(name) => name.to_vec(),
+                // Not a symbol added by `add_symbol`, so only strip the prefix if present.
+                None => match old_prefix {
+                    Some(prefix) if symbol.name.first() == Some(&prefix) => {
+                        symbol.name[1..].to_vec()
+                    }
+                    _ => symbol.name.clone(),
+                },
+            };
+            if let Some(prefix) = new_prefix {
+                name.insert(0, prefix);
+            }
+            symbol.name = name;
+        }
+    }
+
+    /// Specify a custom translator for generic relocations.
+    ///
+    /// The translator is consulted by [`Self::add_relocation`] for generic
+    /// relocations that the file format does not natively support for
+    /// the architecture.
+    pub fn set_custom_relocation_translator(
+        &mut self,
+        translator: Box<dyn RelocationTranslator + Send + Sync>,
+    ) {
+        self.relocation_translator = Some(Arc::from(translator));
+    }
+
     /// Return the name for a standard segment.
     ///
     /// This will vary based on the file format.
@@ -188,6 +490,56 @@ impl<'a> Object<'a> {
         &mut self.sections[section.0]
     }
 
+    /// Get the data of the section with the given `SectionId`.
+    ///
+    /// Unlike [`Section::data`], this returns an error for sections that contain
+    /// uninitialized data.
+    pub fn section_data(&self, section: SectionId) -> Result<&[u8]> {
+        let section = &self.sections[section.0];
+        if section.is_bss() {
+            return Err(Error(format!(
+                "section `{}` contains uninitialized data",
+                section.name().unwrap_or("")
+            )));
+        }
+        Ok(&section.data)
+    }
+
+    /// Return the number of sections.
+    #[inline]
+    pub fn section_count(&self) -> usize {
+        self.sections.len()
+    }
+
+    /// Iterate over the sections in the order that they were added.
+    pub fn sections(&self) -> impl Iterator<Item = (SectionId, &Section<'a>)> + '_ {
+        self.sections
+            .iter()
+            .enumerate()
+            .map(|(index, section)| (SectionId(index), section))
+    }
+
+    /// Get the `SectionId` of the first section with the given name.
+    ///
+    /// For Mach-O, section names are only unique within a segment, so
+    /// [`Self::section_by_segment_and_name`] should be used instead.
+    pub fn section_by_name(&self, name: &[u8]) -> Option<SectionId> {
+        self.sections
+            .iter()
+            .position(|section| section.name == name)
+            .map(SectionId)
+    }
+
+    /// Get the `SectionId` of the first section with the given segment and name.
+    ///
+    /// The segment is only used for Mach-O, and is empty for other formats.
+    pub fn section_by_segment_and_name(&self, segment: &[u8], name: &[u8]) -> Option<SectionId> {
+        self.sections
+            .iter()
+            .position(|section| section.segment == segment && section.name == name)
+            .map(SectionId)
+    }
+
     /// Set the data for an existing section.
     ///
     /// Must not be called for sections that already have data, or that contain uninitialized data.
@@ -201,20 +553,247 @@ impl<'a> Object<'a> {
 
     /// Append data to an existing section. Returns the section offset of the data.
     ///
+    /// The section alignment is increased to `align` if required.
+    /// See [`Section::append_data`].
+    ///
     /// Must not be called for sections that contain uninitialized data.
     /// `align` must be a power of two.
     pub fn append_section_data(&mut self, section: SectionId, data: &[u8], align: u64) -> u64 {
         self.sections[section.0].append_data(data, align)
     }
 
+    /// Append a `u32` to an existing section using the given endianness.
+    /// Returns the section offset of the value.
+    ///
+    /// `endian` may differ from the endianness of the object. This is useful for
+    /// data tables that are read with a fixed endianness.
+    /// See [`Self::append_section_data`].
+    pub fn append_section_u32_endian(
+        &mut self,
+        section: SectionId,
+        value: u32,
+        endian: Endianness,
+        align: u64,
+    ) -> u64 {
+        self.append_section_data(section, pod::bytes_of(&U32::new(endian, value)), align)
+    }
+
+    /// Start appending a structure to an existing section.
+    ///
+    /// The start of the structure is aligned to `align`, which must be a power of two
+    /// and at least the alignment of every field that is appended.
+    /// Use the returned [`StructBuilder`] to append the fields.
+    ///
+    /// Must not be called for sections that contain uninitialized data.
+    pub fn append_section_struct(
+        &mut self,
+        section: SectionId,
+        align: u64,
+    ) -> StructBuilder<'_, 'a> {
+        let base = self.append_section_data(section, &[], align);
+        StructBuilder {
+            object: self,
+            section,
+            base,
+            align,
+        }
+    }
+
+    /// Increase the alignment of an existing section.
+    ///
+    /// See [`Section::set_alignment`].
+    pub fn set_section_alignment(&mut self, section: SectionId, align: u64) -> Result<()> {
+        self.sections[section.0].set_alignment(align)
+    }
+
+    /// Reserve capacity for at least `additional` more bytes of data in an existing section.
+    ///
+    /// See [`Section::reserve_data`].
+    pub fn reserve_section_data(&mut self, section: SectionId, additional: usize) {
+        self.sections[section.0].reserve_data(additional)
+    }
+
+    /// Write data to an existing section at the given offset.
+    ///
+    /// See [`Section::write_data_at`].
+    pub fn write_section_data_at(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<()> {
+        self.sections[section.0].write_data_at(offset, data)
+    }
+
+    /// Set the pattern used to fill alignment padding in an existing section.
+    ///
+    /// The fill is used for padding inserted by subsequent calls to
+    /// [`Self::append_section_data`]. The pattern is repeated relative to the start
+    /// of the section, so that multi-byte NOP instructions remain aligned. To also
+    /// pad the end of the section, append empty data with the required alignment.
+    ///
+    /// Returns an error if the section contains uninitialized data, or if
+    /// [`FillPattern::Nop`] is used for an architecture without a known NOP encoding.
+    pub fn set_section_fill(&mut self, section: SectionId, fill: FillPattern) -> Result<()> {
+        self.sections[section.0].check_data(1)?;
+        let fill = match fill {
+            FillPattern::Zero => Vec::new(),
+            FillPattern::Byte(byte) => vec![byte],
+            FillPattern::Nop => self.nop()?,
+        };
+        self.sections[section.0].fill = fill;
+        Ok(())
+    }
+
+    /// Return the encoding of a NOP instruction for the architecture.
+    fn nop(&self) -> Result<Vec<u8>> {
+        let instruction = |value: u32, endian: Endianness| match endian {
+            Endianness::Little => value.to_le_bytes().to_vec(),
+            Endianness::Big => value.to_be_bytes().to_vec(),
+        };
+        Ok(match self.architecture {
+            Architecture::I386 | Architecture::X86_64 | Architecture::X86_64_X32 => vec![0x90],
+            // AArch64, RISC-V and LoongArch instructions are always little endian.
+            Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => {
+                instruction(0xd503_201f, Endianness::Little)
+            }
+            Architecture::Riscv32 | Architecture::Riscv64 => {
+                instruction(0x0000_0013, Endianness::Little)
+            }
+            Architecture::LoongArch32 | Architecture::LoongArch64 => {
+                instruction(0x0340_0000, Endianness::Little)
+            }
+            // `mov r0, r0`
+            Architecture::Arm => instruction(0xe1a0_0000, self.endian),
+            Architecture::Mips | Architecture::Mips64 | Architecture::Mips64_N32 => vec![0; 4],
+            Architecture::PowerPc | Architecture::PowerPc64 => {
+                instruction(0x6000_0000, self.endian)
+            }
+            Architecture::Sparc | Architecture::Sparc32Plus | Architecture::Sparc64 => {
+                instruction(0x0100_0000, Endianness::Big)
+            }
+            // `bcr 0, %r7`
+            Architecture::S390x => vec![0x07, 0x07],
+            _ => {
+                return Err(Error(format!(
+                    "unsupported NOP fill for architecture {:?}",
+                    self.architecture
+                )));
+            }
+        })
+    }
+
     /// Append zero-initialized data to an existing section. Returns the section offset of the data.
     ///
+    /// The section alignment is increased to `align` if required.
+    ///
     /// Must not be called for sections that contain initialized data.
     /// `align` must be a power of two.
     pub fn append_section_bss(&mut self, section: SectionId, size: u64, align: u64) -> u64 {
         self.sections[section.0].append_bss(size, align)
     }
 
+    /// Set the data for an existing section, checking the arguments.
+    ///
+    /// See [`Section::try_set_data`].
+    pub fn try_set_section_data<T>(&mut self, section: SectionId, data: T, align: u64) -> Result<()>
+    where
+        T: Into<Cow<'a, [u8]>>,
+    {
+        self.sections[section.0].try_set_data(data, align)
+    }
+
+    /// Append data to an existing section, checking the arguments.
+    /// Returns the section offset of the data.
+    ///
+    /// See [`Section::try_append_data`].
+    pub fn try_append_section_data(
+        &mut self,
+        section: SectionId,
+        data: &[u8],
+        align: u64,
+    ) -> Result<u64> {
+        self.sections[section.0].try_append_data(data, align)
+    }
+
+    /// Append zero-initialized data to an existing section, checking the arguments.
+    /// Returns the section offset of the data.
+    ///
+    /// See [`Section::try_append_bss`].
+    pub fn try_append_section_bss(
+        &mut self,
+        section: SectionId,
+        size: u64,
+        align: u64,
+    ) -> Result<u64> {
+        self.sections[section.0].try_append_bss(size, align)
+    }
+
+    /// Reserve a field in the data of a section so that it can be patched later.
+    ///
+    /// `size` is the size of the field in bits, and must be 8, 16, 32 or 64.
+    /// The field must be within the existing data of the section.
+    ///
+    /// Use [`Self::patch_fixup`] to write the value of the field.
+    pub fn reserve_fixup(&mut self, section: SectionId, offset: u64, size: u8) -> Result<FixupId> {
+        if !matches!(size, 8 | 16 | 32 | 64) {
+            return Err(Error(format!("unsupported fixup size {}", size)));
+        }
+        let fixup = Fixup {
+            section,
+            offset,
+            size,
+        };
+        self.check_fixup(&fixup)?;
+        self.fixups.push(fixup);
+        Ok(FixupId(self.fixups.len() - 1))
+    }
+
+    /// Write the value of a field that was reserved by [`Self::reserve_fixup`].
+    ///
+    /// The value is written using the endianness of the object.
+    /// Returns an error if the value does not fit in the field.
+    pub fn patch_fixup(&mut self, fixup: FixupId, value: u64) -> Result<()> {
+        let fixup = &self.fixups[fixup.0];
+        if fixup.size < 64 && value >> fixup.size != 0 {
+            return Err(Error(format!(
+                "fixup value {:#x} does not fit in {} bits",
+                value, fixup.size
+            )));
+        }
+        self.check_fixup(fixup)?;
+        let offset = fixup.offset as usize;
+        let endian = self.endian;
+        let data = self.sections[fixup.section.0].data_mut();
+        match fixup.size {
+            8 => data.write_at(offset, &(value as u8)),
+            16 => data.write_at(offset, &U16::new(endian, value as u16)),
+            32 => data.write_at(offset, &U32::new(endian, value as u32)),
+            _ => data.write_at(offset, &U64::new(endian, value)),
+        }
+        .map_err(|_| Error(format!("invalid fixup offset {}", offset)))
+    }
+
+    fn check_fixup(&self, fixup: &Fixup) -> Result<()> {
+        let section = &self.sections[fixup.section.0];
+        let len = if section.is_bss() {
+            0
+        } else {
+            section.data.len()
+        };
+        let end = fixup.offset.checked_add(u64::from(fixup.size / 8));
+        if end.map_or(true, |end| end > len as u64) {
+            return Err(Error(format!(
+                "invalid fixup offset {}+{} (max {}) in section `{}`",
+                fixup.offset,
+                fixup.size / 8,
+                len,
+                section.name().unwrap_or("")
+            )));
+        }
+        Ok(())
+    }
+
     /// Return the `SectionId` of a standard section.
     ///
     /// If the section doesn't already exist then it is created.
@@ -243,7 +822,9 @@ impl<'a> Object<'a> {
             align: 1,
             data: Cow::Borrowed(&[]),
             relocations: Vec::new(),
+            relocation_content: Vec::new(),
             symbol: None,
+            fill: Vec::new(),
             flags: SectionFlags::None,
         });
 
@@ -278,6 +859,33 @@ impl<'a> Object<'a> {
         }
     }
 
+    /// Return the `SectionId` of a DWARF section.
+    ///
+    /// `name` is the conventional ELF name of the section, such as `.debug_info`.
+    /// This is converted to the naming convention of the file format. For Mach-O,
+    /// the section is placed in the `__DWARF` segment, and the name is changed to
+    /// the form `__debug_info`, truncated to 16 bytes.
+    ///
+    /// If the section doesn't already exist then it is created.
+    pub fn add_dwarf_section(&mut self, name: &[u8]) -> SectionId {
+        let kind = match name {
+            b".debug_str" | b".debug_line_str" => SectionKind::DebugString,
+            _ => SectionKind::Debug,
+        };
+        let (segment, name) = match self.format {
+            #[cfg(feature = "macho")]
+            BinaryFormat::MachO => (
+                self.segment_name(StandardSegment::Debug).to_vec(),
+                self.macho_dwarf_section_name(name),
+            ),
+            _ => (Vec::new(), name.to_vec()),
+        };
+        if let Some(id) = self.section_by_segment_and_name(&segment, &name) {
+            return id;
+        }
+        self.add_section(segment, name, kind)
+    }
+
     /// Add a subsection. Returns the `SectionId` and section offset of the data.
     ///
     /// For Mach-O, this does not create a subsection, and instead uses the
@@ -388,6 +996,55 @@ impl<'a> Object<'a> {
         comdat_id
     }
 
+    /// Add sections to the COMDAT section group of a leader section.
+    ///
+    /// `leader` must be the section containing the symbol of a COMDAT. The
+    /// `associated` sections are added to that COMDAT, so that the linker only
+    /// keeps them if it keeps the leader. For COFF, each associated section is written
+    /// as an associative COMDAT section that refers to the leader, and a section
+    /// symbol is created for each associated section if it does not already exist.
+    ///
+    /// Returns an error if `leader` is not the section of a COMDAT symbol, or if
+    /// an associated section is already in a COMDAT.
+    pub fn associate_sections(
+        &mut self,
+        leader: SectionId,
+        associated: &[SectionId],
+    ) -> Result<()> {
+        let comdat = self
+            .comdats
+            .iter()
+            .position(|comdat| {
+                self.symbols[comdat.symbol.0].section == SymbolSection::Section(leader)
+            })
+            .ok_or_else(|| {
+                Error(format!(
+                    "section `{}` is not a COMDAT leader",
+                    self.sections[leader.0].name().unwrap_or("")
+                ))
+            })?;
+        for (index, section) in associated.iter().enumerate() {
+            if self
+                .comdats
+                .iter()
+                .any(|comdat| comdat.sections.contains(section))
+                || associated[..index].contains(section)
+            {
+                return Err(Error(format!(
+                    "section `{}` is already in a COMDAT",
+                    self.sections[section.0].name().unwrap_or("")
+                )));
+            }
+        }
+        for &section in associated {
+            if self.format == BinaryFormat::Coff {
+                self.section_symbol(section);
+            }
+            self.comdats[comdat].sections.push(section);
+        }
+        Ok(())
+    }
+
     /// Get the `SymbolId` of the symbol with the given name.
     pub fn symbol_id(&self, name: &[u8]) -> Option<SymbolId> {
         self.symbol_map.get(name).cloned()
@@ -405,12 +1062,45 @@ impl<'a> Object<'a> {
         &mut self.symbols[symbol.0]
     }
 
+    /// Return the number of symbols.
+    ///
+    /// This includes section symbols and any other symbols that were added
+    /// automatically.
+    #[inline]
+    pub fn symbol_count(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Iterate over the symbols in the order that they were added.
+    pub fn symbols(&self) -> impl Iterator<Item = (SymbolId, &Symbol)> + '_ {
+        self.symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| (SymbolId(index), symbol))
+    }
+
+    /// Return an iterator over the symbols that are exported.
+    ///
+    /// See [`Symbol::is_exported`].
+    pub fn exported_symbols(&self) -> impl Iterator<Item = (SymbolId, &Symbol)> + '_ {
+        self.symbols().filter(|(_, symbol)| symbol.is_exported())
+    }
+
     /// Add a new symbol and return its `SymbolId`.
     ///
     /// If the symbol is a section symbol that is already defined,
     /// it will update the flags of the existing section symbol
     /// instead of creating adding a new symbol.
     ///
+    /// If the symbol is undefined, and a non-local symbol with the same name
+    /// and kind has already been added, then the `SymbolId` of the existing
+    /// symbol is returned instead of adding a duplicate symbol. This applies
+    /// even if the existing symbol has since been defined. Symbols with
+    /// [`SymbolScope::Compilation`] are never merged. If the existing symbol
+    /// is a weak undefined symbol and the new symbol is not weak, then the
+    /// existing symbol is changed to a strong reference, since a strong reference
+    /// requires the symbol to be defined.
+    ///
     /// The symbol name will be modified to include the global prefix
     /// if the mangling scheme has one.
     pub fn add_symbol(&mut self, mut symbol: Symbol) -> SymbolId {
@@ -430,6 +1120,27 @@ impl<'a> Object<'a> {
                 || symbol.kind == SymbolKind::Data
                 || symbol.kind == SymbolKind::Tls)
         {
+            if symbol.is_undefined() && symbol.scope != SymbolScope::Compilation {
+                if let Some(&symbol_id) = self.symbol_map.get(&symbol.name) {
+                    // A strong reference does not make a COFF weak external strong,
+                    // since the weak external has its own default symbol.
+                    #[cfg(feature = "coff")]
+                    let weak_external = self.coff_weak_externals.contains_key(&symbol_id);
+                    #[cfg(not(feature = "coff"))]
+                    let weak_external = false;
+                    let existing = &mut self.symbols[symbol_id.0];
+                    if existing.kind == symbol.kind && existing.scope != SymbolScope::Compilation {
+                        if existing.is_undefined()
+                            && existing.weak
+                            && !symbol.weak
+                            && !weak_external
+                        {
+                            existing.weak = false;
+                        }
+                        return symbol_id;
+                    }
+                }
+            }
             let unmangled_name = symbol.name.clone();
             if let Some(prefix) = self.mangling.global_prefix() {
                 symbol.name.insert(0, prefix);
@@ -442,6 +1153,72 @@ impl<'a> Object<'a> {
         }
     }
 
+    /// Add multiple symbols.
+    ///
+    /// Each symbol is added as for [`Self::add_symbol`], including merging of
+    /// undefined symbols and mangling of names. The returned ids are in the same
+    /// order as `symbols`.
+    pub fn add_symbols(&mut self, symbols: Vec<Symbol>) -> Vec<SymbolId> {
+        self.symbols.reserve(symbols.len());
+        self.symbol_map.reserve(symbols.len());
+        symbols
+            .into_iter()
+            .map(|symbol| self.add_symbol(symbol))
+            .collect()
+    }
+
+    /// Change the name of a symbol.
+    ///
+    /// `name` is the unmangled name. As for [`Self::add_symbol`], the global
+    /// prefix is added if the mangling scheme has one, and the name is used
+    /// for lookups by [`Self::symbol_id`].
+    ///
+    /// Returns an error if the symbol is a section symbol, or if another
+    /// symbol with the new name has already been added.
+    pub fn rename_symbol(&mut self, symbol_id: SymbolId, mut name: Vec<u8>) -> Result<()> {
+        let symbol = &self.symbols[symbol_id.0];
+        if symbol.kind == SymbolKind::Section {
+            return Err(Error(String::from("cannot rename section symbol")));
+        }
+        if !(symbol.kind == SymbolKind::Text
+            || symbol.kind == SymbolKind::Data
+            || symbol.kind == SymbolKind::Tls)
+        {
+            self.symbols[symbol_id.0].name = name;
+            return Ok(());
+        }
+        if let Some(&existing) = self.symbol_map.get(&name) {
+            if existing != symbol_id {
+                return Err(Error(format!(
+                    "symbol `{}` already exists",
+                    String::from_utf8_lossy(&name)
+                )));
+            }
+        }
+
+        // Remove the old name from the symbol map.
+        let prefix = self.mangling.global_prefix();
+        let old_name = match (prefix, symbol.name.split_first()) {
+            (Some(prefix), Some((&first, rest))) if first == prefix => rest,
+            _ => &symbol.name[..],
+        };
+        if self.symbol_map.get(old_name) == Some(&symbol_id) {
+            self.symbol_map.remove(old_name);
+        } else {
+            // The mangling may have changed since the symbol was added.
+            self.symbol_map.retain(|_, id| *id != symbol_id);
+        }
+
+        if !name.is_empty() {
+            self.symbol_map.insert(name.clone(), symbol_id);
+            if let Some(prefix) = prefix {
+                name.insert(0, prefix);
+            }
+        }
+        self.symbols[symbol_id.0].name = name;
+        Ok(())
+    }
+
     fn add_raw_symbol(&mut self, symbol: Symbol) -> SymbolId {
         let symbol_id = SymbolId(self.symbols.len());
         self.symbols.push(symbol);
@@ -515,17 +1292,99 @@ impl<'a> Object<'a> {
         }
     }
 
-    /// Add a new file symbol and return its `SymbolId`.
-    pub fn add_file_symbol(&mut self, name: Vec<u8>) -> SymbolId {
-        self.add_raw_symbol(Symbol {
-            name,
-            value: 0,
-            size: 0,
-            kind: SymbolKind::File,
-            scope: SymbolScope::Compilation,
-            weak: false,
-            section: SymbolSection::None,
-            flags: SymbolFlags::None,
+    /// Add a weak symbol and a strong symbol at the location of `target`.
+    ///
+    /// This is the pattern used by C libraries that define a weak public name and a
+    /// strong internal name for the same function, such as `read` and `__read`.
+    /// The weak symbol can be overridden by another definition, while the strong
+    /// symbol always refers to this definition.
+    ///
+    /// Both symbols copy the kind, section, value and size of `target`. They have
+    /// the scope of `target`, or [`SymbolScope::Linkage`] if `target` is local.
+    /// The weak symbol is written with the weak binding for the file format, such as
+    /// `STB_WEAK` for ELF or `N_WEAK_DEF` for Mach-O.
+    ///
+    /// Existing undefined symbols with either name are defined by this call.
+    ///
+    /// Returns the IDs of the weak and the strong symbols, in that order.
+    /// Returns an error if `target` is not a text, data or TLS symbol that is
+    /// defined in a section, or if a symbol with either name is already defined.
+    pub fn add_weak_strong_pair(
+        &mut self,
+        weak_name: &[u8],
+        strong_name: &[u8],
+        target: SymbolId,
+    ) -> Result<(SymbolId, SymbolId)> {
+        let target = self.symbol(target);
+        if !matches!(
+            target.kind,
+            SymbolKind::Text | SymbolKind::Data | SymbolKind::Tls
+        ) || !matches!(target.section, SymbolSection::Section(_))
+        {
+            return Err(Error(format!(
+                "invalid weak and strong pair target `{}` kind {:?} section {:?}",
+                target.name().unwrap_or(""),
+                target.kind,
+                target.section
+            )));
+        }
+        for name in [weak_name, strong_name] {
+            if let Some(&symbol_id) = self.symbol_map.get(name) {
+                if !self.symbol(symbol_id).is_undefined() {
+                    return Err(Error(format!(
+                        "symbol `{}` is already defined",
+                        String::from_utf8_lossy(name)
+                    )));
+                }
+            }
+        }
+        let scope = match target.scope {
+            SymbolScope::Dynamic => SymbolScope::Dynamic,
+            _ => SymbolScope::Linkage,
+        };
+        let symbol = Symbol {
+            name: Vec::new(),
+            value: target.value,
+            size: target.size,
+            kind: target.kind,
+            scope,
+            weak: false,
+            section: target.section,
+            flags: SymbolFlags::None,
+        };
+        let mut add_symbol = |name: &[u8], weak: bool| {
+            // Define an existing undefined symbol instead of adding a duplicate.
+            if let Some(&symbol_id) = self.symbol_map.get(name) {
+                let existing = &mut self.symbols[symbol_id.0];
+                *existing = Symbol {
+                    name: mem::take(&mut existing.name),
+                    weak,
+                    ..symbol.clone()
+                };
+                return symbol_id;
+            }
+            self.add_symbol(Symbol {
+                name: name.to_vec(),
+                weak,
+                ..symbol.clone()
+            })
+        };
+        let weak = add_symbol(weak_name, true);
+        let strong = add_symbol(strong_name, false);
+        Ok((weak, strong))
+    }
+
+    /// Add a new file symbol and return its `SymbolId`.
+    pub fn add_file_symbol(&mut self, name: Vec<u8>) -> SymbolId {
+        self.add_raw_symbol(Symbol {
+            name,
+            value: 0,
+            size: 0,
+            kind: SymbolKind::File,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: SymbolSection::None,
+            flags: SymbolFlags::None,
         })
     }
 
@@ -583,6 +1442,25 @@ impl<'a> Object<'a> {
         offset
     }
 
+    /// Append data to an existing section, and update a symbol to refer to it,
+    /// checking the arguments.
+    ///
+    /// Returns an error if the section contains uninitialized data, if `align` is
+    /// not a power of two, or if [`Self::try_set_symbol_data`] returns an error.
+    ///
+    /// See [`Self::add_symbol_data`].
+    pub fn try_add_symbol_data(
+        &mut self,
+        symbol_id: SymbolId,
+        section: SectionId,
+        data: &[u8],
+        align: u64,
+    ) -> Result<u64> {
+        self.section(section).check_data(align)?;
+        self.check_symbol_data(symbol_id, section)?;
+        Ok(self.add_symbol_data(symbol_id, section, data, align))
+    }
+
     /// Append zero-initialized data to an existing section, and update a symbol to refer to it.
     ///
     /// For Mach-O, this also creates a `__thread_vars` entry for TLS symbols, and the
@@ -611,6 +1489,32 @@ impl<'a> Object<'a> {
         offset
     }
 
+    /// Append zero-initialized data to an existing section, and update a symbol to
+    /// refer to it, checking the arguments.
+    ///
+    /// Returns an error if the section contains initialized data, if `align` is
+    /// not a power of two, or if [`Self::try_set_symbol_data`] returns an error.
+    ///
+    /// See [`Self::add_symbol_bss`].
+    pub fn try_add_symbol_bss(
+        &mut self,
+        symbol_id: SymbolId,
+        section: SectionId,
+        size: u64,
+        align: u64,
+    ) -> Result<u64> {
+        let section_ref = self.section(section);
+        section_ref.check_align(align)?;
+        if !section_ref.is_bss() {
+            return Err(Error(format!(
+                "cannot append uninitialized data to section `{}`",
+                section_ref.name().unwrap_or("")
+            )));
+        }
+        self.check_symbol_data(symbol_id, section)?;
+        Ok(self.add_symbol_bss(symbol_id, section, size, align))
+    }
+
     /// Update a symbol to refer to the given data within a section.
     ///
     /// For Mach-O, this also creates a `__thread_vars` entry for TLS symbols, and the
@@ -636,6 +1540,38 @@ impl<'a> Object<'a> {
         symbol.section = SymbolSection::Section(section);
     }
 
+    /// Update a symbol to refer to the given data within a section, checking the arguments.
+    ///
+    /// Returns an error if the symbol is a text symbol and the section contains
+    /// uninitialized data.
+    ///
+    /// See [`Self::set_symbol_data`].
+    pub fn try_set_symbol_data(
+        &mut self,
+        symbol_id: SymbolId,
+        section: SectionId,
+        offset: u64,
+        size: u64,
+    ) -> Result<()> {
+        self.check_symbol_data(symbol_id, section)?;
+        self.set_symbol_data(symbol_id, section, offset, size);
+        Ok(())
+    }
+
+    /// Check that the kind of a symbol is valid for the kind of its section.
+    fn check_symbol_data(&self, symbol_id: SymbolId, section: SectionId) -> Result<()> {
+        let symbol = self.symbol(symbol_id);
+        let section = self.section(section);
+        if symbol.kind == SymbolKind::Text && section.is_bss() {
+            return Err(Error(format!(
+                "text symbol `{}` cannot refer to uninitialized section `{}`",
+                symbol.name().unwrap_or(""),
+                section.name().unwrap_or("")
+            )));
+        }
+        Ok(())
+    }
+
     /// Convert a symbol to a section symbol and offset.
     ///
     /// Returns `None` if the symbol does not have a section.
@@ -654,73 +1590,1440 @@ impl<'a> Object<'a> {
     ///
     /// Relocations must only be added after the referenced symbols have been added
     /// and defined (if applicable).
+    ///
+    /// For PC-relative relocations, the addend is relative to the place of the
+    /// relocation, so it must include any bias due to the processor using a
+    /// different address for the PC. See [`Self::add_pc_relative_relocation`]
+    /// for a way to have this bias applied automatically.
     pub fn add_relocation(&mut self, section: SectionId, mut relocation: Relocation) -> Result<()> {
+        let content = (relocation.flags, relocation.addend);
+        self.prepare_relocation(section, &mut relocation)?;
+        let section = &mut self.sections[section.0];
+        section.relocations.push(relocation);
+        section.relocation_content.push(content);
+        Ok(())
+    }
+
+    /// Add multiple relocations to a section.
+    ///
+    /// This is equivalent to calling [`Self::add_relocation`] for each relocation,
+    /// except that space for the relocations is reserved first. If an error is
+    /// returned, then the relocations prior to the one that caused the error
+    /// will have been added.
+    pub fn add_relocations<I>(&mut self, section: SectionId, relocations: I) -> Result<()>
+    where
+        I: IntoIterator<Item = Relocation>,
+    {
+        let relocations = relocations.into_iter();
+        self.sections[section.0]
+            .relocations
+            .reserve(relocations.size_hint().0);
+        for mut relocation in relocations {
+            let content = (relocation.flags, relocation.addend);
+            self.prepare_relocation(section, &mut relocation)?;
+            let section = &mut self.sections[section.0];
+            section.relocations.push(relocation);
+            section.relocation_content.push(content);
+        }
+        Ok(())
+    }
+
+    /// Add multiple relocations with format specific flags to a section.
+    ///
+    /// Unlike [`Self::add_relocations`], the relocations are added as-is:
+    /// the flags are not translated, and the addends are not adjusted or written
+    /// to the section data. The flags must be the variant for the object's format,
+    /// such as [`RelocationFlags::Elf`] for ELF. For formats that use implicit
+    /// addends, the addend must already be present in the section data, and
+    /// the `addend` field must be 0.
+    ///
+    /// Returns an error, without adding any relocations, if a relocation has flags
+    /// for a different format, an offset outside the section, a field of known size
+    /// that extends past the end of the section, an invalid symbol, or a non-zero
+    /// addend for a relocation that uses an implicit addend.
+    pub fn add_relocations_raw(
+        &mut self,
+        section: SectionId,
+        relocations: Vec<Relocation>,
+    ) -> Result<()> {
+        let format = self.format;
+        let size = self.sections[section.0].size;
+        let symbol_count = self.symbols.len();
+        // Whether all relocations use implicit addends, or `None` if this
+        // depends on the relocation type.
+        let implicit_addend = match format {
+            #[cfg(feature = "elf")]
+            BinaryFormat::Elf => Some(!self.elf_has_relocation_addend()?),
+            BinaryFormat::Coff | BinaryFormat::Xcoff => Some(true),
+            _ => None,
+        };
+        for relocation in &relocations {
+            let native = match relocation.flags {
+                RelocationFlags::Coff { .. } => format == BinaryFormat::Coff,
+                RelocationFlags::Elf { .. } => format == BinaryFormat::Elf,
+                RelocationFlags::MachO { .. } => format == BinaryFormat::MachO,
+                RelocationFlags::Xcoff { .. } => format == BinaryFormat::Xcoff,
+                _ => false,
+            };
+            if !native {
+                return Err(Error(format!(
+                    "invalid relocation flags {:?} for {:?}",
+                    relocation.flags, format
+                )));
+            }
+            // The whole field must be inside the section if its size is known.
+            let field_size = self
+                .relocation_size(relocation)
+                .map_or(1, |bits| (u64::from(bits) + 7) / 8);
+            if relocation
+                .offset
+                .checked_add(field_size)
+                .map_or(true, |end| end > size)
+            {
+                return Err(Error(format!(
+                    "relocation offset {:#x} is outside section `{}`",
+                    relocation.offset,
+                    self.sections[section.0].name().unwrap_or("")
+                )));
+            }
+            if relocation.symbol.0 >= symbol_count {
+                return Err(Error(format!(
+                    "invalid relocation symbol {:?}",
+                    relocation.symbol
+                )));
+            }
+            if relocation.addend != 0 {
+                let implicit = match implicit_addend {
+                    Some(implicit) => implicit,
+                    #[cfg(feature = "macho")]
+                    None if format == BinaryFormat::MachO => {
+                        self.macho_adjust_addend(&mut relocation.clone())?
+                    }
+                    None => true,
+                };
+                if implicit {
+                    return Err(Error(format!(
+                        "invalid addend {} for relocation at offset {:#x} with implicit addend",
+                        relocation.addend, relocation.offset
+                    )));
+                }
+            }
+        }
+        let section = &mut self.sections[section.0];
+        section
+            .relocation_content
+            .extend(relocations.iter().map(|r| (r.flags, r.addend)));
+        if section.relocations.is_empty() {
+            section.relocations = relocations;
+        } else {
+            section.relocations.extend(relocations);
+        }
+        Ok(())
+    }
+
+    /// Translate the relocation flags, and write any implicit addend.
+    ///
+    /// Returns true if the addend is implicit.
+    fn prepare_relocation(
+        &mut self,
+        section: SectionId,
+        relocation: &mut Relocation,
+    ) -> Result<bool> {
+        let translated: Result<()> = match self.format {
+            #[cfg(feature = "coff")]
+            BinaryFormat::Coff => self.coff_translate_relocation(relocation),
+            #[cfg(feature = "elf")]
+            BinaryFormat::Elf => self.elf_translate_relocation(relocation),
+            #[cfg(feature = "macho")]
+            BinaryFormat::MachO => self.macho_translate_relocation(relocation),
+            #[cfg(feature = "xcoff")]
+            BinaryFormat::Xcoff => self.xcoff_translate_relocation(relocation),
+            _ => unimplemented!(),
+        };
+        if let Err(error) = translated {
+            relocation.flags = self.custom_translate_relocation(relocation).ok_or(error)?;
+        }
+        let implicit = match self.format {
+            #[cfg(feature = "coff")]
+            BinaryFormat::Coff => self.coff_adjust_addend(relocation)?,
+            #[cfg(feature = "elf")]
+            BinaryFormat::Elf => self.elf_adjust_addend(relocation)?,
+            #[cfg(feature = "macho")]
+            BinaryFormat::MachO => self.macho_adjust_addend(relocation)?,
+            #[cfg(feature = "xcoff")]
+            BinaryFormat::Xcoff => self.xcoff_adjust_addend(relocation)?,
+            _ => unimplemented!(),
+        };
+        if implicit && relocation.addend != 0 {
+            self.write_relocation_addend(section, relocation)?;
+            relocation.addend = 0;
+        }
+        Ok(implicit)
+    }
+
+    /// Add a relocation whose addend is relative to the end of its symbol.
+    ///
+    /// The relocation resolves to `symbol + symbol_size + addend`, where
+    /// `symbol_size` is the size of `relocation.symbol` when the object is
+    /// written, rather than when the relocation is added. This allows referencing
+    /// the end of a symbol whose data is still being appended.
+    ///
+    /// Returns an error for section symbols.
+    pub fn add_symbol_end_relocation(
+        &mut self,
+        section: SectionId,
+        mut relocation: Relocation,
+    ) -> Result<()> {
+        let symbol = self.symbol(relocation.symbol);
+        if symbol.kind == SymbolKind::Section {
+            return Err(Error(format!(
+                "unsupported end relocation for section symbol `{}`",
+                symbol.name().unwrap_or("")
+            )));
+        }
+        let content = (relocation.flags, relocation.addend);
+        let implicit = self.prepare_relocation(section, &mut relocation)?;
+        let relocations = &mut self.sections[section.0].relocations;
+        self.symbol_end_relocations
+            .insert((section, relocations.len()), implicit);
+        relocations.push(relocation);
+        self.sections[section.0].relocation_content.push(content);
+        Ok(())
+    }
+
+    /// Return the explicit addend to write for a relocation.
+    ///
+    /// This adds the symbol size for relocations added by
+    /// [`Self::add_symbol_end_relocation`].
+    #[cfg(any(feature = "elf", feature = "macho"))]
+    fn relocation_write_addend(&self, section: SectionId, index: usize) -> i64 {
+        let relocation = &self.sections[section.0].relocations[index];
+        match self.symbol_end_relocations.get(&(section, index)) {
+            Some(false) => relocation
+                .addend
+                .wrapping_add(self.symbols[relocation.symbol.0].size as i64),
+            _ => relocation.addend,
+        }
+    }
+
+    /// Return the data to write for a section.
+    ///
+    /// This adds the symbol sizes to the implicit addends of relocations added by
+    /// [`Self::add_symbol_end_relocation`].
+    fn section_write_data(&self, section: SectionId) -> Result<Cow<'_, [u8]>> {
+        let data = &*self.sections[section.0].data;
+        if self.symbol_end_relocations.is_empty() {
+            return Ok(Cow::Borrowed(data));
+        }
+        let mut patched: Option<Vec<u8>> = None;
+        for (index, relocation) in self.sections[section.0].relocations.iter().enumerate() {
+            if self.symbol_end_relocations.get(&(section, index)) != Some(&true) {
+                continue;
+            }
+            let patched = patched.get_or_insert_with(|| data.to_vec());
+            let offset = relocation.offset as usize;
+            let addend = match self.relocation_size(relocation)? {
+                8 => patched.get(offset).map(|x| i64::from(*x as i8)),
+                16 => patched
+                    .get(offset..)
+                    .and_then(|data| pod::from_bytes::<U16<Endianness>>(data).ok())
+                    .map(|(x, _)| i64::from(x.get(self.endian) as i16)),
+                32 => patched
+                    .get(offset..)
+                    .and_then(|data| pod::from_bytes::<U32<Endianness>>(data).ok())
+                    .map(|(x, _)| i64::from(x.get(self.endian) as i32)),
+                64 => patched
+                    .get(offset..)
+                    .and_then(|data| pod::from_bytes::<U64<Endianness>>(data).ok())
+                    .map(|(x, _)| x.get(self.endian) as i64),
+                _ => None,
+            };
+            let addend = addend.ok_or_else(|| {
+                Error(format!("unimplemented relocation addend {:?}", relocation))
+            })?;
+            let size = self.symbols[relocation.symbol.0].size;
+            let relocation = Relocation {
+                addend: addend.wrapping_add(size as i64),
+                ..relocation.clone()
+            };
+            self.write_addend(patched, section, &relocation)?;
+        }
+        Ok(patched.map_or(Cow::Borrowed(data), Cow::Owned))
+    }
+
+    /// Add a PC-relative relocation to a section.
+    ///
+    /// Unlike [`Self::add_relocation`], `addend` is the offset of the target
+    /// from `symbol`, and does not include any bias for the PC. This function
+    /// adds the bias returned by [`Self::pc_relative_bias`] to `addend` and then
+    /// adds a generic [`RelocationKind::Relative`] relocation.
+    pub fn add_pc_relative_relocation(
+        &mut self,
+        section: SectionId,
+        offset: u64,
+        symbol: SymbolId,
+        addend: i64,
+        encoding: RelocationEncoding,
+        size: u8,
+    ) -> Result<()> {
+        self.add_relocation(
+            section,
+            Relocation {
+                offset,
+                symbol,
+                addend: addend + self.pc_relative_bias(encoding, size),
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Relative,
+                    encoding,
+                    size,
+                },
+            },
+        )
+    }
+
+    /// Return the bias that must be added to the addend of a PC-relative relocation.
+    ///
+    /// This is the difference between the place of the relocation and the
+    /// address that the processor uses as the PC when evaluating an instruction
+    /// with the given encoding, and so it depends on both the architecture and
+    /// the encoding:
+    ///
+    /// - On x86, the PC is the address of the next instruction. This assumes
+    ///   that the relocated field is the last field of the instruction, so the
+    ///   bias is the negated size of the field, for all encodings.
+    /// - On ARM, the PC is the address of the instruction plus 8, so the bias is -8.
+    ///   This assumes the instruction uses the A32 instruction set. The bias for
+    ///   T32 (Thumb) instructions is -4, and is not returned by this function.
+    /// - On s390x, the PC is the address of the instruction. For
+    ///   [`RelocationEncoding::S390xDbl`], this assumes that the relocated field
+    ///   is at offset 2 of the instruction, so the bias is 2.
+    /// - Otherwise the bias is 0. This includes AArch64, where the PC is the address
+    ///   of the instruction, and s390x data relocations.
+    pub fn pc_relative_bias(&self, encoding: RelocationEncoding, size: u8) -> i64 {
+        match self.architecture {
+            Architecture::I386 | Architecture::X86_64 | Architecture::X86_64_X32 => {
+                -i64::from(size / 8)
+            }
+            Architecture::Arm => -8,
+            Architecture::S390x if encoding == RelocationEncoding::S390xDbl => 2,
+            _ => 0,
+        }
+    }
+
+    /// Return the relocations that have been added to a section.
+    ///
+    /// See [`Section::relocations`].
+    #[inline]
+    pub fn section_relocations(&self, section: SectionId) -> &[Relocation] {
+        self.sections[section.0].relocations()
+    }
+
+    /// Remove a relocation from a section, and return it.
+    ///
+    /// `index` is the index of the relocation in [`Self::section_relocations`].
+    /// The indices of all later relocations in the section are decreased by one.
+    ///
+    /// If the relocation had an implicit addend, then it remains in the section data.
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_relocation(&mut self, section: SectionId, index: usize) -> Relocation {
+        let relocation = self.sections[section.0].relocations.remove(index);
+        self.sections[section.0].relocation_content.remove(index);
+        if !self.symbol_end_relocations.is_empty() {
+            self.symbol_end_relocations = mem::take(&mut self.symbol_end_relocations)
+                .into_iter()
+                .filter_map(|((end_section, end_index), implicit)| {
+                    if end_section != section || end_index < index {
+                        Some(((end_section, end_index), implicit))
+                    } else if end_index > index {
+                        Some(((end_section, end_index - 1), implicit))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+        }
+        relocation
+    }
+
+    fn custom_translate_relocation(&self, relocation: &Relocation) -> Option<RelocationFlags> {
+        let translator = self.relocation_translator.as_ref()?;
+        if let RelocationFlags::Generic {
+            kind,
+            encoding,
+            size,
+        } = relocation.flags
+        {
+            translator.translate_relocation(self.format, kind, encoding, size)
+        } else {
+            None
+        }
+    }
+
+    fn write_relocation_addend(
+        &mut self,
+        section: SectionId,
+        relocation: &Relocation,
+    ) -> Result<()> {
+        let mut data = mem::take(&mut self.sections[section.0].data);
+        let result = self.write_addend(data.to_mut(), section, relocation);
+        self.sections[section.0].data = data;
+        result
+    }
+
+    /// Write the addend of a relocation to the given data for a section.
+    fn write_addend(
+        &self,
+        data: &mut [u8],
+        section: SectionId,
+        relocation: &Relocation,
+    ) -> Result<()> {
+        let size = self.relocation_size(relocation)?;
+        // Allow both signed and unsigned values, since the field type is not known.
+        if size < 64 && (relocation.addend < -(1 << (size - 1)) || relocation.addend >= 1 << size) {
+            return Err(Error(format!(
+                "relocation overflow in section `{}` at offset {:#x} for symbol `{}`: \
+                 value {} does not fit in {} bits",
+                String::from_utf8_lossy(&self.sections[section.0].name),
+                relocation.offset,
+                self.relocation_symbol_name(relocation.symbol),
+                relocation.addend,
+                size
+            )));
+        }
+        let offset = relocation.offset as usize;
+        match size {
+            8 => data.write_at(offset, &(relocation.addend as u8)),
+            16 => data.write_at(offset, &U16::new(self.endian, relocation.addend as u16)),
+            32 => data.write_at(offset, &U32::new(self.endian, relocation.addend as u32)),
+            64 => data.write_at(offset, &U64::new(self.endian, relocation.addend as u64)),
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented relocation addend {:?}",
+                    relocation
+                )));
+            }
+        }
+        .map_err(|_| {
+            Error(format!(
+                "invalid relocation offset {}+{} (max {}) for symbol `{}`",
+                relocation.offset,
+                size,
+                data.len(),
+                self.relocation_symbol_name(relocation.symbol)
+            ))
+        })
+    }
+
+    /// Return the name of a relocation symbol for use in messages.
+    ///
+    /// Section symbols use the name of their section.
+    fn relocation_symbol_name(&self, symbol_id: SymbolId) -> Cow<'_, str> {
+        let symbol = &self.symbols[symbol_id.0];
+        match (symbol.kind, symbol.section) {
+            (SymbolKind::Section, SymbolSection::Section(section)) => {
+                String::from_utf8_lossy(&self.sections[section.0].name)
+            }
+            _ => String::from_utf8_lossy(&symbol.name),
+        }
+    }
+
+    fn relocation_size(&self, relocation: &Relocation) -> Result<u8> {
+        let size = match self.format {
+            #[cfg(feature = "coff")]
+            BinaryFormat::Coff => self.coff_relocation_size(relocation),
+            #[cfg(feature = "elf")]
+            BinaryFormat::Elf => self.elf_relocation_size(relocation),
+            #[cfg(feature = "macho")]
+            BinaryFormat::MachO => self.macho_relocation_size(relocation),
+            #[cfg(feature = "xcoff")]
+            BinaryFormat::Xcoff => self.xcoff_relocation_size(relocation),
+            _ => unimplemented!(),
+        };
+        match size {
+            Ok(size) => Ok(size),
+            Err(error) => self
+                .relocation_translator
+                .as_ref()
+                .and_then(|t| t.relocation_size(self.format, relocation.flags))
+                .ok_or(error),
+        }
+    }
+
+    /// Return the values that determine how stored relocations are interpreted.
+    ///
+    /// This is the relocation type namespace for the file format, and whether
+    /// addends are stored in the section data.
+    fn relocation_target(&self) -> Result<(u32, bool)> {
         match self.format {
             #[cfg(feature = "coff")]
-            BinaryFormat::Coff => self.coff_translate_relocation(&mut relocation)?,
+            BinaryFormat::Coff => Ok((self.coff_machine()?.into(), true)),
             #[cfg(feature = "elf")]
-            BinaryFormat::Elf => self.elf_translate_relocation(&mut relocation)?,
+            BinaryFormat::Elf => Ok((
+                self.elf_machine()?.into(),
+                !self.elf_has_relocation_addend()?,
+            )),
             #[cfg(feature = "macho")]
-            BinaryFormat::MachO => self.macho_translate_relocation(&mut relocation)?,
+            BinaryFormat::MachO => Ok((self.macho_cpu_type()?.0, true)),
             #[cfg(feature = "xcoff")]
-            BinaryFormat::Xcoff => self.xcoff_translate_relocation(&mut relocation)?,
+            BinaryFormat::Xcoff => Ok((0, true)),
             _ => unimplemented!(),
         }
-        let implicit = match self.format {
-            #[cfg(feature = "coff")]
-            BinaryFormat::Coff => self.coff_adjust_addend(&mut relocation)?,
-            #[cfg(feature = "elf")]
-            BinaryFormat::Elf => self.elf_adjust_addend(&mut relocation)?,
-            #[cfg(feature = "macho")]
-            BinaryFormat::MachO => self.macho_adjust_addend(&mut relocation)?,
-            #[cfg(feature = "xcoff")]
-            BinaryFormat::Xcoff => self.xcoff_adjust_addend(&mut relocation)?,
-            _ => unimplemented!(),
-        };
-        if implicit && relocation.addend != 0 {
-            self.write_relocation_addend(section, &relocation)?;
-            relocation.addend = 0;
+    }
+
+    fn check_relocation_target(&self, previous_target: (u32, bool)) -> Result<()> {
+        if self.relocation_target()? != previous_target {
+            return Err(Error(format!(
+                "cannot retarget relocations to architecture {:?} with sub-architecture {:?}",
+                self.architecture, self.sub_architecture
+            )));
+        }
+        let (_, implicit_addend) = previous_target;
+        if implicit_addend {
+            for section in &self.sections {
+                for relocation in &section.relocations {
+                    self.relocation_size(relocation)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn swap_relocation_addends(&mut self) -> Result<()> {
+        for index in 0..self.sections.len() {
+            let mut fields = Vec::new();
+            for relocation in &self.sections[index].relocations {
+                let size = usize::from(self.relocation_size(relocation)? / 8);
+                fields.push((relocation.offset as usize, size));
+            }
+            let data = self.sections[index].data_mut();
+            for (offset, size) in fields {
+                if let Some(field) = data.get_mut(offset..).and_then(|d| d.get_mut(..size)) {
+                    field.reverse();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return a textual description of the logical structure of the object.
+    ///
+    /// This lists the sections with their relocations, the symbols, and the
+    /// COMDAT groups, in the order that they were added. Relocations refer
+    /// to symbols by name. The output is deterministic, and is intended for
+    /// debugging and for comparison in tests, but the exact format may change
+    /// between releases.
+    pub fn to_debug_string(&self) -> String {
+        let mut s = String::new();
+        self.write_debug_string(&mut s).unwrap();
+        s
+    }
+
+    fn write_debug_string(&self, s: &mut String) -> fmt::Result {
+        use core::fmt::Write;
+
+        let symbol_name = |symbol_id| self.relocation_symbol_name(symbol_id);
+        let symbol_section = |section: SymbolSection| match section {
+            SymbolSection::Section(section) => format!("{}", section.0),
+            _ => format!("{:?}", section),
+        };
+
+        writeln!(
+            s,
+            "format={:?} architecture={:?} sub_architecture={:?} endian={:?} flags={:?}",
+            self.format, self.architecture, self.sub_architecture, self.endian, self.flags
+        )?;
+        for (index, section) in self.sections.iter().enumerate() {
+            writeln!(
+                s,
+                "section {} segment={:?} name={:?} kind={:?} size={:#x} align={:#x} flags={:?}",
+                index,
+                String::from_utf8_lossy(&section.segment),
+                String::from_utf8_lossy(&section.name),
+                section.kind,
+                section.size,
+                section.align,
+                section.flags
+            )?;
+            for relocation in &section.relocations {
+                writeln!(
+                    s,
+                    "    relocation offset={:#x} symbol={:?} addend={} flags={:?}",
+                    relocation.offset,
+                    symbol_name(relocation.symbol),
+                    relocation.addend,
+                    relocation.flags
+                )?;
+            }
+        }
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            writeln!(
+                s,
+                "symbol {} name={:?} kind={:?} scope={:?} weak={} section={} value={:#x} size={:#x} flags={:?}",
+                index,
+                symbol_name(SymbolId(index)),
+                symbol.kind,
+                symbol.scope,
+                symbol.weak,
+                symbol_section(symbol.section),
+                symbol.value,
+                symbol.size,
+                symbol.flags
+            )?;
+        }
+        for (index, comdat) in self.comdats.iter().enumerate() {
+            let sections: Vec<_> = comdat.sections.iter().map(|section| section.0).collect();
+            writeln!(
+                s,
+                "comdat {} kind={:?} symbol={:?} sections={:?}",
+                index,
+                comdat.kind,
+                symbol_name(comdat.symbol),
+                sections
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Append the sections, symbols, relocations and COMDATs of another object.
+    ///
+    /// The sections of `other` are added as new sections, even if a section
+    /// with the same name already exists. Global symbols with the same name are
+    /// merged: an undefined symbol is resolved to a definition in either object,
+    /// a common or weak definition is replaced by a strong definition, and it is an
+    /// error for both objects to contain a strong definition. Relocations and
+    /// COMDATs are updated to refer to the merged sections and symbols.
+    ///
+    /// Returns a [`Remap`] which maps the IDs in `other` to IDs in this object.
+    ///
+    /// Settings such as the file flags are taken from `other` if they have not
+    /// been set for this object. Settings for merged symbols, such as the ELF
+    /// symbol visibility, are combined in the same way.
+    ///
+    /// Returns an error if the objects have a different format, architecture,
+    /// endianness or mangling, if a global symbol is defined in both, or if
+    /// both objects have a different value for a setting. Nothing is appended if
+    /// an error is returned.
+    pub fn append(&mut self, mut other: Object<'a>) -> Result<Remap> {
+        if self.format != other.format
+            || self.architecture != other.architecture
+            || self.sub_architecture != other.sub_architecture
+            || self.endian != other.endian
+        {
+            return Err(Error(format!(
+                "cannot append {:?} {:?} {:?} object to {:?} {:?} {:?} object",
+                other.format,
+                other.architecture,
+                other.endian,
+                self.format,
+                self.architecture,
+                self.endian
+            )));
+        }
+        if self.mangling.global_prefix() != other.mangling.global_prefix() {
+            return Err(Error(format!(
+                "cannot append object with mangling {:?} to object with mangling {:?}",
+                other.mangling, self.mangling
+            )));
+        }
+
+        // Find the global symbols that are in both objects, and check for conflicts
+        // before modifying anything. This is done in symbol order so that errors
+        // do not depend on the `HashMap` iteration order.
+        let mut other_names = vec![None; other.symbols.len()];
+        for (name, symbol_id) in mem::take(&mut other.symbol_map) {
+            other_names[symbol_id.0] = Some(name);
+        }
+        let mut merged = vec![None; other.symbols.len()];
+        for (index, name) in other_names.iter().enumerate() {
+            let Some(&symbol_id) = name.as_ref().and_then(|name| self.symbol_map.get(name)) else {
+                continue;
+            };
+            let symbol = &self.symbols[symbol_id.0];
+            let other_symbol = &other.symbols[index];
+            if symbol.is_local() || other_symbol.is_local() {
+                continue;
+            }
+            let (rank, other_rank) = (symbol.definition_rank(), other_symbol.definition_rank());
+            if rank == 3 && other_rank == 3 {
+                return Err(Error(format!(
+                    "duplicate definition of symbol `{}`",
+                    symbol.name().unwrap_or("")
+                )));
+            }
+            merged[index] = Some((symbol_id, other_rank > rank));
+        }
+        self.check_append_settings(&other, &merged)?;
+
+        let section_base = self.sections.len();
+        let remap_section = |id: SectionId| SectionId(section_base + id.0);
+        let mut remap = Remap {
+            sections: (0..other.sections.len())
+                .map(|index| SectionId(section_base + index))
+                .collect(),
+            symbols: Vec::with_capacity(other.symbols.len()),
+            comdats: (0..other.comdats.len())
+                .map(|index| ComdatId(self.comdats.len() + index))
+                .collect(),
+        };
+
+        // Symbols must be remapped before any references to them, but the
+        // `SymbolFlags` may refer to symbols that are defined later.
+        let mut new_symbols = Vec::new();
+        for (index, mut symbol) in other.symbols.into_iter().enumerate() {
+            if let SymbolSection::Section(id) = symbol.section {
+                symbol.section = SymbolSection::Section(remap_section(id));
+            }
+            if let Some((symbol_id, replace)) = merged[index] {
+                if replace {
+                    self.symbols[symbol_id.0] = symbol;
+                    new_symbols.push(symbol_id);
+                }
+                remap.symbols.push(symbol_id);
+                continue;
+            }
+            let symbol_id = self.add_raw_symbol(symbol);
+            if let Some(name) = other_names[index].take() {
+                match self.symbol_map.get(&name) {
+                    Some(existing) if !self.symbols[existing.0].is_local() => {}
+                    _ => {
+                        self.symbol_map.insert(name, symbol_id);
+                    }
+                }
+            }
+            new_symbols.push(symbol_id);
+            remap.symbols.push(symbol_id);
+        }
+        for symbol_id in new_symbols {
+            let flags = &mut self.symbols[symbol_id.0].flags;
+            match flags {
+                SymbolFlags::CoffSection {
+                    associative_section: Some(section),
+                    ..
+                } => *section = remap.section(*section),
+                SymbolFlags::Xcoff {
+                    containing_csect: Some(symbol),
+                    ..
+                } => *symbol = remap.symbol(*symbol),
+                _ => {}
+            }
+        }
+
+        for mut section in other.sections {
+            for relocation in &mut section.relocations {
+                relocation.symbol = remap.symbol(relocation.symbol);
+            }
+            section.symbol = section.symbol.map(|symbol| remap.symbol(symbol));
+            self.sections.push(section);
+        }
+        for mut comdat in other.comdats {
+            comdat.symbol = remap.symbol(comdat.symbol);
+            for section in &mut comdat.sections {
+                *section = remap.section(*section);
+            }
+            self.comdats.push(comdat);
+        }
+        for mut fixup in other.fixups {
+            fixup.section = remap.section(fixup.section);
+            self.fixups.push(fixup);
+        }
+        for ((section, index), implicit) in other.symbol_end_relocations {
+            self.symbol_end_relocations
+                .insert((remap.section(section), index), implicit);
+        }
+        merge_setting(&mut self.flags, other.flags, &FileFlags::None);
+        if self.relocation_translator.is_none() {
+            self.relocation_translator = other.relocation_translator;
+        }
+        #[cfg(feature = "coff")]
+        for (symbol, stub) in other.stub_symbols {
+            self.stub_symbols
+                .insert(remap.symbol(symbol), remap.symbol(stub));
+        }
+        #[cfg(feature = "coff")]
+        {
+            merge_setting(
+                &mut self.coff_layout,
+                other.coff_layout,
+                &CoffLayout::DataFirst,
+            );
+            merge_setting(&mut self.coff_bigobj, other.coff_bigobj, &false);
+        }
+        #[cfg(feature = "coff")]
+        if self.coff_directives.is_none() {
+            self.coff_directives = other.coff_directives.map(|section| remap.section(section));
+        }
+        #[cfg(feature = "elf")]
+        if self.elf_build_id.is_none() {
+            self.elf_build_id = other.elf_build_id.map(|build_id| elf::ElfBuildId {
+                section: remap.section(build_id.section),
+                ..build_id
+            });
+        }
+        #[cfg(feature = "elf")]
+        if self.elf_checksum.is_none() {
+            self.elf_checksum = other.elf_checksum.map(|checksum| elf::ElfChecksum {
+                section: remap.section(checksum.section),
+                ..checksum
+            });
+        }
+        #[cfg(feature = "elf")]
+        {
+            merge_setting(&mut self.elf_note_align, other.elf_note_align, &4);
+            merge_setting(
+                &mut self.elf_size_inference,
+                other.elf_size_inference,
+                &SymbolSizeInference::None,
+            );
+            merge_setting(&mut self.elf_use_relr, other.elf_use_relr, &false);
+            merge_setting(&mut self.elf_core, other.elf_core, &false);
+            merge_setting(&mut self.elf_static_pie, other.elf_static_pie, &false);
+            if self.elf_entry.is_none() {
+                self.elf_entry = other.elf_entry.map(|symbol| remap.symbol(symbol));
+            }
+        }
+        #[cfg(feature = "elf")]
+        {
+            let version_base = self.elf_versions.len();
+            self.elf_versions.extend(other.elf_versions);
+            for (symbol, (version, hidden)) in other.elf_symbol_versions {
+                // An existing version for a merged symbol has already been
+                // checked to be the same.
+                self.elf_symbol_versions
+                    .entry(remap.symbol(symbol))
+                    .or_insert((VersionId(version_base + version.0), hidden));
+            }
+        }
+        #[cfg(feature = "elf")]
+        for (section, compression) in other.elf_compression {
+            self.elf_compression
+                .insert(remap.section(section), compression);
+        }
+        #[cfg(feature = "elf")]
+        for (symbol, visibility) in other.elf_visibility {
+            self.elf_visibility.insert(remap.symbol(symbol), visibility);
+        }
+        #[cfg(feature = "elf")]
+        for symbol in other.elf_ifunc {
+            self.elf_ifunc.insert(remap.symbol(symbol));
+        }
+        #[cfg(feature = "elf")]
+        for section in other.elf_retain {
+            self.elf_retain.insert(remap.section(section));
+        }
+        #[cfg(feature = "elf")]
+        for (section, sh_type) in other.elf_section_type {
+            self.elf_section_type
+                .insert(remap.section(section), sh_type);
+        }
+        #[cfg(feature = "elf")]
+        for (section, entsize) in other.elf_section_entsize {
+            self.elf_section_entsize
+                .insert(remap.section(section), entsize);
+        }
+        #[cfg(feature = "elf")]
+        for (section, lma) in other.elf_section_lma {
+            self.elf_section_lma.insert(remap.section(section), lma);
+        }
+        #[cfg(feature = "elf")]
+        for (section, address) in other.elf_section_address {
+            self.elf_section_address
+                .insert(remap.section(section), address);
+        }
+        #[cfg(feature = "elf")]
+        self.elf_core_notes.extend_from_slice(&other.elf_core_notes);
+        #[cfg(feature = "coff")]
+        for (symbol, aux) in other.coff_function_aux {
+            self.coff_function_aux.insert(remap.symbol(symbol), aux);
+        }
+        #[cfg(feature = "coff")]
+        for (symbol, (default, search)) in other.coff_weak_externals {
+            self.coff_weak_externals
+                .insert(remap.symbol(symbol), (remap.symbol(default), search));
+        }
+        #[cfg(feature = "macho")]
+        {
+            merge_setting(&mut self.macho_cpu_subtype, other.macho_cpu_subtype, &None);
+            merge_setting(
+                &mut self.macho_build_version,
+                other.macho_build_version,
+                &None,
+            );
+            merge_setting(&mut self.macho_version_min, other.macho_version_min, &false);
+            merge_setting(
+                &mut self.macho_encryption_info,
+                other.macho_encryption_info,
+                &None,
+            );
+            merge_setting(&mut self.macho_uuid, other.macho_uuid, &None);
+            merge_setting(
+                &mut self.macho_subsections_via_symbols,
+                other.macho_subsections_via_symbols,
+                &false,
+            );
+        }
+        #[cfg(feature = "macho")]
+        if self.tlv_bootstrap.is_none() {
+            self.tlv_bootstrap = other.tlv_bootstrap.map(|symbol| remap.symbol(symbol));
+        }
+        #[cfg(feature = "macho")]
+        self.macho_split_info
+            .extend(
+                other
+                    .macho_split_info
+                    .into_iter()
+                    .map(|entry| MachOSplitInfo {
+                        section: remap.section(entry.section),
+                        ..entry
+                    }),
+            );
+        #[cfg(feature = "macho")]
+        for (section, attributes) in other.macho_section_attributes {
+            *self
+                .macho_section_attributes
+                .entry(remap.section(section))
+                .or_insert(0) |= attributes;
+        }
+        #[cfg(feature = "macho")]
+        for symbol in other.macho_alt_entry {
+            self.macho_alt_entry.insert(remap.symbol(symbol));
+        }
+        #[cfg(feature = "xcoff")]
+        for (symbol, csect) in other.xcoff_csect {
+            self.xcoff_csect.insert(remap.symbol(symbol), csect);
+        }
+        #[cfg(feature = "xcoff")]
+        merge_setting(&mut self.xcoff_aux_header, other.xcoff_aux_header, &None);
+        #[cfg(feature = "macho")]
+        for (section, indirect) in other.macho_indirect_symbols {
+            self.macho_indirect_symbols.insert(
+                remap.section(section),
+                macho::MachOIndirectSymbols {
+                    stub_size: indirect.stub_size,
+                    symbols: indirect
+                        .symbols
+                        .into_iter()
+                        .map(|symbol| remap.symbol(symbol))
+                        .collect(),
+                },
+            );
+        }
+        #[cfg(feature = "macho")]
+        self.macho_data_in_code
+            .extend(
+                other
+                    .macho_data_in_code
+                    .into_iter()
+                    .map(|entry| macho::MachODataInCode {
+                        section: remap.section(entry.section),
+                        ..entry
+                    }),
+            );
+
+        Ok(remap)
+    }
+
+    /// Check that the settings of `other` can be merged by [`Self::append`].
+    ///
+    /// `merged` contains the existing symbol for each symbol in `other` that
+    /// is merged with a symbol in this object.
+    fn check_append_settings(
+        &self,
+        other: &Object<'a>,
+        merged: &[Option<(SymbolId, bool)>],
+    ) -> Result<()> {
+        // Return the symbol in this object that a symbol in `other` is merged with.
+        #[cfg(any(
+            feature = "coff",
+            feature = "elf",
+            feature = "macho",
+            feature = "xcoff"
+        ))]
+        let merged_symbol = |symbol: SymbolId| merged[symbol.0].map(|(symbol, _)| symbol);
+        #[cfg(any(feature = "coff", feature = "elf", feature = "xcoff"))]
+        let symbol_name = |symbol: SymbolId| self.symbol(symbol).name().unwrap_or("");
+
+        check_setting("file flags", &self.flags, &other.flags, &FileFlags::None)?;
+        if let (Some(translator), Some(other_translator)) =
+            (&self.relocation_translator, &other.relocation_translator)
+        {
+            if !Arc::ptr_eq(translator, other_translator) {
+                return Err(Error("conflicting relocation translators".into()));
+            }
+        }
+        #[cfg(feature = "coff")]
+        {
+            check_setting(
+                "COFF layout",
+                &self.coff_layout,
+                &other.coff_layout,
+                &CoffLayout::DataFirst,
+            )?;
+            for (symbol, stub) in &other.stub_symbols {
+                if let Some(existing) =
+                    merged_symbol(*symbol).and_then(|symbol| self.stub_symbols.get(&symbol))
+                {
+                    if merged_symbol(*stub) != Some(*existing) {
+                        return Err(Error(format!(
+                            "conflicting stub for symbol `{}`",
+                            symbol_name(*existing)
+                        )));
+                    }
+                }
+            }
+            for (symbol, aux) in &other.coff_function_aux {
+                if let Some(symbol) = merged_symbol(*symbol) {
+                    if matches!(self.coff_function_aux.get(&symbol), Some(existing) if existing != aux)
+                    {
+                        return Err(Error(format!(
+                            "conflicting function auxiliary records for symbol `{}`",
+                            symbol_name(symbol)
+                        )));
+                    }
+                }
+            }
+            for (symbol, (default, search)) in &other.coff_weak_externals {
+                if let Some(symbol) = merged_symbol(*symbol) {
+                    if let Some((existing_default, existing_search)) =
+                        self.coff_weak_externals.get(&symbol)
+                    {
+                        if merged_symbol(*default) != Some(*existing_default)
+                            || search != existing_search
+                        {
+                            return Err(Error(format!(
+                                "conflicting weak external for symbol `{}`",
+                                symbol_name(symbol)
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "elf")]
+        {
+            check_setting(
+                "ELF note alignment",
+                &self.elf_note_align,
+                &other.elf_note_align,
+                &4,
+            )?;
+            check_setting(
+                "ELF symbol size inference",
+                &self.elf_size_inference,
+                &other.elf_size_inference,
+                &SymbolSizeInference::None,
+            )?;
+            if self.elf_build_id.is_some() && other.elf_build_id.is_some() {
+                return Err(Error("both objects have a build ID".into()));
+            }
+            if self.elf_checksum.is_some() && other.elf_checksum.is_some() {
+                return Err(Error("both objects have a checksum section".into()));
+            }
+            if self.elf_entry.is_some() && other.elf_entry.is_some() {
+                return Err(Error("both objects have an entry point".into()));
+            }
+            for (symbol, (version, hidden)) in &other.elf_symbol_versions {
+                if let Some(symbol) = merged_symbol(*symbol) {
+                    if let Some((existing, existing_hidden)) = self.elf_symbol_versions.get(&symbol)
+                    {
+                        if self.elf_versions[existing.0] != other.elf_versions[version.0]
+                            || existing_hidden != hidden
+                        {
+                            return Err(Error(format!(
+                                "conflicting version for symbol `{}`",
+                                symbol_name(symbol)
+                            )));
+                        }
+                    }
+                }
+            }
+            for (symbol, visibility) in &other.elf_visibility {
+                if let Some(symbol) = merged_symbol(*symbol) {
+                    if matches!(self.elf_visibility.get(&symbol), Some(existing) if existing != visibility)
+                    {
+                        return Err(Error(format!(
+                            "conflicting visibility for symbol `{}`",
+                            symbol_name(symbol)
+                        )));
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "macho")]
+        {
+            check_setting(
+                "Mach-O CPU subtype",
+                &self.macho_cpu_subtype,
+                &other.macho_cpu_subtype,
+                &None,
+            )?;
+            check_setting(
+                "Mach-O build version",
+                &self.macho_build_version,
+                &other.macho_build_version,
+                &None,
+            )?;
+            check_setting(
+                "Mach-O encryption info",
+                &self.macho_encryption_info,
+                &other.macho_encryption_info,
+                &None,
+            )?;
+            check_setting("Mach-O UUID", &self.macho_uuid, &other.macho_uuid, &None)?;
+            if let (Some(symbol), Some(other_symbol)) = (self.tlv_bootstrap, other.tlv_bootstrap) {
+                if merged_symbol(other_symbol) != Some(symbol) {
+                    return Err(Error("conflicting TLV bootstrap symbols".into()));
+                }
+            }
+        }
+        #[cfg(feature = "xcoff")]
+        {
+            check_setting(
+                "XCOFF auxiliary header",
+                &self.xcoff_aux_header,
+                &other.xcoff_aux_header,
+                &None,
+            )?;
+            for (symbol, csect) in &other.xcoff_csect {
+                if let Some(symbol) = merged_symbol(*symbol) {
+                    if matches!(self.xcoff_csect.get(&symbol), Some(existing) if existing != csect)
+                    {
+                        return Err(Error(format!(
+                            "conflicting csect for symbol `{}`",
+                            symbol_name(symbol)
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the sections for which `remove` is true, along with the symbols
+    /// defined in them.
+    ///
+    /// Returns an error if a remaining relocation or COMDAT refers to a removed symbol.
+    #[cfg(feature = "elf")]
+    fn remove_sections(&mut self, remove: &[bool]) -> Result<()> {
+        let mut section_map = Vec::with_capacity(self.sections.len());
+        let mut count = 0;
+        for &remove in remove {
+            if remove {
+                section_map.push(None);
+            } else {
+                section_map.push(Some(SectionId(count)));
+                count += 1;
+            }
+        }
+        let mut symbol_map = Vec::with_capacity(self.symbols.len());
+        let mut count = 0;
+        for symbol in &self.symbols {
+            if symbol
+                .section
+                .id()
+                .map_or(false, |section| remove[section.0])
+            {
+                symbol_map.push(None);
+            } else {
+                symbol_map.push(Some(SymbolId(count)));
+                count += 1;
+            }
+        }
+        let remap_section = |section: SectionId| section_map[section.0];
+        let remap_symbol = |symbol: SymbolId, section: &Section<'_>| {
+            symbol_map[symbol.0].ok_or_else(|| {
+                Error(format!(
+                    "section `{}` refers to symbol `{}` in a removed section",
+                    section.name().unwrap_or(""),
+                    self.symbols[symbol.0].name().unwrap_or("")
+                ))
+            })
+        };
+
+        for (index, section) in self.sections.iter().enumerate() {
+            if remove[index] {
+                continue;
+            }
+            for relocation in &section.relocations {
+                remap_symbol(relocation.symbol, section)?;
+            }
+        }
+        for comdat in &self.comdats {
+            if let Some(&section) = comdat.sections.iter().find(|section| !remove[section.0]) {
+                remap_symbol(comdat.symbol, &self.sections[section.0])?;
+            }
+        }
+
+        let mut index = 0;
+        self.sections.retain(|_| {
+            index += 1;
+            !remove[index - 1]
+        });
+        for section in &mut self.sections {
+            for relocation in &mut section.relocations {
+                relocation.symbol = symbol_map[relocation.symbol.0].unwrap();
+            }
+            section.symbol = section.symbol.and_then(|symbol| symbol_map[symbol.0]);
+        }
+        let mut index = 0;
+        self.symbols.retain(|_| {
+            index += 1;
+            symbol_map[index - 1].is_some()
+        });
+        for symbol in &mut self.symbols {
+            if let SymbolSection::Section(section) = symbol.section {
+                symbol.section = SymbolSection::Section(remap_section(section).unwrap());
+            }
+            match &mut symbol.flags {
+                SymbolFlags::CoffSection {
+                    associative_section,
+                    ..
+                } => *associative_section = associative_section.and_then(remap_section),
+                SymbolFlags::Xcoff {
+                    containing_csect, ..
+                } => *containing_csect = containing_csect.and_then(|symbol| symbol_map[symbol.0]),
+                _ => {}
+            }
+        }
+        self.symbol_map = mem::take(&mut self.symbol_map)
+            .into_iter()
+            .filter_map(|(name, symbol)| Some((name, symbol_map[symbol.0]?)))
+            .collect();
+        self.standard_sections = mem::take(&mut self.standard_sections)
+            .into_iter()
+            .filter_map(|(standard, section)| Some((standard, remap_section(section)?)))
+            .collect();
+        self.comdats
+            .retain(|comdat| comdat.sections.iter().any(|section| !remove[section.0]));
+        for comdat in &mut self.comdats {
+            comdat.symbol = symbol_map[comdat.symbol.0].unwrap();
+            comdat.sections = comdat
+                .sections
+                .iter()
+                .filter_map(|section| remap_section(*section))
+                .collect();
+        }
+        self.fixups.retain(|fixup| !remove[fixup.section.0]);
+        for fixup in &mut self.fixups {
+            fixup.section = remap_section(fixup.section).unwrap();
+        }
+        remap_keys(&mut self.symbol_end_relocations, |(section, index)| {
+            Some((remap_section(section)?, index))
+        });
+
+        fn remap_keys<K: Copy + Eq + Hash, V>(
+            map: &mut HashMap<K, V>,
+            remap: impl Fn(K) -> Option<K>,
+        ) {
+            *map = mem::take(map)
+                .into_iter()
+                .filter_map(|(key, value)| Some((remap(key)?, value)))
+                .collect();
+        }
+        #[cfg(any(feature = "elf", feature = "macho"))]
+        fn remap_set<K: Copy + Eq + Hash>(set: &mut HashSet<K>, remap: impl Fn(K) -> Option<K>) {
+            *set = mem::take(set).into_iter().filter_map(remap).collect();
+        }
+        let remap_symbol = |symbol: SymbolId| symbol_map[symbol.0];
+        #[cfg(feature = "coff")]
+        {
+            self.stub_symbols = mem::take(&mut self.stub_symbols)
+                .into_iter()
+                .filter_map(|(symbol, stub)| Some((remap_symbol(symbol)?, remap_symbol(stub)?)))
+                .collect();
+            remap_keys(&mut self.coff_function_aux, remap_symbol);
+            self.coff_weak_externals = mem::take(&mut self.coff_weak_externals)
+                .into_iter()
+                .filter_map(|(symbol, (default, search))| {
+                    Some((remap_symbol(symbol)?, (remap_symbol(default)?, search)))
+                })
+                .collect();
+            self.coff_directives = self.coff_directives.and_then(remap_section);
+        }
+        #[cfg(feature = "elf")]
+        {
+            remap_keys(&mut self.elf_compression, remap_section);
+            remap_keys(&mut self.elf_symbol_versions, remap_symbol);
+            self.elf_build_id = self.elf_build_id.take().and_then(|build_id| {
+                Some(elf::ElfBuildId {
+                    section: remap_section(build_id.section)?,
+                    ..build_id
+                })
+            });
+            self.elf_checksum = self.elf_checksum.take().and_then(|checksum| {
+                Some(elf::ElfChecksum {
+                    section: remap_section(checksum.section)?,
+                    ..checksum
+                })
+            });
+            remap_keys(&mut self.elf_visibility, remap_symbol);
+            remap_set(&mut self.elf_ifunc, remap_symbol);
+            remap_set(&mut self.elf_retain, remap_section);
+            remap_keys(&mut self.elf_section_type, remap_section);
+            remap_keys(&mut self.elf_section_entsize, remap_section);
+            remap_keys(&mut self.elf_section_lma, remap_section);
+            remap_keys(&mut self.elf_section_address, remap_section);
+            self.elf_entry = self.elf_entry.and_then(remap_symbol);
+        }
+        #[cfg(feature = "macho")]
+        {
+            self.tlv_bootstrap = self.tlv_bootstrap.and_then(remap_symbol);
+            self.macho_split_info
+                .retain(|entry| !remove[entry.section.0]);
+            for entry in &mut self.macho_split_info {
+                entry.section = remap_section(entry.section).unwrap();
+            }
+            remap_keys(&mut self.macho_section_attributes, remap_section);
+            remap_set(&mut self.macho_alt_entry, remap_symbol);
+            self.macho_indirect_symbols = mem::take(&mut self.macho_indirect_symbols)
+                .into_iter()
+                .filter_map(|(section, indirect)| {
+                    Some((
+                        remap_section(section)?,
+                        macho::MachOIndirectSymbols {
+                            stub_size: indirect.stub_size,
+                            symbols: indirect
+                                .symbols
+                                .into_iter()
+                                .map(remap_symbol)
+                                .collect::<Option<_>>()?,
+                        },
+                    ))
+                })
+                .collect();
+            self.macho_data_in_code
+                .retain(|entry| !remove[entry.section.0]);
+            for entry in &mut self.macho_data_in_code {
+                entry.section = remap_section(entry.section).unwrap();
+            }
         }
-        self.sections[section.0].relocations.push(relocation);
+        #[cfg(feature = "xcoff")]
+        remap_keys(&mut self.xcoff_csect, remap_symbol);
         Ok(())
     }
 
-    fn write_relocation_addend(
-        &mut self,
-        section: SectionId,
-        relocation: &Relocation,
-    ) -> Result<()> {
-        let size = match self.format {
-            #[cfg(feature = "coff")]
-            BinaryFormat::Coff => self.coff_relocation_size(relocation)?,
-            #[cfg(feature = "elf")]
-            BinaryFormat::Elf => self.elf_relocation_size(relocation)?,
-            #[cfg(feature = "macho")]
-            BinaryFormat::MachO => self.macho_relocation_size(relocation)?,
-            #[cfg(feature = "xcoff")]
-            BinaryFormat::Xcoff => self.xcoff_relocation_size(relocation)?,
-            _ => unimplemented!(),
-        };
-        let data = self.sections[section.0].data_mut();
-        let offset = relocation.offset as usize;
-        match size {
-            32 => data.write_at(offset, &U32::new(self.endian, relocation.addend as u32)),
-            64 => data.write_at(offset, &U64::new(self.endian, relocation.addend as u64)),
-            _ => {
-                return Err(Error(format!(
-                    "unimplemented relocation addend {:?}",
-                    relocation
-                )));
+    /// Return a SHA-1 hash of the contents of the object.
+    ///
+    /// This is used to derive the ELF build ID and the Mach-O UUID.
+    ///
+    /// The hash input is the sections that contain code or data, in the order
+    /// that the sections were added, followed by all of the symbols. For each
+    /// section, this is the section size, the section data (which is empty for
+    /// uninitialized data), and the offset, symbol index, flags and addend of each
+    /// relocation. For each symbol, this is the unmangled name, value, size, kind,
+    /// scope, weak flag, section and flags. Section names, headers, and sections of
+    /// other kinds (such as notes, debug information, and Mach-O TLS descriptors)
+    /// are not included.
+    ///
+    /// Relocations are hashed with the flags and addend that they were added with,
+    /// and the section data that they apply to is excluded, since it may contain
+    /// an implicit addend. This means that objects that only differ in their file
+    /// format have the same hash if their relocations use generic flags and their
+    /// symbols use [`SymbolFlags::None`].
+    pub fn content_hash(&self) -> [u8; 20] {
+        let mut hasher = sha1::Sha1::new();
+        for (index, section) in self.sections.iter().enumerate() {
+            match section.kind {
+                SectionKind::Text
+                | SectionKind::Data
+                | SectionKind::ReadOnlyData
+                | SectionKind::ReadOnlyDataWithRel
+                | SectionKind::ReadOnlyString
+                | SectionKind::UninitializedData
+                | SectionKind::Tls
+                | SectionKind::UninitializedTls => {}
+                _ => continue,
+            }
+            hash_u64(&mut hasher, section.size);
+            let mut data = section.data.to_vec();
+            for (relocation, (flags, _)) in
+                section.relocations.iter().zip(&section.relocation_content)
+            {
+                let size = match flags {
+                    RelocationFlags::Generic { size, .. } => *size,
+                    _ => self.relocation_size(relocation).unwrap_or(0),
+                };
+                let start = relocation.offset as usize;
+                let end = start.saturating_add((usize::from(size) + 7) / 8);
+                if let Some(field) = data.get_mut(start..end) {
+                    field.fill(0);
+                }
+            }
+            hash_u64(&mut hasher, data.len() as u64);
+            hasher.update(&data);
+            hash_u64(&mut hasher, section.relocations.len() as u64);
+            for (reloc_index, (relocation, (flags, addend))) in section
+                .relocations
+                .iter()
+                .zip(&section.relocation_content)
+                .enumerate()
+            {
+                hash_u64(&mut hasher, relocation.offset);
+                hash_u64(&mut hasher, relocation.symbol.0 as u64);
+                hash_u64(&mut hasher, *addend as u64);
+                let end = self
+                    .symbol_end_relocations
+                    .contains_key(&(SectionId(index), reloc_index));
+                hasher.update(&[end as u8]);
+                hash_relocation_flags(&mut hasher, *flags);
             }
         }
-        .map_err(|_| {
-            Error(format!(
-                "invalid relocation offset {}+{} (max {})",
-                relocation.offset,
-                size,
-                data.len()
-            ))
-        })
+
+        let mut unmangled_names = HashMap::new();
+        for (name, symbol_id) in &self.symbol_map {
+            unmangled_names.insert(*symbol_id, name);
+        }
+        hash_u64(&mut hasher, self.symbols.len() as u64);
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            let name = unmangled_names
+                .get(&SymbolId(index))
+                .map_or(&symbol.name[..], |name| &name[..]);
+            hash_u64(&mut hasher, name.len() as u64);
+            hasher.update(name);
+            hash_u64(&mut hasher, symbol.value);
+            hash_u64(&mut hasher, symbol.size);
+            hasher.update(&[symbol.kind as u8, symbol.scope as u8, symbol.weak as u8]);
+            match symbol.section {
+                SymbolSection::None => hasher.update(&[0]),
+                SymbolSection::Undefined => hasher.update(&[1]),
+                SymbolSection::Absolute => hasher.update(&[2]),
+                SymbolSection::Common => hasher.update(&[3]),
+                SymbolSection::Section(id) => {
+                    hasher.update(&[4]);
+                    hash_u64(&mut hasher, id.0 as u64);
+                }
+            }
+            hash_symbol_flags(&mut hasher, symbol.flags);
+        }
+        hasher.finish()
+    }
+
+    /// Check that every COMDAT member section exists and is only in one COMDAT.
+    fn check_comdats(&self) -> Result<()> {
+        let mut members = HashMap::new();
+        for (index, comdat) in self.comdats.iter().enumerate() {
+            let symbol = self.symbols[comdat.symbol.0].name().unwrap_or("");
+            for section in &comdat.sections {
+                let Some(member) = self.sections.get(section.0) else {
+                    return Err(Error(format!(
+                        "COMDAT `{}` has nonexistent section index {}",
+                        symbol, section.0
+                    )));
+                };
+                if let Some(other) = members.insert(section.0, index) {
+                    let other = self.symbols[self.comdats[other].symbol.0]
+                        .name()
+                        .unwrap_or("");
+                    return Err(Error(format!(
+                        "section `{}` is in both COMDAT `{}` and COMDAT `{}`",
+                        member.name().unwrap_or(""),
+                        other,
+                        symbol
+                    )));
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Write the object to a `Vec`.
@@ -730,6 +3033,67 @@ impl<'a> Object<'a> {
         Ok(buffer)
     }
 
+    /// Write the object to a `Vec`, and also return the locations of the defined symbols.
+    ///
+    /// The map is keyed by symbol name, and the value is the section and the offset
+    /// of the symbol within that section. The names are the names in the symbol table,
+    /// which include the global prefix of the mangling scheme, if any. Only symbols
+    /// that are defined in a section are included, and section and file symbols
+    /// are excluded.
+    ///
+    /// This is useful for loaders that need to locate symbols in the written
+    /// object without parsing its symbol table.
+    ///
+    /// Local symbols may share a name with each other or with a non-local symbol.
+    /// If a non-local symbol has the name, then the map contains the non-local symbol.
+    /// Otherwise, a local symbol is only included if no other local symbol has the
+    /// same name, since its location would be ambiguous.
+    #[cfg(feature = "std")]
+    pub fn write_with_symbol_map(&self) -> Result<(Vec<u8>, HashMap<Vec<u8>, (SectionId, u64)>)> {
+        let buffer = self.write()?;
+        let mut map = HashMap::new();
+        let mut locals = HashMap::new();
+        for symbol in &self.symbols {
+            if symbol.name.is_empty()
+                || matches!(symbol.kind, SymbolKind::Section | SymbolKind::File)
+            {
+                continue;
+            }
+            let SymbolSection::Section(section) = symbol.section else {
+                continue;
+            };
+            let location = (section, symbol.value);
+            if symbol.is_local() {
+                locals
+                    .entry(symbol.name.clone())
+                    .and_modify(|local| *local = None)
+                    .or_insert(Some(location));
+            } else {
+                map.insert(symbol.name.clone(), location);
+            }
+        }
+        for (name, location) in locals {
+            if let Some(location) = location {
+                map.entry(name).or_insert(location);
+            }
+        }
+        Ok((buffer, map))
+    }
+
+    /// Compute the size in bytes of the object file that would be written.
+    ///
+    /// This performs the same layout as [`Self::emit`], but stops before
+    /// writing any data. Errors that can only be detected while writing
+    /// the data are not reported.
+    pub fn compute_size(&self) -> Result<usize> {
+        let mut buffer = SizeBuffer::default();
+        let result = self.emit(&mut buffer);
+        match buffer.size {
+            Some(size) => Ok(size),
+            None => result.map(|()| buffer.len),
+        }
+    }
+
     /// Write the object to a `Write` implementation.
     ///
     /// Also flushes the writer.
@@ -746,7 +3110,22 @@ impl<'a> Object<'a> {
     }
 
     /// Write the object to a `WritableBuffer`.
+    ///
+    /// The output is deterministic: it only depends on the sequence of calls
+    /// that were used to build the object.
     pub fn emit(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        for symbol in &self.symbols {
+            // Common symbols use `SymbolSection::Common`, so this doesn't
+            // prevent them from storing their alignment in the value.
+            if symbol.is_undefined() && symbol.value != 0 {
+                return Err(Error(format!(
+                    "undefined symbol `{}` has nonzero value {:#x}",
+                    symbol.name().unwrap_or(""),
+                    symbol.value
+                )));
+            }
+        }
+        self.check_comdats()?;
         match self.format {
             #[cfg(feature = "coff")]
             BinaryFormat::Coff => self.coff_write(buffer),
@@ -791,6 +3170,25 @@ pub enum StandardSection {
     Common,
     /// Notes for GNU properties. Only supported for ELF.
     GnuProperty,
+    /// Pointers to initialization functions, such as C++ static constructors.
+    ///
+    /// This is `.init_array` for ELF, `.CRT$XCU` for COFF, and `__mod_init_func`
+    /// for Mach-O. Unsupported for XCOFF.
+    InitArray,
+    /// Pointers to termination functions.
+    ///
+    /// This is `.fini_array` for ELF, and `__mod_term_func` for Mach-O.
+    /// Unsupported for COFF and XCOFF.
+    FiniArray,
+    /// Pointers to functions that are called before the initialization functions.
+    ///
+    /// This is `.preinit_array` for ELF. Only supported for ELF.
+    PreInitArray,
+    /// Call frame information for unwinding.
+    ///
+    /// This is `.eh_frame` for ELF, and `__TEXT,__eh_frame` for Mach-O.
+    /// Unsupported for COFF and XCOFF.
+    EhFrame,
 }
 
 impl StandardSection {
@@ -808,6 +3206,10 @@ impl StandardSection {
             StandardSection::TlsVariables => SectionKind::TlsVariables,
             StandardSection::Common => SectionKind::Common,
             StandardSection::GnuProperty => SectionKind::Note,
+            StandardSection::InitArray
+            | StandardSection::FiniArray
+            | StandardSection::PreInitArray => SectionKind::Data,
+            StandardSection::EhFrame => SectionKind::ReadOnlyData,
         }
     }
 
@@ -825,6 +3227,10 @@ impl StandardSection {
             StandardSection::TlsVariables,
             StandardSection::Common,
             StandardSection::GnuProperty,
+            StandardSection::InitArray,
+            StandardSection::FiniArray,
+            StandardSection::PreInitArray,
+            StandardSection::EhFrame,
         ]
     }
 }
@@ -834,7 +3240,7 @@ impl StandardSection {
 pub struct SectionId(usize);
 
 /// A section in an object file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Section<'a> {
     segment: Vec<u8>,
     name: Vec<u8>,
@@ -843,7 +3249,12 @@ pub struct Section<'a> {
     align: u64,
     data: Cow<'a, [u8]>,
     relocations: Vec<Relocation>,
+    /// The flags and addend of each relocation as it was added, before translation
+    /// to the file format. Used by `Object::content_hash`.
+    relocation_content: Vec<(RelocationFlags, i64)>,
     symbol: Option<SymbolId>,
+    /// The pattern for alignment padding, or empty for zeros.
+    fill: Vec<u8>,
     /// Section flags that are specific to each file format.
     pub flags: SectionFlags,
 }
@@ -885,6 +3296,12 @@ impl<'a> Section<'a> {
 
     /// Append data to a section.
     ///
+    /// The data is placed at an offset that is a multiple of `align` relative to
+    /// the start of the section. The section alignment is increased to `align` if
+    /// it is smaller, so that the data is also aligned to `align` in the final image.
+    /// Any padding before the data uses the fill pattern set by
+    /// [`Object::set_section_fill`], or zeros by default.
+    ///
     /// Must not be called for sections that contain uninitialized data.
     /// `align` must be a power of two.
     pub fn append_data(&mut self, append_data: &[u8], align: u64) -> u64 {
@@ -898,15 +3315,159 @@ impl<'a> Section<'a> {
         let mut offset = data.len();
         if offset & (align - 1) != 0 {
             offset += align - (offset & (align - 1));
-            data.resize(offset, 0);
+            if self.fill.is_empty() {
+                data.resize(offset, 0);
+            } else {
+                for i in data.len()..offset {
+                    data.push(self.fill[i % self.fill.len()]);
+                }
+            }
         }
         data.extend_from_slice(append_data);
         self.size = data.len() as u64;
         offset as u64
     }
 
+    /// Return the alignment of the section.
+    #[inline]
+    pub fn align(&self) -> u64 {
+        self.align
+    }
+
+    /// Increase the alignment of the section.
+    ///
+    /// If the section already has a greater alignment, then it is unchanged.
+    /// Returns an error if `align` is not a power of two.
+    pub fn set_alignment(&mut self, align: u64) -> Result<()> {
+        self.check_align(align)?;
+        if self.align < align {
+            self.align = align;
+        }
+        Ok(())
+    }
+
+    /// Set the data for a section, checking the arguments.
+    ///
+    /// This is the same as [`Self::set_data`], except that it returns an error
+    /// if the section already has data, if the section contains uninitialized
+    /// data, or if `align` is not a power of two.
+    pub fn try_set_data<T>(&mut self, data: T, align: u64) -> Result<()>
+    where
+        T: Into<Cow<'a, [u8]>>,
+    {
+        self.check_data(align)?;
+        if !self.data.is_empty() {
+            return Err(Error(format!(
+                "section `{}` already has data",
+                self.name().unwrap_or("")
+            )));
+        }
+        self.set_data(data, align);
+        Ok(())
+    }
+
+    /// Append data to a section, checking the arguments.
+    ///
+    /// This is the same as [`Self::append_data`], except that it returns an error
+    /// if the section contains uninitialized data, or if `align` is not a power of two.
+    pub fn try_append_data(&mut self, append_data: &[u8], align: u64) -> Result<u64> {
+        self.check_data(align)?;
+        Ok(self.append_data(append_data, align))
+    }
+
+    /// Append uninitialized data to a section, checking the arguments.
+    ///
+    /// This is the same as [`Self::append_bss`], except that it returns an error
+    /// if the section contains initialized data, or if `align` is not a power of two.
+    pub fn try_append_bss(&mut self, size: u64, align: u64) -> Result<u64> {
+        self.check_align(align)?;
+        if !self.is_bss() {
+            return Err(Error(format!(
+                "cannot append uninitialized data to section `{}`",
+                self.name().unwrap_or("")
+            )));
+        }
+        Ok(self.append_bss(size, align))
+    }
+
+    fn check_data(&self, align: u64) -> Result<()> {
+        self.check_align(align)?;
+        if self.is_bss() {
+            return Err(Error(format!(
+                "cannot add initialized data to section `{}`",
+                self.name().unwrap_or("")
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_align(&self, align: u64) -> Result<()> {
+        if !align.is_power_of_two() {
+            return Err(Error(format!(
+                "invalid alignment {} for section `{}`",
+                align,
+                self.name().unwrap_or("")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Write data to a section at the given offset.
+    ///
+    /// This overwrites any existing data at that offset. If the data extends
+    /// past the end of the section, then the section is extended, and any gap
+    /// between the old end of the section and `offset` is filled with zeros.
+    ///
+    /// Returns an error if the section contains uninitialized data, or if the
+    /// end of the data would overflow.
+    pub fn write_data_at(&mut self, offset: u64, write_data: &[u8]) -> Result<()> {
+        self.check_data(1)?;
+        let end = match offset.checked_add(write_data.len() as u64) {
+            Some(end) if end <= usize::MAX as u64 => end as usize,
+            _ => {
+                return Err(Error(format!(
+                    "invalid offset {:#x} for {} bytes of data in section `{}`",
+                    offset,
+                    write_data.len(),
+                    self.name().unwrap_or("")
+                )));
+            }
+        };
+        let offset = offset as usize;
+        let data = self.data.to_mut();
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[offset..end].copy_from_slice(write_data);
+        self.size = data.len() as u64;
+        Ok(())
+    }
+
+    /// Reserve capacity for at least `additional` more bytes of data.
+    ///
+    /// Data that was set using a borrowed slice is not copied until the first
+    /// mutation. If the data is still borrowed, then this copies it into an
+    /// owned buffer with the extra capacity, so that subsequent appends do not
+    /// need to copy or reallocate.
+    ///
+    /// Must not be called for sections that contain uninitialized data.
+    pub fn reserve_data(&mut self, additional: usize) {
+        debug_assert!(!self.is_bss());
+        match &mut self.data {
+            Cow::Borrowed(data) => {
+                let mut owned = Vec::with_capacity(data.len() + additional);
+                owned.extend_from_slice(data);
+                self.data = Cow::Owned(owned);
+            }
+            Cow::Owned(data) => data.reserve(additional),
+        }
+    }
+
     /// Append uninitialized data to a section.
     ///
+    /// The section alignment is increased to `align` if required, in the same
+    /// way as for [`Self::append_data`].
+    ///
     /// Must not be called for sections that contain initialized data.
     /// `align` must be a power of two.
     pub fn append_bss(&mut self, size: u64, align: u64) -> u64 {
@@ -924,6 +3485,16 @@ impl<'a> Section<'a> {
         offset
     }
 
+    /// Return the relocations that have been added to the section.
+    ///
+    /// These are the relocations as they will be written, after any translation
+    /// to format specific relocation flags, and after any implicit addend has been
+    /// written to the section data.
+    #[inline]
+    pub fn relocations(&self) -> &[Relocation] {
+        &self.relocations
+    }
+
     /// Returns the section as-built so far.
     ///
     /// This requires that the section is not a bss section.
@@ -976,7 +3547,7 @@ impl SymbolSection {
 pub struct SymbolId(usize);
 
 /// A symbol in an object file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Symbol {
     /// The name of the symbol.
     pub name: Vec<u8>,
@@ -1024,10 +3595,26 @@ impl Symbol {
     pub fn is_local(&self) -> bool {
         self.scope == SymbolScope::Compilation
     }
+
+    /// Return the precedence of the symbol definition when merging symbols.
+    fn definition_rank(&self) -> u8 {
+        match self.section {
+            SymbolSection::Undefined => 0,
+            SymbolSection::Common => 1,
+            _ if self.weak => 2,
+            _ => 3,
+        }
+    }
+
+    /// Return true if the symbol is defined and has `SymbolScope::Dynamic`.
+    #[inline]
+    pub fn is_exported(&self) -> bool {
+        self.scope == SymbolScope::Dynamic && !self.is_undefined()
+    }
 }
 
 /// A relocation in an object file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Relocation {
     /// The section offset of the place of the relocation.
     pub offset: u64,
@@ -1043,12 +3630,190 @@ pub struct Relocation {
     pub flags: RelocationFlags,
 }
 
+/// A mapping from the IDs of an object to the IDs of the object it was appended to.
+///
+/// This is returned by [`Object::append`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remap {
+    sections: Vec<SectionId>,
+    symbols: Vec<SymbolId>,
+    comdats: Vec<ComdatId>,
+}
+
+impl Remap {
+    /// Return the new ID of a section.
+    #[inline]
+    pub fn section(&self, section: SectionId) -> SectionId {
+        self.sections[section.0]
+    }
+
+    /// Return the new ID of a symbol.
+    #[inline]
+    pub fn symbol(&self, symbol: SymbolId) -> SymbolId {
+        self.symbols[symbol.0]
+    }
+
+    /// Return the new ID of a COMDAT.
+    #[inline]
+    pub fn comdat(&self, comdat: ComdatId) -> ComdatId {
+        self.comdats[comdat.0]
+    }
+}
+
+/// A helper for appending a structure to a section.
+///
+/// Each field is aligned to its natural alignment relative to the start of the
+/// structure, and the padding is filled as for [`Object::append_section_data`].
+///
+/// This is returned by [`Object::append_section_struct`].
+#[derive(Debug)]
+pub struct StructBuilder<'o, 'a> {
+    object: &'o mut Object<'a>,
+    section: SectionId,
+    base: u64,
+    align: u64,
+}
+
+impl<'o, 'a> StructBuilder<'o, 'a> {
+    /// Return the section offset of the start of the structure.
+    #[inline]
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Return the current size of the structure.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.object.sections[self.section.0].size - self.base
+    }
+
+    /// Return true if no fields have been appended.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn field(&mut self, data: &[u8], align: u64) -> Result<u64> {
+        if align > self.align {
+            return Err(Error(format!(
+                "field alignment {} is greater than structure alignment {}",
+                align, self.align
+            )));
+        }
+        Ok(self.object.append_section_data(self.section, data, align))
+    }
+
+    /// Append a `u32` field using the endianness of the object.
+    ///
+    /// Returns the section offset of the field.
+    pub fn field_u32(&mut self, value: u32) -> Result<u64> {
+        let endian = self.object.endian;
+        self.field(pod::bytes_of(&U32::new(endian, value)), 4)
+    }
+
+    /// Append a `u64` field using the endianness of the object.
+    ///
+    /// Returns the section offset of the field.
+    pub fn field_u64(&mut self, value: u64) -> Result<u64> {
+        let endian = self.object.endian;
+        self.field(pod::bytes_of(&U64::new(endian, value)), 8)
+    }
+
+    /// Append a byte array field with an alignment of 1.
+    ///
+    /// Returns the section offset of the field.
+    pub fn field_bytes(&mut self, data: &[u8]) -> Result<u64> {
+        self.field(data, 1)
+    }
+
+    /// Append a pointer field that is relocated to the address of `symbol`.
+    ///
+    /// The field has the size and alignment of an address for the architecture.
+    /// Returns the section offset of the field.
+    pub fn field_ptr(&mut self, symbol: SymbolId) -> Result<u64> {
+        let address_size = match self.object.architecture.address_size() {
+            Some(address_size) => address_size.bytes(),
+            None => {
+                return Err(Error(format!(
+                    "unknown address size for architecture {:?}",
+                    self.object.architecture
+                )))
+            }
+        };
+        let offset = self.field(&[0; 8][..usize::from(address_size)], address_size.into())?;
+        self.object.add_relocation(
+            self.section,
+            Relocation {
+                offset,
+                symbol,
+                addend: 0,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: address_size * 8,
+                },
+            },
+        )?;
+        Ok(offset)
+    }
+
+    /// Finish the structure by padding its size to a multiple of its alignment.
+    ///
+    /// Returns the section offset of the start of the structure.
+    pub fn finish(self) -> u64 {
+        self.object
+            .append_section_data(self.section, &[], self.align);
+        self.base
+    }
+}
+
+/// An identifier used to reference a field reserved by [`Object::reserve_fixup`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixupId(usize);
+
+#[derive(Debug, Clone)]
+struct Fixup {
+    section: SectionId,
+    offset: u64,
+    size: u8,
+}
+
+/// A translator from generic relocations to format specific relocation flags.
+///
+/// This allows emitting relocations for architectures, or relocation kinds,
+/// that are not natively supported by the writer.
+/// See [`Object::set_custom_relocation_translator`].
+pub trait RelocationTranslator: fmt::Debug {
+    /// Translate a generic relocation into format specific relocation flags.
+    ///
+    /// Returns `None` if the relocation is not supported.
+    fn translate_relocation(
+        &self,
+        format: BinaryFormat,
+        kind: RelocationKind,
+        encoding: RelocationEncoding,
+        size: u8,
+    ) -> Option<RelocationFlags>;
+
+    /// Return the size in bits of the place of a translated relocation.
+    ///
+    /// This is only required for file formats and architectures that store
+    /// the addend in the section data.
+    fn relocation_size(&self, _format: BinaryFormat, _flags: RelocationFlags) -> Option<u8> {
+        None
+    }
+}
+
+/// An identifier used to reference an ELF symbol version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionId(usize);
+
 /// An identifier used to reference a COMDAT section group.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ComdatId(usize);
 
 /// A COMDAT section group.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Comdat {
     /// The COMDAT selection kind.
     ///
@@ -1059,11 +3824,195 @@ pub struct Comdat {
     ///
     /// If this symbol is referenced, then all sections in the group will be included by the
     /// linker.
+    ///
+    /// For ELF, this is the signature symbol of the group. If it is a section symbol, then
+    /// the signature is the section name. Section symbols are not generated for the sections
+    /// in the group, for both [`ComdatKind::Any`] and [`ComdatKind::Group`].
     pub symbol: SymbolId,
     /// The sections in the group.
     pub sections: Vec<SectionId>,
 }
 
+/// Check that a setting has the same value in two objects, unless one of them
+/// has the default value.
+fn check_setting<T: PartialEq + fmt::Debug>(
+    name: &str,
+    value: &T,
+    other: &T,
+    default: &T,
+) -> Result<()> {
+    if value != default && other != default && value != other {
+        return Err(Error(format!(
+            "cannot append object with {} {:?} to object with {} {:?}",
+            name, other, name, value
+        )));
+    }
+    Ok(())
+}
+
+/// Merge a setting that has been checked by `check_setting`.
+fn merge_setting<T: PartialEq>(value: &mut T, other: T, default: &T) {
+    if value == default {
+        *value = other;
+    }
+}
+
+/// Hash a value for `Object::content_hash` as a 64-bit little-endian value.
+fn hash_u64(hasher: &mut sha1::Sha1, value: u64) {
+    hasher.update(&value.to_le_bytes());
+}
+
+/// Hash relocation flags for `Object::content_hash`.
+///
+/// Each variant is a tag byte followed by its fields.
+fn hash_relocation_flags(hasher: &mut sha1::Sha1, flags: RelocationFlags) {
+    match flags {
+        RelocationFlags::Generic {
+            kind,
+            encoding,
+            size,
+        } => hasher.update(&[0, kind as u8, encoding as u8, size]),
+        RelocationFlags::Elf { r_type } => {
+            hasher.update(&[1]);
+            hasher.update(&r_type.to_le_bytes());
+        }
+        RelocationFlags::MachO {
+            r_type,
+            r_pcrel,
+            r_length,
+        } => hasher.update(&[2, r_type, r_pcrel as u8, r_length]),
+        RelocationFlags::Coff { typ } => {
+            hasher.update(&[3]);
+            hasher.update(&typ.to_le_bytes());
+        }
+        RelocationFlags::Xcoff { r_rtype, r_rsize } => hasher.update(&[4, r_rtype, r_rsize]),
+    }
+}
+
+/// Hash symbol flags for `Object::content_hash`.
+///
+/// Each variant is a tag byte followed by its fields. Optional indices are
+/// hashed as the index plus one, or zero if absent.
+fn hash_symbol_flags(hasher: &mut sha1::Sha1, flags: SymbolFlags<SectionId, SymbolId>) {
+    match flags {
+        SymbolFlags::None => hasher.update(&[0]),
+        SymbolFlags::Elf { st_info, st_other } => hasher.update(&[1, st_info, st_other]),
+        SymbolFlags::MachO { n_desc } => {
+            hasher.update(&[2]);
+            hasher.update(&n_desc.to_le_bytes());
+        }
+        SymbolFlags::CoffSection {
+            selection,
+            associative_section,
+        } => {
+            hasher.update(&[3, selection]);
+            hash_u64(hasher, associative_section.map_or(0, |s| s.0 as u64 + 1));
+        }
+        SymbolFlags::Xcoff {
+            n_sclass,
+            x_smtyp,
+            x_smclas,
+            containing_csect,
+        } => {
+            hasher.update(&[4, n_sclass, x_smtyp, x_smclas]);
+            hash_u64(hasher, containing_csect.map_or(0, |s| s.0 as u64 + 1));
+        }
+    }
+}
+
+/// Create a name-based (version 5) UUID from a SHA-1 hash.
+#[cfg(any(feature = "elf", feature = "macho"))]
+fn uuid_from_hash(hash: &[u8; 20]) -> [u8; 16] {
+    let mut uuid = [0; 16];
+    uuid.copy_from_slice(&hash[..16]);
+    uuid[6] = (uuid[6] & 0x0f) | 0x50;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    uuid
+}
+
+/// The pattern used to fill alignment padding in a section.
+///
+/// See [`Object::set_section_fill`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FillPattern {
+    /// Fill with zero bytes. This is the default.
+    Zero,
+    /// Fill with the given byte, such as `0xcc` for x86 `int3`.
+    Byte(u8),
+    /// Fill with NOP instructions for the architecture.
+    Nop,
+}
+
+/// The style of a GNU build ID or Mach-O UUID.
+///
+/// See [`Object::add_build_id`] and [`Object::set_macho_uuid`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BuildIdStyle {
+    /// A 16 byte name-based (version 5) UUID derived from the contents.
+    ///
+    /// This uses the first 16 bytes of the hash described for [`Self::FromContents`].
+    Uuid,
+    /// An explicit 20 byte SHA-1 hash.
+    Sha1(Vec<u8>),
+    /// An explicit 16 byte MD5 hash.
+    Md5(Vec<u8>),
+    /// An explicit 16 byte UUID.
+    ///
+    /// This is not required to be a version 5 UUID, and is used when a UUID
+    /// derived by [`Self::Uuid`] is replaced by its value.
+    ExplicitUuid(Vec<u8>),
+    /// A 20 byte SHA-1 hash of the contents.
+    ///
+    /// This is the hash returned by [`Object::content_hash`], so it does not
+    /// depend on the placement of the sections in the file.
+    FromContents,
+}
+
+/// The algorithm used for a checksum section.
+///
+/// The checksum is stored as a 32-bit value using the endianness of the object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ChecksumKind {
+    /// The CRC-32 used by zlib and IEEE 802.3.
+    Crc32,
+    /// The wrapping sum of the bytes.
+    Sum32,
+}
+
+/// The visibility of an ELF symbol.
+///
+/// Used by [`Object::set_symbol_visibility`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ElfVisibility {
+    /// `STV_DEFAULT`: the visibility is determined by the symbol binding.
+    Default,
+    /// `STV_INTERNAL`: processor specific hidden visibility.
+    Internal,
+    /// `STV_HIDDEN`: the symbol is not visible to other components.
+    Hidden,
+    /// `STV_PROTECTED`: the symbol is visible to other components, but
+    /// references within the defining component cannot be interposed.
+    Protected,
+}
+
+/// The symbols that have their size inferred when writing.
+///
+/// Used by [`Object::set_elf_symbol_size_inference`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SymbolSizeInference {
+    /// Symbol sizes are not inferred.
+    None,
+    /// Infer the size of function symbols.
+    Functions,
+    /// Infer the size of function and data symbols.
+    FunctionsAndData,
+}
+
 /// The symbol name mangling scheme.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[non_exhaustive]
@@ -5,6 +5,7 @@ use core::mem;
 
 use crate::endian::{LittleEndian as LE, *};
 use crate::pe;
+use crate::pod;
 use crate::write::util;
 use crate::write::{Error, Result, WritableBuffer};
 
@@ -43,6 +44,23 @@ pub struct Writer<'a> {
     reloc_blocks: Vec<RelocBlock>,
     relocs: Vec<U16<LE>>,
     reloc_offset: u32,
+
+    import_dlls: Vec<ImportDll>,
+    import_address: u32,
+    import_offset: u32,
+
+    export_dll_name: Vec<u8>,
+    exports: Vec<ExportEntry>,
+    export_base: u32,
+    export_count: u32,
+    export_names: Vec<usize>,
+    export_dll_name_offset: u32,
+    export_address: u32,
+    export_offset: u32,
+
+    codeview: Option<CodeView>,
+    debug_address: u32,
+    debug_offset: u32,
 }
 
 impl<'a> Writer<'a> {
@@ -82,6 +100,23 @@ impl<'a> Writer<'a> {
             reloc_blocks: Vec::new(),
             relocs: Vec::new(),
             reloc_offset: 0,
+
+            import_dlls: Vec::new(),
+            import_address: 0,
+            import_offset: 0,
+
+            export_dll_name: Vec::new(),
+            exports: Vec::new(),
+            export_base: 0,
+            export_count: 0,
+            export_names: Vec::new(),
+            export_dll_name_offset: 0,
+            export_address: 0,
+            export_offset: 0,
+
+            codeview: None,
+            debug_address: 0,
+            debug_offset: 0,
         }
     }
 
@@ -699,6 +734,27 @@ impl<'a> Writer<'a> {
         });
     }
 
+    /// Add multiple base relocations.
+    ///
+    /// Each relocation is a virtual address and one of the `IMAGE_REL_BASED_*`
+    /// constants. Unlike `add_reloc`, the relocations do not need to be sorted;
+    /// they are sorted by address and grouped into a block for each 4K page.
+    /// A relocation belongs to the page containing its first byte, even if the
+    /// value it applies to extends into the next page.
+    ///
+    /// The addresses must be in pages after those of any previously added relocations.
+    pub fn add_relocs<I>(&mut self, relocs: I)
+    where
+        I: IntoIterator<Item = (u32, u16)>,
+    {
+        let mut relocs: Vec<_> = relocs.into_iter().collect();
+        relocs.sort_unstable();
+        relocs.dedup();
+        for (virtual_address, typ) in relocs {
+            self.add_reloc(virtual_address, typ);
+        }
+    }
+
     /// Return true if a base relocation has been added.
     pub fn has_relocs(&mut self) -> bool {
         !self.relocs.is_empty()
@@ -760,6 +816,462 @@ impl<'a> Writer<'a> {
         self.write_align(self.file_alignment);
     }
 
+    /// Add a DLL to the import table.
+    ///
+    /// `name` is the name of the DLL, without a null terminator.
+    ///
+    /// Returns the index of the DLL, for use with `import_address`.
+    pub fn add_import_dll(&mut self, name: Vec<u8>, imports: Vec<Import>) -> usize {
+        self.import_dlls.push(ImportDll {
+            name,
+            imports,
+            ..Default::default()
+        });
+        self.import_dlls.len() - 1
+    }
+
+    /// Reserve an `.idata` section.
+    ///
+    /// This contains the imports that were added with `add_import_dll`: the import
+    /// descriptors, followed by the import lookup tables, the import address tables,
+    /// the hint/name table and the DLL names. The thunks are 32-bit or 64-bit
+    /// depending on whether this is a PE32 or PE32+ file.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_IMPORT` and
+    /// `pe::IMAGE_DIRECTORY_ENTRY_IAT` data directories.
+    pub fn reserve_import_section(&mut self) -> SectionRange {
+        let thunk_size = if self.is_64 { 8 } else { 4 };
+        let descriptors_size = (self.import_dlls.len() as u32 + 1)
+            * mem::size_of::<pe::ImageImportDescriptor>() as u32;
+        let mut offset = util::align_u32(descriptors_size, thunk_size);
+        for dll in &mut self.import_dlls {
+            dll.ilt_offset = offset;
+            offset += (dll.imports.len() as u32 + 1) * thunk_size;
+        }
+        let iat_offset = offset;
+        for dll in &mut self.import_dlls {
+            dll.iat_offset = offset;
+            offset += (dll.imports.len() as u32 + 1) * thunk_size;
+        }
+        let iat_size = offset - iat_offset;
+        for dll in &mut self.import_dlls {
+            dll.hint_name_offsets.clear();
+            for import in &dll.imports {
+                match import {
+                    Import::Ordinal(_) => dll.hint_name_offsets.push(0),
+                    Import::Name(_, name) => {
+                        dll.hint_name_offsets.push(offset);
+                        // Hint, name, null terminator, and padding to an even length.
+                        offset += util::align_u32(2 + name.len() as u32 + 1, 2);
+                    }
+                }
+            }
+        }
+        for dll in &mut self.import_dlls {
+            dll.name_offset = offset;
+            offset += dll.name.len() as u32 + 1;
+        }
+
+        let size = offset;
+        let range = self.reserve_section(
+            *b".idata\0\0",
+            pe::IMAGE_SCN_CNT_INITIALIZED_DATA | pe::IMAGE_SCN_MEM_READ | pe::IMAGE_SCN_MEM_WRITE,
+            size,
+            size,
+        );
+        let dir = &mut self.data_directories[pe::IMAGE_DIRECTORY_ENTRY_IMPORT];
+        debug_assert_eq!(dir.virtual_address, 0);
+        *dir = DataDirectory {
+            virtual_address: range.virtual_address,
+            size: descriptors_size,
+        };
+        let dir = &mut self.data_directories[pe::IMAGE_DIRECTORY_ENTRY_IAT];
+        debug_assert_eq!(dir.virtual_address, 0);
+        *dir = DataDirectory {
+            virtual_address: range.virtual_address + iat_offset,
+            size: iat_size,
+        };
+        self.import_address = range.virtual_address;
+        self.import_offset = range.file_offset;
+        range
+    }
+
+    /// Return the virtual address of the import address table entry for an import.
+    ///
+    /// `dll` is the index returned by `add_import_dll`, and `index` is the index
+    /// of the import for that DLL. The loader stores the address of the imported
+    /// symbol in this entry.
+    ///
+    /// This is only valid after the import section has been reserved.
+    pub fn import_address(&self, dll: usize, index: usize) -> u32 {
+        let thunk_size = if self.is_64 { 8 } else { 4 };
+        let dll = &self.import_dlls[dll];
+        debug_assert!(index < dll.imports.len());
+        self.import_address + dll.iat_offset + index as u32 * thunk_size
+    }
+
+    /// Write an `.idata` section.
+    ///
+    /// This contains the imports that were added with `add_import_dll`.
+    pub fn write_import_section(&mut self) {
+        if self.import_offset == 0 {
+            return;
+        }
+        let address = self.import_address;
+        let offset = self.import_offset;
+        self.pad_until(offset);
+
+        for dll in &self.import_dlls {
+            self.buffer.write(&pe::ImageImportDescriptor {
+                original_first_thunk: U32Bytes::new(LE, address + dll.ilt_offset),
+                time_date_stamp: U32Bytes::new(LE, 0),
+                forwarder_chain: U32Bytes::new(LE, 0),
+                name: U32Bytes::new(LE, address + dll.name_offset),
+                first_thunk: U32Bytes::new(LE, address + dll.iat_offset),
+            });
+        }
+        self.buffer.write(&pe::ImageImportDescriptor {
+            original_first_thunk: U32Bytes::new(LE, 0),
+            time_date_stamp: U32Bytes::new(LE, 0),
+            forwarder_chain: U32Bytes::new(LE, 0),
+            name: U32Bytes::new(LE, 0),
+            first_thunk: U32Bytes::new(LE, 0),
+        });
+
+        // The import lookup tables and the import address tables are identical
+        // until the imports are bound by the loader.
+        for iat in [false, true] {
+            for dll in &self.import_dlls {
+                let table_offset = if iat { dll.iat_offset } else { dll.ilt_offset };
+                let len = offset + table_offset;
+                self.buffer.resize(len as usize);
+                for (import, hint_name_offset) in dll.imports.iter().zip(&dll.hint_name_offsets) {
+                    let thunk = match import {
+                        Import::Ordinal(ordinal) => {
+                            if self.is_64 {
+                                pe::IMAGE_ORDINAL_FLAG64 | u64::from(*ordinal)
+                            } else {
+                                u64::from(pe::IMAGE_ORDINAL_FLAG32 | u32::from(*ordinal))
+                            }
+                        }
+                        Import::Name(..) => u64::from(address + hint_name_offset),
+                    };
+                    if self.is_64 {
+                        self.buffer.write(&U64::new(LE, thunk));
+                    } else {
+                        self.buffer.write(&U32::new(LE, thunk as u32));
+                    }
+                }
+                if self.is_64 {
+                    self.buffer.write(&U64::new(LE, 0));
+                } else {
+                    self.buffer.write(&U32::new(LE, 0));
+                }
+            }
+        }
+
+        for dll in &self.import_dlls {
+            for (import, hint_name_offset) in dll.imports.iter().zip(&dll.hint_name_offsets) {
+                if let Import::Name(hint, name) = import {
+                    debug_assert_eq!(self.buffer.len(), (offset + hint_name_offset) as usize);
+                    self.buffer.write(&U16::new(LE, *hint));
+                    self.buffer.write_bytes(name);
+                    self.buffer.write_bytes(&[0]);
+                    util::write_align(self.buffer, 2);
+                }
+            }
+        }
+        for dll in &self.import_dlls {
+            debug_assert_eq!(self.buffer.len(), (offset + dll.name_offset) as usize);
+            self.buffer.write_bytes(&dll.name);
+            self.buffer.write_bytes(&[0]);
+        }
+
+        self.write_align(self.file_alignment);
+    }
+
+    /// Set the exports for the export table.
+    ///
+    /// `dll_name` is the name of this DLL, without a null terminator.
+    pub fn set_exports(&mut self, dll_name: Vec<u8>, exports: Vec<Export>) {
+        self.export_dll_name = dll_name;
+        self.exports = exports
+            .into_iter()
+            .map(|export| ExportEntry {
+                export,
+                index: 0,
+                name_offset: 0,
+                forward_offset: 0,
+            })
+            .collect();
+    }
+
+    /// Reserve an `.edata` section.
+    ///
+    /// This contains the exports that were set with `set_exports`: the export
+    /// directory, followed by the export address table, the name pointer table,
+    /// the ordinal table, the DLL name, the export names and the forwarder strings.
+    ///
+    /// Exports without an explicit ordinal are assigned the lowest unused ordinals
+    /// starting at the ordinal base. The ordinal base is the lowest explicit ordinal,
+    /// or 1 if there are none. The name pointer table is sorted by name.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_EXPORT` data directory.
+    ///
+    /// Returns an error if an export has neither a name nor an ordinal, or if
+    /// there are duplicate names or ordinals.
+    pub fn reserve_export_section(&mut self) -> Result<SectionRange> {
+        let mut ordinals = Vec::new();
+        for entry in &self.exports {
+            if let Some(ordinal) = entry.export.ordinal {
+                ordinals.push(u32::from(ordinal));
+            } else if entry.export.name.is_none() {
+                return Err(Error(String::from(
+                    "Export must have either a name or an ordinal",
+                )));
+            }
+        }
+        ordinals.sort_unstable();
+        if let Some(ordinal) = ordinals.windows(2).find(|w| w[0] == w[1]) {
+            return Err(Error(format!("Duplicate export ordinal {}", ordinal[0])));
+        }
+        let base = ordinals.first().copied().unwrap_or(1);
+
+        // Assign the unused ordinals in order, skipping over the explicit ones.
+        let mut next = base;
+        let mut explicit = ordinals.iter().peekable();
+        let mut count = ordinals
+            .last()
+            .map(|ordinal| ordinal - base + 1)
+            .unwrap_or(0);
+        for entry in &mut self.exports {
+            let ordinal = match entry.export.ordinal {
+                Some(ordinal) => u32::from(ordinal),
+                None => {
+                    while explicit.peek() == Some(&&next) {
+                        explicit.next();
+                        next += 1;
+                    }
+                    next += 1;
+                    next - 1
+                }
+            };
+            if ordinal > 0xffff {
+                return Err(Error(String::from("Too many exports")));
+            }
+            entry.index = ordinal - base;
+            count = count.max(entry.index + 1);
+        }
+
+        let mut names: Vec<usize> = (0..self.exports.len())
+            .filter(|&i| self.exports[i].export.name.is_some())
+            .collect();
+        names.sort_by(|&a, &b| {
+            self.exports[a]
+                .export
+                .name
+                .cmp(&self.exports[b].export.name)
+        });
+        if let Some(w) = names
+            .windows(2)
+            .find(|w| self.exports[w[0]].export.name == self.exports[w[1]].export.name)
+        {
+            let name = self.exports[w[0]]
+                .export
+                .name
+                .as_deref()
+                .unwrap_or_default();
+            return Err(Error(format!(
+                "Duplicate export name `{}`",
+                String::from_utf8_lossy(name)
+            )));
+        }
+
+        let mut offset = mem::size_of::<pe::ImageExportDirectory>() as u32
+            + count * 4
+            + names.len() as u32 * (4 + 2);
+        self.export_dll_name_offset = offset;
+        offset += self.export_dll_name.len() as u32 + 1;
+        for &i in &names {
+            let entry = &mut self.exports[i];
+            entry.name_offset = offset;
+            offset += entry
+                .export
+                .name
+                .as_ref()
+                .map_or(0, |name| name.len() as u32)
+                + 1;
+        }
+        for entry in &mut self.exports {
+            if let ExportTarget::Forward(forward) = &entry.export.target {
+                entry.forward_offset = offset;
+                offset += forward.len() as u32 + 1;
+            }
+        }
+
+        self.export_base = base;
+        self.export_count = count;
+        self.export_names = names;
+
+        // The data directory covers all of the strings, so that forwarder
+        // strings are recognized by their address.
+        let size = offset;
+        let range = self.reserve_edata_section(size);
+        self.export_address = range.virtual_address;
+        self.export_offset = range.file_offset;
+        Ok(range)
+    }
+
+    /// Write an `.edata` section.
+    ///
+    /// This contains the exports that were set with `set_exports`.
+    pub fn write_export_section(&mut self) {
+        if self.export_offset == 0 {
+            return;
+        }
+        let address = self.export_address;
+        let offset = self.export_offset;
+        self.pad_until(offset);
+
+        let count = self.export_count;
+        let names = self.export_names.len() as u32;
+        let functions = mem::size_of::<pe::ImageExportDirectory>() as u32;
+        self.buffer.write(&pe::ImageExportDirectory {
+            characteristics: U32::new(LE, 0),
+            time_date_stamp: U32::new(LE, 0),
+            major_version: U16::new(LE, 0),
+            minor_version: U16::new(LE, 0),
+            name: U32::new(LE, address + self.export_dll_name_offset),
+            base: U32::new(LE, self.export_base),
+            number_of_functions: U32::new(LE, count),
+            number_of_names: U32::new(LE, names),
+            address_of_functions: U32::new(LE, address + functions),
+            address_of_names: U32::new(LE, address + functions + count * 4),
+            address_of_name_ordinals: U32::new(LE, address + functions + count * 4 + names * 4),
+        });
+
+        // Unused ordinals have an address of 0.
+        let mut addresses = vec![0; count as usize];
+        for entry in &self.exports {
+            addresses[entry.index as usize] = match entry.export.target {
+                ExportTarget::Address(address) => address,
+                ExportTarget::Forward(_) => address + entry.forward_offset,
+            };
+        }
+        for address in addresses {
+            self.buffer.write(&U32::new(LE, address));
+        }
+        for &i in &self.export_names {
+            self.buffer
+                .write(&U32::new(LE, address + self.exports[i].name_offset));
+        }
+        for &i in &self.export_names {
+            self.buffer
+                .write(&U16::new(LE, self.exports[i].index as u16));
+        }
+
+        debug_assert_eq!(
+            self.buffer.len(),
+            (offset + self.export_dll_name_offset) as usize
+        );
+        self.buffer.write_bytes(&self.export_dll_name);
+        self.buffer.write_bytes(&[0]);
+        for &i in &self.export_names {
+            let entry = &self.exports[i];
+            debug_assert_eq!(self.buffer.len(), (offset + entry.name_offset) as usize);
+            if let Some(name) = &entry.export.name {
+                self.buffer.write_bytes(name);
+            }
+            self.buffer.write_bytes(&[0]);
+        }
+        for entry in &self.exports {
+            if let ExportTarget::Forward(forward) = &entry.export.target {
+                debug_assert_eq!(self.buffer.len(), (offset + entry.forward_offset) as usize);
+                self.buffer.write_bytes(forward);
+                self.buffer.write_bytes(&[0]);
+            }
+        }
+
+        self.write_align(self.file_alignment);
+    }
+
+    /// Set the CodeView information for the debug directory.
+    ///
+    /// `guid` and `age` must match the values in the PDB file at `pdb_path`.
+    /// This is used by debuggers to find the PDB file for the image.
+    pub fn set_codeview(&mut self, guid: [u8; 16], age: u32, pdb_path: String) {
+        self.codeview = Some(CodeView {
+            guid,
+            age,
+            pdb_path,
+        });
+    }
+
+    /// Reserve a `.buildid` section.
+    ///
+    /// This contains a debug directory with a single `pe::IMAGE_DEBUG_TYPE_CODEVIEW`
+    /// entry, followed by the `RSDS` record for the information that was set with
+    /// `set_codeview`.
+    ///
+    /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_DEBUG` data directory.
+    ///
+    /// Returns an error if `set_codeview` has not been called.
+    pub fn reserve_debug_section(&mut self) -> Result<SectionRange> {
+        let pdb_path_len = match &self.codeview {
+            Some(codeview) => codeview.pdb_path.len(),
+            None => return Err(Error(String::from("Missing CodeView information"))),
+        };
+        let dir_size = mem::size_of::<pe::ImageDebugDirectory>() as u32;
+        // Signature, GUID, age, path, and null terminator.
+        let size = dir_size + 4 + 16 + 4 + pdb_path_len as u32 + 1;
+        let range = self.reserve_section(
+            *b".buildid",
+            pe::IMAGE_SCN_CNT_INITIALIZED_DATA | pe::IMAGE_SCN_MEM_READ,
+            size,
+            size,
+        );
+        let dir = &mut self.data_directories[pe::IMAGE_DIRECTORY_ENTRY_DEBUG];
+        debug_assert_eq!(dir.virtual_address, 0);
+        *dir = DataDirectory {
+            virtual_address: range.virtual_address,
+            size: dir_size,
+        };
+        self.debug_address = range.virtual_address;
+        self.debug_offset = range.file_offset;
+        Ok(range)
+    }
+
+    /// Write a `.buildid` section.
+    ///
+    /// This contains the CodeView information that was set with `set_codeview`.
+    pub fn write_debug_section(&mut self) {
+        if self.debug_offset == 0 {
+            return;
+        }
+        self.pad_until(self.debug_offset);
+        // `reserve_debug_section` checked that this is set.
+        let codeview = self.codeview.as_ref().unwrap();
+        let dir_size = mem::size_of::<pe::ImageDebugDirectory>() as u32;
+        let data_size = 4 + 16 + 4 + codeview.pdb_path.len() as u32 + 1;
+        self.buffer.write(&pe::ImageDebugDirectory {
+            characteristics: U32::new(LE, 0),
+            time_date_stamp: U32::new(LE, 0),
+            major_version: U16::new(LE, 0),
+            minor_version: U16::new(LE, 0),
+            typ: U32::new(LE, pe::IMAGE_DEBUG_TYPE_CODEVIEW),
+            size_of_data: U32::new(LE, data_size),
+            address_of_raw_data: U32::new(LE, self.debug_address + dir_size),
+            pointer_to_raw_data: U32::new(LE, self.debug_offset + dir_size),
+        });
+        self.buffer.write_bytes(b"RSDS");
+        self.buffer.write_bytes(&codeview.guid);
+        self.buffer.write(&U32::new(LE, codeview.age));
+        self.buffer.write_bytes(codeview.pdb_path.as_bytes());
+        self.buffer.write_bytes(&[0]);
+
+        self.write_align(self.file_alignment);
+    }
+
     /// Reserve the certificate table.
     ///
     /// This also sets the `pe::IMAGE_DIRECTORY_ENTRY_SECURITY` data directory.
@@ -785,6 +1297,54 @@ impl<'a> Writer<'a> {
     }
 }
 
+/// Compute the PE checksum of an image.
+///
+/// This is the 16-bit ones' complement sum of the image, with the carries folded
+/// back in, plus the length of the image. The 4 bytes at `checksum_offset` are
+/// excluded from the sum.
+pub fn checksum(image: &[u8], checksum_offset: usize) -> u32 {
+    let mut sum = 0u32;
+    let mut add = |value: u16| {
+        sum += u32::from(value);
+        sum = (sum & 0xffff) + (sum >> 16);
+    };
+    for (i, word) in image.chunks(2).enumerate() {
+        let offset = i * 2;
+        if offset >= checksum_offset && offset < checksum_offset + 4 {
+            continue;
+        }
+        add(match *word {
+            [lo, hi] => u16::from_le_bytes([lo, hi]),
+            [lo] => u16::from(lo),
+            _ => unreachable!(),
+        });
+    }
+    (sum & 0xffff).wrapping_add(image.len() as u32)
+}
+
+/// Compute the PE checksum of an image and store it in the optional header.
+///
+/// This must be called after the image has been completely written.
+///
+/// Returns an error if the image does not have a valid PE header.
+pub fn set_checksum(image: &mut [u8]) -> Result<()> {
+    let nt_headers_offset = image
+        .get(0x3c..)
+        .and_then(|data| pod::from_bytes::<U32Bytes<LE>>(data).ok())
+        .map(|(e_lfanew, _)| e_lfanew.get(LE) as usize)
+        .ok_or_else(|| Error(String::from("Invalid DOS header")))?;
+    // The checksum has the same offset in both optional header formats.
+    let checksum_offset = nt_headers_offset + 4 + mem::size_of::<pe::ImageFileHeader>() + 64;
+    if image.get(nt_headers_offset..nt_headers_offset + 4) != Some(&b"PE\0\0"[..])
+        || image.len() < checksum_offset + 4
+    {
+        return Err(Error(String::from("Invalid PE headers")));
+    }
+    let checksum = checksum(image, checksum_offset);
+    image[checksum_offset..][..4].copy_from_slice(&checksum.to_le_bytes());
+    Ok(())
+}
+
 /// Information required for writing [`pe::ImageNtHeaders32`] or [`pe::ImageNtHeaders64`].
 #[allow(missing_docs)]
 #[derive(Debug, Clone)]
@@ -837,6 +1397,68 @@ pub struct SectionRange {
     pub file_size: u32,
 }
 
+/// An import for [`Writer::add_import_dll`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Import {
+    /// Import by ordinal.
+    Ordinal(u16),
+    /// Import by name.
+    ///
+    /// Includes a hint for the index into the export name pointer table in the target library.
+    /// The name must not include a null terminator.
+    Name(u16, Vec<u8>),
+}
+
+/// An export for [`Writer::set_exports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+    /// The name of the export.
+    ///
+    /// The name must not include a null terminator. If this is `None`, then the
+    /// export can only be imported by ordinal.
+    pub name: Option<Vec<u8>>,
+    /// The ordinal of the export.
+    ///
+    /// If this is `None`, then an unused ordinal is assigned.
+    pub ordinal: Option<u16>,
+    /// The target of the export.
+    pub target: ExportTarget,
+}
+
+/// The target of an [`Export`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportTarget {
+    /// The address of the export, relative to the image base.
+    Address(u32),
+    /// Forwarded to an export in another DLL.
+    ///
+    /// This is a string such as `OTHERDLL.Func` or `OTHERDLL.#1`, without a null terminator.
+    Forward(Vec<u8>),
+}
+
+struct CodeView {
+    guid: [u8; 16],
+    age: u32,
+    pdb_path: String,
+}
+
+struct ExportEntry {
+    export: Export,
+    index: u32,
+    name_offset: u32,
+    forward_offset: u32,
+}
+
+#[derive(Default)]
+struct ImportDll {
+    name: Vec<u8>,
+    imports: Vec<Import>,
+    ilt_offset: u32,
+    iat_offset: u32,
+    name_offset: u32,
+    hint_name_offsets: Vec<u32>,
+}
+
 struct RelocBlock {
     virtual_address: u32,
     count: u32,
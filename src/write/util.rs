@@ -80,6 +80,38 @@ impl WritableBuffer for Vec<u8> {
     }
 }
 
+/// A [`WritableBuffer`] that records the reserved size, and then fails.
+///
+/// This is used to stop writing once the layout is complete.
+#[derive(Debug, Default)]
+pub(crate) struct SizeBuffer {
+    pub size: Option<usize>,
+    pub len: usize,
+}
+
+impl WritableBuffer for SizeBuffer {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn reserve(&mut self, size: usize) -> Result<(), ()> {
+        self.size = Some(size);
+        Err(())
+    }
+
+    #[inline]
+    fn resize(&mut self, new_len: usize) {
+        self.len = new_len;
+    }
+
+    #[inline]
+    fn write_bytes(&mut self, val: &[u8]) {
+        self.len += val.len();
+    }
+}
+
 /// A [`WritableBuffer`] that streams data to a [`Write`](std::io::Write) implementation.
 ///
 /// [`Self::result`] must be called to determine if an I/O error occurred during writing.
@@ -25,7 +25,98 @@ struct SymbolOffsets {
     containing_csect: Option<SymbolId>,
 }
 
+/// The customizable portion of an XCOFF auxiliary header.
+///
+/// See [`Object::set_xcoff_aux_header`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct XcoffAuxHeader {
+    /// Flags (`o_mflag`).
+    pub flags: u16,
+    /// Text size in bytes.
+    pub tsize: u64,
+    /// Initialized data size in bytes.
+    pub dsize: u64,
+    /// Uninitialized data size in bytes.
+    pub bsize: u64,
+    /// Entry point descriptor (virtual address).
+    pub entry: u64,
+    /// Base address of text (virtual address).
+    pub text_start: u64,
+    /// Base address of data (virtual address).
+    pub data_start: u64,
+}
+
 impl<'a> Object<'a> {
+    /// Specify the information for an XCOFF auxiliary header.
+    ///
+    /// The auxiliary header is written between the file header and the section
+    /// headers, using the 32-bit or 64-bit layout depending on the architecture.
+    /// The section numbers and alignments of the first text, data and bss
+    /// sections are filled in when writing. If the entry point is set, then its
+    /// section number is that of the text or data section, depending on which of
+    /// the address ranges given by `text_start` and `tsize`, or `data_start` and
+    /// `dsize`, contains it.
+    ///
+    /// Writing returns an error if a value does not fit in the 32-bit layout, or
+    /// if the entry point is not in either of these ranges.
+    ///
+    /// Requires `feature = "xcoff"`.
+    #[inline]
+    pub fn set_xcoff_aux_header(&mut self, aux_header: XcoffAuxHeader) {
+        self.xcoff_aux_header = Some(aux_header);
+    }
+
+    /// Set the symbol type and storage mapping class of an XCOFF csect symbol.
+    ///
+    /// `x_smtyp` is one of `XTY_ER`, `XTY_SD` or `XTY_CM`, and `x_smclas` is one
+    /// of the `XMC_*` storage mapping classes, such as `XMC_TC0` for the TOC anchor
+    /// or `XMC_TC` for a TOC entry. These replace the values that are derived from
+    /// the symbol kind and section kind, and are written in the csect auxiliary
+    /// entry. The csect alignment is derived from the section alignment.
+    ///
+    /// Local symbols are written with the `C_HIDEXT` storage class instead of
+    /// `C_STAT`, so that they have a csect auxiliary entry. This has no effect if
+    /// the symbol flags are set to [`SymbolFlags::Xcoff`].
+    ///
+    /// Returns an error if the format is not XCOFF, or if `x_smtyp` is not one of
+    /// the supported symbol types. Label definitions (`XTY_LD`) must use
+    /// [`SymbolFlags::Xcoff`] to specify the containing csect.
+    ///
+    /// Requires `feature = "xcoff"`.
+    pub fn set_xcoff_csect(
+        &mut self,
+        symbol_id: SymbolId,
+        x_smtyp: u8,
+        x_smclas: u8,
+    ) -> Result<()> {
+        if self.format != BinaryFormat::Xcoff {
+            return Err(Error(format!(
+                "unsupported csect for format {:?}",
+                self.format
+            )));
+        }
+        if !matches!(x_smtyp, xcoff::XTY_ER | xcoff::XTY_SD | xcoff::XTY_CM) {
+            return Err(Error(format!(
+                "invalid csect symbol type {} for symbol `{}`",
+                x_smtyp,
+                self.symbol(symbol_id).name().unwrap_or("")
+            )));
+        }
+        self.xcoff_csect.insert(symbol_id, (x_smtyp, x_smclas));
+        Ok(())
+    }
+
+    /// Return the csect alignment for the high bits of `x_smtyp`.
+    fn xcoff_csect_alignment(&self, symbol: &Symbol) -> u8 {
+        match symbol.section {
+            SymbolSection::Section(id) => {
+                (self.sections[id.0].align.trailing_zeros().min(31) as u8) << 3
+            }
+            _ => 0,
+        }
+    }
+
     pub(crate) fn xcoff_section_info(
         &self,
         section: StandardSection,
@@ -66,6 +157,16 @@ impl<'a> Object<'a> {
                 // Unsupported section.
                 (&[], &[], SectionKind::Note, SectionFlags::None)
             }
+            StandardSection::InitArray
+            | StandardSection::FiniArray
+            | StandardSection::PreInitArray => {
+                // Unsupported section.
+                (&[], &[], SectionKind::Data, SectionFlags::None)
+            }
+            StandardSection::EhFrame => {
+                // Unsupported section.
+                (&[], &[], SectionKind::ReadOnlyData, SectionFlags::None)
+            }
         }
     }
 
@@ -220,6 +321,11 @@ impl<'a> Object<'a> {
                 mem::size_of::<xcoff::Symbol32>(),
             )
         };
+        let aux_header_size = match self.xcoff_aux_header {
+            Some(_) if is_64 => mem::size_of::<xcoff::AuxHeader64>(),
+            Some(_) => mem::size_of::<xcoff::AuxHeader32>(),
+            None => 0,
+        };
 
         // Calculate offsets and build strtab.
         let mut offset = 0;
@@ -229,6 +335,8 @@ impl<'a> Object<'a> {
 
         // XCOFF file header.
         offset += hdr_size;
+        // Auxiliary header.
+        offset += aux_header_size;
         // Section headers.
         offset += self.sections.len() * sechdr_size;
 
@@ -278,9 +386,9 @@ impl<'a> Object<'a> {
             symtab_count += 1;
 
             let SymbolFlags::Xcoff {
-                n_sclass,
-                x_smtyp,
-                x_smclas,
+                mut n_sclass,
+                mut x_smtyp,
+                mut x_smclas,
                 containing_csect,
             } = self.symbol_flags(symbol)
             else {
@@ -290,6 +398,18 @@ impl<'a> Object<'a> {
                     symbol.kind
                 )));
             };
+            if symbol.flags == SymbolFlags::None {
+                if let Some(&(smtyp, smclas)) = self.xcoff_csect.get(&SymbolId(index)) {
+                    if n_sclass == xcoff::C_STAT {
+                        n_sclass = xcoff::C_HIDEXT;
+                    }
+                    x_smtyp = match smtyp {
+                        xcoff::XTY_ER => smtyp,
+                        _ => smtyp | self.xcoff_csect_alignment(symbol),
+                    };
+                    x_smclas = smclas;
+                }
+            }
             symbol_offsets[index].storage_class = n_sclass;
             symbol_offsets[index].x_smtyp = x_smtyp;
             symbol_offsets[index].x_smclas = x_smclas;
@@ -332,6 +452,59 @@ impl<'a> Object<'a> {
         let strtab_len = strtab_data.len() + 4;
         offset += strtab_len;
 
+        // Find the section number and alignment of the first section of a kind.
+        let section_info = |kind| {
+            self.sections
+                .iter()
+                .position(|section| section.kind == kind)
+                .map_or((0, 0), |index| {
+                    let align = self.sections[index].align.trailing_zeros() as u16;
+                    (index as u16 + 1, align)
+                })
+        };
+        let (o_sntext, o_algntext) = section_info(SectionKind::Text);
+        let (o_sndata, o_algndata) = section_info(SectionKind::Data);
+        let (o_snbss, _) = section_info(SectionKind::UninitializedData);
+
+        // Check the auxiliary header before writing anything.
+        let mut o_snentry = 0;
+        if let Some(aux_header) = &self.xcoff_aux_header {
+            if !is_64 {
+                for (name, value) in [
+                    ("tsize", aux_header.tsize),
+                    ("dsize", aux_header.dsize),
+                    ("bsize", aux_header.bsize),
+                    ("entry", aux_header.entry),
+                    ("text_start", aux_header.text_start),
+                    ("data_start", aux_header.data_start),
+                ] {
+                    if value > u64::from(u32::MAX) {
+                        return Err(Error(format!(
+                            "XCOFF auxiliary header {} {:#x} is too large",
+                            name, value
+                        )));
+                    }
+                }
+            }
+            // The entry point is in the text or data section, depending on whether
+            // it is a function or a function descriptor.
+            if aux_header.entry != 0 {
+                let entry = aux_header.entry;
+                let contains = |start: u64, size: u64| entry >= start && entry - start < size;
+                if contains(aux_header.text_start, aux_header.tsize) {
+                    o_snentry = o_sntext;
+                } else if contains(aux_header.data_start, aux_header.dsize) {
+                    o_snentry = o_sndata;
+                }
+                if o_snentry == 0 {
+                    return Err(Error(format!(
+                        "XCOFF auxiliary header entry {:#x} is not in the text or data section",
+                        entry
+                    )));
+                }
+            }
+        }
+
         // Start writing.
         buffer
             .reserve(offset)
@@ -345,7 +518,7 @@ impl<'a> Object<'a> {
                 f_timdat: U32::new(BE, 0),
                 f_symptr: U64::new(BE, symtab_offset as u64),
                 f_nsyms: U32::new(BE, symtab_count as u32),
-                f_opthdr: U16::new(BE, 0),
+                f_opthdr: U16::new(BE, aux_header_size as u16),
                 f_flags: match self.flags {
                     FileFlags::Xcoff { f_flags } => U16::new(BE, f_flags),
                     _ => U16::default(),
@@ -359,7 +532,7 @@ impl<'a> Object<'a> {
                 f_timdat: U32::new(BE, 0),
                 f_symptr: U32::new(BE, symtab_offset as u32),
                 f_nsyms: U32::new(BE, symtab_count as u32),
-                f_opthdr: U16::new(BE, 0),
+                f_opthdr: U16::new(BE, aux_header_size as u16),
                 f_flags: match self.flags {
                     FileFlags::Xcoff { f_flags } => U16::new(BE, f_flags),
                     _ => U16::default(),
@@ -368,6 +541,81 @@ impl<'a> Object<'a> {
             buffer.write(&header);
         }
 
+        // Write auxiliary header.
+        if let Some(aux_header) = &self.xcoff_aux_header {
+            if is_64 {
+                let aux_header = xcoff::AuxHeader64 {
+                    o_mflag: U16::new(BE, aux_header.flags),
+                    o_vstamp: U16::new(BE, 1),
+                    o_debugger: U32::new(BE, 0),
+                    o_text_start: U64::new(BE, aux_header.text_start),
+                    o_data_start: U64::new(BE, aux_header.data_start),
+                    o_toc: U64::new(BE, 0),
+                    o_snentry: U16::new(BE, o_snentry),
+                    o_sntext: U16::new(BE, o_sntext),
+                    o_sndata: U16::new(BE, o_sndata),
+                    o_sntoc: U16::new(BE, 0),
+                    o_snloader: U16::new(BE, 0),
+                    o_snbss: U16::new(BE, o_snbss),
+                    o_algntext: U16::new(BE, o_algntext),
+                    o_algndata: U16::new(BE, o_algndata),
+                    o_modtype: U16::new(BE, 0),
+                    o_cpuflag: 0,
+                    o_cputype: 0,
+                    o_textpsize: 0,
+                    o_datapsize: 0,
+                    o_stackpsize: 0,
+                    o_flags: 0,
+                    o_tsize: U64::new(BE, aux_header.tsize),
+                    o_dsize: U64::new(BE, aux_header.dsize),
+                    o_bsize: U64::new(BE, aux_header.bsize),
+                    o_entry: U64::new(BE, aux_header.entry),
+                    o_maxstack: U64::new(BE, 0),
+                    o_maxdata: U64::new(BE, 0),
+                    o_sntdata: U16::new(BE, 0),
+                    o_sntbss: U16::new(BE, 0),
+                    o_x64flags: U16::new(BE, 0),
+                    o_resv3a: U16::new(BE, 0),
+                    o_resv3: [U32::new(BE, 0); 2],
+                };
+                buffer.write(&aux_header);
+            } else {
+                // The values were checked to fit in 32 bits.
+                let aux_header = xcoff::AuxHeader32 {
+                    o_mflag: U16::new(BE, aux_header.flags),
+                    o_vstamp: U16::new(BE, 1),
+                    o_tsize: U32::new(BE, aux_header.tsize as u32),
+                    o_dsize: U32::new(BE, aux_header.dsize as u32),
+                    o_bsize: U32::new(BE, aux_header.bsize as u32),
+                    o_entry: U32::new(BE, aux_header.entry as u32),
+                    o_text_start: U32::new(BE, aux_header.text_start as u32),
+                    o_data_start: U32::new(BE, aux_header.data_start as u32),
+                    o_toc: U32::new(BE, 0),
+                    o_snentry: U16::new(BE, o_snentry),
+                    o_sntext: U16::new(BE, o_sntext),
+                    o_sndata: U16::new(BE, o_sndata),
+                    o_sntoc: U16::new(BE, 0),
+                    o_snloader: U16::new(BE, 0),
+                    o_snbss: U16::new(BE, o_snbss),
+                    o_algntext: U16::new(BE, o_algntext),
+                    o_algndata: U16::new(BE, o_algndata),
+                    o_modtype: U16::new(BE, 0),
+                    o_cpuflag: 0,
+                    o_cputype: 0,
+                    o_maxstack: U32::new(BE, 0),
+                    o_maxdata: U32::new(BE, 0),
+                    o_debugger: U32::new(BE, 0),
+                    o_textpsize: 0,
+                    o_datapsize: 0,
+                    o_stackpsize: 0,
+                    o_flags: 0,
+                    o_sntdata: U16::new(BE, 0),
+                    o_sntbss: U16::new(BE, 0),
+                };
+                buffer.write(&aux_header);
+            }
+        }
+
         // Write section headers.
         for (index, section) in self.sections.iter().enumerate() {
             let mut sectname = [0; 8];
@@ -430,7 +678,7 @@ impl<'a> Object<'a> {
             if len != 0 {
                 write_align(buffer, 4);
                 debug_assert_eq!(section_offsets[index].data_offset, buffer.len());
-                buffer.write_bytes(&section.data);
+                buffer.write_bytes(&self.section_write_data(SectionId(index))?);
             }
         }
 
@@ -252,3 +252,112 @@ fn test_dynsym() {
         }
     }
 }
+
+#[test]
+fn test_gnu_hash() {
+    use object::read::elf::{FileHeader, VersionTable};
+
+    let mut builder = build::elf::Builder::new(object::Endianness::Little, true);
+    builder.header.e_type = elf::ET_DYN;
+    builder.header.e_machine = elf::EM_X86_64;
+    builder.header.e_phoff = 0x40;
+
+    let section = builder.sections.add();
+    section.name = b".shstrtab"[..].into();
+    section.sh_type = elf::SHT_STRTAB;
+    section.data = build::elf::SectionData::SectionString;
+
+    let section = builder.sections.add();
+    section.name = b".text"[..].into();
+    section.sh_type = elf::SHT_PROGBITS;
+    section.sh_flags = (elf::SHF_ALLOC | elf::SHF_EXECINSTR) as u64;
+    section.sh_addralign = 16;
+    section.data = build::elf::SectionData::Data(vec![0xcc; 1000].into());
+    let text_id = section.id();
+
+    let section = builder.sections.add();
+    section.name = b".dynsym"[..].into();
+    section.sh_type = elf::SHT_DYNSYM;
+    section.sh_flags = elf::SHF_ALLOC as u64;
+    section.sh_addralign = 8;
+    section.data = build::elf::SectionData::DynamicSymbol;
+    let dynsym_id = section.id();
+
+    let section = builder.sections.add();
+    section.name = b".dynstr"[..].into();
+    section.sh_type = elf::SHT_STRTAB;
+    section.sh_flags = elf::SHF_ALLOC as u64;
+    section.sh_addralign = 1;
+    section.data = build::elf::SectionData::DynamicString;
+    let dynstr_id = section.id();
+
+    let section = builder.sections.add();
+    section.name = b".gnu.hash"[..].into();
+    section.sh_type = elf::SHT_GNU_HASH;
+    section.sh_flags = elf::SHF_ALLOC as u64;
+    section.sh_addralign = 8;
+    section.data = build::elf::SectionData::GnuHash;
+    let gnu_hash_id = section.id();
+
+    let names: Vec<_> = (0..100).map(|i| format!("func{}", i)).collect();
+    for (i, name) in names.iter().enumerate() {
+        let symbol = builder.dynamic_symbols.add();
+        symbol.name = name.as_bytes().into();
+        symbol.set_st_info(elf::STB_GLOBAL, elf::STT_FUNC);
+        symbol.section = Some(text_id);
+        symbol.st_value = i as u64 * 8;
+    }
+    let symbol = builder.dynamic_symbols.add();
+    symbol.name = b"undefined"[..].into();
+    symbol.set_st_info(elf::STB_GLOBAL, elf::STT_NOTYPE);
+
+    builder.set_gnu_hash_parameters();
+    assert_eq!(builder.gnu_hash_bucket_count, 25);
+    assert_eq!(builder.gnu_hash_bloom_count, 32);
+    builder.set_section_sizes();
+
+    let segment = builder.segments.add();
+    segment.p_type = elf::PT_LOAD;
+    segment.p_flags = elf::PF_R;
+    segment.p_filesz = 0x1000;
+    segment.p_memsz = 0x1000;
+    segment.p_align = 8;
+    segment.append_section(builder.sections.get_mut(text_id));
+    segment.append_section(builder.sections.get_mut(dynsym_id));
+    segment.append_section(builder.sections.get_mut(dynstr_id));
+    segment.append_section(builder.sections.get_mut(gnu_hash_id));
+
+    let mut buf = Vec::new();
+    builder.write(&mut buf).unwrap();
+
+    let header = elf::FileHeader64::<object::Endianness>::parse(&*buf).unwrap();
+    let endian = header.endian().unwrap();
+    let sections = header.sections(endian, &*buf).unwrap();
+    let dynsyms = sections.symbols(endian, &*buf, elf::SHT_DYNSYM).unwrap();
+    let (hash, link) = sections.gnu_hash(endian, &*buf).unwrap().unwrap();
+    assert_eq!(link, dynsyms.section());
+    let versions = VersionTable::default();
+    for (i, name) in names.iter().enumerate() {
+        let (_, symbol) = hash
+            .find(
+                endian,
+                name.as_bytes(),
+                elf::gnu_hash(name.as_bytes()),
+                None,
+                &dynsyms,
+                &versions,
+            )
+            .unwrap();
+        assert_eq!(symbol.st_value.get(endian), i as u64 * 8);
+    }
+    assert!(hash
+        .find(
+            endian,
+            b"undefined",
+            elf::gnu_hash(b"undefined"),
+            None,
+            &dynsyms,
+            &versions,
+        )
+        .is_none());
+}
@@ -0,0 +1,43 @@
+use object::read::archive::ArchiveFile;
+use object::{archive, write};
+
+#[test]
+fn member_header() {
+    let header =
+        write::archive::member_header(b"test.o", 1234, 1700000000, 1000, 100, 0o644).unwrap();
+    assert_eq!(
+        &header[..],
+        &b"test.o/         1700000000  1000  100   644     1234      `\n"[..]
+    );
+
+    assert!(write::archive::member_header(b"a_very_long_name.o", 0, 0, 0, 0, 0).is_err());
+    assert!(write::archive::member_header(b"test.o", 1 << 40, 0, 0, 0, 0).is_err());
+}
+
+#[test]
+fn member_round_trip() {
+    let mut data = archive::MAGIC.to_vec();
+    write::archive::write_member(&mut data, b"odd.o", b"abc", 1, 2, 3, 0o644).unwrap();
+    assert_eq!(data.len() % 2, 0);
+    write::archive::write_member(&mut data, b"even.o", b"abcd", 4, 5, 6, 0o755).unwrap();
+
+    let archive = ArchiveFile::parse(&*data).unwrap();
+    let mut members = archive.members();
+
+    let member = members.next().unwrap().unwrap();
+    assert_eq!(member.name(), b"odd.o");
+    assert_eq!(member.size(), 3);
+    assert_eq!(member.date(), Some(1));
+    assert_eq!(member.uid(), Some(2));
+    assert_eq!(member.gid(), Some(3));
+    assert_eq!(member.mode(), Some(0o644));
+    assert_eq!(member.data(&*data).unwrap(), b"abc");
+
+    let member = members.next().unwrap().unwrap();
+    assert_eq!(member.name(), b"even.o");
+    assert_eq!(member.date(), Some(4));
+    assert_eq!(member.mode(), Some(0o755));
+    assert_eq!(member.data(&*data).unwrap(), b"abcd");
+
+    assert!(members.next().is_none());
+}
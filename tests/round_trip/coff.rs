@@ -1,9 +1,10 @@
-use object::read::{Object, ObjectSection};
-use object::{read, write};
+use object::read::{Object, ObjectComdat, ObjectSection, ObjectSymbol};
+use object::{pe, read, write};
 use object::{
     Architecture, BinaryFormat, Endianness, RelocationEncoding, RelocationFlags, RelocationKind,
     SymbolFlags, SymbolKind, SymbolScope,
 };
+use std::convert::TryInto;
 
 #[test]
 fn reloc_overflow() {
@@ -56,3 +57,802 @@ fn reloc_overflow() {
     }
     assert_eq!(i, 0x10000);
 }
+
+#[test]
+fn compiler_info() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    object
+        .add_coff_compiler_info("test compiler", (1, 2, 3, 4), "test.obj")
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".debug$S").unwrap();
+    let data = section.data().unwrap();
+    let u16_at = |offset: usize| u16::from_le_bytes(data[offset..][..2].try_into().unwrap());
+    let u32_at = |offset: usize| u32::from_le_bytes(data[offset..][..4].try_into().unwrap());
+
+    // CodeView signature, followed by the symbols subsection.
+    assert_eq!(u32_at(0), 4);
+    assert_eq!(u32_at(4), 0xf1);
+    assert_eq!(u32_at(8) as usize, data.len() - 12);
+
+    // S_OBJNAME
+    let mut offset = 12;
+    let len = u16_at(offset) as usize;
+    assert_eq!(u16_at(offset + 2), 0x1101);
+    assert_eq!(u32_at(offset + 4), 0);
+    assert_eq!(&data[offset + 8..][..9], b"test.obj\0");
+    offset += 2 + len;
+    assert_eq!(offset % 4, 0);
+
+    // S_COMPILE3
+    let len = u16_at(offset) as usize;
+    assert_eq!(u16_at(offset + 2), 0x113c);
+    assert_eq!(u16_at(offset + 8), 0xd0);
+    for i in 0..8 {
+        assert_eq!(u16_at(offset + 10 + i * 2), [1, 2, 3, 4][i % 4]);
+    }
+    assert_eq!(&data[offset + 26..][..14], b"test compiler\0");
+    offset += 2 + len;
+    assert_eq!(offset, data.len());
+}
+
+#[test]
+fn compiler_info_invalid() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    assert!(object
+        .add_coff_compiler_info("test compiler", (1, 2, 3, 4), "test.obj")
+        .is_err());
+
+    // The record length must fit in 16 bits.
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let compiler = "x".repeat(0x10000);
+    assert!(object
+        .add_coff_compiler_info(&compiler, (1, 2, 3, 4), "test.obj")
+        .is_err());
+}
+
+#[test]
+fn add_relocations() {
+    let build = |batch: bool| {
+        let mut object =
+            write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0; 64], 4);
+        let symbol = object.add_symbol(write::Symbol {
+            name: b"f".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        let relocations = (0..16).map(|i| write::Relocation {
+            offset: i * 4,
+            symbol,
+            addend: i as i64 - 4,
+            flags: RelocationFlags::Generic {
+                kind: RelocationKind::Relative,
+                encoding: RelocationEncoding::Generic,
+                size: 32,
+            },
+        });
+        if batch {
+            object.add_relocations(text, relocations).unwrap();
+        } else {
+            for relocation in relocations {
+                object.add_relocation(text, relocation).unwrap();
+            }
+        }
+        object.write().unwrap()
+    };
+
+    let bytes = build(true);
+    assert_eq!(bytes, build(false));
+
+    let object = read::File::parse(&*bytes).unwrap();
+    let text = object.section_by_name(".text").unwrap();
+    assert_eq!(text.relocations().count(), 16);
+    // The implicit addends are written to the section data.
+    let data = text.data().unwrap();
+    assert_eq!(&data[4..8], &[1, 0, 0, 0]);
+}
+
+#[test]
+fn dynamic_scope() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 3], 1);
+    let mut add = |name: &[u8], value, scope, section| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 1,
+            kind: SymbolKind::Text,
+            scope,
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        })
+    };
+    let section = write::SymbolSection::Section(text);
+    let exported = add(b"exported", 0, SymbolScope::Dynamic, section);
+    add(b"hidden", 1, SymbolScope::Linkage, section);
+    add(b"local", 2, SymbolScope::Compilation, section);
+    add(
+        b"imported",
+        0,
+        SymbolScope::Dynamic,
+        write::SymbolSection::Undefined,
+    );
+    let ids: Vec<_> = object.exported_symbols().map(|(id, _)| id).collect();
+    assert_eq!(ids, [exported]);
+    object
+        .add_coff_exports(write::CoffExportStyle::Msvc)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let drectve = object.section_by_name(".drectve").unwrap();
+    assert_eq!(drectve.data().unwrap(), b" /EXPORT:\"exported\"");
+    let symbol = object.symbol_by_name("exported").unwrap();
+    assert!(symbol.is_global());
+}
+
+#[test]
+fn function_aux() {
+    use object::pe;
+    use object::read::coff::{CoffFile, ImageSymbol};
+    use object::LittleEndian;
+
+    let mut object = write::Object::new(BinaryFormat::Coff, Architecture::I386, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    let mut functions = Vec::new();
+    for (name, lines) in [(&b"f1"[..], (3, 7)), (&b"f2"[..], (9, 20))] {
+        let offset = object.append_section_data(text, &[0xc3; 12], 4);
+        let symbol = object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: offset,
+            size: 12,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+        object
+            .add_coff_function_aux(symbol, 10, lines.0, lines.1)
+            .unwrap();
+        functions.push((offset, lines));
+    }
+    let undefined = object.add_symbol(write::Symbol {
+        name: b"undef".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    assert!(object.add_coff_function_aux(undefined, 0, 0, 0).is_err());
+    let mut elf = write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = elf.section_id(write::StandardSection::Text);
+    let function = elf.add_symbol(write::Symbol {
+        name: b"function".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    assert!(elf.add_coff_function_aux(function, 0, 0, 0).is_err());
+
+    let bytes = object.write().unwrap();
+    let file = CoffFile::<_>::parse(&*bytes).unwrap();
+    let symbols = file.coff_symbol_table();
+    let strings = symbols.strings();
+    let index_of = |name: &[u8]| {
+        symbols
+            .iter()
+            .find(|(_, symbol)| symbol.name(strings) == Ok(name))
+            .unwrap()
+            .0
+    };
+    let f1 = index_of(b"_f1");
+    let f2 = index_of(b"_f2");
+    for (index, next, (offset, (begin_line, end_line))) in
+        [(f1, f2.0, functions[0]), (f2, 0, functions[1])]
+    {
+        let symbol = symbols.symbol(index).unwrap();
+        assert_eq!(symbol.number_of_aux_symbols(), 1);
+        let aux = symbols.aux_function(index).unwrap();
+        assert_eq!(aux.tag_index.get(LittleEndian), index.0 as u32 + 2);
+        assert_eq!(aux.total_size.get(LittleEndian), 10);
+        assert_eq!(aux.pointer_to_next_function.get(LittleEndian), next as u32);
+
+        let begin = symbols.symbol(read::SymbolIndex(index.0 + 2)).unwrap();
+        assert_eq!(begin.name(strings), Ok(&b".bf"[..]));
+        assert_eq!(begin.storage_class(), pe::IMAGE_SYM_CLASS_FUNCTION);
+        assert_eq!(begin.value(), offset as u32);
+        let begin_aux = symbols
+            .get::<pe::ImageAuxSymbolFunctionBeginEnd>(read::SymbolIndex(index.0 + 2), 1)
+            .unwrap();
+        assert_eq!(begin_aux.linenumber.get(LittleEndian), begin_line);
+        let next_begin = if next == 0 { 0 } else { next as u32 + 2 };
+        assert_eq!(
+            begin_aux.pointer_to_next_function.get(LittleEndian),
+            next_begin
+        );
+
+        let end = symbols.symbol(read::SymbolIndex(index.0 + 4)).unwrap();
+        assert_eq!(end.name(strings), Ok(&b".ef"[..]));
+        assert_eq!(end.value(), offset as u32 + 10);
+        let end_aux = symbols
+            .get::<pe::ImageAuxSymbolFunctionBeginEnd>(read::SymbolIndex(index.0 + 4), 1)
+            .unwrap();
+        assert_eq!(end_aux.linenumber.get(LittleEndian), end_line);
+    }
+}
+
+#[test]
+fn aux_symbol_count() {
+    use object::read::coff::{CoffFile, CoffHeader, ImageSymbol};
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    // A file name longer than one symbol record needs two auxiliary records.
+    object.add_file_symbol(b"a_long_source_file_name.c".to_vec());
+    let text = object.section_id(write::StandardSection::Text);
+    object.section_symbol(text);
+    let offset = object.append_section_data(text, &[0xc3; 4], 4);
+    let mut add_symbol = |name: &[u8], weak| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: offset,
+            size: 4,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        })
+    };
+    add_symbol(b"plain", false);
+    add_symbol(b"weak", true);
+    let function = add_symbol(b"function", false);
+    object.add_coff_function_aux(function, 4, 1, 2).unwrap();
+
+    let bytes = object.write().unwrap();
+    let file = CoffFile::<_>::parse(&*bytes).unwrap();
+    let symbols = file.coff_symbol_table();
+    let strings = symbols.strings();
+    let mut count = 0;
+    let mut aux_counts = Vec::new();
+    for (index, symbol) in symbols.iter() {
+        assert_eq!(index.0, count);
+        count += 1 + usize::from(symbol.number_of_aux_symbols());
+        aux_counts.push((
+            symbol.name(strings).unwrap(),
+            symbol.number_of_aux_symbols(),
+        ));
+    }
+    assert_eq!(file.coff_header().number_of_symbols() as usize, count);
+    assert_eq!(symbols.len(), count);
+    assert_eq!(
+        aux_counts,
+        [
+            (&b".file"[..], 2),
+            (&b".text"[..], 1),
+            (&b"plain"[..], 0),
+            (&b".weak.weak.plain"[..], 0),
+            (&b"weak"[..], 1),
+            (&b"function"[..], 1),
+            (&b".bf"[..], 1),
+            (&b".ef"[..], 1),
+        ]
+    );
+}
+
+#[test]
+fn init_array() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let section = object.section_id(write::StandardSection::InitArray);
+    object.append_section_data(section, &[0; 8], 8);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".CRT$XCU").unwrap();
+    assert_eq!(section.kind(), object::SectionKind::ReadOnlyData);
+}
+
+#[test]
+fn layout() {
+    use object::read::coff::{CoffFile, CoffHeader};
+    use object::LittleEndian as LE;
+
+    let write = |layout| {
+        let mut object =
+            write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+        object.set_coff_layout(layout);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0xc3; 16], 4);
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(data, &[0; 8], 8);
+        let symbol = object.add_symbol(write::Symbol {
+            name: b"a_long_symbol_name".to_vec(),
+            value: 0,
+            size: 16,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+        object
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset: 0,
+                    symbol,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 64,
+                    },
+                },
+            )
+            .unwrap();
+        object.write().unwrap()
+    };
+
+    for layout in [
+        write::CoffLayout::DataFirst,
+        write::CoffLayout::SymbolTableFirst,
+    ] {
+        let bytes = write(layout);
+        let file = CoffFile::<_>::parse(&*bytes).unwrap();
+        let header = file.coff_header();
+        let symtab_offset = header.pointer_to_symbol_table() as usize;
+        let symtab_end =
+            symtab_offset + header.number_of_symbols() as usize * object::pe::IMAGE_SIZEOF_SYMBOL;
+        let strtab_len = u32::from_le_bytes(bytes[symtab_end..][..4].try_into().unwrap());
+        let symbols_end = symtab_end + strtab_len as usize;
+
+        let mut data_ranges = Vec::new();
+        for section in file.coff_section_table().iter() {
+            let offset = section.pointer_to_raw_data.get(LE) as usize;
+            data_ranges.push((offset, offset + section.size_of_raw_data.get(LE) as usize));
+            let count = section.number_of_relocations.get(LE) as usize;
+            if count != 0 {
+                let offset = section.pointer_to_relocations.get(LE) as usize;
+                data_ranges.push((offset, offset + count * 10));
+            }
+        }
+        for (start, end) in data_ranges {
+            match layout {
+                write::CoffLayout::DataFirst => assert!(end <= symtab_offset),
+                _ => assert!(start >= symbols_end),
+            }
+        }
+        if layout == write::CoffLayout::DataFirst {
+            assert_eq!(symbols_end, bytes.len());
+        }
+
+        let object = read::File::parse(&*bytes).unwrap();
+        let text = object.section_by_name(".text").unwrap();
+        assert_eq!(text.data().unwrap(), &[0xc3; 16]);
+        let symbol = object.symbol_by_name("a_long_symbol_name").unwrap();
+        let data = object.section_by_name(".data").unwrap();
+        let (offset, relocation) = data.relocations().next().unwrap();
+        assert_eq!(offset, 0);
+        assert_eq!(
+            relocation.target(),
+            read::RelocationTarget::Symbol(symbol.index())
+        );
+    }
+}
+
+#[test]
+fn bigobj() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    object.add_file_symbol(b"a_file_name_longer_than_one_symbol_record.c".to_vec());
+    let mut section = None;
+    for i in 0..70000 {
+        let name = format!(".text${}", i).into_bytes();
+        let id = object.add_section(Vec::new(), name, object::SectionKind::Text);
+        section = Some(id);
+    }
+    let section = section.unwrap();
+    object.append_section_data(section, &[0xc3; 8], 4);
+    object.section_symbol(section);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 4,
+        size: 4,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(section),
+        flags: SymbolFlags::None,
+    });
+    object.add_comdat(write::Comdat {
+        kind: object::ComdatKind::Any,
+        symbol,
+        sections: vec![section],
+    });
+    object
+        .add_relocation(
+            section,
+            write::Relocation {
+                offset: 0,
+                symbol,
+                addend: 0,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        )
+        .unwrap();
+
+    // Too many sections for the regular format.
+    assert!(object.write().is_err());
+
+    object.set_coff_bigobj(true);
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    assert!(matches!(object, read::File::CoffBig(_)));
+    assert_eq!(object.sections().count(), 70000);
+
+    let file = object.symbols().next().unwrap();
+    assert_eq!(file.kind(), SymbolKind::File);
+    assert_eq!(
+        file.name(),
+        Ok("a_file_name_longer_than_one_symbol_record.c")
+    );
+
+    let func = object.symbol_by_name("func").unwrap();
+    assert_eq!(func.address(), 4);
+    assert_eq!(func.section_index(), Some(read::SectionIndex(70000)));
+    let section = object.section_by_index(read::SectionIndex(70000)).unwrap();
+    assert_eq!(section.name(), Ok(".text$69999"));
+    assert_eq!(section.data(), Ok(&[0xc3; 8][..]));
+
+    let (offset, relocation) = section.relocations().next().unwrap();
+    assert_eq!(offset, 0);
+    assert_eq!(
+        relocation.target(),
+        read::RelocationTarget::Symbol(func.index())
+    );
+
+    let comdat = object.comdats().next().unwrap();
+    assert_eq!(comdat.kind(), object::ComdatKind::Any);
+    assert_eq!(
+        comdat.sections().collect::<Vec<_>>(),
+        [read::SectionIndex(70000)]
+    );
+}
+
+#[test]
+fn directives() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3], 1);
+    object.add_symbol(write::Symbol {
+        name: b"exported".to_vec(),
+        value: 0,
+        size: 1,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    object
+        .add_coff_directive(b"/DEFAULTLIB:\"msvcrt\"")
+        .unwrap();
+    object
+        .add_coff_exports(write::CoffExportStyle::Msvc)
+        .unwrap();
+    object
+        .add_coff_directive(b"/DEFAULTLIB:\"oldnames\"")
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let sections: Vec<_> = object
+        .sections()
+        .filter(|section| section.name() == Ok(".drectve"))
+        .collect();
+    assert_eq!(sections.len(), 1);
+    let drectve = &sections[0];
+    assert_eq!(
+        drectve.data().unwrap(),
+        &b" /DEFAULTLIB:\"msvcrt\" /EXPORT:\"exported\" /DEFAULTLIB:\"oldnames\""[..]
+    );
+    let read::SectionFlags::Coff { characteristics } = drectve.flags() else {
+        panic!("unexpected flags");
+    };
+    assert_eq!(
+        characteristics,
+        pe::IMAGE_SCN_LNK_INFO | pe::IMAGE_SCN_LNK_REMOVE | pe::IMAGE_SCN_ALIGN_1BYTES
+    );
+    assert!(object
+        .symbols()
+        .all(|symbol| symbol.section_index() != Some(drectve.index())));
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    assert!(object
+        .add_coff_directive(b"/DEFAULTLIB:\"msvcrt\"")
+        .is_err());
+    assert!(object
+        .add_coff_exports(write::CoffExportStyle::Msvc)
+        .is_err());
+}
+
+#[test]
+fn weak_external() {
+    use object::read::coff::{CoffFile, ImageSymbol};
+    use object::LittleEndian;
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3], 1);
+    let default = object.add_symbol(write::Symbol {
+        name: b"func_default".to_vec(),
+        value: 0,
+        size: 1,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    object
+        .add_coff_weak_external(b"func".to_vec(), default, write::CoffWeakSearch::Alias)
+        .unwrap();
+    let weak = object
+        .add_coff_weak_external(b"func2".to_vec(), default, write::CoffWeakSearch::Library)
+        .unwrap();
+    assert!(object
+        .add_coff_weak_external(b"func3".to_vec(), weak, write::CoffWeakSearch::Library)
+        .is_err());
+
+    let bytes = object.write().unwrap();
+    let file = CoffFile::<_>::parse(&*bytes).unwrap();
+    let symbols = file.coff_symbol_table();
+    let strings = symbols.strings();
+    let names: Vec<_> = symbols
+        .iter()
+        .map(|(_, symbol)| symbol.name(strings).unwrap())
+        .collect();
+    // No default symbols are generated.
+    assert_eq!(names, [&b"func_default"[..], b"func", b"func2"]);
+
+    for (index, search) in [
+        (1, pe::IMAGE_WEAK_EXTERN_SEARCH_ALIAS),
+        (2, pe::IMAGE_WEAK_EXTERN_SEARCH_LIBRARY),
+    ] {
+        let index = symbols.iter().nth(index).map(|(index, _)| index).unwrap();
+        let symbol = symbols.symbol(index).unwrap();
+        assert_eq!(symbol.storage_class(), pe::IMAGE_SYM_CLASS_WEAK_EXTERNAL);
+        assert_eq!(symbol.section_number(), pe::IMAGE_SYM_UNDEFINED);
+        let aux = symbols.aux_weak_external(index).unwrap();
+        assert_eq!(aux.weak_default_sym_index.get(LittleEndian), 0);
+        assert_eq!(aux.weak_search_type.get(LittleEndian), search);
+    }
+
+    let object = read::File::parse(&*bytes).unwrap();
+    let func = object.symbol_by_name("func").unwrap();
+    assert!(func.is_weak());
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let default = object.add_symbol(write::Symbol {
+        name: b"func_default".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    assert!(object
+        .add_coff_weak_external(b"func".to_vec(), default, write::CoffWeakSearch::Alias)
+        .is_err());
+}
+
+#[test]
+fn weak_external_existing_reference() {
+    use object::read::coff::{CoffFile, ImageSymbol};
+    use object::LittleEndian;
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xe8, 0, 0, 0, 0, 0xc3], 1);
+    let mut add = |name: &[u8], section| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        })
+    };
+    let default = add(b"func_default", write::SymbolSection::Section(text));
+    let func = add(b"func", write::SymbolSection::Undefined);
+    object
+        .add_relocation(
+            text,
+            write::Relocation {
+                offset: 1,
+                symbol: func,
+                addend: 0,
+                flags: RelocationFlags::Coff {
+                    typ: pe::IMAGE_REL_AMD64_REL32,
+                },
+            },
+        )
+        .unwrap();
+    let weak = object
+        .add_coff_weak_external(b"func".to_vec(), default, write::CoffWeakSearch::Alias)
+        .unwrap();
+    assert_eq!(weak, func);
+    // A later strong reference does not make the weak external strong.
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    assert_eq!(weak, func);
+    // Defined symbols and existing weak externals are rejected.
+    assert!(object
+        .add_coff_weak_external(b"func".to_vec(), default, write::CoffWeakSearch::Alias)
+        .is_err());
+    assert!(object
+        .add_coff_weak_external(
+            b"func_default".to_vec(),
+            default,
+            write::CoffWeakSearch::Alias
+        )
+        .is_err());
+
+    let bytes = object.write().unwrap();
+    let file = CoffFile::<_>::parse(&*bytes).unwrap();
+    let symbols = file.coff_symbol_table();
+    let strings = symbols.strings();
+    let (index, symbol) = symbols
+        .iter()
+        .find(|(_, symbol)| symbol.name(strings).unwrap() == b"func")
+        .unwrap();
+    assert_eq!(symbol.storage_class(), pe::IMAGE_SYM_CLASS_WEAK_EXTERNAL);
+    assert_eq!(symbol.number_of_aux_symbols(), 1);
+    let aux = symbols.aux_weak_external(index).unwrap();
+    assert_eq!(aux.weak_default_sym_index.get(LittleEndian), 0);
+    assert_eq!(
+        aux.weak_search_type.get(LittleEndian),
+        pe::IMAGE_WEAK_EXTERN_SEARCH_ALIAS
+    );
+
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".text").unwrap();
+    let (_, relocation) = section.relocations().next().unwrap();
+    let read::RelocationTarget::Symbol(symbol) = relocation.target() else {
+        panic!("unexpected relocation target");
+    };
+    assert_eq!(symbol, object.symbol_by_name("func").unwrap().index());
+    assert!(object.symbol_by_name("func").unwrap().is_weak());
+}
+
+#[test]
+fn feat_flags() {
+    use object::read::coff::{CoffFile, ImageSymbol};
+
+    let mut object = write::Object::new(BinaryFormat::Coff, Architecture::I386, Endianness::Little);
+    let first = object
+        .add_coff_feat_flags(write::coff::FEAT00_SAFE_SEH)
+        .unwrap();
+    let symbol = object
+        .add_coff_feat_flags(write::coff::FEAT00_SAFE_SEH | write::coff::FEAT00_GUARD_CF)
+        .unwrap();
+    assert_eq!(first, symbol);
+
+    let bytes = object.write().unwrap();
+    let file = CoffFile::<_>::parse(&*bytes).unwrap();
+    let symbols = file.coff_symbol_table();
+    let mut iter = symbols.iter();
+    let (_, symbol) = iter.next().unwrap();
+    // The name is not mangled.
+    assert_eq!(symbol.name(symbols.strings()), Ok(&b"@feat.00"[..]));
+    assert_eq!(symbol.value(), 0x801);
+    assert_eq!(symbol.section_number(), pe::IMAGE_SYM_ABSOLUTE);
+    assert_eq!(symbol.storage_class(), pe::IMAGE_SYM_CLASS_STATIC);
+    assert!(iter.next().is_none());
+
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+    assert!(object
+        .add_coff_feat_flags(write::coff::FEAT00_SAFE_SEH)
+        .is_err());
+}
+
+#[test]
+fn rel32_relocation() {
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    // cmp dword ptr [rip + data], imm32
+    object.append_section_data(text, &[0x81, 0x3d, 0, 0, 0, 0, 0, 0, 0, 0], 4);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 16], 4);
+    let symbol = object.section_symbol(data);
+    object
+        .add_coff_rel32_relocation(text, 2, symbol, 8, 4)
+        .unwrap();
+    assert!(object
+        .add_coff_rel32_relocation(text, 2, symbol, 8, 6)
+        .is_err());
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let text = object.section_by_name(".text").unwrap();
+    let (offset, relocation) = text.relocations().next().unwrap();
+    assert_eq!(offset, 2);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Coff {
+            typ: pe::IMAGE_REL_AMD64_REL32_4
+        }
+    );
+    assert_eq!(relocation.kind(), RelocationKind::Relative);
+    assert_eq!(relocation.addend(), -8);
+    assert!(relocation.has_implicit_addend());
+    assert_eq!(&text.data().unwrap()[2..6], &8u32.to_le_bytes());
+}
+
+#[test]
+fn rel32_relocation_arch() {
+    let mut object = write::Object::new(BinaryFormat::Coff, Architecture::I386, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 8], 4);
+    let symbol = object.section_symbol(text);
+    assert!(object
+        .add_coff_rel32_relocation(text, 0, symbol, 0, 0)
+        .is_err());
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 8], 4);
+    let symbol = object.section_symbol(text);
+    assert!(object
+        .add_coff_rel32_relocation(text, 0, symbol, 0, 0)
+        .is_err());
+    assert!(object.section_relocations(text).is_empty());
+}
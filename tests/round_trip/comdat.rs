@@ -215,3 +215,226 @@ fn elf_x86_64_comdat() {
     assert_eq!(comdat_sections.next(), Some(section2_index));
     assert_eq!(comdat_sections.next(), None);
 }
+
+#[test]
+fn coff_associate_sections() {
+    use object::read::coff::{CoffFile, ImageSymbol};
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+
+    let leader = object.add_subsection(write::StandardSection::Text, b"f");
+    let offset = object.append_section_data(leader, &[0xc3], 1);
+    object.section_symbol(leader);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"f".to_vec(),
+        value: offset,
+        size: 1,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(leader),
+        flags: SymbolFlags::None,
+    });
+    object.add_comdat(write::Comdat {
+        kind: ComdatKind::Any,
+        symbol,
+        sections: vec![leader],
+    });
+
+    let mut associated = Vec::new();
+    for name in [&b".pdata$f"[..], b".xdata$f", b".debug$S"] {
+        let section = object.add_section(Vec::new(), name.to_vec(), SectionKind::ReadOnlyData);
+        object.append_section_data(section, &[0; 4], 4);
+        associated.push(section);
+    }
+
+    // The leader must be a COMDAT leader, and sections can't be associated twice.
+    assert!(object
+        .associate_sections(associated[0], &associated[1..])
+        .is_err());
+    object.associate_sections(leader, &associated).unwrap();
+    assert!(object.associate_sections(leader, &associated[..1]).is_err());
+
+    let bytes = object.write().unwrap();
+    let file = CoffFile::<_>::parse(&*bytes).unwrap();
+    let symbols = file.coff_symbol_table();
+    let mut selections = Vec::new();
+    for (index, symbol) in symbols.iter() {
+        if symbol.has_aux_section() {
+            let aux = symbols.aux_section(index).unwrap();
+            selections.push((
+                symbol.name(symbols.strings()).unwrap(),
+                aux.selection,
+                aux.number.get(object::LittleEndian),
+            ));
+        }
+    }
+    assert_eq!(
+        selections,
+        [
+            (&b".text$f"[..], pe::IMAGE_COMDAT_SELECT_ANY, 0),
+            (&b".pdata$f"[..], pe::IMAGE_COMDAT_SELECT_ASSOCIATIVE, 1),
+            (&b".xdata$f"[..], pe::IMAGE_COMDAT_SELECT_ASSOCIATIVE, 1),
+            (&b".debug$S"[..], pe::IMAGE_COMDAT_SELECT_ASSOCIATIVE, 1),
+        ]
+    );
+}
+
+#[test]
+fn elf_associate_sections() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+    let leader = object.add_subsection(write::StandardSection::Text, b"f");
+    let offset = object.append_section_data(leader, &[0xc3], 1);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"f".to_vec(),
+        value: offset,
+        size: 1,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(leader),
+        flags: SymbolFlags::None,
+    });
+    object.add_comdat(write::Comdat {
+        kind: ComdatKind::Any,
+        symbol,
+        sections: vec![leader],
+    });
+    let associated = object.add_section(Vec::new(), b".data.f".to_vec(), SectionKind::Data);
+    object.append_section_data(associated, &[0; 4], 4);
+    object.associate_sections(leader, &[associated]).unwrap();
+
+    // Section symbols are not needed for ELF groups.
+    assert_eq!(object.symbols().count(), 1);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let comdat = object.comdats().next().unwrap();
+    let names: Vec<_> = comdat
+        .sections()
+        .map(|index| object.section_by_index(index).unwrap().name().unwrap())
+        .collect();
+    assert_eq!(names, [".text.f", ".data.f"]);
+}
+
+#[test]
+fn elf_group() {
+    use object::elf;
+    use object::read::elf::{FileHeader, SectionHeader};
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+    let section1 = object.add_section(Vec::new(), b".meta1".to_vec(), SectionKind::Data);
+    object.append_section_data(section1, &[0, 1, 2, 3], 4);
+    let section2 = object.add_section(Vec::new(), b".meta2".to_vec(), SectionKind::Data);
+    object.append_section_data(section2, &[0, 1, 2, 3], 4);
+    let symbol = object.section_symbol(section1);
+    object.add_comdat(write::Comdat {
+        kind: ComdatKind::Group,
+        symbol,
+        sections: vec![section1, section2],
+    });
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<Endianness>::parse(&*bytes).unwrap();
+    let endian = header.endian().unwrap();
+    let sections = header.sections(endian, &*bytes).unwrap();
+    let group = sections
+        .iter()
+        .find(|s| s.sh_type(endian) == elf::SHT_GROUP)
+        .unwrap();
+    let (flags, members) = group.group(endian, &*bytes).unwrap().unwrap();
+    assert_eq!(flags, 0);
+    let members: Vec<_> = members
+        .iter()
+        .map(|index| {
+            let section = sections
+                .section(object::SectionIndex(index.get(endian) as usize))
+                .unwrap();
+            sections.section_name(endian, section).unwrap()
+        })
+        .collect();
+    assert_eq!(members, [&b".meta1"[..], b".meta2"]);
+
+    // Groups without `GRP_COMDAT` are not COMDATs.
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.comdats().count(), 0);
+
+    // COFF has no equivalent.
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let section = object.add_section(Vec::new(), b".meta".to_vec(), SectionKind::Data);
+    object.append_section_data(section, &[0, 1, 2, 3], 4);
+    let symbol = object.section_symbol(section);
+    object.add_comdat(write::Comdat {
+        kind: ComdatKind::Group,
+        symbol,
+        sections: vec![section],
+    });
+    assert!(object.write().is_err());
+}
+
+#[test]
+fn invalid_comdat_sections() {
+    for format in [BinaryFormat::Coff, BinaryFormat::Elf] {
+        let mut object = write::Object::new(format, Architecture::X86_64, Endianness::Little);
+        let add_comdat = |object: &mut write::Object, name: &[u8]| {
+            let section = object.add_subsection(write::StandardSection::Text, name);
+            object.append_section_data(section, &[0xc3], 1);
+            object.section_symbol(section);
+            let symbol = object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 1,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Linkage,
+                weak: false,
+                section: write::SymbolSection::Section(section),
+                flags: SymbolFlags::None,
+            });
+            let comdat = object.add_comdat(write::Comdat {
+                kind: ComdatKind::Any,
+                symbol,
+                sections: vec![section],
+            });
+            (section, comdat)
+        };
+        let (section1, comdat1) = add_comdat(&mut object, b"f1");
+        let (_, comdat2) = add_comdat(&mut object, b"f2");
+        object.write().unwrap();
+
+        // A section from another object that does not exist in this one.
+        let mut other = write::Object::new(format, Architecture::X86_64, Endianness::Little);
+        for _ in 0..4 {
+            add_comdat(&mut other, b"g");
+        }
+        let (nonexistent, _) = add_comdat(&mut other, b"g");
+        object.comdat_mut(comdat1).sections.push(nonexistent);
+        let error = object.write().unwrap_err().to_string();
+        assert!(error.contains("nonexistent"), "{}", error);
+        object.comdat_mut(comdat1).sections.pop();
+
+        // A section in two COMDATs.
+        object.comdat_mut(comdat2).sections.push(section1);
+        let error = object.write().unwrap_err().to_string();
+        assert!(error.contains("`f1` and COMDAT `f2`"), "{}", error);
+        object.comdat_mut(comdat2).sections.pop();
+
+        // Writing the COMDAT flags happens after validation.
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        assert_eq!(object.comdats().count(), 2);
+        if format == BinaryFormat::Coff {
+            for section in object.sections() {
+                let read::SectionFlags::Coff { characteristics } = section.flags() else {
+                    panic!();
+                };
+                assert_ne!(characteristics & pe::IMAGE_SCN_LNK_COMDAT, 0);
+            }
+        }
+    }
+}
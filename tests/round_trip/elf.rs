@@ -1,9 +1,11 @@
-use object::read::elf::{FileHeader, SectionHeader};
+use object::read::elf::{Dyn, FileHeader, ProgramHeader, Rel, Rela, SectionHeader, Sym};
 use object::read::{Object, ObjectSection, ObjectSymbol};
 use object::{
-    elf, read, write, Architecture, BinaryFormat, Endianness, LittleEndian, SectionIndex,
-    SectionKind, SymbolFlags, SymbolKind, SymbolScope, SymbolSection, U32,
+    elf, read, write, Architecture, BinaryFormat, Endianness, FileFlags, LittleEndian,
+    RelocationEncoding, RelocationFlags, RelocationKind, SectionIndex, SectionKind, SymbolFlags,
+    SymbolKind, SymbolScope, SymbolSection, U32,
 };
+use std::convert::TryInto;
 use std::io::Write;
 
 #[test]
@@ -130,6 +132,96 @@ fn compression_zlib() {
     assert_eq!(data, &*uncompressed);
 }
 
+#[cfg(feature = "compression")]
+#[test]
+fn compression_precompressed() {
+    use object::read::ObjectSection;
+
+    let data = b"test data data data";
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(data).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    for architecture in [Architecture::I386, Architecture::X86_64] {
+        let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+        let section = object.add_section(
+            Vec::new(),
+            b".debug_info".to_vec(),
+            object::SectionKind::Debug,
+        );
+        if architecture == Architecture::I386 {
+            // The uncompressed size must fit in the compression header.
+            assert!(object
+                .set_section_data_compressed(
+                    section,
+                    1 << 32,
+                    &compressed,
+                    object::elf::ELFCOMPRESS_ZLIB,
+                    4,
+                )
+                .is_err());
+        }
+        object
+            .set_section_data_compressed(
+                section,
+                data.len() as u64,
+                &compressed,
+                object::elf::ELFCOMPRESS_ZLIB,
+                4,
+            )
+            .unwrap();
+        let bytes = object.write().unwrap();
+
+        let object = read::File::parse(&*bytes).unwrap();
+        let section = object.section_by_name(".debug_info").unwrap();
+        let read::SectionFlags::Elf { sh_flags } = section.flags() else {
+            panic!("unexpected section flags");
+        };
+        assert_ne!(sh_flags & u64::from(object::elf::SHF_COMPRESSED), 0);
+        let file_range = section.compressed_file_range().unwrap();
+        assert_eq!(file_range.format, read::CompressionFormat::Zlib);
+        assert_eq!(file_range.uncompressed_size, data.len() as u64);
+        assert_eq!(file_range.compressed_size, compressed.len() as u64);
+        let uncompressed = section.uncompressed_data().unwrap();
+        assert_eq!(data, &*uncompressed);
+    }
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn compression_write() {
+    let data: Vec<u8> = (0..0x1000).map(|i| (i % 7) as u8).collect();
+
+    for compression in [
+        read::CompressionFormat::Zlib,
+        read::CompressionFormat::Zstandard,
+    ] {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0x90; 4], 4);
+        assert!(object.set_section_compression(text, compression).is_err());
+        let section = object.add_dwarf_section(b".debug_info");
+        object.append_section_data(section, &data, 1);
+        object
+            .set_section_compression(section, compression)
+            .unwrap();
+        let bytes = object.write().unwrap();
+
+        let object = read::File::parse(&*bytes).unwrap();
+        let section = object.section_by_name(".debug_info").unwrap();
+        let read::SectionFlags::Elf { sh_flags } = section.flags() else {
+            panic!("unexpected section flags");
+        };
+        assert_ne!(sh_flags & u64::from(elf::SHF_COMPRESSED), 0);
+        let file_range = section.compressed_file_range().unwrap();
+        assert_eq!(file_range.format, compression);
+        assert_eq!(file_range.uncompressed_size, data.len() as u64);
+        assert!(file_range.compressed_size < data.len() as u64);
+        assert_eq!(&*section.uncompressed_data().unwrap(), &data[..]);
+    }
+}
+
 #[cfg(feature = "compression")]
 #[test]
 fn compression_gnu() {
@@ -300,3 +392,3059 @@ fn gnu_property_inner<Elf: FileHeader<Endian = Endianness>>(architecture: Archit
     assert!(props.next().unwrap().is_none());
     assert!(notes.next().unwrap().is_none());
 }
+
+#[test]
+fn custom_relocation_translator() {
+    #[derive(Debug)]
+    struct CskyPlt;
+
+    impl write::RelocationTranslator for CskyPlt {
+        fn translate_relocation(
+            &self,
+            format: BinaryFormat,
+            kind: RelocationKind,
+            encoding: RelocationEncoding,
+            size: u8,
+        ) -> Option<RelocationFlags> {
+            match (format, kind, encoding, size) {
+                (
+                    BinaryFormat::Elf,
+                    RelocationKind::PltRelative,
+                    RelocationEncoding::Generic,
+                    32,
+                ) => Some(RelocationFlags::Elf {
+                    r_type: elf::R_CKCORE_PLT32,
+                }),
+                _ => None,
+            }
+        }
+    }
+
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Csky, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 8], 4);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let relocation = || write::Relocation {
+        offset: 4,
+        symbol: func,
+        addend: 0,
+        flags: RelocationFlags::Generic {
+            kind: RelocationKind::PltRelative,
+            encoding: RelocationEncoding::Generic,
+            size: 32,
+        },
+    };
+
+    // Not natively supported.
+    assert!(object.add_relocation(text, relocation()).is_err());
+
+    object.set_custom_relocation_translator(Box::new(CskyPlt));
+    object.add_relocation(text, relocation()).unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.architecture(), Architecture::Csky);
+    let text = object.section_by_name(".text").unwrap();
+    let (offset, relocation) = text.relocations().next().unwrap();
+    assert_eq!(offset, 4);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_CKCORE_PLT32
+        }
+    );
+}
+
+#[test]
+fn relative_relocation() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 8], 8);
+    let target = object.add_symbol(write::Symbol {
+        name: b"target".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_symbol_data(target, data, &[1; 8], 8);
+    let pointer = object.append_section_data(data, &[0; 8], 8);
+    object
+        .add_elf_relative_relocation(data, pointer, target, 4)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let data = object.section_by_name(".data").unwrap();
+    let mut relocations = data.relocations();
+    let (offset, relocation) = relocations.next().unwrap();
+    assert_eq!(offset, 16);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_X86_64_RELATIVE
+        }
+    );
+    let symbol = match relocation.target() {
+        read::RelocationTarget::Symbol(symbol) => object.symbol_by_index(symbol).unwrap(),
+        target => panic!("unexpected relocation target {:?}", target),
+    };
+    assert_eq!(symbol.kind(), SymbolKind::Section);
+    assert_eq!(symbol.section_index(), Some(data.index()));
+    assert_eq!(relocation.addend(), 12);
+    assert!(relocations.next().is_none());
+}
+
+#[test]
+fn set_architecture() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 8], 4);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object
+        .add_relocation(
+            text,
+            write::Relocation {
+                offset: 4,
+                symbol: func,
+                addend: -4,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Relative,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        )
+        .unwrap();
+
+    // The relocation types are not valid for AArch64.
+    assert!(object
+        .set_architecture(Architecture::Aarch64, None, Endianness::Little)
+        .is_err());
+    assert_eq!(object.architecture(), Architecture::X86_64);
+
+    object
+        .set_architecture(Architecture::X86_64_X32, None, Endianness::Little)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.architecture(), Architecture::X86_64_X32);
+    assert!(!object.is_64());
+    let text = object.section_by_name(".text").unwrap();
+    let (offset, relocation) = text.relocations().next().unwrap();
+    assert_eq!(offset, 4);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_X86_64_PC32
+        }
+    );
+    assert_eq!(relocation.addend(), -4);
+}
+
+#[test]
+fn set_architecture_endian() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Mips, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 4], 4);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"symbol".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object
+        .add_relocation(
+            data,
+            write::Relocation {
+                offset: 0,
+                symbol,
+                addend: 0x1234,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        )
+        .unwrap();
+    object
+        .set_architecture(Architecture::Mips, None, Endianness::Big)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.endianness(), Endianness::Big);
+    let data = object.section_by_name(".data").unwrap();
+    assert_eq!(data.data().unwrap(), &[0, 0, 0x12, 0x34]);
+}
+
+#[test]
+fn build_id_placement() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xcc; 0x2000], 16);
+
+    let build_id = [0x5a; 20];
+    let mut note = Vec::new();
+    note.extend_from_slice(&4u32.to_le_bytes());
+    note.extend_from_slice(&(build_id.len() as u32).to_le_bytes());
+    note.extend_from_slice(&elf::NT_GNU_BUILD_ID.to_le_bytes());
+    note.extend_from_slice(elf::ELF_NOTE_GNU);
+    note.push(0);
+    note.extend_from_slice(&build_id);
+    let section = object.add_section(
+        Vec::new(),
+        b".note.gnu.build-id".to_vec(),
+        SectionKind::Note,
+    );
+    object.append_section_data(section, &note, 4);
+
+    let bytes = object.write().unwrap();
+    //std::fs::write(&"build_id_placement.o", &bytes).unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.build_id().unwrap(), Some(&build_id[..]));
+    let note = object.section_by_name(".note.gnu.build-id").unwrap();
+    let (note_offset, _) = note.file_range().unwrap();
+    assert!(note_offset < 0x1000);
+    let text = object.section_by_name(".text").unwrap();
+    let (text_offset, _) = text.file_range().unwrap();
+    assert!(note_offset < text_offset);
+}
+
+#[test]
+fn fixup() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::PowerPc, Endianness::Big);
+    let data = object.section_id(write::StandardSection::Data);
+    let offset = object.append_section_data(data, &[0xff; 8], 4);
+    let fixup = object.reserve_fixup(data, offset + 2, 32).unwrap();
+    assert!(object.reserve_fixup(data, offset + 6, 32).is_err());
+    assert!(object.reserve_fixup(data, offset, 24).is_err());
+    assert!(object.patch_fixup(fixup, 0x1_0000_0000).is_err());
+    object.patch_fixup(fixup, 0x1234_5678).unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let data = object.section_by_name(".data").unwrap();
+    assert_eq!(
+        data.data().unwrap(),
+        &[0xff, 0xff, 0x12, 0x34, 0x56, 0x78, 0xff, 0xff]
+    );
+}
+
+#[test]
+fn file_symbol_order() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    let local = object.add_symbol(write::Symbol {
+        name: b"local".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_symbol_data(local, data, &[0; 8], 8);
+    object.add_symbol(write::Symbol {
+        name: b"global".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let section_symbol = object.section_symbol(data);
+    object.add_file_symbol(b"file.c".to_vec());
+    let offset = object.append_section_data(data, &[0; 8], 8);
+    object
+        .add_relocation(
+            data,
+            write::Relocation {
+                offset,
+                symbol: section_symbol,
+                addend: 0,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: 64,
+                },
+            },
+        )
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let mut symbols = object.symbols();
+    let symbol = symbols.next().unwrap();
+    assert_eq!(symbol.kind(), SymbolKind::File);
+    assert_eq!(symbol.name(), Ok("file.c"));
+    let symbol = symbols.next().unwrap();
+    assert_eq!(symbol.kind(), SymbolKind::Section);
+    let section_index = symbol.index();
+    let symbol = symbols.next().unwrap();
+    assert_eq!(symbol.name(), Ok("local"));
+    let symbol = symbols.next().unwrap();
+    assert_eq!(symbol.name(), Ok("global"));
+    assert!(symbols.next().is_none());
+
+    let data = object.section_by_name(".data").unwrap();
+    let (_, relocation) = data.relocations().next().unwrap();
+    assert_eq!(
+        relocation.target(),
+        read::RelocationTarget::Symbol(section_index)
+    );
+}
+
+#[test]
+fn duplicate_undefined_symbol() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let undefined = || write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    };
+    let func = object.add_symbol(undefined());
+    assert_eq!(object.add_symbol(undefined()), func);
+
+    // A strong reference to a weak undefined symbol makes it strong.
+    let weak = || write::Symbol {
+        name: b"weak".to_vec(),
+        weak: true,
+        ..undefined()
+    };
+    let weak_func = object.add_symbol(weak());
+    assert_eq!(object.add_symbol(weak()), weak_func);
+    assert!(object.symbol(weak_func).weak);
+    let strong = write::Symbol {
+        weak: false,
+        ..weak()
+    };
+    assert_eq!(object.add_symbol(strong), weak_func);
+    assert!(!object.symbol(weak_func).weak);
+    assert_eq!(object.add_symbol(weak()), weak_func);
+    assert!(!object.symbol(weak_func).weak);
+
+    // A reference after the definition still uses the defined symbol.
+    let text = object.section_id(write::StandardSection::Text);
+    object.add_symbol_data(func, text, &[0xc3], 1);
+    assert_eq!(object.add_symbol(undefined()), func);
+
+    // Local symbols with the same name are distinct.
+    let local = || write::Symbol {
+        name: b"local".to_vec(),
+        scope: SymbolScope::Compilation,
+        ..undefined()
+    };
+    let local1 = object.add_symbol(local());
+    object.add_symbol_data(local1, text, &[0xc3], 1);
+    let local2 = object.add_symbol(local());
+    object.add_symbol_data(local2, text, &[0xc3], 1);
+    assert_ne!(local1, local2);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let names: Vec<_> = object.symbols().map(|s| s.name().unwrap()).collect();
+    assert_eq!(names, ["local", "local", "func", "weak"]);
+}
+
+#[test]
+fn undefined_symbol_value() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"undefined".to_vec(),
+        value: 0x10,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    assert!(object.write().is_err());
+
+    object.symbol_mut(symbol).value = 0;
+    object.add_common_symbol(
+        write::Symbol {
+            name: b"common".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        },
+        8,
+        8,
+    );
+    object.write().unwrap();
+}
+
+#[test]
+fn got_symbol() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+    let got = object.elf_got_symbol();
+    assert_eq!(object.elf_got_symbol(), got);
+
+    let data = object.section_id(write::StandardSection::Data);
+    let local = object.add_symbol(write::Symbol {
+        name: b"local".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_symbol_data(local, data, &[0; 4], 4);
+
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 16], 4);
+    object
+        .add_relocation(
+            text,
+            write::Relocation {
+                offset: 2,
+                symbol: got,
+                addend: 2,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::GotBaseRelative,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        )
+        .unwrap();
+    object
+        .add_relocation(
+            text,
+            write::Relocation {
+                offset: 8,
+                symbol: local,
+                addend: 0,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::GotBaseOffset,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        )
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let got = object.symbol_by_name("_GLOBAL_OFFSET_TABLE_").unwrap();
+    assert!(got.is_undefined());
+    assert!(got.is_global());
+    assert_eq!(got.kind(), SymbolKind::Unknown);
+
+    let text = object.section_by_name(".text").unwrap();
+    let mut relocations = text.relocations();
+    let (offset, relocation) = relocations.next().unwrap();
+    assert_eq!(offset, 2);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_386_GOTPC
+        }
+    );
+    assert_eq!(
+        relocation.target(),
+        read::RelocationTarget::Symbol(got.index())
+    );
+    let (offset, relocation) = relocations.next().unwrap();
+    assert_eq!(offset, 8);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_386_GOTOFF
+        }
+    );
+    assert!(relocations.next().is_none());
+}
+
+#[test]
+fn debug_string() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_symbol_data(func, text, &[0xe8, 0, 0, 0, 0, 0xc3], 16);
+    let external = object.add_symbol(write::Symbol {
+        name: b"external".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object
+        .add_relocation(
+            text,
+            write::Relocation {
+                offset: 1,
+                symbol: external,
+                addend: -4,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Relative,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        )
+        .unwrap();
+
+    assert_eq!(
+        object.to_debug_string(),
+        "\
+format=Elf architecture=X86_64 sub_architecture=None endian=Little flags=None
+section 0 segment=\"\" name=\".text\" kind=Text size=0x6 align=0x10 flags=None
+    relocation offset=0x1 symbol=\"external\" addend=-4 flags=Elf { r_type: 2 }
+symbol 0 name=\"func\" kind=Text scope=Linkage weak=false section=0 value=0x0 size=0x6 flags=None
+symbol 1 name=\"external\" kind=Text scope=Linkage weak=false section=Undefined value=0x0 size=0x0 flags=None
+"
+    );
+}
+
+#[test]
+fn iterate_sections_and_symbols() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    let data = object.section_id(write::StandardSection::Data);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let section_symbol = object.section_symbol(data);
+
+    assert_eq!(object.section_count(), 2);
+    let sections: Vec<_> = object
+        .sections()
+        .map(|(id, section)| (id, section.name().unwrap()))
+        .collect();
+    assert_eq!(sections, [(text, ".text"), (data, ".data")]);
+
+    assert_eq!(object.symbol_count(), 2);
+    let symbols: Vec<_> = object
+        .symbols()
+        .map(|(id, symbol)| (id, symbol.kind))
+        .collect();
+    assert_eq!(
+        symbols,
+        [
+            (func, SymbolKind::Text),
+            (section_symbol, SymbolKind::Section)
+        ]
+    );
+}
+
+#[test]
+fn pc_relative_bias() {
+    for (architecture, encoding, size, r_type, addend) in [
+        (
+            Architecture::X86_64,
+            RelocationEncoding::X86Branch,
+            32,
+            elf::R_X86_64_PLT32,
+            -4,
+        ),
+        (
+            Architecture::X86_64,
+            RelocationEncoding::Generic,
+            32,
+            elf::R_X86_64_PC32,
+            -4,
+        ),
+        (
+            Architecture::Aarch64,
+            RelocationEncoding::AArch64Call,
+            26,
+            elf::R_AARCH64_CALL26,
+            0,
+        ),
+        (
+            Architecture::S390x,
+            RelocationEncoding::S390xDbl,
+            32,
+            elf::R_390_PC32DBL,
+            2,
+        ),
+    ] {
+        let endian = if architecture == Architecture::S390x {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        };
+        let mut object = write::Object::new(BinaryFormat::Elf, architecture, endian);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0; 8], 4);
+        let func = object.add_symbol(write::Symbol {
+            name: b"func".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        assert_eq!(object.pc_relative_bias(encoding, size), addend);
+        object
+            .add_pc_relative_relocation(text, 0, func, 0, encoding, size)
+            .unwrap();
+
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        let text = object.section_by_name(".text").unwrap();
+        let (_, relocation) = text.relocations().next().unwrap();
+        assert_eq!(relocation.flags(), RelocationFlags::Elf { r_type });
+        assert_eq!(relocation.addend(), addend);
+    }
+
+    // The bias depends on the architecture as well as the encoding.
+    for (architecture, encoding, size, addend) in [
+        (Architecture::I386, RelocationEncoding::Generic, 32, -4),
+        (Architecture::Arm, RelocationEncoding::Generic, 24, -8),
+        (Architecture::Aarch64, RelocationEncoding::Generic, 32, 0),
+        (Architecture::S390x, RelocationEncoding::Generic, 32, 0),
+    ] {
+        let object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+        assert_eq!(object.pc_relative_bias(encoding, size), addend);
+    }
+}
+
+#[test]
+fn tls_local_exec() {
+    for (architecture, relocations) in [
+        (
+            Architecture::X86_64,
+            &[(RelocationEncoding::Generic, 32, elf::R_X86_64_TPOFF32)][..],
+        ),
+        (
+            Architecture::Aarch64,
+            &[
+                (
+                    RelocationEncoding::AArch64AddHi12,
+                    12,
+                    elf::R_AARCH64_TLSLE_ADD_TPREL_HI12,
+                ),
+                (
+                    RelocationEncoding::AArch64AddLo12,
+                    12,
+                    elf::R_AARCH64_TLSLE_ADD_TPREL_LO12_NC,
+                ),
+            ][..],
+        ),
+    ] {
+        let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+        let tdata = object.section_id(write::StandardSection::Tls);
+        let offset = object.append_section_data(tdata, &[0; 8], 8);
+        let var = object.add_symbol(write::Symbol {
+            name: b"var".to_vec(),
+            value: offset,
+            size: 8,
+            kind: SymbolKind::Tls,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: write::SymbolSection::Section(tdata),
+            flags: SymbolFlags::None,
+        });
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0; 8], 4);
+        for (i, &(encoding, size, _)) in relocations.iter().enumerate() {
+            object
+                .add_relocation(
+                    text,
+                    write::Relocation {
+                        offset: i as u64 * 4,
+                        symbol: var,
+                        addend: 4,
+                        flags: RelocationFlags::Generic {
+                            kind: RelocationKind::TpOffset,
+                            encoding,
+                            size,
+                        },
+                    },
+                )
+                .unwrap();
+        }
+
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        let text = object.section_by_name(".text").unwrap();
+        let mut read_relocations = text.relocations();
+        for &(encoding, size, r_type) in relocations {
+            let (_, relocation) = read_relocations.next().unwrap();
+            assert_eq!(relocation.flags(), RelocationFlags::Elf { r_type });
+            assert_eq!(relocation.kind(), RelocationKind::TpOffset);
+            assert_eq!(relocation.encoding(), encoding);
+            assert_eq!(relocation.size(), size);
+            // The addend is the offset from the symbol, without any TLS block bias.
+            assert_eq!(relocation.addend(), 4);
+            let read::RelocationTarget::Symbol(symbol) = relocation.target() else {
+                panic!("unexpected relocation target {:?}", relocation.target());
+            };
+            assert_eq!(object.symbol_by_index(symbol).unwrap().name(), Ok("var"));
+        }
+        assert!(read_relocations.next().is_none());
+    }
+}
+
+#[test]
+fn reserve_section_data() {
+    let data = [1; 16];
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let section = object.section_id(write::StandardSection::Data);
+    object.set_section_data(section, &data[..], 8);
+    // The data is borrowed until it is modified.
+    assert_eq!(object.section(section).data().as_ptr(), data.as_ptr());
+
+    object.reserve_section_data(section, 16);
+    let owned = object.section(section).data().as_ptr();
+    assert_ne!(owned, data.as_ptr());
+    assert_eq!(object.append_section_data(section, &[2; 16], 8), 16);
+    // Appending within the reserved capacity does not copy the data again.
+    assert_eq!(object.section(section).data().as_ptr(), owned);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".data").unwrap();
+    let mut expected = [1; 32];
+    expected[16..].fill(2);
+    assert_eq!(section.data().unwrap(), &expected[..]);
+}
+
+#[test]
+fn set_section_alignment() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let bss = object.section_id(write::StandardSection::UninitializedData);
+    object.append_section_bss(bss, 16, 8);
+    assert!(object.set_section_alignment(bss, 48).is_err());
+    assert!(object.set_section_alignment(bss, 0).is_err());
+    object.set_section_alignment(bss, 64).unwrap();
+    // Alignment is never lowered.
+    object.set_section_alignment(bss, 16).unwrap();
+    assert_eq!(object.section(bss).align(), 64);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".bss").unwrap();
+    assert_eq!(section.align(), 64);
+    assert_eq!(section.size(), 16);
+}
+
+#[test]
+fn note_alignment() {
+    for (align, size) in [(4, 28), (8, 32)] {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        assert!(object.set_elf_note_alignment(2).is_err());
+        object.set_elf_note_alignment(align).unwrap();
+        let section = object.add_section(Vec::new(), b".note.test".to_vec(), SectionKind::Note);
+        assert_eq!(object.add_elf_note(section, b"abcde", 1, b"desc1"), 0);
+        assert_eq!(object.add_elf_note(section, b"abcde", 2, b"desc2"), size);
+
+        let bytes = &*object.write().unwrap();
+        let header = elf::FileHeader64::<LittleEndian>::parse(bytes).unwrap();
+        let endian = header.endian().unwrap();
+        let sections = header.sections(endian, bytes).unwrap();
+        let section = sections.section(SectionIndex(1)).unwrap();
+        assert_eq!(section.sh_addralign(endian), align);
+        assert_eq!(section.sh_size(endian), 2 * size);
+        let mut notes = section.notes(endian, bytes).unwrap().unwrap();
+        let note = notes.next().unwrap().unwrap();
+        assert_eq!(note.name(), b"abcde");
+        assert_eq!(note.desc(), b"desc1");
+        assert_eq!(note.n_type(endian), 1);
+        let note = notes.next().unwrap().unwrap();
+        assert_eq!(note.desc(), b"desc2");
+        assert_eq!(note.n_type(endian), 2);
+        assert!(notes.next().unwrap().is_none());
+    }
+}
+
+#[test]
+fn checked_section_data() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    let bss = object.section_id(write::StandardSection::UninitializedData);
+
+    assert!(object.try_append_section_data(data, &[1; 4], 3).is_err());
+    assert!(object.try_append_section_data(bss, &[1; 4], 4).is_err());
+    assert!(object.try_append_section_bss(data, 4, 4).is_err());
+    assert!(object.try_append_section_bss(bss, 4, 0).is_err());
+    assert!(object.try_set_section_data(bss, &[1; 4][..], 4).is_err());
+
+    object.try_set_section_data(data, &[1; 4][..], 4).unwrap();
+    assert!(object.try_set_section_data(data, &[1; 4][..], 4).is_err());
+    assert_eq!(object.try_append_section_data(data, &[2; 4], 8), Ok(8));
+    assert_eq!(object.try_append_section_bss(bss, 4, 4), Ok(0));
+    assert_eq!(object.try_append_section_bss(bss, 4, 16), Ok(16));
+}
+
+#[test]
+fn ifunc_relocation() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0x90; 16], 16);
+    let resolver = object.add_symbol(write::Symbol {
+        name: b"resolver".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_symbol_data(resolver, text, &[0xc3], 16);
+    let data = object.section_id(write::StandardSection::Data);
+    let pointer = object.append_section_data(data, &[0; 8], 8);
+    object
+        .add_elf_ifunc_relocation(data, pointer, resolver)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let data = object.section_by_name(".data").unwrap();
+    let mut relocations = data.relocations();
+    let (offset, relocation) = relocations.next().unwrap();
+    assert_eq!(offset, 0);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_X86_64_IRELATIVE
+        }
+    );
+    let symbol = match relocation.target() {
+        read::RelocationTarget::Symbol(symbol) => object.symbol_by_index(symbol).unwrap(),
+        target => panic!("unexpected relocation target {:?}", target),
+    };
+    assert_eq!(symbol.kind(), SymbolKind::Section);
+    let text = object.section_by_name(".text").unwrap();
+    assert_eq!(symbol.section_index(), Some(text.index()));
+    assert_eq!(relocation.addend(), 16);
+    assert!(relocations.next().is_none());
+}
+
+#[test]
+fn remove_relocation() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 16], 8);
+    for (offset, name) in [(0, &b"kept"[..]), (8, &b"discarded"[..])] {
+        let symbol = object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset,
+                    symbol,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 64,
+                    },
+                },
+            )
+            .unwrap();
+    }
+
+    let relocations = object.section_relocations(data);
+    assert_eq!(relocations.len(), 2);
+    assert_eq!(
+        relocations[1].flags,
+        RelocationFlags::Elf {
+            r_type: elf::R_X86_64_64
+        }
+    );
+    let discarded = object.symbol_id(b"discarded").unwrap();
+    let index = relocations
+        .iter()
+        .position(|relocation| relocation.symbol == discarded)
+        .unwrap();
+    let relocation = object.remove_relocation(data, index);
+    assert_eq!(relocation.offset, 8);
+    assert_eq!(object.section(data).relocations().len(), 1);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let data = object.section_by_name(".data").unwrap();
+    let mut relocations = data.relocations();
+    let (offset, _) = relocations.next().unwrap();
+    assert_eq!(offset, 0);
+    assert!(relocations.next().is_none());
+}
+
+#[test]
+fn write_section_data_at() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Sharc, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[1; 4], 4);
+    // Literal pool after a gap.
+    object.write_section_data_at(text, 8, &[2; 4]).unwrap();
+    // Overwrite within the existing data.
+    object.write_section_data_at(text, 2, &[3; 2]).unwrap();
+    assert_eq!(object.append_section_data(text, &[4; 2], 2), 12);
+
+    let bss = object.section_id(write::StandardSection::UninitializedData);
+    assert!(object.write_section_data_at(bss, 0, &[1]).is_err());
+    assert!(object.write_section_data_at(text, u64::MAX, &[1]).is_err());
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let text = object.section_by_name(".text").unwrap();
+    assert_eq!(
+        text.data().unwrap(),
+        &[1, 1, 3, 3, 0, 0, 0, 0, 2, 2, 2, 2, 4, 4]
+    );
+}
+
+#[test]
+fn dynamic_scope() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 3], 1);
+    let mut add = |name: &[u8], value, scope, section| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 1,
+            kind: SymbolKind::Text,
+            scope,
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        })
+    };
+    let section = write::SymbolSection::Section(text);
+    let exported = add(b"exported", 0, SymbolScope::Dynamic, section);
+    add(b"hidden", 1, SymbolScope::Linkage, section);
+    add(b"local", 2, SymbolScope::Compilation, section);
+    add(
+        b"imported",
+        0,
+        SymbolScope::Dynamic,
+        write::SymbolSection::Undefined,
+    );
+    let ids: Vec<_> = object.exported_symbols().map(|(id, _)| id).collect();
+    assert_eq!(ids, [exported]);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let scope = |name| object.symbol_by_name(name).unwrap().scope();
+    assert_eq!(scope("exported"), SymbolScope::Dynamic);
+    assert_eq!(scope("hidden"), SymbolScope::Linkage);
+    assert_eq!(scope("local"), SymbolScope::Compilation);
+}
+
+#[test]
+fn append() {
+    // Create an object that defines `name` (unless it is empty) and
+    // references `reference`.
+    fn create(name: &[u8], reference: &[u8]) -> write::Object<'static> {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0x90; 16], 16);
+        if !name.is_empty() {
+            object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 16,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Linkage,
+                weak: false,
+                section: write::SymbolSection::Section(text),
+                flags: SymbolFlags::None,
+            });
+        }
+        let symbol = object.add_symbol(write::Symbol {
+            name: reference.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object
+            .add_relocation(
+                text,
+                write::Relocation {
+                    offset: 8,
+                    symbol,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 64,
+                    },
+                },
+            )
+            .unwrap();
+        object
+    }
+
+    let mut object = create(b"main", b"helper");
+    let other = create(b"helper", b"main");
+    let other_text = other.section_by_name(b".text").unwrap();
+    let other_main = other.symbol_id(b"main").unwrap();
+    let other_helper = other.symbol_id(b"helper").unwrap();
+    let remap = object.append(other).unwrap();
+    assert_eq!(remap.symbol(other_main), object.symbol_id(b"main").unwrap());
+    assert_eq!(
+        remap.symbol(other_helper),
+        object.symbol_id(b"helper").unwrap()
+    );
+    assert_eq!(
+        object.symbol(remap.symbol(other_helper)).section,
+        write::SymbolSection::Section(remap.section(other_text))
+    );
+    assert_eq!(
+        object.section_relocations(remap.section(other_text))[0].symbol,
+        remap.symbol(other_main)
+    );
+
+    // Undefined references to a symbol that is already defined are allowed.
+    object.append(create(b"", b"helper")).unwrap();
+    assert!(object.append(create(b"main", b"other")).is_err());
+    assert!(object
+        .append(write::Object::new(
+            BinaryFormat::Elf,
+            Architecture::Aarch64,
+            Endianness::Little
+        ))
+        .is_err());
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let mut texts = object.sections().filter(|s| s.name() == Ok(".text"));
+    let (first, second) = (texts.next().unwrap(), texts.next().unwrap());
+    assert_eq!(texts.count(), 1);
+    let main = object.symbol_by_name("main").unwrap();
+    let helper = object.symbol_by_name("helper").unwrap();
+    assert_eq!(main.section_index(), Some(first.index()));
+    assert_eq!(helper.section_index(), Some(second.index()));
+    for (section, target) in [(first, helper.index()), (second, main.index())] {
+        let (_, relocation) = section.relocations().next().unwrap();
+        assert_eq!(relocation.target(), read::RelocationTarget::Symbol(target));
+    }
+}
+
+#[test]
+fn append_settings() {
+    let create = || {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0x90; 16], 16);
+        let symbol = object.add_symbol(write::Symbol {
+            name: b"func".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        (object, symbol)
+    };
+
+    // Settings that are only set in the appended object are used.
+    let (mut object, _) = create();
+    let (mut other, _) = create();
+    other.flags = FileFlags::Elf {
+        os_abi: elf::ELFOSABI_GNU,
+        abi_version: 0,
+        e_flags: 0,
+    };
+    other.set_elf_note_alignment(8).unwrap();
+    object.append(other).unwrap();
+    assert_eq!(
+        object.flags,
+        FileFlags::Elf {
+            os_abi: elf::ELFOSABI_GNU,
+            abi_version: 0,
+            e_flags: 0,
+        }
+    );
+
+    // Different settings are an error, and nothing is appended.
+    let (mut other, _) = create();
+    other.flags = FileFlags::Elf {
+        os_abi: elf::ELFOSABI_FREEBSD,
+        abi_version: 0,
+        e_flags: 0,
+    };
+    assert!(object.append(other).is_err());
+    let (mut other, _) = create();
+    other.set_elf_note_alignment(4).unwrap();
+    object.append(other).unwrap();
+    assert_eq!(object.sections().count(), 3);
+
+    // Settings for merged symbols must also match.
+    let (mut object, symbol) = create();
+    object
+        .set_symbol_visibility(symbol, write::ElfVisibility::Hidden)
+        .unwrap();
+    let (mut other, other_symbol) = create();
+    other
+        .set_symbol_visibility(other_symbol, write::ElfVisibility::Protected)
+        .unwrap();
+    assert!(object.append(other).is_err());
+    assert_eq!(object.sections().count(), 1);
+    let (mut other, other_symbol) = create();
+    other
+        .set_symbol_visibility(other_symbol, write::ElfVisibility::Hidden)
+        .unwrap();
+    object.append(other).unwrap();
+}
+
+#[test]
+fn add_relocations_raw() {
+    let create = |raw: bool| {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(data, &[0; 0x100], 8);
+        let symbol = object.section_symbol(data);
+        let relocations: Vec<_> = (0..0x20)
+            .map(|i| write::Relocation {
+                offset: i * 8,
+                symbol,
+                addend: i as i64,
+                flags: RelocationFlags::Elf {
+                    r_type: elf::R_X86_64_64,
+                },
+            })
+            .collect();
+        if raw {
+            object.add_relocations_raw(data, relocations).unwrap();
+        } else {
+            for relocation in relocations {
+                object.add_relocation(data, relocation).unwrap();
+            }
+        }
+        object
+    };
+    assert_eq!(
+        create(true).write().unwrap(),
+        create(false).write().unwrap()
+    );
+
+    let mut object = create(true);
+    let data = object.section_id(write::StandardSection::Data);
+    let symbol = object.section_symbol(data);
+    let relocation = |offset, flags| write::Relocation {
+        offset,
+        symbol,
+        addend: 0,
+        flags,
+    };
+    let generic = RelocationFlags::Generic {
+        kind: RelocationKind::Absolute,
+        encoding: RelocationEncoding::Generic,
+        size: 64,
+    };
+    let native = RelocationFlags::Elf {
+        r_type: elf::R_X86_64_64,
+    };
+    assert!(object
+        .add_relocations_raw(data, vec![relocation(0, native), relocation(8, generic)])
+        .is_err());
+    assert!(object
+        .add_relocations_raw(data, vec![relocation(0x100, native)])
+        .is_err());
+    assert_eq!(object.section_relocations(data).len(), 0x20);
+
+    // The addend must be in the section data for implicit addends.
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 8], 4);
+    let symbol = object.section_symbol(data);
+    let relocation = |offset, addend| write::Relocation {
+        offset,
+        symbol,
+        addend,
+        flags: RelocationFlags::Elf {
+            r_type: elf::R_386_32,
+        },
+    };
+    assert!(object
+        .add_relocations_raw(data, vec![relocation(0, 4)])
+        .is_err());
+    object
+        .add_relocations_raw(data, vec![relocation(0, 0)])
+        .unwrap();
+
+    // The field of a relocation must not extend past the end of the section.
+    assert!(object
+        .add_relocations_raw(data, vec![relocation(6, 0)])
+        .is_err());
+    object
+        .add_relocations_raw(data, vec![relocation(4, 0)])
+        .unwrap();
+    assert_eq!(object.section_relocations(data).len(), 2);
+}
+
+#[test]
+fn content_build_id() {
+    fn build_id(build_id_first: bool, text_data: &[u8], align: u64) -> Vec<u8> {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        object.set_elf_note_alignment(align).unwrap();
+        if build_id_first {
+            object
+                .add_build_id(write::BuildIdStyle::FromContents)
+                .unwrap();
+        } else {
+            // Non-allocatable sections are not hashed.
+            let comment = object.add_section(Vec::new(), b".comment".to_vec(), SectionKind::Other);
+            object.append_section_data(comment, b"compiler\0", 1);
+        }
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, text_data, 16);
+        let bss = object.section_id(write::StandardSection::UninitializedData);
+        object.append_section_bss(bss, 0x40, 8);
+        if !build_id_first {
+            object
+                .add_build_id(write::BuildIdStyle::FromContents)
+                .unwrap();
+        }
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        object.build_id().unwrap().unwrap().to_vec()
+    }
+
+    let id = build_id(true, &[0x90; 16], 4);
+    assert_eq!(id.len(), 20);
+    assert_ne!(id, [0; 20]);
+    assert_eq!(build_id(false, &[0x90; 16], 4), id);
+    assert_eq!(build_id(true, &[0x90; 16], 8), id);
+    assert_ne!(build_id(true, &[0xc3; 16], 4), id);
+
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    assert!(object
+        .add_build_id(write::BuildIdStyle::FromContents)
+        .is_err());
+}
+
+#[test]
+fn build_id_styles() {
+    let build_id = |style| {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0x90; 16], 16);
+        object.add_build_id(style)?;
+        assert!(object
+            .add_build_id(write::BuildIdStyle::FromContents)
+            .is_err());
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        let section = object.section_by_name(".note.gnu.build-id").unwrap();
+        assert_eq!(section.kind(), SectionKind::Note);
+        Ok::<_, write::Error>(object.build_id().unwrap().unwrap().to_vec())
+    };
+
+    let sha1: Vec<u8> = (0..20).collect();
+    assert_eq!(
+        build_id(write::BuildIdStyle::Sha1(sha1.clone())).unwrap(),
+        sha1
+    );
+    let md5: Vec<u8> = (0..16).collect();
+    assert_eq!(
+        build_id(write::BuildIdStyle::Md5(md5.clone())).unwrap(),
+        md5
+    );
+    assert_eq!(
+        build_id(write::BuildIdStyle::ExplicitUuid(md5.clone())).unwrap(),
+        md5
+    );
+    assert!(build_id(write::BuildIdStyle::ExplicitUuid(sha1.clone())).is_err());
+    assert!(build_id(write::BuildIdStyle::Sha1(md5)).is_err());
+    assert!(build_id(write::BuildIdStyle::Md5(sha1)).is_err());
+
+    let contents = build_id(write::BuildIdStyle::FromContents).unwrap();
+    let uuid = build_id(write::BuildIdStyle::Uuid).unwrap();
+    assert_eq!(uuid.len(), 16);
+    assert_eq!(uuid[6] >> 4, 5);
+    assert_eq!(uuid[8] >> 6, 2);
+    assert_eq!(uuid[..6], contents[..6]);
+    assert_eq!(build_id(write::BuildIdStyle::Uuid).unwrap(), uuid);
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    assert!(object.add_build_id(write::BuildIdStyle::Uuid).is_err());
+}
+
+#[test]
+fn build_id_debug_path() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0x90; 16], 16);
+    assert_eq!(object.build_id_debug_path(), None);
+
+    let mut sha1 = object.clone();
+    sha1.add_build_id(write::BuildIdStyle::Sha1((0xab..0xbf).collect()))
+        .unwrap();
+    assert_eq!(
+        sha1.build_id_debug_path().unwrap(),
+        ".build-id/ab/acadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbe.debug"
+    );
+
+    // A build ID derived from the contents matches the one that is written.
+    object
+        .add_build_id(write::BuildIdStyle::FromContents)
+        .unwrap();
+    let path = object.build_id_debug_path().unwrap();
+    let bytes = object.write().unwrap();
+    let build_id = read::File::parse(&*bytes)
+        .unwrap()
+        .build_id()
+        .unwrap()
+        .unwrap()
+        .to_vec();
+    let hex: String = build_id.iter().map(|b| format!("{:02x}", b)).collect();
+    assert_eq!(path, format!(".build-id/{}/{}.debug", &hex[..2], &hex[2..]));
+}
+
+#[test]
+fn symbol_version() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 4], 4);
+    let mut add = |name: &[u8], value, section| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        })
+    };
+    let malloc = add(b"malloc", 0, write::SymbolSection::Undefined);
+    let foo = add(b"foo", 0, write::SymbolSection::Section(text));
+    let foo_old = add(b"foo_old", 2, write::SymbolSection::Section(text));
+
+    let glibc = object.add_version_need(b"libc.so.6", b"GLIBC_2.2.5");
+    let vers_1 = object.add_version_definition(b"VERS_1");
+    let vers_2 = object.add_version_definition(b"VERS_2");
+    assert!(object.set_symbol_version(malloc, vers_1, false).is_err());
+    assert!(object.set_symbol_version(foo, glibc, true).is_err());
+    object.set_symbol_version(malloc, glibc, true).unwrap();
+    object.set_symbol_version(foo, vers_2, false).unwrap();
+    object.set_symbol_version(foo_old, vers_1, true).unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let malloc = object.symbol_by_name("malloc@GLIBC_2.2.5").unwrap();
+    assert!(malloc.is_undefined());
+    let foo = object.symbol_by_name("foo@@VERS_2").unwrap();
+    assert_eq!(foo.address(), 0);
+    let foo_old = object.symbol_by_name("foo_old@VERS_1").unwrap();
+    assert_eq!(foo_old.address(), 2);
+    assert!(object.symbol_by_name("foo").is_none());
+
+    let bytes = &*bytes;
+    let header = elf::FileHeader64::<Endianness>::parse(bytes).unwrap();
+    let endian = header.endian().unwrap();
+    let sections = header.sections(endian, bytes).unwrap();
+    let symbols = sections.symbols(endian, bytes, elf::SHT_SYMTAB).unwrap();
+    let strings = symbols.strings();
+
+    let (versyms, link) = sections.gnu_versym(endian, bytes).unwrap().unwrap();
+    assert_eq!(link, symbols.section());
+    assert_eq!(versyms.len(), symbols.len());
+
+    let (mut verdefs, link) = sections.gnu_verdef(endian, bytes).unwrap().unwrap();
+    assert_eq!(link, symbols.string_section());
+    let verdef_section = sections
+        .section_by_name(endian, b".gnu.version_d")
+        .unwrap()
+        .1;
+    assert_eq!(verdef_section.sh_info(endian), 2);
+    for (ndx, name) in [(3, &b"VERS_1"[..]), (4, b"VERS_2")] {
+        let (verdef, mut verdauxs) = verdefs.next().unwrap().unwrap();
+        assert_eq!(verdef.vd_ndx.get(endian), ndx);
+        assert_eq!(verdef.vd_hash.get(endian), elf::hash(name));
+        let verdaux = verdauxs.next().unwrap().unwrap();
+        assert_eq!(verdaux.name(endian, strings).unwrap(), name);
+    }
+    assert!(verdefs.next().unwrap().is_none());
+
+    let (mut verneeds, link) = sections.gnu_verneed(endian, bytes).unwrap().unwrap();
+    assert_eq!(link, symbols.string_section());
+    let verneed_section = sections
+        .section_by_name(endian, b".gnu.version_r")
+        .unwrap()
+        .1;
+    assert_eq!(verneed_section.sh_info(endian), 1);
+    let (verneed, mut vernauxs) = verneeds.next().unwrap().unwrap();
+    assert_eq!(verneed.file(endian, strings).unwrap(), b"libc.so.6");
+    let vernaux = vernauxs.next().unwrap().unwrap();
+    assert_eq!(vernaux.name(endian, strings).unwrap(), b"GLIBC_2.2.5");
+    assert_eq!(vernaux.vna_hash.get(endian), elf::hash(b"GLIBC_2.2.5"));
+    assert_eq!(vernaux.vna_other.get(endian), 2);
+    assert!(vernauxs.next().unwrap().is_none());
+    assert!(verneeds.next().unwrap().is_none());
+
+    let versions = sections.versions(endian, bytes).unwrap().unwrap();
+    let version = |name: &[u8]| {
+        let (index, _) = symbols
+            .iter()
+            .enumerate()
+            .find(|(_, sym)| sym.name(endian, strings).unwrap() == name)
+            .unwrap();
+        let index = versions.version_index(endian, read::SymbolIndex(index));
+        let version = versions.version(index).unwrap().unwrap();
+        (version.name(), version.file(), index.is_hidden())
+    };
+    assert_eq!(
+        version(b"malloc@GLIBC_2.2.5"),
+        (&b"GLIBC_2.2.5"[..], Some(&b"libc.so.6"[..]), true)
+    );
+    assert_eq!(version(b"foo@@VERS_2"), (&b"VERS_2"[..], None, false));
+    assert_eq!(version(b"foo_old@VERS_1"), (&b"VERS_1"[..], None, true));
+}
+
+#[test]
+fn section_lma() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::PowerPc64, Endianness::Big);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 4], 4);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[1; 8], 8);
+    object.set_section_lma(data, 0x0800_4000).unwrap();
+    object.set_section_lma(text, 0x1234).unwrap();
+    object.set_section_lma(text, 0x0800_0000).unwrap();
+
+    let bytes = &*object.write().unwrap();
+    let header = elf::FileHeader64::<Endianness>::parse(bytes).unwrap();
+    let endian = header.endian().unwrap();
+    let sections = header.sections(endian, bytes).unwrap();
+    let section = sections
+        .iter()
+        .find(|s| sections.section_name(endian, s).unwrap() == b".note.object.lma")
+        .unwrap();
+    assert_eq!(section.sh_type(endian), elf::SHT_NOTE);
+    assert_eq!(section.sh_flags(endian), 0);
+    let mut notes = section.notes(endian, bytes).unwrap().unwrap();
+    let note = notes.next().unwrap().unwrap();
+    assert_eq!(note.name(), b"object");
+    assert_eq!(note.n_type(endian), write::elf::NT_OBJECT_LMA);
+    assert!(notes.next().unwrap().is_none());
+
+    let mut lmas = Vec::new();
+    for entry in note.desc().chunks_exact(16) {
+        let index = u32::from_be_bytes(entry[..4].try_into().unwrap());
+        assert_eq!(&entry[4..8], &[0; 4]);
+        let lma = u64::from_be_bytes(entry[8..].try_into().unwrap());
+        let section = sections.section(SectionIndex(index as usize)).unwrap();
+        lmas.push((sections.section_name(endian, section).unwrap(), lma));
+    }
+    assert_eq!(
+        lmas,
+        [(&b".text"[..], 0x0800_0000), (&b".data"[..], 0x0800_4000)]
+    );
+
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    assert!(object.set_section_lma(text, 0).is_err());
+}
+
+#[test]
+fn section_entsize() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let strings = object.section_id(write::StandardSection::ReadOnlyString);
+    object.append_section_data(strings, b"hello\0world\0", 1);
+    let constants = object.add_section(
+        Vec::new(),
+        b".rodata.cst8".to_vec(),
+        SectionKind::ReadOnlyData,
+    );
+    object.append_section_data(constants, &[1; 16], 8);
+    object.set_section_entsize(constants, 8).unwrap();
+    assert!(object.set_section_entsize(constants, 0).is_err());
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    let sections = header.sections(LittleEndian, &*bytes).unwrap();
+    let section_by_name = |name: &[u8]| {
+        sections
+            .iter()
+            .find(|s| sections.section_name(LittleEndian, s).unwrap() == name)
+            .unwrap()
+    };
+
+    let section = section_by_name(b".rodata.str1.1");
+    assert_eq!(
+        section.sh_flags(LittleEndian),
+        u64::from(elf::SHF_ALLOC | elf::SHF_MERGE | elf::SHF_STRINGS)
+    );
+    assert_eq!(section.sh_entsize(LittleEndian), 1);
+
+    let section = section_by_name(b".rodata.cst8");
+    assert_eq!(
+        section.sh_flags(LittleEndian),
+        u64::from(elf::SHF_ALLOC | elf::SHF_MERGE)
+    );
+    assert_eq!(section.sh_entsize(LittleEndian), 8);
+
+    // The section size must be a multiple of the entry size.
+    object.append_section_data(constants, &[1; 4], 1);
+    assert!(object.write().is_err());
+}
+
+#[test]
+fn init_fini_array() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    let offset = object.append_section_data(text, &[0xc3], 1);
+    let function = object.add_symbol(write::Symbol {
+        name: b"init".to_vec(),
+        value: offset,
+        size: 1,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    for standard_section in [
+        write::StandardSection::InitArray,
+        write::StandardSection::FiniArray,
+    ] {
+        let section = object.section_id(standard_section);
+        assert_eq!(object.section_id(standard_section), section);
+        let offset = object.append_section_data(section, &[0; 8], 8);
+        object
+            .add_relocation(
+                section,
+                write::Relocation {
+                    offset,
+                    symbol: function,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 64,
+                    },
+                },
+            )
+            .unwrap();
+    }
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    let sections = header.sections(LittleEndian, &*bytes).unwrap();
+    for (name, sh_type) in [
+        (&b".init_array"[..], elf::SHT_INIT_ARRAY),
+        (&b".fini_array"[..], elf::SHT_FINI_ARRAY),
+    ] {
+        let section = sections
+            .iter()
+            .find(|s| sections.section_name(LittleEndian, s).unwrap() == name)
+            .unwrap();
+        assert_eq!(section.sh_type(LittleEndian), sh_type);
+        assert_eq!(
+            section.sh_flags(LittleEndian),
+            u64::from(elf::SHF_ALLOC | elf::SHF_WRITE)
+        );
+    }
+}
+
+#[test]
+fn preinit_array() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    let offset = object.append_section_data(text, &[0xc3; 2], 1);
+    let mut add_function = |name: &[u8], value| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 1,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        })
+    };
+    let f1 = add_function(b"f1", offset);
+    let f2 = add_function(b"f2", offset + 1);
+    assert_eq!(object.add_preinit_array_entry(f1).unwrap(), 0);
+    assert_eq!(object.add_preinit_array_entry(f2).unwrap(), 8);
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    let sections = header.sections(LittleEndian, &*bytes).unwrap();
+    let (_, section) = sections
+        .section_by_name(LittleEndian, b".preinit_array")
+        .unwrap();
+    assert_eq!(section.sh_type(LittleEndian), elf::SHT_PREINIT_ARRAY);
+    assert_eq!(
+        section.sh_flags(LittleEndian),
+        u64::from(elf::SHF_ALLOC | elf::SHF_WRITE)
+    );
+    assert_eq!(section.sh_size(LittleEndian), 16);
+
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".preinit_array").unwrap();
+    let relocations: Vec<_> = section
+        .relocations()
+        .map(|(offset, relocation)| {
+            let read::RelocationTarget::Symbol(symbol) = relocation.target() else {
+                panic!();
+            };
+            let symbol = object.symbol_by_index(symbol).unwrap();
+            (
+                offset,
+                relocation.kind(),
+                relocation.size(),
+                symbol.name().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        relocations,
+        [
+            (0, RelocationKind::Absolute, 64, "f1".to_string()),
+            (8, RelocationKind::Absolute, 64, "f2".to_string()),
+        ]
+    );
+
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    let symbol = object.section_symbol(text);
+    assert!(object.add_preinit_array_entry(symbol).is_err());
+}
+
+#[test]
+fn msp430() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::Msp430, Endianness::Little);
+    object.flags = object::FileFlags::Elf {
+        os_abi: elf::ELFOSABI_NONE,
+        abi_version: 0,
+        e_flags: elf::EF_MSP430_MACH_MSP430X,
+    };
+    let text = object.section_id(write::StandardSection::Text);
+    let offset = object.append_section_data(text, &[0; 4], 2);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"counter".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object
+        .add_relocation(
+            text,
+            write::Relocation {
+                offset: offset + 2,
+                symbol,
+                addend: 4,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: 16,
+                },
+            },
+        )
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader32::<LittleEndian>::parse(&*bytes).unwrap();
+    assert_eq!(header.e_machine(LittleEndian), elf::EM_MSP430);
+    assert_eq!(header.e_flags(LittleEndian), elf::EF_MSP430_MACH_MSP430X);
+
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.architecture(), Architecture::Msp430);
+    let text = object.section_by_name(".text").unwrap();
+    let (offset, relocation) = text.relocations().next().unwrap();
+    assert_eq!(offset, 2);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_MSP430_16_BYTE
+        }
+    );
+    assert_eq!(relocation.size(), 16);
+    assert_eq!(relocation.addend(), 4);
+}
+
+#[test]
+fn implicit_addend_16() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    let offset = object.append_section_data(data, &[0; 4], 2);
+    let symbol = object.section_symbol(data);
+    object
+        .add_relocation(
+            data,
+            write::Relocation {
+                offset: offset + 2,
+                symbol,
+                addend: 0x1234,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: 16,
+                },
+            },
+        )
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let data = object.section_by_name(".data").unwrap();
+    assert_eq!(data.data().unwrap(), &[0, 0, 0x34, 0x12]);
+    let (_, relocation) = data.relocations().next().unwrap();
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_386_16
+        }
+    );
+    assert!(relocation.has_implicit_addend());
+}
+
+#[test]
+fn symbol_size_inference() {
+    let sizes = |inference| {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        object.set_elf_symbol_size_inference(inference);
+        let text = object.section_id(write::StandardSection::Text);
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(text, &[0x90; 0x30], 16);
+        object.append_section_data(data, &[0; 0x10], 8);
+        for (name, value, size, kind, section) in [
+            ("f1", 0, 0, SymbolKind::Text, text),
+            ("f1_label", 0x8, 0, SymbolKind::Label, text),
+            ("f2", 0x10, 0, SymbolKind::Text, text),
+            ("f2_local", 0x14, 0, SymbolKind::Label, text),
+            ("f3", 0x20, 4, SymbolKind::Text, text),
+            ("f4", 0x28, 0, SymbolKind::Text, text),
+            ("d1", 0, 0, SymbolKind::Data, data),
+            ("d2", 0x8, 0, SymbolKind::Data, data),
+        ] {
+            object.add_symbol(write::Symbol {
+                name: name.as_bytes().to_vec(),
+                value,
+                size,
+                kind,
+                scope: if kind == SymbolKind::Text {
+                    SymbolScope::Linkage
+                } else {
+                    SymbolScope::Compilation
+                },
+                weak: false,
+                section: write::SymbolSection::Section(section),
+                flags: SymbolFlags::None,
+            });
+        }
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        object
+            .symbols()
+            .filter(|symbol| symbol.kind() != SymbolKind::Section)
+            .map(|symbol| (symbol.name().unwrap().to_string(), symbol.size()))
+            .collect::<std::collections::HashMap<_, _>>()
+    };
+
+    let none = sizes(write::SymbolSizeInference::None);
+    assert!(none.iter().all(|(name, &size)| size == 0 || name == "f3"));
+
+    let functions = sizes(write::SymbolSizeInference::Functions);
+    assert_eq!(functions["f1"], 0x10);
+    assert_eq!(functions["f1_label"], 0);
+    assert_eq!(functions["f2"], 0x10);
+    assert_eq!(functions["f2_local"], 0);
+    assert_eq!(functions["f3"], 4);
+    assert_eq!(functions["f4"], 0x8);
+    assert_eq!(functions["d1"], 0);
+    assert_eq!(functions["d2"], 0);
+
+    let all = sizes(write::SymbolSizeInference::FunctionsAndData);
+    assert_eq!(all["f1"], 0x10);
+    assert_eq!(all["d1"], 0x8);
+    assert_eq!(all["d2"], 0x8);
+}
+
+#[test]
+fn section_type() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let unwind = object.add_section(Vec::new(), b".eh_frame".to_vec(), SectionKind::ReadOnlyData);
+    object.append_section_data(unwind, &[0; 8], 8);
+    object
+        .set_section_type(unwind, elf::SHT_X86_64_UNWIND)
+        .unwrap();
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[1; 8], 8);
+    let bss = object.section_id(write::StandardSection::UninitializedData);
+    object.append_section_bss(bss, 8, 8);
+    assert!(object.set_section_type(bss, elf::SHT_PROGBITS).is_err());
+    assert!(object.set_section_type(data, elf::SHT_NOBITS).is_err());
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    let sections = header.sections(LittleEndian, &*bytes).unwrap();
+    for (name, sh_type, sh_flags) in [
+        (&b".eh_frame"[..], elf::SHT_X86_64_UNWIND, elf::SHF_ALLOC),
+        (
+            &b".data"[..],
+            elf::SHT_PROGBITS,
+            elf::SHF_ALLOC | elf::SHF_WRITE,
+        ),
+        (
+            &b".bss"[..],
+            elf::SHT_NOBITS,
+            elf::SHF_ALLOC | elf::SHF_WRITE,
+        ),
+    ] {
+        let section = sections
+            .iter()
+            .find(|s| sections.section_name(LittleEndian, s).unwrap() == name)
+            .unwrap();
+        assert_eq!(section.sh_type(LittleEndian), sh_type);
+        assert_eq!(section.sh_flags(LittleEndian), u64::from(sh_flags));
+    }
+}
+
+#[test]
+fn comdat_section_index_overflow() {
+    use object::read::ObjectComdat;
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    // Each COMDAT adds a group section and a function section.
+    let count = 35_000;
+    for i in 0..count {
+        let name = format!("func{}", i).into_bytes();
+        let section = object.add_subsection(write::StandardSection::Text, &name);
+        let offset = object.append_section_data(section, &[0xcc], 1);
+        let symbol = object.add_symbol(write::Symbol {
+            name,
+            value: offset,
+            size: 1,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: true,
+            section: write::SymbolSection::Section(section),
+            flags: SymbolFlags::None,
+        });
+        object.add_comdat(write::Comdat {
+            kind: object::ComdatKind::Any,
+            symbol,
+            sections: vec![section],
+        });
+    }
+    let bytes = object.write().unwrap();
+
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    assert_eq!(header.e_shnum.get(LittleEndian), 0);
+    assert_eq!(header.e_shstrndx.get(LittleEndian), elf::SHN_XINDEX);
+    let sections = header.sections(LittleEndian, &*bytes).unwrap();
+    assert!(sections.len() > 70_000);
+    let section0 = header.section_0(LittleEndian, &*bytes).unwrap().unwrap();
+    assert_eq!(section0.sh_size(LittleEndian), sections.len() as u64);
+    assert_eq!(section0.sh_link(LittleEndian) as usize, sections.len() - 1);
+    assert!(sections
+        .iter()
+        .any(|section| section.sh_type(LittleEndian) == elf::SHT_SYMTAB_SHNDX));
+
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.comdats().count(), count);
+    for comdat in object.comdats() {
+        let symbol = object.symbol_by_index(comdat.symbol()).unwrap();
+        let mut comdat_sections = comdat.sections();
+        let section = comdat_sections.next().unwrap();
+        assert!(comdat_sections.next().is_none());
+        assert_eq!(symbol.section(), SymbolSection::Section(section));
+        let section = object.section_by_index(section).unwrap();
+        assert_eq!(
+            section.name().unwrap(),
+            format!(".text.{}", symbol.name().unwrap())
+        );
+    }
+}
+
+#[test]
+fn symbol_visibility() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 4], 4);
+    let mut symbols = Vec::new();
+    for (name, scope, visibility, st_other) in [
+        ("default", SymbolScope::Dynamic, None, elf::STV_DEFAULT),
+        ("hidden", SymbolScope::Linkage, None, elf::STV_HIDDEN),
+        (
+            "protected",
+            SymbolScope::Dynamic,
+            Some(write::ElfVisibility::Protected),
+            elf::STV_PROTECTED,
+        ),
+        (
+            "internal",
+            SymbolScope::Linkage,
+            Some(write::ElfVisibility::Internal),
+            elf::STV_INTERNAL,
+        ),
+        (
+            "exported",
+            SymbolScope::Linkage,
+            Some(write::ElfVisibility::Default),
+            elf::STV_DEFAULT,
+        ),
+    ] {
+        let symbol = object.add_symbol(write::Symbol {
+            name: name.as_bytes().to_vec(),
+            value: 0,
+            size: 4,
+            kind: SymbolKind::Text,
+            scope,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+        if let Some(visibility) = visibility {
+            object.set_symbol_visibility(symbol, visibility).unwrap();
+        }
+        symbols.push((name, st_other));
+    }
+    let section_symbol = object.section_symbol(text);
+    assert!(object
+        .set_symbol_visibility(section_symbol, write::ElfVisibility::Protected)
+        .is_err());
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    for (name, st_other) in symbols {
+        let symbol = object.symbol_by_name(name).unwrap();
+        match symbol.flags() {
+            SymbolFlags::Elf {
+                st_other: other, ..
+            } => assert_eq!(other, st_other, "{}", name),
+            flags => panic!("unexpected flags {:?}", flags),
+        }
+    }
+}
+
+#[test]
+fn aarch64_features() {
+    for (bti, pac) in [(true, true), (true, false), (false, true)] {
+        let mut object =
+            write::Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+        // An unrelated property does not prevent setting the features.
+        object.add_elf_gnu_property_u32(elf::GNU_PROPERTY_X86_FEATURE_1_AND, 0);
+        object.set_aarch64_features(bti, pac).unwrap();
+        assert!(object.set_aarch64_features(bti, pac).is_err());
+        let bytes = object.write().unwrap();
+
+        let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+        let sections = header.sections(LittleEndian, &*bytes).unwrap();
+        let section = sections
+            .iter()
+            .find(|s| sections.section_name(LittleEndian, s).unwrap() == b".note.gnu.property")
+            .unwrap();
+        let mut notes = section.notes(LittleEndian, &*bytes).unwrap().unwrap();
+        notes.next().unwrap().unwrap();
+        let note = notes.next().unwrap().unwrap();
+        let mut props = note.gnu_properties(LittleEndian).unwrap();
+        let prop = props.next().unwrap().unwrap();
+        assert_eq!(prop.pr_type(), elf::GNU_PROPERTY_AARCH64_FEATURE_1_AND);
+        let features = prop.data_u32(LittleEndian).unwrap();
+        assert_eq!(features & elf::GNU_PROPERTY_AARCH64_FEATURE_1_BTI != 0, bti);
+        assert_eq!(features & elf::GNU_PROPERTY_AARCH64_FEATURE_1_PAC != 0, pac);
+        assert!(props.next().unwrap().is_none());
+        assert!(notes.next().unwrap().is_none());
+    }
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    assert!(object.set_aarch64_features(true, true).is_err());
+}
+
+#[test]
+fn arch_e_flags() {
+    let e_flags = |architecture, flags| {
+        let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+        object.flags = flags;
+        let bytes = object.write()?;
+        let object = read::File::parse(&*bytes).unwrap();
+        match object.flags() {
+            object::FileFlags::Elf { e_flags, .. } => Ok::<_, write::Error>(e_flags),
+            flags => panic!("unexpected flags {:?}", flags),
+        }
+    };
+
+    assert_eq!(
+        e_flags(
+            Architecture::Riscv64,
+            object::FileFlags::elf_riscv(object::RiscVFloatAbi::Double, true)
+        )
+        .unwrap(),
+        elf::EF_RISCV_FLOAT_ABI_DOUBLE | elf::EF_RISCV_RVC
+    );
+    assert_eq!(
+        e_flags(
+            Architecture::Arm,
+            object::FileFlags::elf_arm(object::ArmFloatAbi::Hard)
+        )
+        .unwrap(),
+        elf::EF_ARM_EABI_VER5 | elf::EF_ARM_ABI_FLOAT_HARD
+    );
+    assert_eq!(
+        e_flags(
+            Architecture::Mips,
+            object::FileFlags::elf_mips(object::MipsAbi::O32, elf::EF_MIPS_ARCH_32R2)
+        )
+        .unwrap(),
+        elf::EF_MIPS_ARCH_32R2 | elf::EF_MIPS_ABI_O32
+    );
+    assert_eq!(
+        e_flags(
+            Architecture::Mips64_N32,
+            object::FileFlags::elf_mips(object::MipsAbi::N32, elf::EF_MIPS_ARCH_64R2)
+        )
+        .unwrap(),
+        elf::EF_MIPS_ARCH_64R2 | elf::EF_MIPS_ABI2
+    );
+
+    // The ABI must be consistent with the architecture.
+    assert!(e_flags(
+        Architecture::Mips64,
+        object::FileFlags::elf_mips(object::MipsAbi::O32, elf::EF_MIPS_ARCH_64)
+    )
+    .is_err());
+    assert!(e_flags(
+        Architecture::Mips,
+        object::FileFlags::elf_mips(object::MipsAbi::N32, elf::EF_MIPS_ARCH_32)
+    )
+    .is_err());
+    assert!(e_flags(
+        Architecture::Arm,
+        object::FileFlags::Elf {
+            os_abi: 0,
+            abi_version: 0,
+            e_flags: elf::EF_ARM_ABI_FLOAT_SOFT | elf::EF_ARM_ABI_FLOAT_HARD,
+        }
+    )
+    .is_err());
+}
+
+#[test]
+fn elf_osabi() {
+    let header = |object: &write::Object| -> write::Result<(u8, u8)> {
+        let bytes = object.write()?;
+        let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+        Ok((header.e_ident.os_abi, header.e_ident.abi_version))
+    };
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    assert!(object.set_elf_osabi(elf::ELFOSABI_GNU, 0).is_err());
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    assert_eq!(header(&object).unwrap(), (elf::ELFOSABI_NONE, 0));
+    object.set_elf_osabi(elf::ELFOSABI_FREEBSD, 1).unwrap();
+    assert_eq!(header(&object).unwrap(), (elf::ELFOSABI_FREEBSD, 1));
+
+    // An ifunc requires the GNU OS ABI.
+    let text = object.section_id(write::StandardSection::Text);
+    let offset = object.append_section_data(text, &[0xc3], 16);
+    object.add_symbol(write::Symbol {
+        name: b"ifunc".to_vec(),
+        value: offset,
+        size: 1,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::Elf {
+            st_info: (elf::STB_GLOBAL << 4) | elf::STT_GNU_IFUNC,
+            st_other: elf::STV_DEFAULT,
+        },
+    });
+    assert_eq!(header(&object).unwrap(), (elf::ELFOSABI_FREEBSD, 1));
+    object.set_elf_osabi(elf::ELFOSABI_NONE, 0).unwrap();
+    assert_eq!(header(&object).unwrap(), (elf::ELFOSABI_GNU, 0));
+    object.set_elf_osabi(elf::ELFOSABI_SOLARIS, 0).unwrap();
+    assert!(header(&object).is_err());
+}
+
+#[test]
+fn ifunc_symbol() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0x90; 16], 16);
+    let offset = object.append_section_data(text, &[0xc3], 16);
+    let mut add_symbol = |name: &[u8], value, section| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 1,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        })
+    };
+    let ifunc = add_symbol(b"ifunc", offset, write::SymbolSection::Section(text));
+    let undefined = add_symbol(b"undefined", 0, write::SymbolSection::Undefined);
+    assert!(object.set_symbol_ifunc(undefined).is_err());
+    object.set_symbol_ifunc(ifunc).unwrap();
+    let data = object.section_id(write::StandardSection::Data);
+    let pointer = object.append_section_data(data, &[0; 8], 8);
+    object
+        .add_elf_ifunc_relocation(data, pointer, ifunc)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    assert_eq!(header.e_ident.os_abi, elf::ELFOSABI_GNU);
+
+    let object = read::File::parse(&*bytes).unwrap();
+    let symbol = object.symbol_by_name("ifunc").unwrap();
+    assert_eq!(symbol.kind(), SymbolKind::Text);
+    assert_eq!(symbol.address(), offset);
+    assert_eq!(
+        symbol.flags(),
+        SymbolFlags::Elf {
+            st_info: (elf::STB_GLOBAL << 4) | elf::STT_GNU_IFUNC,
+            st_other: elf::STV_DEFAULT,
+        }
+    );
+
+    let data = object.section_by_name(".data").unwrap();
+    let mut relocations = data.relocations();
+    let (offset, relocation) = relocations.next().unwrap();
+    assert_eq!(offset, pointer);
+    assert_eq!(
+        relocation.flags(),
+        RelocationFlags::Elf {
+            r_type: elf::R_X86_64_IRELATIVE
+        }
+    );
+    let symbol = match relocation.target() {
+        read::RelocationTarget::Symbol(symbol) => object.symbol_by_index(symbol).unwrap(),
+        target => panic!("unexpected relocation target {:?}", target),
+    };
+    assert_eq!(symbol.kind(), SymbolKind::Section);
+    let text = object.section_by_name(".text").unwrap();
+    assert_eq!(symbol.section_index(), Some(text.index()));
+    assert_eq!(relocation.addend(), 16);
+    assert!(relocations.next().is_none());
+}
+
+/// A bitwise CRC-32, independent of the implementation used by the writer.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn checksum_section() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Big);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[1, 2, 3, 4, 5, 6, 7, 8], 4);
+    object
+        .add_checksum_section(b".checksum".to_vec(), write::ChecksumKind::Crc32, None)
+        .unwrap();
+    assert!(object
+        .add_checksum_section(b".checksum2".to_vec(), write::ChecksumKind::Sum32, None)
+        .is_err());
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[9; 8], 4);
+
+    let bytes = object.write().unwrap();
+    let file = read::File::parse(&*bytes).unwrap();
+    let section = file.section_by_name(".checksum").unwrap();
+    let (offset, size) = section.file_range().unwrap();
+    assert_eq!(size, 4);
+    let offset = offset as usize;
+    let mut input = bytes[..offset].to_vec();
+    input.extend_from_slice(&bytes[offset + 4..]);
+    assert_eq!(
+        u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()),
+        crc32(&input)
+    );
+
+    // Only include the text section.
+    let text_range = file.section_by_name(".text").unwrap().file_range().unwrap();
+    let text_range = text_range.0..text_range.0 + text_range.1;
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[1, 2, 3, 4, 5, 6, 7, 8], 4);
+    object
+        .add_checksum_section(
+            b".checksum".to_vec(),
+            write::ChecksumKind::Sum32,
+            Some(text_range.clone()),
+        )
+        .unwrap();
+    let bytes = object.write().unwrap();
+    let file = read::File::parse(&*bytes).unwrap();
+    assert_eq!(
+        file.section_by_name(".text")
+            .unwrap()
+            .file_range()
+            .unwrap()
+            .0,
+        text_range.start
+    );
+    let section = file.section_by_name(".checksum").unwrap();
+    assert_eq!(section.data(), Ok(&36u32.to_le_bytes()[..]));
+
+    // The range must be within the file.
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Little);
+    object
+        .add_checksum_section(
+            b".checksum".to_vec(),
+            write::ChecksumKind::Sum32,
+            Some(0..0x10000),
+        )
+        .unwrap();
+    assert!(object.write().is_err());
+}
+
+#[test]
+fn relr() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 8], 8);
+    let target = object.add_symbol(write::Symbol {
+        name: b"target".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(data),
+        flags: SymbolFlags::None,
+    });
+    object
+        .add_elf_relative_relocation(data, 0, target, 0)
+        .unwrap();
+    object.write().unwrap();
+
+    // Relocatable files can't use `SHT_RELR`.
+    object.set_elf_use_relr(true);
+    assert!(object.write().is_err());
+}
+
+#[test]
+fn static_pie() {
+    fn check<Elf: FileHeader<Endian = Endianness>>(architecture: Architecture, use_relr: bool) {
+        let word_size = architecture.address_size().unwrap().bytes() as u64;
+        let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+        object.set_elf_static_pie(true).unwrap();
+        object.set_elf_use_relr(use_relr);
+        let text = object.section_id(write::StandardSection::Text);
+        let start = object.add_symbol(write::Symbol {
+            name: b"_start".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.add_symbol_data(start, text, &[0xc3; 4], 16);
+        object.set_section_address(text, 0x1000).unwrap();
+        object.set_elf_entry(start).unwrap();
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(data, &vec![0; 32 * word_size as usize], 8);
+        object.set_section_address(data, 0x2008).unwrap();
+        // Aligned pointers, including a run that can use a bitmap, and an unaligned pointer.
+        let offsets = [0, 1, 2, 20].map(|i| i * word_size);
+        let unaligned = 30 * word_size + 1;
+        for &offset in offsets.iter().chain(Some(&unaligned)) {
+            object
+                .add_elf_relative_relocation(data, offset, start, offset as i64)
+                .unwrap();
+        }
+
+        let bytes = object.write().unwrap();
+        let header = Elf::parse(&*bytes).unwrap();
+        let endian = header.endian().unwrap();
+        assert_eq!(header.e_type(endian), elf::ET_DYN);
+        assert_eq!(header.e_entry(endian).into(), 0x1000);
+
+        let segments = header.program_headers(endian, &*bytes).unwrap();
+        assert!(segments
+            .iter()
+            .all(|segment| segment.p_type(endian) != elf::PT_INTERP));
+        let loads: Vec<_> = segments
+            .iter()
+            .filter(|segment| segment.p_type(endian) == elf::PT_LOAD)
+            .collect();
+        assert_eq!(loads.len(), 3);
+        for segment in &loads {
+            let align = segment.p_align(endian).into();
+            assert_eq!(
+                segment.p_offset(endian).into() % align,
+                segment.p_vaddr(endian).into() % align
+            );
+        }
+        assert_eq!(loads[1].p_vaddr(endian).into(), 0x2008);
+        assert_eq!(loads[1].p_flags(endian), elf::PF_R | elf::PF_W);
+        assert!(loads[2].p_vaddr(endian).into() >= 0x3000);
+
+        let dynamic = segments
+            .iter()
+            .find_map(|segment| segment.dynamic(endian, &*bytes).unwrap())
+            .unwrap();
+        let tags: Vec<_> = dynamic
+            .iter()
+            .map(|d| (d.d_tag(endian).into() as u32, d.d_val(endian).into()))
+            .collect();
+        let (rel_tag, rel_size) = if header.is_type_64() {
+            (elf::DT_RELA, 24)
+        } else {
+            (elf::DT_REL, 8)
+        };
+        let rel_count = if use_relr { 1 } else { 5 };
+        let mut expected = vec![rel_tag, rel_tag + 1, rel_tag + 2];
+        if use_relr {
+            expected.extend([elf::DT_RELR, elf::DT_RELRSZ, elf::DT_RELRENT]);
+        }
+        expected.push(elf::DT_NULL);
+        assert_eq!(tags.iter().map(|t| t.0).collect::<Vec<_>>(), expected);
+        assert_eq!(tags[1].1, rel_count * rel_size);
+        assert_eq!(tags[2].1, rel_size);
+
+        let sections = header.sections(endian, &*bytes).unwrap();
+        let (_, data_section) = sections.section_by_name(endian, b".data").unwrap();
+        assert_eq!(data_section.sh_addr(endian).into(), 0x2008);
+        let section_data = data_section.data(endian, &*bytes).unwrap();
+        // The final addresses are written to the section data.
+        for &offset in offsets.iter().chain(Some(&unaligned)) {
+            let value = &section_data[offset as usize..][..word_size as usize];
+            let mut expected = (0x1000 + offset).to_le_bytes().to_vec();
+            expected.truncate(word_size as usize);
+            assert_eq!(value, &expected[..]);
+        }
+
+        let rel_name = if header.is_type_64() {
+            &b".rela.dyn"[..]
+        } else {
+            &b".rel.dyn"[..]
+        };
+        let (_, rel_section) = sections.section_by_name(endian, rel_name).unwrap();
+        assert_eq!(
+            rel_section.sh_flags(endian).into(),
+            u64::from(elf::SHF_ALLOC)
+        );
+        assert_eq!(rel_section.sh_addr(endian).into(), tags[0].1);
+        let mut relocations = Vec::new();
+        if let Some((rela, _)) = rel_section.rela(endian, &*bytes).unwrap() {
+            for rela in rela {
+                assert_eq!(rela.r_sym(endian, false), 0);
+                assert_eq!(rela.r_type(endian, false), elf::R_X86_64_RELATIVE);
+                let offset = rela.r_offset(endian).into();
+                assert_eq!(
+                    rela.r_addend(endian).into() as u64,
+                    0x1000 + offset - 0x2008
+                );
+                relocations.push(offset);
+            }
+        } else {
+            let (rel, _) = rel_section.rel(endian, &*bytes).unwrap().unwrap();
+            for rel in rel {
+                assert_eq!(rel.r_sym(endian), 0);
+                assert_eq!(rel.r_type(endian), elf::R_386_RELATIVE);
+                relocations.push(rel.r_offset(endian).into());
+            }
+        }
+        if use_relr {
+            assert_eq!(relocations, [0x2008 + unaligned]);
+            let (_, relr_section) = sections.section_by_name(endian, b".relr.dyn").unwrap();
+            assert_eq!(relr_section.sh_type(endian), elf::SHT_RELR);
+            assert_eq!(relr_section.sh_addr(endian).into(), tags[3].1);
+            // One address entry and one bitmap entry.
+            assert_eq!(relr_section.sh_size(endian).into(), 2 * word_size);
+            let relr: Vec<u64> = relr_section
+                .relr(endian, &*bytes)
+                .unwrap()
+                .unwrap()
+                .map(Into::into)
+                .collect();
+            let expected: Vec<u64> = offsets.iter().map(|offset| 0x2008 + offset).collect();
+            assert_eq!(relr, expected);
+        } else {
+            let mut expected: Vec<u64> = offsets.iter().map(|offset| 0x2008 + offset).collect();
+            expected.push(0x2008 + unaligned);
+            assert_eq!(relocations, expected);
+        }
+
+        // Symbol values are addresses, and there are no static relocations.
+        let file = read::File::parse(&*bytes).unwrap();
+        assert_eq!(file.symbol_by_name("_start").unwrap().address(), 0x1000);
+        assert!(file
+            .sections()
+            .all(|section| section.relocations().next().is_none()));
+
+        // Only relative relocations are supported.
+        let mut absolute = object.clone();
+        absolute
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset: 8 * word_size,
+                    symbol: start,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: word_size as u8 * 8,
+                    },
+                },
+            )
+            .unwrap();
+        assert!(absolute.write().is_err());
+
+        // Sections must not overlap.
+        let mut overlap = object.clone();
+        overlap.set_section_address(data, 0x1000).unwrap();
+        assert!(overlap.write().is_err());
+
+        // Static-PIE executables can't also be core files.
+        let mut core = object.clone();
+        core.set_elf_core(true).unwrap();
+        assert!(core.write().is_err());
+    }
+
+    for use_relr in [false, true] {
+        check::<elf::FileHeader64<Endianness>>(Architecture::X86_64, use_relr);
+        check::<elf::FileHeader32<Endianness>>(Architecture::I386, use_relr);
+    }
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    assert!(object.set_elf_static_pie(true).is_err());
+}
+
+#[test]
+fn symbol_end_relocation() {
+    for architecture in [Architecture::X86_64, Architecture::I386] {
+        let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+        let data = object.section_id(write::StandardSection::Data);
+        let table = object.add_symbol(write::Symbol {
+            name: b"table".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.add_symbol_data(table, data, &[0; 8], 8);
+        let relocation = |offset, addend| write::Relocation {
+            offset,
+            symbol: table,
+            addend,
+            flags: RelocationFlags::Generic {
+                kind: RelocationKind::Absolute,
+                encoding: RelocationEncoding::Generic,
+                size: 32,
+            },
+        };
+        // This is removed later, which changes the index of the end relocation.
+        object.add_relocation(data, relocation(4, 0)).unwrap();
+        object
+            .add_symbol_end_relocation(
+                data,
+                write::Relocation {
+                    offset: 0,
+                    symbol: table,
+                    addend: 4,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 32,
+                    },
+                },
+            )
+            .unwrap();
+        // Grow the symbol after adding the relocation.
+        let size = object.symbol(table).size + 24;
+        object.symbol_mut(table).size = size;
+        object.append_section_data(data, &[0; 24], 1);
+        object.remove_relocation(data, 0);
+        assert_eq!(object.section_relocations(data)[0].symbol, table);
+        if architecture == Architecture::X86_64 {
+            // A relocation at the same offset must not be changed.
+            object.add_relocation(data, relocation(0, 1)).unwrap();
+        }
+
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        let section = object.section_by_name(".data").unwrap();
+        let relocations: Vec<_> = section.relocations().collect();
+        if architecture == Architecture::X86_64 {
+            assert_eq!(relocations.len(), 2);
+            assert_eq!(relocations[1].0, 0);
+            assert_eq!(relocations[1].1.addend(), 1);
+        } else {
+            assert_eq!(relocations.len(), 1);
+        }
+        let (offset, ref relocation) = relocations[0];
+        assert_eq!(offset, 0);
+        let addend = if relocation.has_implicit_addend() {
+            let data = section.data().unwrap();
+            i64::from(u32::from_le_bytes(data[..4].try_into().unwrap()))
+        } else {
+            relocation.addend()
+        };
+        assert_eq!(addend, 32 + 4, "{:?}", architecture);
+    }
+}
+
+#[test]
+fn section_retain() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let counters = object.add_section(Vec::new(), b"__llvm_prf_cnts".to_vec(), SectionKind::Data);
+    object.append_section_data(counters, &[0; 8], 8);
+    let other = object.add_section(Vec::new(), b".data.other".to_vec(), SectionKind::Data);
+    object.append_section_data(other, &[0; 8], 8);
+    object.set_section_retain(counters, true).unwrap();
+    object.set_section_retain(other, true).unwrap();
+    object.set_section_retain(other, false).unwrap();
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    assert_eq!(header.e_ident.os_abi, elf::ELFOSABI_GNU);
+
+    let object = read::File::parse(&*bytes).unwrap();
+    let flags = |name| {
+        let read::SectionFlags::Elf { sh_flags } = object.section_by_name(name).unwrap().flags()
+        else {
+            panic!("unexpected flags");
+        };
+        sh_flags
+    };
+    assert_ne!(flags("__llvm_prf_cnts") & u64::from(elf::SHF_GNU_RETAIN), 0);
+    assert_eq!(flags(".data.other") & u64::from(elf::SHF_GNU_RETAIN), 0);
+
+    // Other OS ABIs do not support the flag.
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let section = object.add_section(Vec::new(), b".data.keep".to_vec(), SectionKind::Data);
+    object.set_section_retain(section, true).unwrap();
+    object.set_elf_osabi(elf::ELFOSABI_SOLARIS, 0).unwrap();
+    assert!(object.write().is_err());
+
+    let mut object =
+        write::Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let section = object.add_section(Vec::new(), b".data".to_vec(), SectionKind::Data);
+    assert!(object.set_section_retain(section, true).is_err());
+}
+
+#[test]
+fn bb_addr_map() {
+    fn read_uleb128(data: &mut &[u8]) -> u64 {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = data[0];
+            *data = &data[1..];
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return result;
+            }
+            shift += 7;
+        }
+    }
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0x90; 0x200], 16);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0x10,
+        size: 0x1f0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    let block = |offset, size, metadata| write::elf::BbAddrMapBlock {
+        offset,
+        size,
+        metadata,
+    };
+    let mut map = write::elf::BbAddrMap::new();
+    map.add_function(
+        func,
+        vec![block(0, 8, 0), block(0x10, 0x100, 4), block(0x110, 0xe0, 1)],
+    );
+    object.add_elf_bb_addr_map(&map).unwrap();
+
+    let mut invalid = write::elf::BbAddrMap::new();
+    invalid.add_function(func, vec![block(0x10, 8, 0), block(0x14, 8, 0)]);
+    assert!(object.add_elf_bb_addr_map(&invalid).is_err());
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".llvm_bb_addr_map").unwrap();
+    assert_eq!(section.flags(), read::SectionFlags::Elf { sh_flags: 0 });
+    assert_eq!(section.kind(), SectionKind::Elf(elf::SHT_LLVM_BB_ADDR_MAP));
+
+    let mut relocations = section.relocations();
+    let (offset, relocation) = relocations.next().unwrap();
+    assert_eq!(offset, 2);
+    assert_eq!(relocation.kind(), RelocationKind::Absolute);
+    assert_eq!(relocation.size(), 64);
+    let symbol = object.symbol_by_name("func").unwrap();
+    assert_eq!(
+        relocation.target(),
+        read::RelocationTarget::Symbol(symbol.index())
+    );
+    assert!(relocations.next().is_none());
+
+    let data = section.data().unwrap();
+    // Version and features.
+    assert_eq!(&data[..2], &[2, 0]);
+    let mut data = &data[10..];
+    assert_eq!(read_uleb128(&mut data), 3);
+    let mut blocks = Vec::new();
+    let mut end = 0;
+    for _ in 0..3 {
+        let id = read_uleb128(&mut data);
+        let offset = end + read_uleb128(&mut data);
+        let size = read_uleb128(&mut data);
+        let metadata = read_uleb128(&mut data);
+        end = offset + size;
+        blocks.push((id, offset, size, metadata));
+    }
+    assert!(data.is_empty());
+    assert_eq!(
+        blocks,
+        [(0, 0, 8, 0), (1, 0x10, 0x100, 4), (2, 0x110, 0xe0, 1)]
+    );
+
+    // The address size must be known.
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::Unknown, Endianness::Little);
+    assert!(object
+        .add_elf_bb_addr_map(&write::elf::BbAddrMap::new())
+        .is_err());
+}
+
+#[test]
+fn split_debug() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_symbol_data(func, text, &[0xc3; 16], 16);
+    let debug_info = object.add_section(Vec::new(), b".debug_info".to_vec(), SectionKind::Debug);
+    object.append_section_data(debug_info, &[0; 16], 1);
+    object
+        .add_relocation(
+            debug_info,
+            write::Relocation {
+                offset: 8,
+                symbol: func,
+                addend: 0,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: 64,
+                },
+            },
+        )
+        .unwrap();
+    let debug_str =
+        object.add_section(Vec::new(), b".debug_str".to_vec(), SectionKind::DebugString);
+    object.append_section_data(debug_str, b"func\0", 1);
+    object
+        .add_build_id(write::BuildIdStyle::FromContents)
+        .unwrap();
+
+    let (stripped, debug) = object.emit_split_debug_with_name(b"test.debug").unwrap();
+
+    let stripped = read::File::parse(&*stripped).unwrap();
+    assert!(stripped.section_by_name(".debug_info").is_none());
+    assert!(stripped.section_by_name(".debug_str").is_none());
+    let text = stripped.section_by_name(".text").unwrap();
+    assert_eq!(text.data().unwrap(), &[0xc3; 16]);
+    assert_eq!(
+        stripped.symbol_by_name("func").unwrap().section_index(),
+        Some(text.index())
+    );
+    let (name, crc) = stripped.gnu_debuglink().unwrap().unwrap();
+    assert_eq!(name, b"test.debug");
+    assert_eq!(crc, crc32(&debug));
+
+    let debug = read::File::parse(&*debug).unwrap();
+    assert_eq!(debug.build_id().unwrap(), stripped.build_id().unwrap());
+    let debug_info = debug.section_by_name(".debug_info").unwrap();
+    assert_eq!(debug_info.relocations().count(), 1);
+    assert_eq!(
+        debug.section_by_name(".debug_str").unwrap().data().unwrap(),
+        b"func\0"
+    );
+    // The text section keeps its size and flags, but not its contents.
+    let text = debug.section_by_name(".text").unwrap();
+    assert_eq!(text.size(), 16);
+    assert_eq!(text.kind(), SectionKind::UninitializedData);
+    assert_eq!(
+        text.flags(),
+        object::SectionFlags::Elf {
+            sh_flags: u64::from(elf::SHF_ALLOC | elf::SHF_EXECINSTR)
+        }
+    );
+    assert!(debug.symbol_by_name("func").is_some());
+}
+
+#[test]
+fn split_debug_uuid() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 16], 16);
+    let debug_info = object.add_section(Vec::new(), b".debug_info".to_vec(), SectionKind::Debug);
+    object.append_section_data(debug_info, &[0; 16], 1);
+    object.add_build_id(write::BuildIdStyle::Uuid).unwrap();
+
+    let (stripped, debug) = object.emit_split_debug().unwrap();
+    let stripped = read::File::parse(&*stripped).unwrap();
+    let debug = read::File::parse(&*debug).unwrap();
+    let build_id = stripped.build_id().unwrap().unwrap();
+    assert_eq!(build_id.len(), 16);
+    assert_eq!(build_id[6] >> 4, 5);
+    assert_eq!(debug.build_id().unwrap().unwrap(), build_id);
+    // The default debug file name is derived from the build ID.
+    let hex = |id: &[u8]| id.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    let (name, _) = stripped.gnu_debuglink().unwrap().unwrap();
+    assert_eq!(name, format!("{}.debug", hex(build_id)).as_bytes());
+
+    // Without a build ID, the default name is derived from the content hash.
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 16], 16);
+    let (stripped, _) = object.emit_split_debug().unwrap();
+    let stripped = read::File::parse(&*stripped).unwrap();
+    let (name, _) = stripped.gnu_debuglink().unwrap().unwrap();
+    assert_eq!(
+        name,
+        format!("{}.debug", hex(&object.content_hash())).as_bytes()
+    );
+}
+
+#[test]
+fn relocation_overflow() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 8], 4);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"target".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let relocation = |addend| write::Relocation {
+        offset: 6,
+        symbol,
+        addend,
+        flags: RelocationFlags::Generic {
+            kind: RelocationKind::Absolute,
+            encoding: RelocationEncoding::Generic,
+            size: 16,
+        },
+    };
+
+    // Both signed and unsigned values are accepted.
+    object.add_relocation(data, relocation(-0x8000)).unwrap();
+    object.add_relocation(data, relocation(0xffff)).unwrap();
+
+    let error = object
+        .add_relocation(data, relocation(0x12345))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("`.data`"), "{}", error);
+    assert!(error.contains("offset 0x6"), "{}", error);
+    assert!(error.contains("symbol `target`"), "{}", error);
+    assert!(
+        error.contains("value 74565 does not fit in 16 bits"),
+        "{}",
+        error
+    );
+    assert!(object.add_relocation(data, relocation(-0x8001)).is_err());
+}
+
+#[test]
+fn core_file() {
+    use object::read::elf::ProgramHeader;
+    use object::write::elf::{CoreNote, CorePrPsInfo, CorePrStatus};
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let status = CorePrStatus {
+        signal: 11,
+        pid: 100,
+        registers: (0..27).collect(),
+        ..Default::default()
+    };
+    assert!(object
+        .add_core_note(CoreNote::PrStatus(status.clone()))
+        .is_err());
+    object.set_elf_core(true).unwrap();
+    assert!(object
+        .add_core_note(CoreNote::PrStatus(CorePrStatus {
+            registers: vec![0; 26],
+            ..status.clone()
+        }))
+        .is_err());
+    object.add_core_note(CoreNote::PrStatus(status)).unwrap();
+    object
+        .add_core_note(CoreNote::PrPsInfo(CorePrPsInfo {
+            sname: b'R',
+            pid: 100,
+            fname: b"crash".to_vec(),
+            psargs: b"crash --now".to_vec(),
+            ..Default::default()
+        }))
+        .unwrap();
+
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[1; 16], 16);
+    object.set_section_address(data, 0x1000).unwrap();
+
+    let bytes = object.write().unwrap();
+    let header = elf::FileHeader64::<LittleEndian>::parse(&*bytes).unwrap();
+    let endian = header.endian().unwrap();
+    assert_eq!(header.e_type(endian), elf::ET_CORE);
+
+    let segments = header.program_headers(endian, &*bytes).unwrap();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].p_type(endian), elf::PT_NOTE);
+    let mut notes = segments[0].notes(endian, &*bytes).unwrap().unwrap();
+
+    let note = notes.next().unwrap().unwrap();
+    assert_eq!(note.name(), b"CORE");
+    assert_eq!(note.n_type(endian), elf::NT_PRSTATUS);
+    let desc = note.desc();
+    assert_eq!(desc.len(), 336);
+    assert_eq!(&desc[0..4], &11u32.to_le_bytes());
+    assert_eq!(&desc[12..14], &11u16.to_le_bytes());
+    assert_eq!(&desc[32..36], &100u32.to_le_bytes());
+    assert_eq!(&desc[112..120], &0u64.to_le_bytes());
+    assert_eq!(&desc[320..328], &26u64.to_le_bytes());
+
+    let note = notes.next().unwrap().unwrap();
+    assert_eq!(note.name(), b"CORE");
+    assert_eq!(note.n_type(endian), elf::NT_PRPSINFO);
+    let desc = note.desc();
+    assert_eq!(desc.len(), 136);
+    assert_eq!(desc[1], b'R');
+    assert_eq!(&desc[24..28], &100u32.to_le_bytes());
+    assert_eq!(&desc[40..56], b"crash\0\0\0\0\0\0\0\0\0\0\0");
+    assert_eq!(&desc[56..67], b"crash --now");
+    assert!(notes.next().unwrap().is_none());
+
+    let load = &segments[1];
+    assert_eq!(load.p_type(endian), elf::PT_LOAD);
+    assert_eq!(load.p_flags(endian), elf::PF_R | elf::PF_W);
+    assert_eq!(load.p_vaddr(endian), 0x1000);
+    assert_eq!(load.p_memsz(endian), 16);
+    assert_eq!(load.data(endian, &*bytes).unwrap(), &[1; 16]);
+    let align = load.p_align(endian);
+    assert_eq!(load.p_offset(endian) % align, load.p_vaddr(endian) % align);
+
+    // The file offset can't be congruent to an address that isn't aligned.
+    let mut misaligned = object.clone();
+    misaligned.set_section_address(data, 0x1008).unwrap();
+    assert!(misaligned.write().is_err());
+
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.kind(), object::ObjectKind::Core);
+    let data = object.section_by_name(".data").unwrap();
+    assert_eq!(data.address(), 0x1000);
+}
+
+#[test]
+fn core_note_sizes() {
+    use object::read::elf::ProgramHeader;
+    use object::write::elf::{CoreNote, CorePrPsInfo, CorePrStatus};
+
+    fn note_sizes<Elf: FileHeader<Endian = Endianness>>(data: &[u8]) -> Vec<usize> {
+        let header = Elf::parse(data).unwrap();
+        let endian = header.endian().unwrap();
+        let segment = &header.program_headers(endian, data).unwrap()[0];
+        let mut notes = segment.notes(endian, data).unwrap().unwrap();
+        let mut sizes = Vec::new();
+        while let Some(note) = notes.next().unwrap() {
+            sizes.push(note.desc().len());
+        }
+        sizes
+    }
+
+    for (arch, num_registers, prstatus_size, prpsinfo_size) in [
+        (Architecture::I386, 17, 144, 124),
+        (Architecture::Arm, 18, 148, 124),
+        (Architecture::Aarch64, 34, 392, 136),
+        (Architecture::Riscv64, 32, 376, 136),
+    ] {
+        let mut object = write::Object::new(BinaryFormat::Elf, arch, Endianness::Little);
+        object.set_elf_core(true).unwrap();
+        object
+            .add_core_note(CoreNote::PrStatus(CorePrStatus {
+                registers: vec![0; num_registers],
+                ..Default::default()
+            }))
+            .unwrap();
+        object
+            .add_core_note(CoreNote::PrPsInfo(CorePrPsInfo::default()))
+            .unwrap();
+
+        let bytes = object.write().unwrap();
+        let sizes = match arch.address_size().unwrap() {
+            object::AddressSize::U64 => note_sizes::<elf::FileHeader64<Endianness>>(&bytes),
+            _ => note_sizes::<elf::FileHeader32<Endianness>>(&bytes),
+        };
+        assert_eq!(sizes, [prstatus_size, prpsinfo_size], "{:?}", arch);
+    }
+}
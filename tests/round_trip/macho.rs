@@ -1,6 +1,9 @@
 use object::read::macho::MachHeader;
-use object::read::{Object, ObjectSection};
-use object::{macho, read, write, Architecture, BinaryFormat, Endianness};
+use object::read::{Object, ObjectSection, ObjectSymbol};
+use object::{
+    macho, read, write, Architecture, BinaryFormat, Endianness, SymbolFlags, SymbolKind,
+    SymbolScope,
+};
 
 // Test that segment size is valid when the first section needs alignment.
 #[test]
@@ -62,3 +65,923 @@ fn issue_552_section_file_alignment() {
     assert_eq!(section.address(), 32);
     assert_eq!(section.size(), 1);
 }
+
+#[test]
+fn section_by_name() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    let other = object.add_section(
+        b"__OTHER".to_vec(),
+        b"__text".to_vec(),
+        object::SectionKind::Text,
+    );
+
+    assert_eq!(object.section_by_name(b"__text"), Some(text));
+    assert_eq!(object.section_by_name(b"__data"), None);
+    assert_eq!(
+        object.section_by_segment_and_name(b"__TEXT", b"__text"),
+        Some(text)
+    );
+    assert_eq!(
+        object.section_by_segment_and_name(b"__OTHER", b"__text"),
+        Some(other)
+    );
+    assert_eq!(
+        object.section_by_segment_and_name(b"__DATA", b"__text"),
+        None
+    );
+}
+
+#[test]
+fn dwarf_sections() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::Aarch64,
+        Endianness::Little,
+    );
+    let info = object.add_dwarf_section(b".debug_info");
+    assert_eq!(object.add_dwarf_section(b".debug_info"), info);
+    object.append_section_data(info, &[1; 4], 1);
+    let str_offsets = object.add_dwarf_section(b".debug_str_offsets");
+    object.append_section_data(str_offsets, &[2; 4], 1);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name("__debug_info").unwrap();
+    assert_eq!(section.segment_name().unwrap(), Some("__DWARF"));
+    assert_eq!(section.kind(), object::SectionKind::Debug);
+    assert_eq!(section.data().unwrap(), &[1; 4]);
+    let section = object.section_by_name("__debug_str_offs").unwrap();
+    assert_eq!(section.segment_name().unwrap(), Some("__DWARF"));
+    assert_eq!(section.data().unwrap(), &[2; 4]);
+}
+
+#[test]
+fn rename_symbol() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let symbol = |name: &[u8]| write::Symbol {
+        name: name.to_vec(),
+        value: 0,
+        size: 0,
+        kind: object::SymbolKind::Data,
+        scope: object::SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: object::SymbolFlags::None,
+    };
+    let foo = object.add_symbol(symbol(b"foo"));
+    let baz = object.add_symbol(symbol(b"baz"));
+
+    object.rename_symbol(foo, b"bar".to_vec()).unwrap();
+    assert_eq!(object.symbol(foo).name, b"_bar");
+    assert_eq!(object.symbol_id(b"bar"), Some(foo));
+    assert_eq!(object.symbol_id(b"foo"), None);
+
+    assert!(object.rename_symbol(baz, b"bar".to_vec()).is_err());
+    assert_eq!(object.symbol_id(b"baz"), Some(baz));
+
+    let data = object.section_id(write::StandardSection::Data);
+    let section_symbol = object.section_symbol(data);
+    assert!(object
+        .rename_symbol(section_symbol, b"section".to_vec())
+        .is_err());
+}
+
+#[test]
+fn dynamic_scope() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 3], 1);
+    let mut add = |name: &[u8], value, scope, section| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 1,
+            kind: SymbolKind::Text,
+            scope,
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        })
+    };
+    let section = write::SymbolSection::Section(text);
+    let exported = add(b"exported", 0, SymbolScope::Dynamic, section);
+    add(b"hidden", 1, SymbolScope::Linkage, section);
+    add(b"local", 2, SymbolScope::Compilation, section);
+    add(
+        b"imported",
+        0,
+        SymbolScope::Dynamic,
+        write::SymbolSection::Undefined,
+    );
+    let ids: Vec<_> = object.exported_symbols().map(|(id, _)| id).collect();
+    assert_eq!(ids, [exported]);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let scope = |name| object.symbol_by_name(name).unwrap().scope();
+    assert_eq!(scope("_exported"), SymbolScope::Dynamic);
+    assert_eq!(scope("_hidden"), SymbolScope::Linkage);
+    assert_eq!(scope("_local"), SymbolScope::Compilation);
+}
+
+#[test]
+fn remangle_existing() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    object.set_mangling(write::Mangling::None);
+    let text = object.section_id(write::StandardSection::Text);
+    let mut add = |name: &[u8], kind| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        })
+    };
+    let foo = add(b"foo", SymbolKind::Text);
+    // Already starts with the prefix, but it is part of the unmangled name.
+    let underscore = add(b"_bar", SymbolKind::Data);
+    let label = add(b"label", SymbolKind::Label);
+    let section = object.section_symbol(text);
+
+    object.remangle_existing(write::Mangling::MachO);
+    assert_eq!(object.symbol(foo).name, b"_foo");
+    assert_eq!(object.symbol(underscore).name, b"__bar");
+    assert_eq!(object.symbol(label).name, b"label");
+    assert!(object.symbol(section).name.is_empty());
+    assert_eq!(object.symbol_id(b"foo"), Some(foo));
+    assert_eq!(object.symbol_id(b"_bar"), Some(underscore));
+
+    // Symbols added later use the new mangling.
+    let baz = object.add_symbol(write::Symbol {
+        name: b"baz".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    assert_eq!(object.symbol(baz).name, b"_baz");
+
+    object.remangle_existing(write::Mangling::None);
+    assert_eq!(object.symbol(foo).name, b"foo");
+    assert_eq!(object.symbol(underscore).name, b"_bar");
+    assert_eq!(object.symbol(baz).name, b"baz");
+}
+
+#[test]
+fn init_fini_array() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    for standard_section in [
+        write::StandardSection::InitArray,
+        write::StandardSection::FiniArray,
+    ] {
+        let section = object.section_id(standard_section);
+        object.append_section_data(section, &[0; 8], 8);
+    }
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    for (name, flags) in [
+        ("__mod_init_func", macho::S_MOD_INIT_FUNC_POINTERS),
+        ("__mod_term_func", macho::S_MOD_TERM_FUNC_POINTERS),
+    ] {
+        let section = object.section_by_name(name).unwrap();
+        assert_eq!(section.segment_name().unwrap(), Some("__DATA"));
+        assert_eq!(section.flags(), object::SectionFlags::MachO { flags });
+    }
+}
+
+#[test]
+fn split_info() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0xc3; 0x10], 4);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 0x300], 8);
+    let entry = |kind, section, offset| write::MachOSplitInfo {
+        kind,
+        section,
+        offset,
+    };
+    assert!(object
+        .set_macho_split_info(vec![entry(0, data, 0)])
+        .is_err());
+    assert!(object
+        .set_macho_split_info(vec![entry(2, data, 0x300)])
+        .is_err());
+    object
+        .set_macho_split_info(vec![
+            entry(2, data, 0x200),
+            entry(1, text, 4),
+            entry(2, data, 0),
+            entry(2, data, 8),
+        ])
+        .unwrap();
+
+    let bytes = &*object.write().unwrap();
+    let file = read::File::parse(bytes).unwrap();
+    let data_address = file.section_by_name("__data").unwrap().address();
+    assert_eq!(data_address, 0x10);
+
+    let header = macho::MachHeader64::parse(bytes, 0).unwrap();
+    let endian: Endianness = header.endian().unwrap();
+    let mut commands = header.load_commands(endian, bytes, 0).unwrap();
+    let mut split_info = None;
+    while let Some(command) = commands.next().unwrap() {
+        if command.cmd() == macho::LC_SEGMENT_SPLIT_INFO {
+            split_info = Some(
+                command
+                    .data::<macho::LinkeditDataCommand<Endianness>>()
+                    .unwrap(),
+            );
+        }
+    }
+    let split_info = split_info.unwrap();
+    let offset = split_info.dataoff.get(endian) as usize;
+    let size = split_info.datasize.get(endian) as usize;
+    assert_eq!(size % 8, 0);
+    let mut stream = &bytes[offset..][..size];
+
+    // Decode the stream back into (kind, address) pairs.
+    let read_uleb128 = |stream: &mut &[u8]| {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = stream[0];
+            *stream = &stream[1..];
+            value |= u64::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+        }
+    };
+    let mut entries = Vec::new();
+    loop {
+        let kind = stream[0];
+        stream = &stream[1..];
+        if kind == 0 {
+            break;
+        }
+        let mut address = 0;
+        loop {
+            let delta = read_uleb128(&mut stream);
+            if delta == 0 {
+                break;
+            }
+            address += delta;
+            entries.push((kind, address));
+        }
+    }
+    assert!(stream.iter().all(|&byte| byte == 0));
+    assert_eq!(
+        entries,
+        [
+            (1, 4),
+            (2, data_address),
+            (2, data_address + 8),
+            (2, data_address + 0x200),
+        ]
+    );
+}
+
+#[test]
+fn data_in_code() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::Aarch64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 0x20], 4);
+    let text2 = object.add_section(
+        b"__TEXT".to_vec(),
+        b"__text2".to_vec(),
+        object::SectionKind::Text,
+    );
+    object.append_section_data(text2, &[0; 0x10], 4);
+    let kind = write::MachODataInCodeKind::JumpTable32;
+    assert!(object.add_macho_data_in_code(text, 0x10, 0, kind).is_err());
+    assert!(object
+        .add_macho_data_in_code(text, 0x10, 0x11, kind)
+        .is_err());
+    object
+        .add_macho_data_in_code(text2, 4, 8, write::MachODataInCodeKind::Data)
+        .unwrap();
+    object
+        .add_macho_data_in_code(text, 0x10, 0x10, kind)
+        .unwrap();
+    object
+        .add_macho_data_in_code(text, 0, 2, write::MachODataInCodeKind::JumpTable8)
+        .unwrap();
+
+    let bytes = &*object.write().unwrap();
+    let file = read::File::parse(bytes).unwrap();
+    let text2_address = file.section_by_name("__text2").unwrap().address();
+    assert_eq!(text2_address, 0x20);
+
+    let header = macho::MachHeader64::parse(bytes, 0).unwrap();
+    let endian: Endianness = header.endian().unwrap();
+    let mut commands = header.load_commands(endian, bytes, 0).unwrap();
+    let mut data_in_code = None;
+    while let Some(command) = commands.next().unwrap() {
+        if command.cmd() == macho::LC_DATA_IN_CODE {
+            data_in_code = Some(
+                command
+                    .data::<macho::LinkeditDataCommand<Endianness>>()
+                    .unwrap(),
+            );
+        }
+    }
+    let data_in_code = data_in_code.unwrap();
+    let offset = data_in_code.dataoff.get(endian) as usize;
+    let size = data_in_code.datasize.get(endian) as usize;
+    let entries = object::pod::slice_from_all_bytes::<macho::DataInCodeEntry<Endianness>>(
+        &bytes[offset..][..size],
+    )
+    .unwrap();
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.offset.get(endian),
+                entry.length.get(endian),
+                u32::from(entry.kind.get(endian)),
+            )
+        })
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            (0, 2, macho::DICE_KIND_JUMP_TABLE8),
+            (0x10, 0x10, macho::DICE_KIND_JUMP_TABLE32),
+            (text2_address as u32 + 4, 8, macho::DICE_KIND_DATA),
+        ]
+    );
+}
+
+#[test]
+fn alt_entry() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::Aarch64,
+        Endianness::Little,
+    );
+    object.set_subsections_via_symbols();
+    let text = object.section_id(write::StandardSection::Text);
+    let add_symbol = |object: &mut write::Object, name: &[u8]| {
+        let symbol = object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.add_symbol_data(symbol, text, &[0; 8], 4);
+        symbol
+    };
+    add_symbol(&mut object, b"func");
+    let alt = add_symbol(&mut object, b"func_alt");
+    let undefined = object.add_symbol(write::Symbol {
+        name: b"undefined".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    assert!(object.set_macho_alt_entry(undefined).is_err());
+    object.set_macho_alt_entry(alt).unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let header = macho::MachHeader64::<Endianness>::parse(&*bytes, 0).unwrap();
+    assert_ne!(
+        header.flags(Endianness::Little) & macho::MH_SUBSECTIONS_VIA_SYMBOLS,
+        0
+    );
+    let text = object.section_by_name("__text").unwrap();
+    let func = object.symbol_by_name("_func").unwrap();
+    let alt = object.symbol_by_name("_func_alt").unwrap();
+    assert_eq!(func.section_index(), Some(text.index()));
+    assert_eq!(alt.section_index(), Some(text.index()));
+    assert_eq!((func.address(), alt.address()), (0, 8));
+    assert_eq!(func.flags(), object::SymbolFlags::MachO { n_desc: 0 });
+    assert_eq!(
+        alt.flags(),
+        object::SymbolFlags::MachO {
+            n_desc: macho::N_ALT_ENTRY
+        }
+    );
+}
+
+#[test]
+fn section_attributes() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::Aarch64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 4], 4);
+    let metadata = object.add_section(
+        b"__DATA".to_vec(),
+        b"__swift5_types".to_vec(),
+        object::SectionKind::ReadOnlyData,
+    );
+    object.append_section_data(metadata, &[0; 4], 4);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 4], 4);
+
+    assert!(object
+        .add_macho_section_attributes(metadata, macho::S_CSTRING_LITERALS)
+        .is_err());
+    object
+        .add_macho_section_attributes(metadata, macho::S_ATTR_NO_DEAD_STRIP)
+        .unwrap();
+    object
+        .add_macho_section_attributes(metadata, macho::S_ATTR_LIVE_SUPPORT)
+        .unwrap();
+    object
+        .add_macho_section_attributes(text, macho::S_ATTR_NO_DEAD_STRIP)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let flags = |name| match object.section_by_name(name).unwrap().flags() {
+        object::SectionFlags::MachO { flags } => flags,
+        flags => panic!("{:?}", flags),
+    };
+    assert_eq!(
+        flags("__swift5_types"),
+        macho::S_REGULAR | macho::S_ATTR_NO_DEAD_STRIP | macho::S_ATTR_LIVE_SUPPORT
+    );
+    assert_eq!(
+        flags("__text"),
+        macho::S_ATTR_PURE_INSTRUCTIONS
+            | macho::S_ATTR_SOME_INSTRUCTIONS
+            | macho::S_ATTR_NO_DEAD_STRIP
+    );
+    // The default is unchanged.
+    assert_eq!(flags("__data"), macho::S_REGULAR);
+}
+
+#[test]
+fn compact_unwind() {
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    let mut add_symbol = |name: &[u8], section| {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        })
+    };
+    let f1 = add_symbol(b"f1", write::SymbolSection::Undefined);
+    let f2 = add_symbol(b"f2", write::SymbolSection::Undefined);
+    let personality = add_symbol(b"__gxx_personality_v0", write::SymbolSection::Undefined);
+    let lsda = add_symbol(b"lsda", write::SymbolSection::Undefined);
+    object.add_symbol_data(f1, text, &[0xc3; 4], 16);
+    object.add_symbol_data(f2, text, &[0xc3; 8], 16);
+    let data = object.section_id(write::StandardSection::ReadOnlyData);
+    object.add_symbol_data(lsda, data, &[0xff; 4], 4);
+
+    assert_eq!(
+        object
+            .add_macho_compact_unwind(f1, 4, 0x0100_0000, None, None)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        object
+            .add_macho_compact_unwind(f2, 8, 0x4100_0000, Some(personality), Some(lsda))
+            .unwrap(),
+        32
+    );
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name("__compact_unwind").unwrap();
+    assert_eq!(section.segment_name().unwrap(), Some("__LD"));
+    assert_eq!(
+        section.flags(),
+        object::SectionFlags::MachO {
+            flags: macho::S_ATTR_DEBUG
+        }
+    );
+    let data = section.data().unwrap();
+    assert_eq!(data.len(), 64);
+    assert_eq!(&data[8..16], &[4, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(&data[16..32], &[0; 16]);
+    assert_eq!(&data[40..48], &[8, 0, 0, 0, 0, 0, 0, 0x41]);
+
+    let mut relocations: Vec<_> = section
+        .relocations()
+        .map(|(offset, relocation)| {
+            let read::RelocationTarget::Symbol(symbol) = relocation.target() else {
+                panic!();
+            };
+            let symbol = object.symbol_by_index(symbol).unwrap();
+            (
+                offset,
+                relocation.size(),
+                symbol.name().unwrap().to_string(),
+            )
+        })
+        .collect();
+    relocations.sort();
+    assert_eq!(
+        relocations,
+        [
+            (0, 64, "_f1".to_string()),
+            (32, 64, "_f2".to_string()),
+            (48, 64, "___gxx_personality_v0".to_string()),
+            (56, 64, "_lsda".to_string()),
+        ]
+    );
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    let symbol = object.section_symbol(text);
+    assert!(object
+        .add_macho_compact_unwind(symbol, 0, 0, None, None)
+        .is_err());
+
+    // The address size must be known.
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::Unknown,
+        Endianness::Little,
+    );
+    let text = object.section_id(write::StandardSection::Text);
+    let symbol = object.section_symbol(text);
+    assert!(object
+        .add_macho_compact_unwind(symbol, 0, 0, None, None)
+        .is_err());
+    assert!(object
+        .section_by_segment_and_name(b"__LD", b"__compact_unwind")
+        .is_none());
+}
+
+#[test]
+fn version_min() {
+    let write = |platform, version_min| {
+        let mut object = write::Object::new(
+            BinaryFormat::MachO,
+            Architecture::X86_64,
+            Endianness::Little,
+        );
+        let mut build_version = write::MachOBuildVersion::default();
+        build_version.platform = platform;
+        build_version.minos = write::MachOBuildVersion::encode_version(10, 9, 0);
+        build_version.sdk = write::MachOBuildVersion::encode_version(10, 13, 2);
+        object.set_macho_build_version(build_version);
+        object.set_macho_version_min(version_min);
+        object.write()
+    };
+    assert_eq!(
+        write::MachOBuildVersion::encode_version(10, 9, 0),
+        0x000a_0900
+    );
+
+    let bytes = write(macho::PLATFORM_MACOS, true).unwrap();
+    let header = macho::MachHeader64::<Endianness>::parse(&*bytes, 0).unwrap();
+    let endian = header.endian().unwrap();
+    let mut commands = header.load_commands(endian, &*bytes, 0).unwrap();
+    let mut version_min = None;
+    while let Some(command) = commands.next().unwrap() {
+        assert_ne!(command.cmd(), macho::LC_BUILD_VERSION);
+        if command.cmd() == macho::LC_VERSION_MIN_MACOSX {
+            version_min = Some(
+                command
+                    .data::<macho::VersionMinCommand<Endianness>>()
+                    .unwrap(),
+            );
+        }
+    }
+    let version_min = version_min.unwrap();
+    assert_eq!(version_min.cmdsize.get(endian), 16);
+    assert_eq!(version_min.version.get(endian), 0x000a_0900);
+    assert_eq!(version_min.sdk.get(endian), 0x000a_0d02);
+
+    let bytes = write(macho::PLATFORM_IOSSIMULATOR, true).unwrap();
+    let header = macho::MachHeader64::<Endianness>::parse(&*bytes, 0).unwrap();
+    let mut commands = header.load_commands(endian, &*bytes, 0).unwrap();
+    let mut found = false;
+    while let Some(command) = commands.next().unwrap() {
+        found |= command.cmd() == macho::LC_VERSION_MIN_IPHONEOS;
+    }
+    assert!(found);
+
+    // The build version is used by default.
+    let bytes = write(macho::PLATFORM_MACOS, false).unwrap();
+    let header = macho::MachHeader64::<Endianness>::parse(&*bytes, 0).unwrap();
+    let mut commands = header.load_commands(endian, &*bytes, 0).unwrap();
+    let mut build_version = None;
+    while let Some(command) = commands.next().unwrap() {
+        if let Some(command) = command.build_version().unwrap() {
+            build_version = Some(command);
+        }
+    }
+    assert_eq!(build_version.unwrap().minos.get(endian), 0x000a_0900);
+
+    assert!(write(macho::PLATFORM_DRIVERKIT, true).is_err());
+}
+
+#[test]
+fn encryption_info() {
+    for architecture in [Architecture::Aarch64, Architecture::Arm] {
+        let mut object = write::Object::new(BinaryFormat::MachO, architecture, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0; 0x3000], 4);
+        assert!(object.set_macho_encryption_info(0x1001, 0x1000, 0).is_err());
+        assert!(object.set_macho_encryption_info(0x1000, 0x1001, 0).is_err());
+
+        // The region must be within the file.
+        object.set_macho_encryption_info(0x1000, 0x4000, 0).unwrap();
+        assert!(object.write().is_err());
+
+        object.set_macho_encryption_info(0x1000, 0x2000, 0).unwrap();
+        let bytes = object.write().unwrap();
+        let file = read::File::parse(&*bytes).unwrap();
+        assert_eq!(file.architecture(), architecture);
+
+        let mut found = false;
+        let endian = Endianness::Little;
+        let mut check = |cmd: u32, data: &[u8]| {
+            if cmd == macho::LC_ENCRYPTION_INFO || cmd == macho::LC_ENCRYPTION_INFO_64 {
+                let command =
+                    object::pod::from_bytes::<macho::EncryptionInfoCommand32<Endianness>>(data)
+                        .unwrap()
+                        .0;
+                assert_eq!(command.cryptoff.get(endian), 0x1000);
+                assert_eq!(command.cryptsize.get(endian), 0x2000);
+                assert_eq!(command.cryptid.get(endian), 0);
+                let expect = match architecture {
+                    Architecture::Aarch64 => (macho::LC_ENCRYPTION_INFO_64, 24),
+                    _ => (macho::LC_ENCRYPTION_INFO, 20),
+                };
+                assert_eq!((cmd, command.cmdsize.get(endian)), expect);
+                found = true;
+            }
+        };
+        if architecture == Architecture::Aarch64 {
+            let header = macho::MachHeader64::<Endianness>::parse(&*bytes, 0).unwrap();
+            let mut commands = header.load_commands(endian, &*bytes, 0).unwrap();
+            while let Some(command) = commands.next().unwrap() {
+                check(command.cmd(), command.raw_data());
+            }
+        } else {
+            let header = macho::MachHeader32::<Endianness>::parse(&*bytes, 0).unwrap();
+            let mut commands = header.load_commands(endian, &*bytes, 0).unwrap();
+            while let Some(command) = commands.next().unwrap() {
+                check(command.cmd(), command.raw_data());
+            }
+        }
+        assert!(found);
+    }
+}
+
+#[test]
+fn fat() {
+    use object::read::macho::{FatArch, MachOFatFile32};
+
+    let mut objects = Vec::new();
+    for architecture in [Architecture::X86_64, Architecture::Aarch64] {
+        let mut object = write::Object::new(BinaryFormat::MachO, architecture, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0xc3; 16], 4);
+        objects.push(object);
+    }
+    let refs: Vec<_> = objects.iter().collect();
+    let bytes = write::Object::write_macho_fat(&refs).unwrap();
+
+    let fat = MachOFatFile32::parse(&*bytes).unwrap();
+    let arches = fat.arches();
+    assert_eq!(arches.len(), 2);
+    for (arch, object) in arches.iter().zip(objects.iter()) {
+        assert_eq!(arch.architecture(), object.architecture());
+        assert_eq!(arch.align(), 12);
+        assert_eq!(arch.offset() % 0x1000, 0);
+        let data = arch.data(&*bytes).unwrap();
+        assert_eq!(data, &*object.write().unwrap());
+        let file = read::File::parse(data).unwrap();
+        assert_eq!(file.architecture(), object.architecture());
+    }
+
+    let duplicate = [&objects[0], &objects[0]];
+    assert!(write::Object::write_macho_fat(&duplicate).is_err());
+    assert!(write::Object::write_macho_fat(&[]).is_err());
+    let elf = write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    assert!(write::Object::write_macho_fat(&[&objects[0], &elf]).is_err());
+}
+
+#[test]
+fn indirect_symbols() {
+    use object::read::macho::MachOFile64;
+
+    let mut object = write::Object::new(
+        BinaryFormat::MachO,
+        Architecture::X86_64,
+        Endianness::Little,
+    );
+    let foo = object.add_symbol(write::Symbol {
+        name: b"foo".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let bar = object.add_symbol(write::Symbol {
+        name: b"bar".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+
+    let got = object.add_section(
+        b"__DATA".to_vec(),
+        b"__got".to_vec(),
+        object::SectionKind::Data,
+    );
+    object.section_mut(got).flags = object::SectionFlags::MachO {
+        flags: macho::S_NON_LAZY_SYMBOL_POINTERS,
+    };
+    object.append_section_data(got, &[0; 16], 8);
+    object
+        .set_macho_indirect_symbols(got, vec![bar, foo], 0)
+        .unwrap();
+
+    let stubs = object.add_section(
+        b"__TEXT".to_vec(),
+        b"__stubs".to_vec(),
+        object::SectionKind::Text,
+    );
+    object.section_mut(stubs).flags = object::SectionFlags::MachO {
+        flags: macho::S_SYMBOL_STUBS
+            | macho::S_ATTR_PURE_INSTRUCTIONS
+            | macho::S_ATTR_SOME_INSTRUCTIONS,
+    };
+    object.append_section_data(stubs, &[0xcc; 6], 2);
+    object
+        .set_macho_indirect_symbols(stubs, vec![foo], 6)
+        .unwrap();
+
+    let bytes = object.write().unwrap();
+    let file = MachOFile64::<Endianness>::parse(&*bytes).unwrap();
+    let endian = file.endian();
+
+    let symbols: Vec<_> = file.symbols().map(|s| s.name().unwrap()).collect();
+    let dysymtab = file
+        .macho_load_commands()
+        .unwrap()
+        .find_map(|command| command.unwrap().dysymtab().unwrap())
+        .unwrap();
+    let indirect = object::pod::slice_from_bytes::<object::U32<Endianness>>(
+        &bytes[dysymtab.indirectsymoff.get(endian) as usize..],
+        dysymtab.nindirectsyms.get(endian) as usize,
+    )
+    .unwrap()
+    .0;
+    let indirect: Vec<_> = indirect
+        .iter()
+        .map(|index| symbols[index.get(endian) as usize])
+        .collect();
+    assert_eq!(indirect, ["_bar", "_foo", "_foo"]);
+
+    let section = file.section_by_name("__got").unwrap();
+    let section = section.macho_section();
+    assert_eq!(section.reserved1.get(endian), 0);
+    assert_eq!(section.reserved2.get(endian), 0);
+    let section = file.section_by_name("__stubs").unwrap();
+    let section = section.macho_section();
+    assert_eq!(section.reserved1.get(endian), 2);
+    assert_eq!(section.reserved2.get(endian), 6);
+
+    // The section size must match the number of indirect symbols.
+    object.append_section_data(stubs, &[0xcc; 6], 2);
+    assert!(object.write().is_err());
+}
+
+#[test]
+fn eh_frame_personality() {
+    for (architecture, r_type, implicit_addend) in [
+        (Architecture::X86_64, macho::X86_64_RELOC_GOT, 4),
+        (Architecture::Aarch64, macho::ARM64_RELOC_POINTER_TO_GOT, 0),
+    ] {
+        let mut object = write::Object::new(BinaryFormat::MachO, architecture, Endianness::Little);
+        let personality = object.add_symbol(write::Symbol {
+            name: b"__gxx_personality_v0".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        let eh_frame = object.section_id(write::StandardSection::EhFrame);
+        object.append_section_data(eh_frame, &[0; 32], 8);
+        object
+            .add_macho_eh_frame_personality(eh_frame, 20, personality)
+            .unwrap();
+
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        let section = object.section_by_name("__eh_frame").unwrap();
+        assert_eq!(section.segment_name().unwrap(), Some("__TEXT"));
+        assert_eq!(
+            section.flags(),
+            object::SectionFlags::MachO {
+                flags: macho::S_COALESCED
+                    | macho::S_ATTR_NO_TOC
+                    | macho::S_ATTR_STRIP_STATIC_SYMS
+                    | macho::S_ATTR_LIVE_SUPPORT
+            }
+        );
+        assert_eq!(
+            section.data().unwrap()[20..24],
+            u32::to_le_bytes(implicit_addend)
+        );
+
+        let relocations: Vec<_> = section.relocations().collect();
+        assert_eq!(relocations.len(), 1);
+        let (offset, relocation) = &relocations[0];
+        assert_eq!(*offset, 20);
+        assert_eq!(
+            relocation.flags(),
+            object::RelocationFlags::MachO {
+                r_type,
+                r_pcrel: true,
+                r_length: 2,
+            }
+        );
+        let read::RelocationTarget::Symbol(symbol) = relocation.target() else {
+            panic!();
+        };
+        let symbol = object.symbol_by_index(symbol).unwrap();
+        assert_eq!(symbol.name(), Ok("___gxx_personality_v0"));
+    }
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let eh_frame = object.section_id(write::StandardSection::EhFrame);
+    assert_eq!(object.section(eh_frame).name(), Some(".eh_frame"));
+    let symbol = object.section_symbol(eh_frame);
+    assert!(object
+        .add_macho_eh_frame_personality(eh_frame, 0, symbol)
+        .is_err());
+}
@@ -7,12 +7,14 @@ use object::{
     SectionKind, SymbolFlags, SymbolKind, SymbolScope, SymbolSection,
 };
 
+mod archive;
 mod bss;
 mod coff;
 mod comdat;
 mod common;
 mod elf;
 mod macho;
+mod pe;
 mod section_flags;
 mod tls;
 
@@ -688,3 +690,925 @@ fn xcoff_powerpc() {
         assert_eq!(relocation.addend(), 0);
     }
 }
+
+#[test]
+fn xcoff_aux_header() {
+    use object::read::xcoff::{AuxHeader, FileHeader, XcoffFile};
+
+    /// Return the auxiliary header fields that are set by the writer.
+    fn read_aux_header<Xcoff: FileHeader>(data: &[u8]) -> [u64; 11] {
+        let file = XcoffFile::<Xcoff>::parse(data).unwrap();
+        let header = file.xcoff_aux_header().unwrap();
+        [
+            header.o_mflag().into(),
+            header.o_vstamp().into(),
+            header.o_tsize().into(),
+            header.o_dsize().into(),
+            header.o_bsize().into(),
+            header.o_entry().into(),
+            header.o_text_start().into(),
+            header.o_data_start().into(),
+            header.o_sntext().into(),
+            header.o_sndata().into(),
+            header.o_snentry().into(),
+        ]
+    }
+
+    for arch in [Architecture::PowerPc, Architecture::PowerPc64] {
+        let mut object = write::Object::new(BinaryFormat::Xcoff, arch, Endianness::Big);
+        object.flags = object::FileFlags::Xcoff {
+            f_flags: object::xcoff::F_EXEC,
+        };
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[1; 16], 4);
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(data, &[2; 8], 4);
+
+        let mut aux_header = write::XcoffAuxHeader::default();
+        aux_header.flags = 0x1234;
+        aux_header.tsize = 16;
+        aux_header.dsize = 8;
+        aux_header.bsize = 4;
+        // The entry point is a function descriptor in the data section.
+        aux_header.entry = 0x2000_0004;
+        aux_header.text_start = 0x1000_0000;
+        aux_header.data_start = 0x2000_0000;
+        object.set_xcoff_aux_header(aux_header);
+
+        let bytes = object.write().unwrap();
+        let fields = if arch == Architecture::PowerPc64 {
+            read_aux_header::<object::xcoff::FileHeader64>(&bytes)
+        } else {
+            read_aux_header::<object::xcoff::FileHeader32>(&bytes)
+        };
+        assert_eq!(
+            fields,
+            [
+                0x1234,
+                1,
+                16,
+                8,
+                4,
+                0x2000_0004,
+                0x1000_0000,
+                0x2000_0000,
+                1,
+                2,
+                2
+            ]
+        );
+
+        // The section headers follow the auxiliary header.
+        let file = read::File::parse(&*bytes).unwrap();
+        let section = file.section_by_name(".data").unwrap();
+        assert_eq!(section.data().unwrap(), &[2; 8]);
+
+        // The entry point must be in the text or data section.
+        aux_header.entry = 0x1000_0100;
+        object.set_xcoff_aux_header(aux_header);
+        assert!(object.write().is_err());
+    }
+
+    // Values that don't fit are detected before anything is written.
+    let mut object =
+        write::Object::new(BinaryFormat::Xcoff, Architecture::PowerPc, Endianness::Big);
+    let mut aux_header = write::XcoffAuxHeader::default();
+    aux_header.data_start = 0x1_0000_0000;
+    object.set_xcoff_aux_header(aux_header);
+    let mut buffer = Vec::new();
+    assert!(object.emit(&mut buffer).is_err());
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn xcoff_csect() {
+    use object::xcoff;
+
+    for (arch, size) in [(Architecture::PowerPc, 32), (Architecture::PowerPc64, 64)] {
+        let mut object = write::Object::new(BinaryFormat::Xcoff, arch, Endianness::Big);
+        let mut add_symbol = |name: &[u8], scope| {
+            object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 0,
+                kind: SymbolKind::Data,
+                scope,
+                weak: false,
+                section: write::SymbolSection::Undefined,
+                flags: SymbolFlags::None,
+            })
+        };
+        let toc = add_symbol(b"TOC", SymbolScope::Compilation);
+        let entry = add_symbol(b"x", SymbolScope::Compilation);
+        let x = add_symbol(b"x", SymbolScope::Linkage);
+        let y = add_symbol(b"y", SymbolScope::Linkage);
+
+        let data = object.section_id(write::StandardSection::Data);
+        object.set_section_alignment(data, 8).unwrap();
+        object.add_symbol_data(toc, data, &[], 8);
+        let offset = object.add_symbol_data(entry, data, &[0; 8][..size / 8], 8);
+        object.add_symbol_data(y, data, &[0; 4], 4);
+        object
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset,
+                    symbol: x,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: size as u8,
+                    },
+                },
+            )
+            .unwrap();
+        object
+            .set_xcoff_csect(toc, xcoff::XTY_SD, xcoff::XMC_TC0)
+            .unwrap();
+        object
+            .set_xcoff_csect(entry, xcoff::XTY_SD, xcoff::XMC_TC)
+            .unwrap();
+        object
+            .set_xcoff_csect(x, xcoff::XTY_ER, xcoff::XMC_RW)
+            .unwrap();
+        assert!(object
+            .set_xcoff_csect(entry, xcoff::XTY_LD, xcoff::XMC_TC)
+            .is_err());
+
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        let symbols: Vec<_> = object
+            .symbols()
+            .map(|symbol| (symbol.name().unwrap().to_string(), symbol.flags()))
+            .collect();
+        // The alignment of 8 is stored in the high bits of the symbol type.
+        // Symbols without a csect set are unchanged.
+        let csect = |n_sclass, x_smtyp, x_smclas| SymbolFlags::Xcoff {
+            n_sclass,
+            x_smtyp,
+            x_smclas,
+            containing_csect: None,
+        };
+        assert_eq!(
+            symbols,
+            [
+                (
+                    "TOC".to_string(),
+                    csect(xcoff::C_HIDEXT, (3 << 3) | xcoff::XTY_SD, xcoff::XMC_TC0)
+                ),
+                (
+                    "x".to_string(),
+                    csect(xcoff::C_HIDEXT, (3 << 3) | xcoff::XTY_SD, xcoff::XMC_TC)
+                ),
+                (
+                    "x".to_string(),
+                    csect(xcoff::C_EXT, xcoff::XTY_ER, xcoff::XMC_RW)
+                ),
+                (
+                    "y".to_string(),
+                    csect(xcoff::C_EXT, xcoff::XTY_SD, xcoff::XMC_RW)
+                ),
+            ]
+        );
+    }
+
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::PowerPc64, Endianness::Big);
+    let data = object.section_id(write::StandardSection::Data);
+    let symbol = object.section_symbol(data);
+    assert!(object
+        .set_xcoff_csect(symbol, xcoff::XTY_SD, xcoff::XMC_TC0)
+        .is_err());
+}
+
+#[test]
+fn compute_size() {
+    for (format, arch) in [
+        (BinaryFormat::Coff, Architecture::X86_64),
+        (BinaryFormat::Elf, Architecture::X86_64),
+        (BinaryFormat::MachO, Architecture::X86_64),
+        (BinaryFormat::Xcoff, Architecture::PowerPc64),
+    ]
+    .iter()
+    .copied()
+    {
+        let endian = if format == BinaryFormat::Xcoff {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        };
+        let mut object = write::Object::new(format, arch, endian);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[1; 30], 16);
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(data, &[2; 9], 8);
+        let bss = object.section_id(write::StandardSection::UninitializedData);
+        object.append_section_bss(bss, 0x100, 8);
+        let func = object.add_symbol(write::Symbol {
+            name: b"a_long_function_name".to_vec(),
+            value: 0,
+            size: 30,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+        object
+            .add_relocation(
+                data,
+                write::Relocation {
+                    offset: 0,
+                    symbol: func,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 64,
+                    },
+                },
+            )
+            .unwrap();
+
+        let bytes = object.write().unwrap();
+        assert_eq!(object.compute_size().unwrap(), bytes.len(), "{:?}", format);
+    }
+}
+
+#[test]
+fn deterministic_output() {
+    fn create(format: BinaryFormat, arch: Architecture, endian: Endianness) -> Vec<u8> {
+        let mut object = write::Object::new(format, arch, endian);
+        object.add_file_symbol(b"file.c".to_vec());
+        let text = object.section_id(write::StandardSection::Text);
+        let data = object.section_id(write::StandardSection::Data);
+        object.section_id(write::StandardSection::ReadOnlyData);
+        object.section_id(write::StandardSection::UninitializedData);
+        let mut symbols = Vec::new();
+        for i in 0..100 {
+            let (section, kind) = if i % 2 == 0 {
+                (text, SymbolKind::Text)
+            } else {
+                (data, SymbolKind::Data)
+            };
+            let offset = object.append_section_data(section, &[i as u8; 8], 8);
+            symbols.push(object.add_symbol(write::Symbol {
+                name: format!("symbol_with_a_long_name_{}", i).into_bytes(),
+                value: offset,
+                size: 8,
+                kind,
+                scope: if i % 3 == 0 {
+                    SymbolScope::Compilation
+                } else {
+                    SymbolScope::Linkage
+                },
+                weak: i % 7 == 0,
+                section: write::SymbolSection::Section(section),
+                flags: SymbolFlags::None,
+            }));
+            symbols.push(object.add_symbol(write::Symbol {
+                name: format!("undefined_{}", i).into_bytes(),
+                value: 0,
+                size: 0,
+                kind,
+                scope: SymbolScope::Dynamic,
+                weak: false,
+                section: write::SymbolSection::Undefined,
+                flags: SymbolFlags::None,
+            }));
+        }
+        for (i, symbol) in symbols.into_iter().enumerate() {
+            object
+                .add_relocation(
+                    data,
+                    write::Relocation {
+                        offset: i as u64 * 8 % 0x190,
+                        symbol,
+                        addend: 0,
+                        flags: RelocationFlags::Generic {
+                            kind: RelocationKind::Absolute,
+                            encoding: RelocationEncoding::Generic,
+                            size: 64,
+                        },
+                    },
+                )
+                .unwrap();
+        }
+        object.write().unwrap()
+    }
+
+    for (format, arch, endian) in [
+        (BinaryFormat::Coff, Architecture::X86_64, Endianness::Little),
+        (BinaryFormat::Elf, Architecture::X86_64, Endianness::Little),
+        (
+            BinaryFormat::MachO,
+            Architecture::Aarch64,
+            Endianness::Little,
+        ),
+        (
+            BinaryFormat::Xcoff,
+            Architecture::PowerPc64,
+            Endianness::Big,
+        ),
+    ]
+    .iter()
+    .copied()
+    {
+        let expected = create(format, arch, endian);
+        for _ in 0..50 {
+            assert_eq!(create(format, arch, endian), expected, "{:?}", format);
+        }
+    }
+}
+
+#[test]
+fn append_raises_alignment() {
+    for format in [BinaryFormat::Coff, BinaryFormat::Elf, BinaryFormat::MachO] {
+        let mut object = write::Object::new(format, Architecture::X86_64, Endianness::Little);
+        let data = object.section_id(write::StandardSection::Data);
+        assert_eq!(object.append_section_data(data, &[1; 3], 8), 0);
+        assert_eq!(object.section(data).align(), 8);
+        assert_eq!(object.append_section_data(data, &[2; 64], 64), 64);
+        assert_eq!(object.section(data).align(), 64);
+        // A smaller alignment does not reduce the section alignment.
+        assert_eq!(object.append_section_data(data, &[3; 1], 4), 128);
+        assert_eq!(object.section(data).align(), 64);
+
+        let bss = object.section_id(write::StandardSection::UninitializedData);
+        object.append_section_bss(bss, 8, 8);
+        assert_eq!(object.append_section_bss(bss, 8, 64), 64);
+        assert_eq!(object.section(bss).align(), 64);
+
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        for section in object.sections() {
+            match section.kind() {
+                SectionKind::Data | SectionKind::UninitializedData => {
+                    assert_eq!(section.align(), 64, "{:?} {:?}", format, section.name())
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn symbol_data_bss() {
+    for format in [BinaryFormat::Coff, BinaryFormat::Elf, BinaryFormat::MachO] {
+        let mut object = write::Object::new(format, Architecture::X86_64, Endianness::Little);
+        let mut add_symbol = |name: &[u8], kind| {
+            object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 0,
+                kind,
+                scope: SymbolScope::Linkage,
+                weak: false,
+                section: write::SymbolSection::Undefined,
+                flags: SymbolFlags::None,
+            })
+        };
+        let data_symbol = add_symbol(b"data", SymbolKind::Data);
+        let bss_symbol = add_symbol(b"bss", SymbolKind::Data);
+        let func_symbol = add_symbol(b"func", SymbolKind::Text);
+        let data = object.section_id(write::StandardSection::Data);
+        let bss = object.section_id(write::StandardSection::UninitializedData);
+
+        // Initialized data in a bss section, and vice versa.
+        assert!(object
+            .try_add_symbol_data(data_symbol, bss, &[1; 4], 4)
+            .is_err());
+        assert!(object.try_add_symbol_bss(bss_symbol, data, 4, 4).is_err());
+        assert_eq!(object.section(data).data(), &[]);
+
+        // A function in a bss section.
+        assert!(object.try_set_symbol_data(func_symbol, bss, 0, 4).is_err());
+        assert!(object.try_add_symbol_bss(func_symbol, bss, 4, 4).is_err());
+        assert!(object.symbol(func_symbol).is_undefined());
+
+        object
+            .try_add_symbol_data(data_symbol, data, &[1; 4], 4)
+            .unwrap();
+        object.try_add_symbol_bss(bss_symbol, bss, 4, 4).unwrap();
+        assert_eq!(
+            object.symbol(bss_symbol).section,
+            write::SymbolSection::Section(bss)
+        );
+    }
+}
+
+#[test]
+fn append_section_u32_endian() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0xff], 1);
+    let big = object.append_section_u32_endian(data, 0x1234_5678, Endianness::Big, 4);
+    let little = object.append_section_u32_endian(data, 0x1234_5678, Endianness::Little, 4);
+    assert_eq!((big, little), (4, 8));
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    assert_eq!(object.endianness(), Endianness::Little);
+    let section = object.section_by_name(".data").unwrap();
+    assert_eq!(
+        section.data().unwrap(),
+        &[0xff, 0, 0, 0, 0x12, 0x34, 0x56, 0x78, 0x78, 0x56, 0x34, 0x12]
+    );
+}
+
+#[test]
+fn empty_object() {
+    for (format, architecture, endian) in [
+        (BinaryFormat::Coff, Architecture::X86_64, Endianness::Little),
+        (BinaryFormat::Elf, Architecture::X86_64, Endianness::Little),
+        (BinaryFormat::Elf, Architecture::Mips, Endianness::Big),
+        (
+            BinaryFormat::MachO,
+            Architecture::Aarch64,
+            Endianness::Little,
+        ),
+        (
+            BinaryFormat::Xcoff,
+            Architecture::PowerPc64,
+            Endianness::Big,
+        ),
+    ] {
+        let object = write::Object::new(format, architecture, endian);
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        assert_eq!(object.format(), format);
+        assert_eq!(object.architecture(), architecture);
+        assert_eq!(object.symbols().count(), 0, "{:?}", format);
+        let sections: Vec<_> = object
+            .sections()
+            .map(|section| section.name().unwrap())
+            .collect();
+        match format {
+            // The null section is not included.
+            BinaryFormat::Elf => assert_eq!(sections, [".symtab", ".strtab", ".shstrtab"]),
+            _ => assert!(sections.is_empty(), "{:?} {:?}", format, sections),
+        }
+        if format == BinaryFormat::Coff {
+            // The file header and the string table size.
+            assert_eq!(bytes.len(), 20 + 4);
+        }
+    }
+}
+
+#[test]
+fn section_data() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[1, 2, 3, 4], 4);
+    assert_eq!(object.section_data(data).unwrap(), &[1, 2, 3, 4]);
+    let bss = object.section_id(write::StandardSection::UninitializedData);
+    object.append_section_bss(bss, 8, 8);
+    assert!(object.section_data(bss).is_err());
+}
+
+#[test]
+fn append_section_struct() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0xff], 1);
+    let target = object.add_symbol(write::Symbol {
+        name: b"target".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+
+    // struct { uint32_t a; void *p; uint32_t b; }
+    let mut builder = object.append_section_struct(data, 8);
+    assert_eq!(builder.base(), 8);
+    assert!(builder.is_empty());
+    assert_eq!(builder.field_u32(1).unwrap(), 8);
+    assert_eq!(builder.field_ptr(target).unwrap(), 16);
+    assert_eq!(builder.field_u32(2).unwrap(), 24);
+    assert_eq!(builder.len(), 20);
+    assert!(builder.field_bytes(&[3; 2]).is_ok());
+    assert_eq!(builder.finish(), 8);
+
+    let mut builder = object.append_section_struct(data, 4);
+    assert_eq!(builder.base(), 32);
+    assert!(builder.field_u64(0).is_err());
+    assert_eq!(builder.finish(), 32);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let section = object.section_by_name(".data").unwrap();
+    let mut expect = vec![0xff, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0];
+    expect.extend_from_slice(&[0; 8]);
+    expect.extend_from_slice(&[2, 0, 0, 0, 3, 3, 0, 0]);
+    assert_eq!(section.data().unwrap(), &expect[..]);
+
+    let relocations = section.relocations().collect::<Vec<_>>();
+    assert_eq!(relocations.len(), 1);
+    let (offset, relocation) = &relocations[0];
+    assert_eq!(*offset, 16);
+    assert_eq!(relocation.kind(), RelocationKind::Absolute);
+    assert_eq!(relocation.size(), 64);
+    let symbol = object.symbol_by_name("target").unwrap();
+    assert_eq!(
+        relocation.target(),
+        read::RelocationTarget::Symbol(symbol.index())
+    );
+}
+
+#[test]
+fn content_hash() {
+    let object = |format| {
+        let mut object = write::Object::new(format, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0x90; 16], 16);
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(data, &[1, 2, 3, 4], 4);
+        let bss = object.section_id(write::StandardSection::UninitializedData);
+        object.append_section_bss(bss, 0x40, 8);
+        object
+    };
+
+    let mut elf = object(BinaryFormat::Elf);
+    let mut macho = object(BinaryFormat::MachO);
+    let hash = elf.content_hash();
+    assert_eq!(macho.content_hash(), hash);
+
+    // Format specific sections are not included.
+    elf.add_build_id(write::BuildIdStyle::Uuid).unwrap();
+    let comment = elf.add_section(Vec::new(), b".comment".to_vec(), SectionKind::Other);
+    elf.append_section_data(comment, b"compiler\0", 1);
+    assert_eq!(elf.content_hash(), hash);
+
+    assert!(elf.set_macho_uuid(write::BuildIdStyle::Uuid).is_err());
+    assert!(macho
+        .set_macho_uuid(write::BuildIdStyle::FromContents)
+        .is_err());
+    macho.set_macho_uuid(write::BuildIdStyle::Uuid).unwrap();
+
+    let elf_bytes = elf.write().unwrap();
+    let elf_file = read::File::parse(&*elf_bytes).unwrap();
+    let build_id = elf_file.build_id().unwrap().unwrap();
+    let macho_bytes = macho.write().unwrap();
+    let macho_file = read::File::parse(&*macho_bytes).unwrap();
+    let uuid = macho_file.mach_uuid().unwrap().unwrap();
+    assert_eq!(build_id, &uuid[..]);
+    assert_eq!(uuid[..6], hash[..6]);
+    assert_eq!(uuid[6] >> 4, 5);
+
+    // Symbols and relocations are included.
+    let mut elf = object(BinaryFormat::Elf);
+    let text = elf.section_id(write::StandardSection::Text);
+    let func = elf.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let symbol_hash = elf.content_hash();
+    assert_ne!(symbol_hash, hash);
+    elf.set_symbol_data(func, text, 0, 16);
+    let defined_hash = elf.content_hash();
+    assert_ne!(defined_hash, symbol_hash);
+    let relocation = |addend| write::Relocation {
+        offset: 4,
+        symbol: func,
+        addend,
+        flags: RelocationFlags::Generic {
+            kind: RelocationKind::Relative,
+            encoding: RelocationEncoding::Generic,
+            size: 32,
+        },
+    };
+    let mut other = elf.clone();
+    elf.add_relocation(text, relocation(0)).unwrap();
+    other.add_relocation(text, relocation(8)).unwrap();
+    assert_ne!(elf.content_hash(), defined_hash);
+    assert_ne!(elf.content_hash(), other.content_hash());
+
+    // Symbols and relocations are hashed independently of the file format.
+    let linked = |format| {
+        let mut object = object(format);
+        let text = object.section_id(write::StandardSection::Text);
+        let data = object.section_id(write::StandardSection::Data);
+        let mut add = |name: &[u8], scope, section| {
+            object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 0,
+                kind: SymbolKind::Text,
+                scope,
+                weak: false,
+                section,
+                flags: SymbolFlags::None,
+            })
+        };
+        let func = add(
+            b"func",
+            SymbolScope::Linkage,
+            write::SymbolSection::Undefined,
+        );
+        let main = add(
+            b"main",
+            SymbolScope::Dynamic,
+            write::SymbolSection::Undefined,
+        );
+        object.set_symbol_data(main, text, 0, 16);
+        let relocation = |offset, symbol, addend, kind, size| write::Relocation {
+            offset,
+            symbol,
+            addend,
+            flags: RelocationFlags::Generic {
+                kind,
+                encoding: RelocationEncoding::Generic,
+                size,
+            },
+        };
+        object
+            .add_relocation(text, relocation(4, func, -4, RelocationKind::Relative, 32))
+            .unwrap();
+        object
+            .add_relocation(data, relocation(0, main, 2, RelocationKind::Absolute, 32))
+            .unwrap();
+        object
+    };
+    let elf = linked(BinaryFormat::Elf);
+    let macho = linked(BinaryFormat::MachO);
+    assert_eq!(elf.symbol(elf.symbol_id(b"func").unwrap()).name, b"func");
+    assert_eq!(
+        macho.symbol(macho.symbol_id(b"func").unwrap()).name,
+        b"_func"
+    );
+    assert_ne!(elf.content_hash(), hash);
+    assert_eq!(elf.content_hash(), macho.content_hash());
+
+    let mut macho = object(BinaryFormat::MachO);
+    let md5: Vec<u8> = (0..16).collect();
+    macho
+        .set_macho_uuid(write::BuildIdStyle::Md5(md5.clone()))
+        .unwrap();
+    let macho_bytes = macho.write().unwrap();
+    let macho_file = read::File::parse(&*macho_bytes).unwrap();
+    assert_eq!(macho_file.mach_uuid().unwrap().unwrap()[..], md5[..]);
+}
+
+#[test]
+fn write_with_symbol_map() {
+    for format in [BinaryFormat::Elf, BinaryFormat::MachO] {
+        let mut object = write::Object::new(format, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0xcc; 4], 16);
+        let func = object.add_symbol(write::Symbol {
+            name: b"func".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.add_symbol_data(func, text, &[0xc3], 16);
+        object.add_symbol(write::Symbol {
+            name: b"undefined".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.section_symbol(text);
+        let (name, local) = match format {
+            BinaryFormat::MachO => (&b"_func"[..], &b"_local"[..]),
+            _ => (&b"func"[..], &b"local"[..]),
+        };
+        let mut add_local = |name: &[u8], value| {
+            object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value,
+                size: 0,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Compilation,
+                weak: false,
+                section: write::SymbolSection::Section(text),
+                flags: SymbolFlags::None,
+            });
+        };
+        // A local with the same name as a non-local.
+        add_local(b"func", 2);
+        // Locals with ambiguous names.
+        add_local(b"dup", 0);
+        add_local(b"dup", 1);
+        add_local(b"local", 3);
+
+        let (bytes, map) = object.write_with_symbol_map().unwrap();
+        assert_eq!(bytes, object.write().unwrap());
+        assert_eq!(map.len(), 2, "{:?}", format);
+        assert_eq!(map[name], (text, 16));
+        assert_eq!(map[local], (text, 3));
+
+        let file = read::File::parse(&*bytes).unwrap();
+        let section = file
+            .section_by_name(object.section(text).name().unwrap())
+            .unwrap();
+        let symbol = file
+            .symbols()
+            .find(|symbol| symbol.is_global() && symbol.is_definition())
+            .unwrap();
+        assert_eq!(symbol.section_index(), Some(section.index()));
+        assert_eq!(symbol.address() - section.address(), 16);
+    }
+}
+
+#[test]
+fn weak_strong_pair() {
+    for format in [BinaryFormat::Elf, BinaryFormat::MachO] {
+        let mut object = write::Object::new(format, Architecture::X86_64, Endianness::Little);
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0xcc; 4], 16);
+        let target = object.add_symbol(write::Symbol {
+            name: b"__read_impl".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.add_symbol_data(target, text, &[0xc3; 4], 16);
+        // An existing reference is defined by the pair.
+        let reference = object.add_symbol(write::Symbol {
+            name: b"__read".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        let (weak, strong) = object
+            .add_weak_strong_pair(b"read", b"__read", target)
+            .unwrap();
+        assert_eq!(strong, reference);
+        assert!(object.symbol(weak).weak);
+        assert!(!object.symbol(strong).weak);
+        assert!(object
+            .add_weak_strong_pair(b"read", b"__read2", target)
+            .is_err());
+        let undefined = object.add_symbol(write::Symbol {
+            name: b"undefined".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        assert!(object
+            .add_weak_strong_pair(b"write", b"__write", undefined)
+            .is_err());
+
+        let bytes = object.write().unwrap();
+        let object = read::File::parse(&*bytes).unwrap();
+        let prefix = match format {
+            BinaryFormat::MachO => "_",
+            _ => "",
+        };
+        let symbol = |name: &str| {
+            object
+                .symbol_by_name(&format!("{}{}", prefix, name))
+                .unwrap()
+        };
+        let (target, weak, strong) = (symbol("__read_impl"), symbol("read"), symbol("__read"));
+        assert!(target.is_local());
+        assert!(weak.is_weak() && weak.is_global(), "{:?}", format);
+        assert!(!strong.is_weak() && strong.is_global(), "{:?}", format);
+        for symbol in [&weak, &strong] {
+            assert_eq!(symbol.address(), target.address());
+            assert_eq!(symbol.section_index(), target.section_index());
+            assert_eq!(symbol.kind(), SymbolKind::Text);
+        }
+    }
+}
+
+#[test]
+fn add_symbols() {
+    let new_object = || {
+        let mut object = write::Object::new(
+            BinaryFormat::MachO,
+            Architecture::X86_64,
+            Endianness::Little,
+        );
+        let text = object.section_id(write::StandardSection::Text);
+        object.append_section_data(text, &[0xc3; 16], 16);
+        let data = object.section_id(write::StandardSection::Data);
+        object.append_section_data(data, &[0; 16], 8);
+        (object, text, data)
+    };
+    let symbol = |name: &[u8], kind, section| write::Symbol {
+        name: name.to_vec(),
+        value: 0,
+        size: 0,
+        kind,
+        scope: if section == write::SymbolSection::Undefined {
+            SymbolScope::Unknown
+        } else {
+            SymbolScope::Linkage
+        },
+        weak: false,
+        section,
+        flags: SymbolFlags::None,
+    };
+
+    let (mut bulk, text, data) = new_object();
+    let symbols = vec![
+        symbol(
+            b"func",
+            SymbolKind::Text,
+            write::SymbolSection::Section(text),
+        ),
+        symbol(
+            b"var",
+            SymbolKind::Data,
+            write::SymbolSection::Section(data),
+        ),
+        symbol(b"extern", SymbolKind::Text, write::SymbolSection::Undefined),
+        symbol(b"extern", SymbolKind::Text, write::SymbolSection::Undefined),
+        symbol(
+            b"extern_data",
+            SymbolKind::Data,
+            write::SymbolSection::Undefined,
+        ),
+    ];
+    let ids = bulk.add_symbols(symbols.clone());
+    assert_eq!(ids.len(), symbols.len());
+    // Duplicate undefined symbols are merged as for `add_symbol`.
+    assert_eq!(ids[2], ids[3]);
+
+    let (mut single, _, _) = new_object();
+    for (symbol, id) in symbols.into_iter().zip(ids.iter()) {
+        assert_eq!(single.add_symbol(symbol), *id);
+    }
+    for name in [&b"func"[..], b"var", b"extern", b"extern_data"] {
+        let id = bulk.symbol_id(name).unwrap();
+        assert_eq!(single.symbol_id(name), Some(id));
+        assert_eq!(bulk.symbol(id).name, single.symbol(id).name);
+    }
+    assert_eq!(bulk.symbol(ids[0]).name, b"_func");
+}
+
+#[test]
+fn section_fill() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object
+        .set_section_fill(text, write::FillPattern::Byte(0xcc))
+        .unwrap();
+    object.append_section_data(text, &[0xc3; 3], 16);
+    assert_eq!(object.append_section_data(text, &[0xc3; 2], 8), 8);
+    object.append_section_data(text, &[], 16);
+
+    let bytes = object.write().unwrap();
+    let object = read::File::parse(&*bytes).unwrap();
+    let text = object.section_by_name(".text").unwrap();
+    let mut expect = [0xcc; 16];
+    expect[..3].copy_from_slice(&[0xc3; 3]);
+    expect[8..10].copy_from_slice(&[0xc3; 2]);
+    assert_eq!(text.data().unwrap(), &expect);
+
+    // Multi-byte NOPs are aligned relative to the start of the section.
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object
+        .set_section_fill(text, write::FillPattern::Nop)
+        .unwrap();
+    object.append_section_data(text, &[0; 4], 4);
+    object.append_section_data(text, &[], 16);
+    assert_eq!(
+        &object.section(text).data()[4..],
+        &[0x1f, 0x20, 0x03, 0xd5].repeat(3)[..]
+    );
+
+    let data = object.section_id(write::StandardSection::UninitializedData);
+    assert!(object
+        .set_section_fill(data, write::FillPattern::Byte(0xcc))
+        .is_err());
+}
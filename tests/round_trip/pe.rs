@@ -0,0 +1,403 @@
+use object::read::pe::{ImageNtHeaders, ImageThunkData, PeFile, PeFile32, PeFile64};
+use object::write::pe::Import::{Name, Ordinal};
+use object::{pe, write, LittleEndian as LE};
+
+fn nt_headers(is_64: bool, address_of_entry_point: u32) -> write::pe::NtHeaders {
+    write::pe::NtHeaders {
+        machine: if is_64 {
+            pe::IMAGE_FILE_MACHINE_AMD64
+        } else {
+            pe::IMAGE_FILE_MACHINE_I386
+        },
+        time_date_stamp: 0,
+        characteristics: if is_64 {
+            pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_LARGE_ADDRESS_AWARE
+        } else {
+            pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_32BIT_MACHINE
+        },
+        major_linker_version: 0,
+        minor_linker_version: 0,
+        address_of_entry_point,
+        image_base: if is_64 { 0x1_4000_0000 } else { 0x40_0000 },
+        major_operating_system_version: 6,
+        minor_operating_system_version: 0,
+        major_image_version: 0,
+        minor_image_version: 0,
+        major_subsystem_version: 6,
+        minor_subsystem_version: 0,
+        subsystem: pe::IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        dll_characteristics: pe::IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE,
+        size_of_stack_reserve: 0x10_0000,
+        size_of_stack_commit: 0x1000,
+        size_of_heap_reserve: 0x10_0000,
+        size_of_heap_commit: 0x1000,
+    }
+}
+
+#[test]
+fn base_relocations() {
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    // Unsorted, with a 64-bit fixup straddling the page boundary at 0x2000.
+    writer.add_relocs([
+        (0x2008, pe::IMAGE_REL_BASED_DIR64),
+        (0x1ffc, pe::IMAGE_REL_BASED_DIR64),
+        (0x1010, pe::IMAGE_REL_BASED_DIR64),
+        (0x3000, pe::IMAGE_REL_BASED_HIGHLOW),
+    ]);
+
+    writer.reserve_dos_header();
+    writer.reserve_nt_headers(16);
+    writer.reserve_section_headers(2);
+    let text_range = writer.reserve_text_section(0x3000);
+    writer.reserve_reloc_section();
+
+    writer.write_empty_dos_header().unwrap();
+    writer.write_nt_headers(write::pe::NtHeaders {
+        machine: pe::IMAGE_FILE_MACHINE_AMD64,
+        time_date_stamp: 0,
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_LARGE_ADDRESS_AWARE,
+        major_linker_version: 0,
+        minor_linker_version: 0,
+        address_of_entry_point: text_range.virtual_address,
+        image_base: 0x1_4000_0000,
+        major_operating_system_version: 6,
+        minor_operating_system_version: 0,
+        major_image_version: 0,
+        minor_image_version: 0,
+        major_subsystem_version: 6,
+        minor_subsystem_version: 0,
+        subsystem: pe::IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        dll_characteristics: pe::IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE
+            | pe::IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA,
+        size_of_stack_reserve: 0x10_0000,
+        size_of_stack_commit: 0x1000,
+        size_of_heap_reserve: 0x10_0000,
+        size_of_heap_commit: 0x1000,
+    });
+    writer.write_section_headers();
+    writer.pad_until(text_range.file_offset);
+    writer.write(&vec![0xcc; text_range.file_size as usize]);
+    writer.write_reloc_section();
+    assert_eq!(writer.reserved_len() as usize, writer.len());
+
+    let file = PeFile64::parse(&*data).unwrap();
+    let blocks = file
+        .data_directories()
+        .relocation_blocks(&*data, &file.section_table())
+        .unwrap()
+        .unwrap();
+    let blocks: Vec<_> = blocks
+        .map(|block| {
+            let block = block.unwrap();
+            let size = block.size();
+            let relocs: Vec<_> = block
+                .map(|reloc| (reloc.virtual_address, reloc.typ))
+                .collect();
+            (size, relocs)
+        })
+        .collect();
+    assert_eq!(
+        blocks,
+        [
+            // Padded to an even number of entries.
+            (
+                12,
+                vec![
+                    (0x1010, pe::IMAGE_REL_BASED_DIR64),
+                    (0x1ffc, pe::IMAGE_REL_BASED_DIR64)
+                ]
+            ),
+            (12, vec![(0x2008, pe::IMAGE_REL_BASED_DIR64)]),
+            (12, vec![(0x3000, pe::IMAGE_REL_BASED_HIGHLOW)]),
+        ]
+    );
+}
+
+#[test]
+fn imports() {
+    for is_64 in [false, true] {
+        let mut data = Vec::new();
+        let mut writer = write::pe::Writer::new(is_64, 0x1000, 0x200, &mut data);
+        let kernel32 = writer.add_import_dll(
+            b"KERNEL32.dll".to_vec(),
+            vec![
+                Name(0x167, b"ExitProcess".to_vec()),
+                Name(0x2a4, b"GetStdHandle".to_vec()),
+            ],
+        );
+        let ws2_32 = writer.add_import_dll(b"WS2_32.dll".to_vec(), vec![Ordinal(115)]);
+
+        writer.reserve_dos_header();
+        writer.reserve_nt_headers(16);
+        writer.reserve_section_headers(2);
+        let text_range = writer.reserve_text_section(0x10);
+        let idata_range = writer.reserve_import_section();
+        let exit_process = writer.import_address(kernel32, 0);
+        let get_std_handle = writer.import_address(kernel32, 1);
+        let wsa_startup = writer.import_address(ws2_32, 0);
+
+        writer.write_empty_dos_header().unwrap();
+        writer.write_nt_headers(nt_headers(is_64, text_range.virtual_address));
+        writer.write_section_headers();
+        writer.write_section(text_range.file_offset, &[0xcc; 0x10]);
+        writer.write_import_section();
+        assert_eq!(writer.reserved_len() as usize, writer.len());
+
+        let (iat, imports) = if is_64 {
+            read_imports::<pe::ImageNtHeaders64>(&data)
+        } else {
+            read_imports::<pe::ImageNtHeaders32>(&data)
+        };
+        let thunk_size = if is_64 { 8 } else { 4 };
+        assert!(iat.0 >= idata_range.virtual_address);
+        assert_eq!(iat.1, 5 * thunk_size);
+        assert_eq!(get_std_handle, exit_process + thunk_size);
+        assert_eq!(wsa_startup, exit_process + 3 * thunk_size);
+        assert_eq!(
+            imports,
+            [
+                (
+                    &b"KERNEL32.dll"[..],
+                    exit_process,
+                    Name(0x167, b"ExitProcess".to_vec())
+                ),
+                (
+                    &b"KERNEL32.dll"[..],
+                    get_std_handle,
+                    Name(0x2a4, b"GetStdHandle".to_vec())
+                ),
+                (&b"WS2_32.dll"[..], wsa_startup, Ordinal(115)),
+            ]
+        );
+    }
+}
+
+#[test]
+fn exports() {
+    use object::read::pe::ExportTarget;
+    use write::pe::{Export, ExportTarget::*};
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.set_exports(
+        b"test.dll".to_vec(),
+        vec![
+            Export {
+                name: Some(b"zeta".to_vec()),
+                ordinal: None,
+                target: Address(0x1000),
+            },
+            Export {
+                name: Some(b"alpha".to_vec()),
+                ordinal: Some(5),
+                target: Address(0x1008),
+            },
+            Export {
+                name: None,
+                ordinal: Some(8),
+                target: Address(0x1010),
+            },
+            Export {
+                name: Some(b"Sleep".to_vec()),
+                ordinal: None,
+                target: Forward(b"KERNEL32.Sleep".to_vec()),
+            },
+        ],
+    );
+
+    writer.reserve_dos_header();
+    writer.reserve_nt_headers(16);
+    writer.reserve_section_headers(2);
+    let text_range = writer.reserve_text_section(0x20);
+    writer.reserve_export_section().unwrap();
+
+    writer.write_empty_dos_header().unwrap();
+    let mut nt_headers = nt_headers(true, 0);
+    nt_headers.characteristics |= pe::IMAGE_FILE_DLL;
+    writer.write_nt_headers(nt_headers);
+    writer.write_section_headers();
+    writer.write_section(text_range.file_offset, &[0xcc; 0x20]);
+    writer.write_export_section();
+    assert_eq!(writer.reserved_len() as usize, writer.len());
+
+    let file = PeFile64::parse(&*data).unwrap();
+    let export_table = file.export_table().unwrap().unwrap();
+    assert_eq!(export_table.ordinal_base(), 5);
+    assert_eq!(
+        export_table
+            .name_from_pointer(export_table.directory().name.get(LE))
+            .unwrap(),
+        b"test.dll"
+    );
+    // Names are sorted, and unassigned ordinals fill the gaps.
+    let mut exports: Vec<_> = export_table
+        .exports()
+        .unwrap()
+        .into_iter()
+        .map(|export| {
+            let target = match export.target {
+                ExportTarget::Address(address) => Address(address),
+                ExportTarget::ForwardByName(dll, name) => Forward([dll, b".", name].concat()),
+                ExportTarget::ForwardByOrdinal(..) => unreachable!(),
+            };
+            (export.ordinal, export.name, target)
+        })
+        .collect();
+    exports.sort_by_key(|export| export.0);
+    assert_eq!(
+        exports,
+        [
+            (5, Some(&b"alpha"[..]), Address(0x1008)),
+            (6, Some(&b"zeta"[..]), Address(0x1000)),
+            (7, Some(&b"Sleep"[..]), Forward(b"KERNEL32.Sleep".to_vec())),
+            (8, None, Address(0x1010)),
+        ]
+    );
+    let names: Vec<_> = export_table
+        .name_iter()
+        .map(|(name, _)| export_table.name_from_pointer(name).unwrap())
+        .collect();
+    assert_eq!(names, [&b"Sleep"[..], b"alpha", b"zeta"]);
+
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.set_exports(
+        b"test.dll".to_vec(),
+        vec![
+            Export {
+                name: Some(b"f".to_vec()),
+                ordinal: None,
+                target: Address(0x1000),
+            },
+            Export {
+                name: Some(b"f".to_vec()),
+                ordinal: None,
+                target: Address(0x1008),
+            },
+        ],
+    );
+    writer.reserve_dos_header();
+    writer.reserve_nt_headers(16);
+    writer.reserve_section_headers(1);
+    assert!(writer.reserve_export_section().is_err());
+}
+
+#[test]
+fn codeview() {
+    use object::Object;
+
+    let guid = *b"0123456789abcdef";
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(false, 0x1000, 0x200, &mut data);
+    writer.set_codeview(guid, 3, String::from("C:\\build\\test.pdb"));
+
+    writer.reserve_dos_header();
+    writer.reserve_nt_headers(16);
+    writer.reserve_section_headers(2);
+    let text_range = writer.reserve_text_section(0x10);
+    writer.reserve_debug_section().unwrap();
+
+    writer.write_empty_dos_header().unwrap();
+    writer.write_nt_headers(nt_headers(false, text_range.virtual_address));
+    writer.write_section_headers();
+    writer.write_section(text_range.file_offset, &[0xcc; 0x10]);
+    writer.write_debug_section();
+    assert_eq!(writer.reserved_len() as usize, writer.len());
+
+    let file = PeFile32::parse(&*data).unwrap();
+    let codeview = file.pdb_info().unwrap().unwrap();
+    assert_eq!(codeview.guid(), guid);
+    assert_eq!(codeview.age(), 3);
+    assert_eq!(codeview.path(), b"C:\\build\\test.pdb");
+
+    // The CodeView information must be set first.
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(false, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header();
+    writer.reserve_nt_headers(16);
+    writer.reserve_section_headers(1);
+    assert!(writer.reserve_debug_section().is_err());
+}
+
+#[test]
+fn checksum() {
+    // Carries are folded back into the low 16 bits, a trailing odd byte is
+    // treated as a word, and the checksum field is skipped.
+    assert_eq!(write::pe::checksum(&[1, 0, 2, 0, 0xff, 0xff], 100), 3 + 6);
+    assert_eq!(write::pe::checksum(&[1, 0, 0xaa, 0xbb, 2], 100), 0xbbad + 5);
+    assert_eq!(
+        write::pe::checksum(&[1, 0, 0xaa, 0xbb, 0xcc, 0xdd, 2, 0], 2),
+        3 + 8
+    );
+
+    let mut data = Vec::new();
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(16);
+    writer.reserve_section_headers(1);
+    let text_range = writer.reserve_text_section(0x10);
+    writer.write_dos_header_and_stub().unwrap();
+    writer.write_nt_headers(nt_headers(true, text_range.virtual_address));
+    writer.write_section_headers();
+    writer.write_section(text_range.file_offset, &[0xc3; 0x10]);
+
+    write::pe::set_checksum(&mut data).unwrap();
+    let file = PeFile64::parse(&*data).unwrap();
+    let check_sum = file.nt_headers().optional_header.check_sum.get(LE);
+
+    // Compute the expected checksum independently: the sum of the 16-bit words
+    // of the file with the checksum field excluded and carries folded into the
+    // low 16 bits, plus the file length.
+    let check_sum_offset = file.dos_header().nt_headers_offset() as usize
+        + 4
+        + std::mem::size_of::<pe::ImageFileHeader>()
+        + 64;
+    let mut sum = 0u32;
+    for (i, word) in data.chunks(2).enumerate() {
+        if i * 2 == check_sum_offset || i * 2 == check_sum_offset + 2 {
+            continue;
+        }
+        sum += u32::from(word[0]) | u32::from(word.get(1).copied().unwrap_or(0)) << 8;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    assert_ne!(check_sum, 0);
+    assert_eq!(check_sum, sum + data.len() as u32);
+    assert!(write::pe::set_checksum(&mut [0; 0x40]).is_err());
+}
+
+/// The DLL name, IAT address, and import for each import.
+type Imports<'data> = Vec<(&'data [u8], u32, write::pe::Import)>;
+
+/// Return the IAT data directory and the imports with their IAT addresses.
+fn read_imports<Pe: ImageNtHeaders>(data: &[u8]) -> ((u32, u32), Imports<'_>) {
+    let file = PeFile::<Pe>::parse(data).unwrap();
+    let iat = file
+        .data_directories()
+        .get(pe::IMAGE_DIRECTORY_ENTRY_IAT)
+        .unwrap();
+    let thunk_size = std::mem::size_of::<Pe::ImageThunkData>() as u32;
+    let import_table = file.import_table().unwrap().unwrap();
+    let mut imports = Vec::new();
+    let mut descriptors = import_table.descriptors().unwrap();
+    while let Some(descriptor) = descriptors.next().unwrap() {
+        let dll = import_table.name(descriptor.name.get(LE)).unwrap();
+        // The lookup table and the address table are identical before binding.
+        let mut lookup = import_table
+            .thunks(descriptor.original_first_thunk.get(LE))
+            .unwrap();
+        let mut address = descriptor.first_thunk.get(LE);
+        let mut thunks = import_table.thunks(address).unwrap();
+        while let Some(thunk) = thunks.next::<Pe>().unwrap() {
+            let expect = lookup.next::<Pe>().unwrap().unwrap();
+            assert_eq!(thunk.raw(), expect.raw());
+            let import = match import_table.import::<Pe>(thunk).unwrap() {
+                object::read::pe::Import::Ordinal(ordinal) => Ordinal(ordinal),
+                object::read::pe::Import::Name(hint, name) => Name(hint, name.to_vec()),
+            };
+            imports.push((dll, address, import));
+            address += thunk_size;
+        }
+        assert!(lookup.next::<Pe>().unwrap().is_none());
+    }
+    ((iat.virtual_address.get(LE), iat.size.get(LE)), imports)
+}